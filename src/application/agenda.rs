@@ -0,0 +1,114 @@
+use super::*;
+
+// An agenda is everything happening on one day, which is what each square of a calendar shows
+// Unlike `lessons_on`, this knows about holidays, so there are no lessons during them
+
+#[derive(Clone, Debug)]
+/// Everything happening on one day.
+pub struct Agenda {
+	pub date: Date,
+	/// Whether the day is in a holiday. There are never any lessons on holidays.
+	pub holiday: bool,
+	/// The lessons as (course index, start, end), in the application's time zone and sorted by when they start.
+	pub lessons: Vec<(usize, Time, Time)>,
+	/// The tasks due as (course index, task index), most important first.
+	pub tasks: Vec<(usize, usize)>,
+}
+
+impl Agenda {
+	/// Returns whether nothing at all is happening.
+	pub fn is_empty(&self) -> bool {
+		!self.holiday && self.lessons.is_empty() && self.tasks.is_empty()
+	}
+}
+
+impl Application {
+	/// Returns everything happening on `date`.
+	pub fn agenda(&self, date: Date) -> Agenda {
+		let holiday = self.is_holiday(date);
+
+		let mut lessons: Vec<(usize, Time, Time)> = Vec::new();
+		if !holiday {
+			for (index, course) in self.courses.iter().enumerate() {
+				for (start, end) in course.lessons_on(date, self.timezone) {
+					lessons.push((index, start, end));
+				}
+			}
+			lessons.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+		}
+
+		let query = TaskQuery::new()
+			.due_between(date, date)
+			.sort_by(TaskSort::Priority);
+		let tasks = self
+			.tasks(&query)
+			.iter()
+			.map(|entry| (entry.course_index, entry.task_index))
+			.collect();
+
+		Agenda {
+			date,
+			holiday,
+			lessons,
+			tasks,
+		}
+	}
+
+	/// Returns the agenda of every day from `from` to `to`, including both.
+	pub fn agendas(&self, from: Date, to: Date) -> Vec<Agenda> {
+		let mut agendas = Vec::new();
+		let mut date = from;
+		while date <= to {
+			agendas.push(self.agenda(date));
+			date = match date.offset(1) {
+				Ok(next) => next,
+				Err(()) => break,
+			};
+		}
+		agendas
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn agenda() {
+		let mut application = Application::default();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Monday, Time::new(11, 0).unwrap(), Time::new(12, 0).unwrap());
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_time(Day::Monday, Time::new(9, 0).unwrap(), Time::new(10, 0).unwrap());
+		science.add_task("Lab report", "", Date::new(2020, 3, 2).unwrap());
+		application.add_course(math);
+		application.add_course(science);
+
+		// 2020-03-02 is a Monday
+		let monday = Date::new(2020, 3, 2).unwrap();
+		let agenda = application.agenda(monday);
+		assert!(!agenda.holiday);
+		let courses: Vec<usize> = agenda.lessons.iter().map(|lesson| lesson.0).collect();
+		assert_eq!(courses, vec![1, 0]);
+		assert_eq!(agenda.tasks, vec![(1, 0)]);
+
+		// On a holiday, the lessons are gone but the task is still due
+		application.add_holiday(monday, monday + 4);
+		let agenda = application.agenda(monday);
+		assert!(agenda.holiday);
+		assert!(agenda.lessons.is_empty());
+		assert_eq!(agenda.tasks, vec![(1, 0)]);
+
+		let week = application.agendas(monday.offset(-1).unwrap(), monday + 5);
+		assert_eq!(week.len(), 7);
+		assert!(week[0].is_empty());
+		assert!(week[5].holiday);
+		assert!(week[6].is_empty());
+	}
+}
@@ -1,7 +1,9 @@
 use super::*;
 use course::*;
+use legacy::LegacyApplication;
 use std::fs::File;
 use std::io::*;
+use std::time::{Duration, Instant, SystemTime};
 
 // The #[derive()] statement automatically implements some traits for us
 // For example, here, we can serialize and deserialize this object
@@ -15,20 +17,181 @@ pub struct Application {
 	pub courses: Vec<Course>,
 	/// A list of holidays made up of (starting date, ending date).
 	pub holidays: Vec<(Date, Date)>,
+	/// The time zone everything is displayed in, or the computer's own zone if `None`.
+	pub timezone: Option<Zone>,
+	/// How dates and times are shown, and which days are school days.
+	pub locale: Locale,
+	/// When reminders go off. See reminder.rs.
+	pub reminders: ReminderSettings,
+	/// How long focus sessions and their breaks last. See focus.rs.
+	pub focus: FocusSettings,
+	/// The focus session that's going, or that's over but still has rounds to log.
+	pub focus_session: Option<FocusSession>,
+	/// The grades course averages get, for the GPA. See grade.rs.
+	pub grading_scale: GradingScale,
+	/// Whether the undo history is saved along with the application, so it's still there next time.
+	pub keep_history: bool,
+	/// The edits that can be undone and redone. See history.rs.
+	/// This isn't saved with the rest of the application, it has its own file if `keep_history` is on.
+	#[serde(skip)]
+	pub history: History,
+	/// Whether anything has changed since the last save.
+	#[serde(skip)]
+	dirty: bool,
+	/// When the last change was made, so autosaving can wait until the user stops for a moment.
+	#[serde(skip)]
+	changed_at: Option<Instant>,
+	/// Everyone watching for changes, like the GUI's lists. See observer.rs.
+	#[serde(skip)]
+	pub(super) observers: Observers,
+}
+
+/// What every saved file starts with, so we can tell it apart from files saved before there was a version.
+const MAGIC: &[u8; 4] = b"SCHD";
+/// The version of the saved format, which goes up by one whenever a field is added to anything that's saved.
+/// Bincode doesn't save the names of fields, so a file can only be read by the version that wrote it.
+/// Bump this along with any change to the saved structs, and keep a way to read the old version (see legacy.rs).
+pub(super) const FORMAT_VERSION: u32 = 1;
+
+/// Puts the magic bytes and the format version in front of `buf`, the way every saved file starts.
+pub(super) fn with_header(buf: Vec<u8>) -> Vec<u8> {
+	let mut file = MAGIC.to_vec();
+	file.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+	file.extend(buf);
+	file
+}
+
+/// Splits a saved file into its format version and the rest of it.
+/// Files saved before there was a version don't have one, so they get `None`.
+pub(super) fn split_header(buf: &[u8]) -> (Option<u32>, &[u8]) {
+	if buf.len() >= 8 && buf.starts_with(MAGIC) {
+		let mut version = [0; 4];
+		version.copy_from_slice(&buf[4..8]);
+		(Some(u32::from_le_bytes(version)), &buf[8..])
+	} else {
+		(None, buf)
+	}
+}
+
+#[derive(Serialize, Clone, Debug)]
+/// Two lessons that happen at the same time.
+pub struct Conflict {
+	pub date: Date,
+	/// The indexes of the two courses, the one that starts first coming first.
+	pub first: usize,
+	pub second: usize,
+	/// When the two lessons overlap.
+	pub start: Time,
+	pub end: Time,
 }
 
 impl Application {
 	/// Creates a new, default course and pushes it into the courses.
 	pub fn new_course(&mut self) {
-		self.courses.push(Course::default());
+		self.add_course(Course::default());
 	}
 
 	pub fn add_course(&mut self, course: Course) {
-		self.courses.push(course);
+		let label = format!("Add course '{}'", course.name);
+		self.apply(label, Command::InsertCourse(self.courses.len(), course));
 	}
 
 	pub fn rm_course(&mut self, index: usize) {
-		self.courses.remove(index);
+		let label = format!("Remove course '{}'", self.courses[index].name);
+		self.apply(label, Command::RemoveCourse(index));
+	}
+
+	/// Returns the date of today in the application's time zone.
+	pub fn today(&self) -> Date {
+		Date::today_in(self.timezone)
+	}
+
+	/// Returns every lesson on `date` as (course, start, end), converted into the application's time zone.
+	/// Sorted by starting time, so it can be shown as-is.
+	pub fn lessons_on(&self, date: Date) -> Vec<(&Course, Time, Time)> {
+		let mut lessons: Vec<(&Course, Time, Time)> = self
+			.courses
+			.iter()
+			.flat_map(|course| {
+				course
+					.lessons_on(date, self.timezone)
+					.into_iter()
+					.map(move |(start, end)| (course, start, end))
+			})
+			.collect();
+
+		lessons.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+		lessons
+	}
+
+	/// Returns every pair of lessons on `date` that overlap.
+	/// Lessons that end exactly when another starts don't count, since you can just walk over.
+	pub fn conflicts_on(&self, date: Date) -> Vec<Conflict> {
+		let mut lessons: Vec<(usize, Time, Time)> = self
+			.courses
+			.iter()
+			.enumerate()
+			.flat_map(|(index, course)| {
+				course
+					.lessons_on(date, self.timezone)
+					.into_iter()
+					.map(move |(start, end)| (index, start, end))
+			})
+			.collect();
+		lessons.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+		// Since they're sorted, a lesson can only clash with the ones after it that start before it ends
+		let mut conflicts = Vec::new();
+		for (i, &(first, _, first_end)) in lessons.iter().enumerate() {
+			for &(second, second_start, second_end) in &lessons[i + 1..] {
+				if second_start >= first_end {
+					break;
+				}
+				conflicts.push(Conflict {
+					date,
+					first,
+					second,
+					start: second_start,
+					end: if first_end < second_end { first_end } else { second_end },
+				});
+			}
+		}
+		conflicts
+	}
+
+	/// Moves the `time_index`th time of the `course_index`th course to `date`, from `start` to `end`.
+	/// The new times are in the application's time zone, like everything on the screen, and are converted to the course's.
+	///
+	/// The lesson then repeats on that day of the week instead, and is merged with any of the course's
+	/// own lessons that it now overlaps (see `Course::add_time`).
+	/// Returns the lessons of other courses it now clashes with on `date`, or an error if it'd go past midnight
+	/// in the course's time zone (which `times` can't hold).
+	pub fn move_lesson(
+		&mut self,
+		course_index: usize,
+		time_index: usize,
+		date: Date,
+		start: Time,
+		end: Time,
+	) -> std::result::Result<Vec<Conflict>, ()> {
+		let course_zone = self.courses[course_index].timezone.or(self.timezone);
+		let (course_date, course_start) = Zone::convert(date, start, self.timezone, course_zone);
+		let (end_date, course_end) = Zone::convert(date, end, self.timezone, course_zone);
+		if end_date != course_date || course_start >= course_end {
+			return Err(());
+		}
+
+		let label = format!("Move lesson '{}'", self.courses[course_index].name);
+		self.edit_course(course_index, label, |course| {
+			course.rm_time(time_index);
+			course.add_time(course_date.weekday(), course_start, course_end);
+		});
+
+		Ok(self
+			.conflicts_on(date)
+			.into_iter()
+			.filter(|conflict| conflict.first == course_index || conflict.second == course_index)
+			.collect())
 	}
 
 	/// Horrendous function that checks every holiday and finds overlaps, removing them if necessary.
@@ -37,14 +200,14 @@ impl Application {
 	///  - Me, 2020-01-01
 	///
 	/// This function is similar to `recheck_times` in course.rs, so if you read that you can skip this.
-	fn recheck_holidays(&mut self) {
-		let len = self.holidays.len();
+	fn recheck_holidays(holidays: &mut Vec<(Date, Date)>) {
+		let len = holidays.len();
 		// Since we don't know whether there are duplicates, we need to check every date against every other
 		// Therefore, we have a nested for loop that iterates over the index of every element in the Vec
 		for i in 0..len {
 			for j in 0..len {
 				// We reset the len variable because we're removing items in the loop
-				let len = self.holidays.len();
+				let len = holidays.len();
 
 				// We need to check if we're out of the loop now
 				// Also we check if we're comparing the same items, in which case we skip
@@ -57,8 +220,8 @@ impl Application {
 				// We get the elements at the array at this point
 				// start_i and end_i are mutable variables, which means we can change them
 				// start_other and end_other is what we're checking against
-				let (start_other, end_other) = self.holidays[j];
-				let (start_i, end_i) = &mut self.holidays[i];
+				let (start_other, end_other) = holidays[j];
+				let (start_i, end_i) = &mut holidays[i];
 
 				// If the start of the other date is in between the current element
 				if start_other.is_between(*start_i, *end_i) {
@@ -85,7 +248,7 @@ impl Application {
 					// <--|--------|----------|--------|-->
 
 					// And we can safely remove so and eo without having to move anything
-					self.holidays.remove(j);
+					holidays.remove(j);
 				}
 			}
 		}
@@ -102,57 +265,148 @@ impl Application {
 
 	/// Creates a new default holiday from today to today, and adds it to the list of holidays.
 	pub fn new_holiday(&mut self) {
-		self.holidays.push((Date::today(), Date::today()));
+		let today = self.today();
+		self.edit_holidays("Add holiday", |holidays| holidays.push((today, today)));
 	}
 
 	pub fn add_holiday(&mut self, start_date: Date, end_date: Date) {
-		self.holidays.push((start_date, end_date));
-		// Have to check it twice here, or else some edge cases won't catch
-		// TODO: Is there a way around this?
-		self.recheck_holidays();
-		self.recheck_holidays();
+		self.edit_holidays("Add holiday", |holidays| {
+			holidays.push((start_date, end_date));
+			// Have to check it twice here, or else some edge cases won't catch
+			// TODO: Is there a way around this?
+			Self::recheck_holidays(holidays);
+			Self::recheck_holidays(holidays);
+		});
 	}
 
 	/// Removes holiday at index.
 	/// Useful because the GUI module returns an index when a GUI element in a list is selected.
 	pub fn rm_holiday(&mut self, index: usize) {
-		self.holidays.remove(index);
+		self.edit_holidays("Remove holiday", |holidays| {
+			holidays.remove(index);
+		});
 	}
 
-	/// Returns the memory representation of this object.
+	/// Returns the memory representation of this object, after the format version.
 	/// Useful for when writing to a file, as it is tiny in comparison to JSON and the like.
 	pub fn serialize(&self) -> Vec<u8> {
-		bincode::serialize(self).unwrap()
+		with_header(bincode::serialize(self).unwrap())
+	}
+
+	/// Reads an application back from what `serialize` returned, or from a file saved by an older version.
+	/// Returns an error if it's from a newer version, or it can't be read at all.
+	pub fn deserialize(buf: &[u8]) -> std::result::Result<Self, ()> {
+		match split_header(buf) {
+			(Some(FORMAT_VERSION), buf) => bincode::deserialize(buf).map_err(|_| ()),
+			// Files from before the version was saved only had the courses and holidays
+			(None, buf) => bincode::deserialize::<LegacyApplication>(buf)
+				.map(Self::from)
+				.map_err(|_| ()),
+			(Some(_), _) => Err(()),
+		}
 	}
 
 	/// Saves this application to `location`.
 	/// Uses its in-memory representation, see `serialize`.
-	pub fn save(&self, location: &str) {
+	/// The undo history is saved next to it if `keep_history` is on, and deleted if not.
+	pub fn save(&mut self, location: &str) {
 		if File::open(location).is_err() {
 			let mut file = File::create(location).unwrap();
-			file.write_all(&self.serialize()).unwrap();
+			// `Self::` is needed since serde also has a `serialize` method, and it would pick that one for `&mut self`
+			file.write_all(&Self::serialize(self)).unwrap();
 		} else {
 			std::fs::remove_file(location).unwrap();
 			self.save(location);
+			return;
+		}
+
+		if self.keep_history {
+			self.history.save(location);
+		} else {
+			History::forget(location);
+		}
+		self.dirty = false;
+	}
+
+	/// Remembers that something changed, so it gets saved.
+	/// Every command goes through here (see history.rs), so there's no need to call it for those.
+	pub(super) fn mark_changed(&mut self) {
+		self.dirty = true;
+		self.changed_at = Some(Instant::now());
+	}
+
+	/// Returns whether there are changes that haven't been saved yet.
+	pub fn is_dirty(&self) -> bool {
+		self.dirty
+	}
+
+	/// Returns whether there are unsaved changes, and nothing has changed for at least `delay`.
+	/// Waiting a little stops us from saving after every single keypress.
+	pub fn should_autosave(&self, delay: Duration) -> bool {
+		match self.changed_at {
+			Some(changed_at) => self.dirty && changed_at.elapsed() >= delay,
+			None => false,
+		}
+	}
+
+	/// Turns keeping the undo history between sessions on or off.
+	/// This isn't an edit that can be undone, but it does need saving.
+	pub fn set_keep_history(&mut self, keep_history: bool) {
+		if self.keep_history != keep_history {
+			self.keep_history = keep_history;
+			self.mark_changed();
+			self.notify(Change::Settings);
 		}
 	}
 
 	/// Tries to load an application from a file, returns an error if unsuccessful.
+	/// A file that can't be read properly (like one that's halfway through being saved) is an error too.
 	pub fn load(location: &str) -> std::result::Result<Self, ()> {
 		// If we can successfully open the file at `location`,
 		if let Ok(mut file) = File::open(location) {
 			let mut buf = Vec::new();
-			file.read_to_end(&mut buf).unwrap();
+			file.read_to_end(&mut buf).map_err(|_| ())?;
 			// Then, deserialize that buffer and return the result
-			Ok(Application::from(buf))
+			let mut application = Self::deserialize(&buf)?;
+			if application.keep_history {
+				application.history = History::load(location);
+			}
+			Ok(application)
 		} else {
 			Err(())
 		}
 	}
 
-	/// Tries to load an application, and returns an empty one if it fails.
-	pub fn load_or_default(location: &str) -> Self {
-		Self::load(location).unwrap_or_default()
+	/// Returns when the file at `location` was last changed, or `None` if it doesn't exist.
+	/// Useful for noticing when someone else (like the GUI) has saved over it.
+	pub fn last_modified(location: &str) -> Option<SystemTime> {
+		std::fs::metadata(location)
+			.and_then(|metadata| metadata.modified())
+			.ok()
+	}
+
+	/// Tries to load an application, and returns an empty one if there isn't one saved yet.
+	/// If there is one, but it can't be read, it's copied somewhere safe and an error saying where is returned.
+	pub fn load_or_default(location: &str) -> std::result::Result<Self, String> {
+		match Self::load(location) {
+			Ok(application) => Ok(application),
+			// Starting with an empty application would overwrite the file the next time it's saved
+			// Losing everything is much worse than not starting, so we keep a copy and stop here instead
+			Err(()) if std::path::Path::new(location).exists() => {
+				let backup = format!("{}.bak", location);
+				match std::fs::copy(location, &backup) {
+					Ok(_) => Err(format!(
+						"The data in '{}' can't be read, so a copy of it was kept in '{}'. Move '{}' somewhere else to start again.",
+						location, backup, location
+					)),
+					Err(error) => Err(format!(
+						"The data in '{}' can't be read, and it couldn't be copied to '{}' either: {}",
+						location, backup, error
+					)),
+				}
+			}
+			Err(()) => Ok(Self::default()),
+		}
 	}
 }
 
@@ -161,6 +415,17 @@ impl Default for Application {
 		Self {
 			courses: Vec::new(),
 			holidays: Vec::new(),
+			timezone: None,
+			locale: Locale::default(),
+			reminders: ReminderSettings::default(),
+			focus: FocusSettings::default(),
+			focus_session: None,
+			grading_scale: GradingScale::default(),
+			keep_history: false,
+			history: History::default(),
+			dirty: false,
+			changed_at: None,
+			observers: Observers::default(),
 		}
 	}
 }
@@ -170,7 +435,7 @@ impl From<Vec<u8>> for Application {
 	/// Useful, as the memory representation is a byte array.
 	/// Basically the `deserialize` method.
 	fn from(buf: Vec<u8>) -> Self {
-		bincode::deserialize(&buf).unwrap()
+		Self::deserialize(&buf).unwrap()
 	}
 }
 
@@ -183,6 +448,7 @@ impl From<Vec<u8>> for Application {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::convert::TryFrom;
 
 	// Helper function, returns a Date and asserts it is real
 	fn date(year: u16, month: u8, day: u8) -> Date {
@@ -224,4 +490,211 @@ mod tests {
 
 		assert!(application.is_holiday(date(2010, 7, 1)));
 	}
+
+	#[test]
+	fn conflicts() {
+		let time = |hour| Time::new(hour, 0).unwrap();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Wednesday, time(9), time(11));
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_time(Day::Wednesday, time(10), time(12));
+		let mut art = Course::new("Art", "Kahlo", "A1");
+		art.add_time(Day::Wednesday, time(11), time(12));
+		art.add_time(Day::Thursday, time(9), time(10));
+
+		let mut application = Application::default();
+		application.add_course(art);
+		application.add_course(math);
+		application.add_course(science);
+
+		// 2020-01-08 is a Wednesday
+		// Math clashes with Science, and Science with Art, but Art starts right as Math ends
+		let conflicts = application.conflicts_on(date(2020, 1, 8));
+		assert_eq!(conflicts.len(), 2);
+		assert_eq!((conflicts[0].first, conflicts[0].second), (1, 2));
+		assert_eq!((conflicts[0].start, conflicts[0].end), (time(10), time(11)));
+		assert_eq!((conflicts[1].first, conflicts[1].second), (2, 0));
+		assert!(application.conflicts_on(date(2020, 1, 9)).is_empty());
+	}
+
+	#[test]
+	fn move_lesson() {
+		let time = |hour| Time::new(hour, 0).unwrap();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Wednesday, time(9), time(10));
+		math.add_time(Day::Thursday, time(13), time(14));
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_time(Day::Thursday, time(9), time(11));
+
+		let mut application = Application {
+			timezone: Some(Zone::try_from("UTC".to_string()).unwrap()),
+			..Application::default()
+		};
+		application.add_course(math);
+		application.add_course(science);
+
+		// Moving Wednesday's lesson onto Thursday (2020-01-09) clashes with Science, and it's the same as the other lesson
+		let thursday = date(2020, 1, 9);
+		let conflicts = application
+			.move_lesson(0, 0, thursday, time(10), time(12))
+			.unwrap();
+		assert_eq!(conflicts.len(), 1);
+		assert_eq!((conflicts[0].start, conflicts[0].end), (time(10), time(11)));
+		assert_eq!(
+			application.courses[0].times,
+			vec![
+				(Day::Thursday, time(13), time(14)),
+				(Day::Thursday, time(10), time(12))
+			]
+		);
+
+		// Stretching it over the other lesson merges them into one
+		let half_past = Time::new(13, 30).unwrap();
+		let conflicts = application
+			.move_lesson(0, 1, thursday, time(11), half_past)
+			.unwrap();
+		assert!(conflicts.is_empty());
+		assert_eq!(
+			application.courses[0].times,
+			vec![(Day::Thursday, time(11), time(14))]
+		);
+
+		// It's one edit each time, so it can be undone
+		application.undo();
+		assert_eq!(application.courses[0].times.len(), 2);
+
+		// Tokyo is 9 hours ahead, so 20:00 in UTC is 05:00 the next day there, and the lesson moves a day on
+		application.courses[1].timezone = Some(Zone::try_from("Asia/Tokyo".to_string()).unwrap());
+		application
+			.move_lesson(1, 0, thursday, time(20), time(21))
+			.unwrap();
+		assert_eq!(
+			application.courses[1].times,
+			vec![(Day::Friday, time(5), time(6))]
+		);
+		assert_eq!(
+			application.courses[1].lessons_on(thursday, application.timezone),
+			vec![(time(20), time(21))]
+		);
+
+		// But 14:00 to 16:00 in UTC would be 23:00 to 01:00 there, which can't be stored
+		assert!(application
+			.move_lesson(1, 0, thursday, time(14), time(16))
+			.is_err());
+	}
+
+	#[test]
+	fn legacy_format() {
+		// What the very first version saved for one course with an essay (whose "Plan" step is done),
+		// a quiz that was ticked off using the old dummy step, and one holiday
+		let buf: Vec<u8> = vec![
+			1, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 77, 97, 116, 104, 6, 0, 0, 0, 0, 0, 0,
+			0, 87, 105, 108, 108, 105, 115, 3, 0, 0, 0, 0, 0, 0, 0, 83, 51, 55, 1, 0, 0, 0, 0, 0,
+			0, 0, 1, 0, 0, 0, 9, 0, 10, 30, 2, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 69,
+			115, 115, 97, 121, 12, 0, 0, 0, 0, 0, 0, 0, 79, 110, 32, 102, 114, 97, 99, 116, 105,
+			111, 110, 115, 228, 7, 3, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 67,
+			111, 109, 112, 108, 101, 116, 101, 100, 63, 1, 4, 0, 0, 0, 0, 0, 0, 0, 80, 108, 97,
+			110, 4, 0, 0, 0, 0, 0, 0, 0, 81, 117, 105, 122, 0, 0, 0, 0, 0, 0, 0, 0, 228, 7, 3, 9,
+			1, 0, 0, 0, 0, 0, 0, 0, 1, 10, 0, 0, 0, 0, 0, 0, 0, 67, 111, 109, 112, 108, 101, 116,
+			101, 100, 63, 1, 0, 0, 0, 0, 0, 0, 0, 228, 7, 4, 6, 228, 7, 4, 17,
+		];
+		let application = Application::deserialize(&buf).unwrap();
+
+		assert_eq!(application.courses.len(), 1);
+		let math = &application.courses[0];
+		assert_eq!(
+			(
+				math.name.as_str(),
+				math.teacher.as_str(),
+				math.room.as_str()
+			),
+			("Math", "Willis", "S37")
+		);
+		assert_eq!(
+			math.times,
+			vec![(
+				Day::Monday,
+				Time::new(9, 0).unwrap(),
+				Time::new(10, 30).unwrap()
+			)]
+		);
+		assert_eq!(math.credits, 1.0);
+
+		// The dummy step is gone, and the quiz is done because its dummy step was ticked
+		let essay = &math.tasks[0];
+		assert_eq!(
+			(essay.desc.as_str(), essay.due),
+			("On fractions", date(2020, 3, 2))
+		);
+		assert_eq!(essay.steps.len(), 1);
+		assert_eq!(essay.steps[0].desc, "Plan");
+		assert!(essay.is_complete());
+		let quiz = &math.tasks[1];
+		assert!(quiz.steps.is_empty());
+		assert!(quiz.is_complete());
+		assert_ne!(essay.id, quiz.id);
+
+		assert_eq!(
+			application.holidays,
+			vec![(date(2020, 4, 6), date(2020, 4, 17))]
+		);
+
+		// Saving it again writes the new format, which reads back the same
+		let saved = Application::deserialize(&application.serialize()).unwrap();
+		assert_eq!(saved.courses[0].tasks[1].id, quiz.id);
+
+		// But a file from a newer version than this one isn't guessed at
+		let mut newer = application.serialize();
+		newer[4] += 1;
+		assert!(Application::deserialize(&newer).is_err());
+	}
+
+	#[test]
+	fn unsaved_changes() {
+		let location = std::env::temp_dir().join("scheduler-unsaved-changes-test.bin");
+		let location = location.to_str().unwrap();
+		let mut application = Application::default();
+		assert!(!application.is_dirty());
+		assert!(!application.should_autosave(Duration::from_secs(0)));
+
+		application.new_course();
+		assert!(application.is_dirty());
+		assert!(application.should_autosave(Duration::from_secs(0)));
+		assert!(!application.should_autosave(Duration::from_secs(60)));
+
+		application.save(location);
+		assert!(!application.is_dirty());
+		assert!(!Application::load(location).unwrap().is_dirty());
+
+		// Undoing is a change too
+		application.undo();
+		assert!(application.is_dirty());
+
+		std::fs::remove_file(location).unwrap();
+	}
+
+	#[test]
+	fn unreadable_file() {
+		let location = std::env::temp_dir().join("scheduler-unreadable-file-test.bin");
+		let location = location.to_str().unwrap();
+		let backup = format!("{}.bak", location);
+
+		// Nothing saved yet is fine, it's just a new application
+		let _ = std::fs::remove_file(location);
+		assert!(Application::load_or_default(location)
+			.unwrap()
+			.courses
+			.is_empty());
+
+		// But something that can't be read is an error, and it's kept instead of being overwritten later
+		std::fs::write(location, b"SCHD\x01\x00\x00\x00 half a file").unwrap();
+		assert!(Application::load_or_default(location).is_err());
+		assert_eq!(
+			std::fs::read(&backup).unwrap(),
+			std::fs::read(location).unwrap()
+		);
+
+		std::fs::remove_file(location).unwrap();
+		std::fs::remove_file(&backup).unwrap();
+	}
 }
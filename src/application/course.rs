@@ -2,7 +2,7 @@ use super::*;
 use task::Task;
 
 // See application.rs to understand #[derive()]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 /// This struct contains details for a course.
 pub struct Course {
 	/// The name, teacher, and room of this Course as Strings.
@@ -13,6 +13,19 @@ pub struct Course {
 	pub times: Vec<(Day, Time, Time)>,
 	/// A list of all the tasks for that course.
 	pub tasks: Vec<Task>,
+	/// The time zone the lesson times are written in, if it's different to the application's.
+	/// Useful for online lectures at a university somewhere else in the world.
+	pub timezone: Option<Zone>,
+	/// Free-form labels, like "science" or "online".
+	pub tags: Vec<String>,
+	/// How many minutes before each lesson to remind about it, or `None` for the application's setting.
+	pub reminder_minutes: Option<u32>,
+	/// Everything graded in the course, like tests and homework. See grade.rs.
+	pub assessments: Vec<Assessment>,
+	/// How much each category of assessment counts, like ("Exams", 50.0). Without any, each assessment counts by its own weight.
+	pub category_weights: Vec<(String, f64)>,
+	/// How much the course counts towards the GPA.
+	pub credits: f64,
 }
 
 impl Course {
@@ -25,6 +38,12 @@ impl Course {
 			room: room.into(),
 			times: Vec::new(),
 			tasks: Vec::new(),
+			timezone: None,
+			tags: Vec::new(),
+			reminder_minutes: None,
+			assessments: Vec::new(),
+			category_weights: Vec::new(),
+			credits: 1.0,
 		}
 	}
 
@@ -125,10 +144,58 @@ impl Course {
 		self.times.remove(index);
 	}
 
+	/// Checks if the course has a tag, ignoring case.
+	pub fn has_tag(&self, tag: &str) -> bool {
+		task::has_tag(&self.tags, tag)
+	}
+
 	/// Checks if there is a lesson on a specific day
 	pub fn is_on_day(&self, expected: Day) -> bool {
 		self.times.iter().any(|&(day, _, _)| day == expected)
 	}
+
+	/// Returns the (start, end) of every lesson that happens on `date`, as seen from `zone`.
+	/// If the course has no time zone of its own, its times are taken to already be in `zone`.
+	pub fn lessons_on(&self, date: Date, zone: Option<Zone>) -> Vec<(Time, Time)> {
+		let course_zone = self.timezone.or(zone);
+		let mut lessons = Vec::new();
+
+		// A lesson on Monday morning in Tokyo is on Sunday evening in London
+		// So we have to check the days either side of `date` in the course's zone too
+		// (unless it's the first or last date we can hold, where there's nothing on the other side)
+		for course_date in [date.offset(-1), Ok(date), date.offset(1)].iter().flatten() {
+			for &(day, start, end) in &self.times {
+				if course_date.weekday() != day {
+					continue;
+				}
+
+				let (start_date, start) = Zone::convert(*course_date, start, course_zone, zone);
+				let (_, end) = Zone::convert(*course_date, end, course_zone, zone);
+				if start_date == date {
+					lessons.push((start, end));
+				}
+			}
+		}
+
+		lessons.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		lessons
+	}
+
+	/// Returns the index in `times` of the lesson that starts at `start` on `date`, as seen from `zone`.
+	/// This is the way back from a lesson in `lessons_on` to the time it came from.
+	pub fn time_index_on(&self, date: Date, start: Time, zone: Option<Zone>) -> Option<usize> {
+		let course_zone = self.timezone.or(zone);
+		for course_date in [date.offset(-1), Ok(date), date.offset(1)].iter().flatten() {
+			for (index, &(day, time_start, _)) in self.times.iter().enumerate() {
+				if course_date.weekday() == day
+					&& Zone::convert(*course_date, time_start, course_zone, zone) == (date, start)
+				{
+					return Some(index);
+				}
+			}
+		}
+		None
+	}
 }
 
 impl Default for Course {
@@ -146,6 +213,7 @@ impl Default for Course {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::convert::TryFrom;
 
 	fn time(hour: u8, minute: u8) -> Time {
 		Time::new(hour, minute).unwrap()
@@ -169,6 +237,45 @@ mod tests {
 		assert!(!math.is_on_day(Day::Monday));
 	}
 
+	#[test]
+	fn lessons_on() {
+		let utc = Some(Zone::try_from("UTC".to_string()).unwrap());
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.timezone = Some(Zone::try_from("Asia/Tokyo".to_string()).unwrap());
+		math.add_time(Day::Monday, time(8, 0), time(10, 0));
+
+		// 2020-01-05 is a Sunday, 2020-01-06 is a Monday
+		let sunday = Date::new(2020, 1, 5).unwrap();
+		let monday = Date::new(2020, 1, 6).unwrap();
+
+		assert_eq!(math.lessons_on(sunday, utc), vec![(time(23, 0), time(1, 0))]);
+		assert!(math.lessons_on(monday, utc).is_empty());
+
+		// Without a zone of its own, the course is in the application's zone
+		math.timezone = None;
+		assert_eq!(math.lessons_on(monday, utc), vec![(time(8, 0), time(10, 0))]);
+	}
+
+	#[test]
+	fn time_index_on() {
+		let utc = Some(Zone::try_from("UTC".to_string()).unwrap());
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Monday, time(8, 0), time(10, 0));
+		math.add_time(Day::Tuesday, time(8, 0), time(10, 0));
+
+		// 2020-01-06 is a Monday
+		let monday = Date::new(2020, 1, 6).unwrap();
+		assert_eq!(math.time_index_on(monday + 1, time(8, 0), utc), Some(1));
+		assert_eq!(math.time_index_on(monday, time(9, 0), utc), None);
+
+		// Monday morning in Tokyo is Sunday night in UTC
+		math.timezone = Some(Zone::try_from("Asia/Tokyo".to_string()).unwrap());
+		assert_eq!(
+			math.time_index_on(monday.offset(-1).unwrap(), time(23, 0), utc),
+			Some(0)
+		);
+	}
+
 	#[test]
 	fn add_time() {
 		let mut math = Course::new("Math", "Willis", "S37");
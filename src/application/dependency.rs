@@ -0,0 +1,312 @@
+use super::*;
+
+// A task can depend on other tasks, like a final submission depending on its draft, even in other courses
+// Tasks point at each other by ID (see `Task::id`), since indexes change whenever a task is removed
+// If a task that others depend on is removed, its ID is just ignored, and undoing the removal brings it back
+
+impl Application {
+	/// Returns where the task with `id` is, as (course index, task index), if it's still around.
+	pub fn find_task(&self, id: u64) -> Option<(usize, usize)> {
+		for (course_index, course) in self.courses.iter().enumerate() {
+			if let Some(task_index) = course.tasks.iter().position(|task| task.id == id) {
+				return Some((course_index, task_index));
+			}
+		}
+		None
+	}
+
+	/// Returns the tasks that the task at (course index, task index) depends on, as (course index, task index).
+	pub fn prerequisites(&self, course_index: usize, task_index: usize) -> Vec<(usize, usize)> {
+		self.courses[course_index].tasks[task_index]
+			.depends_on
+			.iter()
+			.filter_map(|&id| self.find_task(id))
+			.collect()
+	}
+
+	/// Returns the tasks that the task at (course index, task index) depends on, which still aren't complete.
+	pub fn blockers(&self, course_index: usize, task_index: usize) -> Vec<(usize, usize)> {
+		self.prerequisites(course_index, task_index)
+			.into_iter()
+			.filter(|&(c, t)| !self.courses[c].tasks[t].is_complete())
+			.collect()
+	}
+
+	/// Returns whether the task at (course index, task index) can't be started yet, because it's not complete
+	/// and something it depends on isn't either.
+	pub fn is_blocked(&self, course_index: usize, task_index: usize) -> bool {
+		!self.courses[course_index].tasks[task_index].is_complete()
+			&& !self.blockers(course_index, task_index).is_empty()
+	}
+
+	/// Returns the tasks that depend on the task at (course index, task index).
+	pub fn dependents(&self, course_index: usize, task_index: usize) -> Vec<(usize, usize)> {
+		let id = self.courses[course_index].tasks[task_index].id;
+		self.tasks(&TaskQuery::new().sort_by(TaskSort::Course))
+			.iter()
+			.filter(|entry| entry.task.depends_on.contains(&id))
+			.map(|entry| (entry.course_index, entry.task_index))
+			.collect()
+	}
+
+	/// Like `Task::status`, but a task that can't be started yet is "Blocked", unless it's already overdue.
+	pub fn task_status(&self, course_index: usize, task_index: usize, today: Date) -> &str {
+		let task = &self.courses[course_index].tasks[task_index];
+		if !task.is_overdue(today) && self.is_blocked(course_index, task_index) {
+			"Blocked"
+		} else {
+			task.status(today)
+		}
+	}
+
+	/// Makes the task at `task` depend on the task at `prerequisite`, both (course index, task index),
+	/// as one edit that can be undone.
+	/// A task can't depend on itself, or on a task that depends on it, so those are errors.
+	pub fn add_dependency(
+		&mut self,
+		task: (usize, usize),
+		prerequisite: (usize, usize),
+	) -> Result<(), String> {
+		let id = self.courses[task.0].tasks[task.1].id;
+		let prerequisite_id = self.courses[prerequisite.0].tasks[prerequisite.1].id;
+		let name = |(c, t): (usize, usize)| self.courses[c].tasks[t].name.clone();
+
+		if id == prerequisite_id {
+			return Err(format!("'{}' can't depend on itself.", name(task)));
+		}
+		if self.courses[task.0].tasks[task.1]
+			.depends_on
+			.contains(&prerequisite_id)
+		{
+			return Err(format!(
+				"'{}' already depends on '{}'.",
+				name(task),
+				name(prerequisite)
+			));
+		}
+		if self.depends_on(prerequisite_id, id) {
+			return Err(format!(
+				"'{}' already depends on '{}', so it can't be the other way around too.",
+				name(prerequisite),
+				name(task)
+			));
+		}
+
+		let label = format!("Make '{}' depend on '{}'", name(task), name(prerequisite));
+		self.edit_task(task.0, task.1, label, |task| {
+			task.depends_on.push(prerequisite_id)
+		});
+		Ok(())
+	}
+
+	/// Makes the task at `task` not depend on the task at `prerequisite` anymore, as one edit that can be undone.
+	pub fn rm_dependency(&mut self, task: (usize, usize), prerequisite: (usize, usize)) {
+		let prerequisite_id = self.courses[prerequisite.0].tasks[prerequisite.1].id;
+		let label = format!(
+			"Stop '{}' depending on '{}'",
+			self.courses[task.0].tasks[task.1].name,
+			self.courses[prerequisite.0].tasks[prerequisite.1].name
+		);
+		self.edit_task(task.0, task.1, label, |task| {
+			task.depends_on.retain(|&id| id != prerequisite_id)
+		});
+	}
+
+	/// Returns whether the task with ID `id` depends on the task with ID `other`, directly or through other tasks.
+	pub fn depends_on(&self, id: u64, other: u64) -> bool {
+		// Every task on the way is only looked at once, so a loop that's already there can't go on forever
+		let mut seen = vec![id];
+		let mut next = vec![id];
+		while let Some(id) = next.pop() {
+			let (c, t) = match self.find_task(id) {
+				Some(position) => position,
+				None => continue,
+			};
+			for &prerequisite in &self.courses[c].tasks[t].depends_on {
+				if prerequisite == other {
+					return true;
+				}
+				if !seen.contains(&prerequisite) {
+					seen.push(prerequisite);
+					next.push(prerequisite);
+				}
+			}
+		}
+		false
+	}
+
+	/// Returns some tasks that depend on each other in a loop, as (course index, task index), if there are any.
+	/// `add_dependency` never makes loops, but dependencies can be changed directly (like over the server).
+	pub fn dependency_loop(&self) -> Option<Vec<(usize, usize)>> {
+		let mut left_over = self.task_positions(|_| true);
+		self.dependency_order(&mut left_over);
+		// Whatever couldn't be put in order depends on something in a loop, so following its
+		// dependencies (which are also left over) long enough always goes round the loop
+		let first = *left_over.first()?;
+		let mut path = vec![first];
+		loop {
+			let (c, t) = *path.last().unwrap();
+			let next = self
+				.prerequisites(c, t)
+				.into_iter()
+				.find(|prerequisite| left_over.contains(prerequisite))?;
+			if let Some(start) = path.iter().position(|&task| task == next) {
+				return Some(path.split_off(start));
+			}
+			path.push(next);
+		}
+	}
+
+	/// Returns every task that isn't complete, in an order they can be done in: each task comes after
+	/// everything it depends on, and otherwise the soonest due (then most important) come first.
+	/// Tasks stuck in a loop of dependencies can't go in order, so they come at the end.
+	pub fn work_order(&self) -> Vec<(usize, usize)> {
+		let mut left_over = self.task_positions(|task| !task.is_complete());
+		let mut order = self.dependency_order(&mut left_over);
+		order.append(&mut left_over);
+		order
+	}
+
+	/// Returns every task that `include` says to, as (course index, task index), soonest due first.
+	fn task_positions<F: Fn(&Task) -> bool>(&self, include: F) -> Vec<(usize, usize)> {
+		self.tasks(&TaskQuery::new())
+			.iter()
+			.filter(|entry| include(entry.task))
+			.map(|entry| (entry.course_index, entry.task_index))
+			.collect()
+	}
+
+	/// Takes the tasks out of `waiting` in order, as in `work_order`, and returns them.
+	/// The ones left in `waiting` couldn't be put in order because of a loop.
+	/// Dependencies on tasks that aren't waiting don't count, so they don't hold anything up.
+	fn dependency_order(&self, waiting: &mut Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+		// Since the tasks start out sorted, picking the first one that's ready is picking the best one
+		let mut order = Vec::new();
+		// This is the simple way of doing it, looking through everything for each task, but there aren't
+		// enough tasks for a cleverer way to matter
+		while let Some(ready) = waiting.iter().position(|&(c, t)| {
+			self.prerequisites(c, t)
+				.iter()
+				.all(|prerequisite| !waiting.contains(prerequisite))
+		}) {
+			order.push(waiting.remove(ready));
+		}
+		order
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn date(day: u8) -> Date {
+		Date::new(2020, 1, day).unwrap()
+	}
+
+	// Math: Draft (due 10th), Final (due 20th)
+	// Science: Notes (due 5th), Report (due 15th)
+	fn application() -> Application {
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_task("Draft", "", date(10));
+		math.add_task("Final", "", date(20));
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_task("Notes", "", date(5));
+		science.add_task("Report", "", date(15));
+
+		let mut application = Application::default();
+		application.add_course(math);
+		application.add_course(science);
+		application
+	}
+
+	fn names(application: &Application, tasks: &[(usize, usize)]) -> Vec<String> {
+		tasks
+			.iter()
+			.map(|&(c, t)| application.courses[c].tasks[t].name.clone())
+			.collect()
+	}
+
+	#[test]
+	fn ids() {
+		let application = application();
+		assert_ne!(
+			application.courses[0].tasks[0].id,
+			application.courses[0].tasks[1].id
+		);
+		let id = application.courses[1].tasks[1].id;
+		assert_eq!(application.find_task(id), Some((1, 1)));
+	}
+
+	#[test]
+	fn blocking() {
+		let mut application = application();
+
+		// The final needs the draft, and the draft needs the science notes
+		application.add_dependency((0, 1), (0, 0)).unwrap();
+		application.add_dependency((0, 0), (1, 0)).unwrap();
+		assert!(application.is_blocked(0, 1));
+		assert_eq!(application.task_status(0, 1, date(1)), "Blocked");
+		assert_eq!(application.dependents(1, 0), vec![(0, 0)]);
+
+		application.complete_tasks(&[(1, 0)], true);
+		assert!(!application.is_blocked(0, 0));
+		assert!(application.is_blocked(0, 1));
+		application.complete_tasks(&[(0, 0)], true);
+		assert_eq!(application.task_status(0, 1, date(1)), "Not Done");
+
+		// Undoing brings the dependency back along with the task, since it's only pointed at by ID
+		application.rm_dependency((0, 1), (0, 0));
+		assert!(application.prerequisites(0, 1).is_empty());
+		application.undo();
+		assert_eq!(application.prerequisites(0, 1), vec![(0, 0)]);
+	}
+
+	#[test]
+	fn loops() {
+		let mut application = application();
+		application.add_dependency((0, 1), (0, 0)).unwrap();
+		application.add_dependency((0, 0), (1, 0)).unwrap();
+
+		assert!(application.add_dependency((0, 0), (0, 0)).is_err());
+		assert!(application.add_dependency((0, 1), (0, 0)).is_err());
+		// Notes -> Final would go round: Final needs Draft, which needs Notes
+		assert!(application.add_dependency((1, 0), (0, 1)).is_err());
+		assert!(application.dependency_loop().is_none());
+
+		// A loop made directly is still found
+		let id = application.courses[0].tasks[1].id;
+		application.edit_task(1, 0, "Loop", |task| task.depends_on.push(id));
+		let mut found = application.dependency_loop().unwrap();
+		found.sort();
+		assert_eq!(found, vec![(0, 0), (0, 1), (1, 0)]);
+	}
+
+	#[test]
+	fn work_order() {
+		let mut application = application();
+		assert_eq!(
+			names(&application, &application.work_order()),
+			vec!["Notes", "Draft", "Report", "Final"]
+		);
+
+		// The report needs the final, so it has to wait, even though it's due sooner
+		application.add_dependency((1, 1), (0, 1)).unwrap();
+		assert_eq!(
+			names(&application, &application.work_order()),
+			vec!["Notes", "Draft", "Final", "Report"]
+		);
+
+		// Complete tasks are left out, and don't hold anything up
+		application.complete_tasks(&[(0, 1)], true);
+		assert_eq!(
+			names(&application, &application.work_order()),
+			vec!["Notes", "Draft", "Report"]
+		);
+	}
+}
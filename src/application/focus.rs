@@ -0,0 +1,525 @@
+use super::*;
+use std::convert::TryFrom;
+
+// A focus session is a pomodoro: a few rounds of work on one task, with short breaks between them and a long one at the end
+// Like the timer, it's just the moment it started (saved with the application), and everything else is worked out from that
+// So it keeps going between runs of the CLI, and the daemon can tell when it moves on without ever changing anything
+// Each round of work that's finished gets logged against the task's time, and a round that's cut short doesn't count
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// How long the parts of a focus session last.
+pub struct FocusSettings {
+	/// How many minutes each round of work lasts.
+	pub work_minutes: u32,
+	/// How many minutes the breaks between rounds last.
+	pub short_break_minutes: u32,
+	/// How many minutes the break at the end of the session lasts.
+	pub long_break_minutes: u32,
+	/// How many rounds of work there are in a session.
+	pub rounds: u32,
+}
+
+impl Default for FocusSettings {
+	fn default() -> Self {
+		Self {
+			work_minutes: 25,
+			short_break_minutes: 5,
+			long_break_minutes: 15,
+			rounds: 4,
+		}
+	}
+}
+
+/// The longest any part of a focus session can be set to last, which is a day.
+pub const MAX_FOCUS_MINUTES: u32 = 24 * 60;
+/// The most rounds a focus session can be set to have.
+pub const MAX_FOCUS_ROUNDS: u32 = 100;
+
+impl FocusSettings {
+	/// Returns how many minutes `phase` lasts.
+	/// Nothing lasts less than a minute, so a session always moves along.
+	pub fn minutes(&self, phase: Phase) -> u32 {
+		let minutes = match phase {
+			Phase::Work => self.work_minutes,
+			Phase::ShortBreak => self.short_break_minutes,
+			Phase::LongBreak => self.long_break_minutes,
+		};
+		minutes.max(1)
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// One part of a focus session.
+pub enum Phase {
+	Work,
+	ShortBreak,
+	LongBreak,
+}
+
+impl Phase {
+	pub fn as_str(&self) -> &str {
+		match self {
+			Self::Work => "Work",
+			Self::ShortBreak => "Short break",
+			Self::LongBreak => "Long break",
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// A focus session on a task, which might still be going or might be over.
+pub struct FocusSession {
+	/// The task being worked on, by its ID, so other tasks can be removed in the meantime.
+	pub task_id: u64,
+	/// When the first round of work started.
+	pub started: (Date, Time),
+	/// The settings when the session started, so changing them doesn't move a session that's already going.
+	pub settings: FocusSettings,
+	/// How many rounds of work have been logged against the task so far.
+	pub logged: u32,
+}
+
+impl FocusSession {
+	pub fn new(task_id: u64, started: (Date, Time), settings: FocusSettings) -> Self {
+		Self {
+			task_id,
+			started,
+			settings,
+			logged: 0,
+		}
+	}
+
+	/// Returns every phase in the session in order, with how many minutes after the start it starts.
+	pub fn phases(&self) -> Vec<(u32, Phase)> {
+		let rounds = self.settings.rounds.max(1);
+		let mut phases = Vec::new();
+		let mut start: u32 = 0;
+		for round in 1..=rounds {
+			let rest = if round == rounds {
+				Phase::LongBreak
+			} else {
+				Phase::ShortBreak
+			};
+			for phase in [Phase::Work, rest].iter() {
+				phases.push((start, *phase));
+				// Settings are kept within limits when they're typed in, but this can't be allowed to overflow either way
+				start = start.saturating_add(self.settings.minutes(*phase));
+			}
+		}
+		phases
+	}
+
+	/// Returns how many minutes the whole session lasts, including the long break at the end.
+	pub fn length(&self) -> u32 {
+		self.phases()
+			.last()
+			.map(|(start, phase)| start.saturating_add(self.settings.minutes(*phase)))
+			.unwrap_or(0)
+	}
+
+	/// Returns the date and time `minutes` after the session started.
+	pub fn at(&self, minutes: u32) -> (Date, Time) {
+		let (date, time) = self.started;
+		// Only a session started in the last minutes of the last date we can hold could go past it
+		time.offset(date, minutes as i64).unwrap_or(self.started)
+	}
+
+	/// Returns how many minutes into the session `now` is, which is never negative.
+	fn elapsed(&self, now: (Date, Time)) -> u32 {
+		let (date, time) = self.started;
+		u32::try_from(time.minutes_until(date, now.1, now.0).max(0)).unwrap_or(u32::MAX)
+	}
+
+	/// Returns what the session is up to at `now`: the phase, which round it's in (from 1), and when that phase ends.
+	/// Returns `None` once the session is over.
+	pub fn phase_at(&self, now: (Date, Time)) -> Option<(Phase, u32, (Date, Time))> {
+		let elapsed = self.elapsed(now);
+		let mut round = 0;
+		for (start, phase) in self.phases() {
+			if phase == Phase::Work {
+				round += 1;
+			}
+			let end = start.saturating_add(self.settings.minutes(phase));
+			if elapsed < end {
+				return Some((phase, round, self.at(end)));
+			}
+		}
+		None
+	}
+
+	/// Returns how many rounds of work were finished by `now`.
+	pub fn rounds_done(&self, now: (Date, Time)) -> u32 {
+		let elapsed = self.elapsed(now);
+		self.phases()
+			.into_iter()
+			.filter(|(start, phase)| {
+				*phase == Phase::Work
+					&& start.saturating_add(self.settings.minutes(*phase)) <= elapsed
+			})
+			.count() as u32
+	}
+
+	/// Returns whether the session, including its long break, is over at `now`.
+	pub fn is_over(&self, now: (Date, Time)) -> bool {
+		self.elapsed(now) >= self.length()
+	}
+}
+
+impl Application {
+	/// Returns (course index, task index) of the task the focus session is on, if there's a session and it's still around.
+	pub fn focus_task(&self) -> Option<(usize, usize)> {
+		self.focus_session
+			.and_then(|session| self.find_task(session.task_id))
+	}
+
+	/// Returns the name of the task the focus session is on, for labels and notifications.
+	fn focus_task_name(&self) -> String {
+		match self.focus_task() {
+			Some((course_index, task_index)) => {
+				self.courses[course_index].tasks[task_index].name.clone()
+			}
+			None => "a removed task".to_string(),
+		}
+	}
+
+	/// Starts a focus session on the task at (course index, task index) at `now`, as an edit that can be undone.
+	/// A session that's already going is stopped first (see `stop_focus`), and so is a running timer,
+	/// so the same time isn't counted twice.
+	pub fn start_focus(&mut self, course_index: usize, task_index: usize, now: (Date, Time)) {
+		self.stop_focus(now);
+
+		let task = &self.courses[course_index].tasks[task_index];
+		let label = format!("Start focus session on '{}'", task.name);
+		let session = FocusSession::new(task.id, now, self.focus);
+		let mut commands = Vec::new();
+		if let Some((running_course, running_task)) = self.running_timer() {
+			let mut course = self.courses[running_course].clone();
+			course.tasks[running_task].stop_timer(now);
+			commands.push(Command::SetCourse(running_course, course));
+		}
+		commands.push(Command::SetFocusSession(Some(session)));
+		self.apply(label, Command::Batch(commands));
+	}
+
+	/// Works out the commands that log every round of the focus session that was finished by `now`,
+	/// and haven't been logged yet. The session is ended as well if `stop` is set, or if it's over.
+	/// Returns the commands with how many minutes they log, or `None` if there's nothing to do.
+	fn focus_commands(&self, now: (Date, Time), stop: bool) -> Option<(Vec<Command>, u32)> {
+		let session = self.focus_session?;
+		let rounds = session.rounds_done(now);
+		let mut commands = Vec::new();
+		let mut minutes: u32 = 0;
+
+		if rounds > session.logged {
+			// If the task was removed, there's nowhere to log the time, but the rounds still count as logged
+			if let Some((course_index, task_index)) = self.focus_task() {
+				let mut course = self.courses[course_index].clone();
+				let work_minutes = session.settings.minutes(Phase::Work);
+				let starts = session
+					.phases()
+					.into_iter()
+					.filter(|(_, phase)| *phase == Phase::Work)
+					.map(|(start, _)| start)
+					.skip(session.logged as usize)
+					.take((rounds - session.logged) as usize);
+				for start in starts {
+					course.tasks[task_index].time_spent.push(TimeEntry {
+						date: session.at(start).0,
+						minutes: work_minutes,
+					});
+					minutes = minutes.saturating_add(work_minutes);
+				}
+				commands.push(Command::SetCourse(course_index, course));
+			}
+		}
+
+		if stop || session.is_over(now) {
+			commands.push(Command::SetFocusSession(None));
+		} else if rounds > session.logged {
+			commands.push(Command::SetFocusSession(Some(FocusSession {
+				logged: rounds,
+				..session
+			})));
+		}
+
+		if commands.is_empty() {
+			None
+		} else {
+			Some((commands, minutes))
+		}
+	}
+
+	/// Logs every round of the focus session finished by `now` against its task, as an edit that can be undone.
+	/// A session that's over is ended too. Returns how many minutes were logged.
+	/// Nothing changes if there's nothing new to log, so this is fine to call every so often.
+	pub fn log_focus(&mut self, now: (Date, Time)) -> u32 {
+		match self.focus_commands(now, false) {
+			Some((commands, minutes)) => {
+				let label = format!("Log focus time on '{}'", self.focus_task_name());
+				self.apply(label, Command::Batch(commands));
+				minutes
+			}
+			None => 0,
+		}
+	}
+
+	/// Ends the focus session at `now`, as an edit that can be undone.
+	/// The rounds that were finished are logged, but one that's cut short isn't.
+	/// Returns how many minutes were logged, or `None` if there wasn't a session.
+	pub fn stop_focus(&mut self, now: (Date, Time)) -> Option<u32> {
+		let (commands, minutes) = self.focus_commands(now, true)?;
+		let label = format!("Stop focus session on '{}'", self.focus_task_name());
+		self.apply(label, Command::Batch(commands));
+		Some(minutes)
+	}
+
+	/// Returns a reminder for every time the focus session moves on to its next phase after `from`,
+	/// up to and including `to`, and one for when it's over.
+	pub fn focus_reminders_between(&self, from: (Date, Time), to: (Date, Time)) -> Vec<Reminder> {
+		let (session, (course_index, task_index)) = match (self.focus_session, self.focus_task()) {
+			(Some(session), Some(task)) => (session, task),
+			_ => return Vec::new(),
+		};
+		let name = &self.courses[course_index].tasks[task_index].name;
+		let settings = session.settings;
+		let rounds = settings.rounds.max(1);
+		let reminder = |(date, time), title, body| Reminder {
+			date,
+			time,
+			course_index,
+			task_index: Some(task_index),
+			focus: true,
+			about: "focus".to_string(),
+			title,
+			body,
+		};
+
+		let mut reminders = Vec::new();
+		let mut round = 0;
+		for (start, phase) in session.phases() {
+			let end = session.at(start.saturating_add(settings.minutes(phase)));
+			let (title, body) = match phase {
+				Phase::Work => {
+					round += 1;
+					(
+						format!("Back to {}", name),
+						format!(
+							"Round {} of {}, until {}",
+							round,
+							rounds,
+							self.locale.format_time(end.1)
+						),
+					)
+				}
+				Phase::ShortBreak | Phase::LongBreak => (
+					format!("Time for a {} minute break", settings.minutes(phase)),
+					format!(
+						"{} of {} rounds of {} done, back at {}",
+						round,
+						rounds,
+						name,
+						self.locale.format_time(end.1)
+					),
+				),
+			};
+			// The first round starts when the session does, which nobody needs telling about
+			if start > 0 {
+				reminders.push(reminder(session.at(start), title, body));
+			}
+		}
+		reminders.push(reminder(
+			session.at(session.length()),
+			format!("Focus session on {} is over", name),
+			format!(
+				"{} rounds, {} of work",
+				rounds,
+				format_minutes(rounds.saturating_mul(settings.minutes(Phase::Work)))
+			),
+		));
+
+		reminders.retain(|reminder| {
+			let when = (reminder.date, reminder.time);
+			from < when && when <= to
+		});
+		reminders
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn date(year: u16, month: u8, day: u8) -> Date {
+		Date::new(year, month, day).unwrap()
+	}
+
+	fn time(hour: u8, minute: u8) -> Time {
+		Time::new(hour, minute).unwrap()
+	}
+
+	// 2 rounds of 25 minutes from 09:00, so work 09:00-09:25, break to 09:30, work to 09:55, long break to 10:10
+	fn application() -> Application {
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_task("Essay", "", date(2020, 1, 10));
+		math.add_task("Worksheet", "", date(2020, 1, 10));
+
+		let mut application = Application::default();
+		application.add_course(math);
+		application.reminders.enabled = false;
+		application.focus.rounds = 2;
+		application.start_focus(0, 0, (date(2020, 1, 8), time(9, 0)));
+		application
+	}
+
+	#[test]
+	fn phases() {
+		let application = application();
+		let session = application.focus_session.unwrap();
+		assert_eq!(
+			session.phases(),
+			vec![
+				(0, Phase::Work),
+				(25, Phase::ShortBreak),
+				(30, Phase::Work),
+				(55, Phase::LongBreak)
+			]
+		);
+		assert_eq!(session.length(), 70);
+
+		let at = |hour, minute| (date(2020, 1, 8), time(hour, minute));
+		assert_eq!(
+			session.phase_at(at(9, 27)),
+			Some((Phase::ShortBreak, 1, at(9, 30)))
+		);
+		assert_eq!(session.phase_at(at(9, 30)).unwrap().1, 2);
+		assert_eq!(session.rounds_done(at(9, 54)), 1);
+		assert_eq!(session.rounds_done(at(9, 55)), 2);
+		assert!(!session.is_over(at(10, 9)));
+		assert!(session.is_over(at(10, 10)));
+		assert_eq!(session.phase_at(at(10, 10)), None);
+	}
+
+	#[test]
+	fn huge_settings() {
+		// Settings this big can't be typed in anymore, but an old file could still have them
+		let settings = FocusSettings {
+			work_minutes: u32::MAX,
+			short_break_minutes: u32::MAX,
+			long_break_minutes: u32::MAX,
+			rounds: 3,
+		};
+		let session = FocusSession::new(0, (date(2020, 1, 8), time(9, 0)), settings);
+		assert_eq!(session.phases()[1], (u32::MAX, Phase::ShortBreak));
+		assert_eq!(session.length(), u32::MAX);
+		let now = (date(2020, 1, 8), time(10, 0));
+		assert_eq!(session.phase_at(now).unwrap().0, Phase::Work);
+		assert_eq!(session.rounds_done(now), 0);
+
+		// The reminders are worked out from the same numbers, down to the summary at the end
+		// The session lasts thousands of years, so that's how far ahead they're looked for
+		let mut application = application();
+		application.focus_session = Some(FocusSession {
+			task_id: application.courses[0].tasks[0].id,
+			..session
+		});
+		let reminders = application.focus_reminders_between(
+			(date(2020, 1, 1), time(0, 0)),
+			(date(60000, 1, 1), time(0, 0)),
+		);
+		assert_eq!(
+			reminders.last().unwrap().body,
+			format!("3 rounds, {} of work", format_minutes(u32::MAX))
+		);
+		assert_eq!(application.log_focus(now), 0);
+	}
+
+	#[test]
+	fn logging() {
+		let mut application = application();
+		let wednesday = date(2020, 1, 8);
+
+		// Halfway through the first round, there's nothing to log yet, and nothing changes
+		let undo_count = application.history.undo_list().len();
+		assert_eq!(application.log_focus((wednesday, time(9, 10))), 0);
+		assert_eq!(application.history.undo_list().len(), undo_count);
+
+		assert_eq!(application.log_focus((wednesday, time(9, 40))), 25);
+		assert_eq!(application.log_focus((wednesday, time(9, 45))), 0);
+		assert_eq!(application.focus_session.unwrap().logged, 1);
+		assert_eq!(application.courses[0].tasks[0].total_minutes(), 25);
+
+		// Once it's over, the second round is logged and the session ends by itself
+		assert_eq!(application.log_focus((wednesday, time(11, 0))), 25);
+		assert_eq!(application.focus_session, None);
+		assert_eq!(application.courses[0].tasks[0].total_minutes(), 50);
+
+		// Undoing brings the session back, and takes the time off again
+		application.undo();
+		assert_eq!(application.focus_session.unwrap().logged, 1);
+		assert_eq!(application.courses[0].tasks[0].total_minutes(), 25);
+	}
+
+	#[test]
+	fn stopping() {
+		let mut application = application();
+		let wednesday = date(2020, 1, 8);
+
+		// The second round is cut short, so only the first one counts
+		assert_eq!(application.stop_focus((wednesday, time(9, 50))), Some(25));
+		assert_eq!(application.focus_session, None);
+		assert_eq!(application.stop_focus((wednesday, time(9, 50))), None);
+
+		// Starting a session stops the timer, so the time isn't counted twice
+		application.start_timer(0, 1, (wednesday, time(10, 0)));
+		application.start_focus(0, 0, (wednesday, time(10, 30)));
+		assert_eq!(application.running_timer(), None);
+		assert_eq!(application.courses[0].tasks[1].total_minutes(), 30);
+		assert_eq!(application.focus_task(), Some((0, 0)));
+
+		// Starting another one ends the first, and logs what it finished
+		application.start_focus(0, 1, (wednesday, time(11, 0)));
+		assert_eq!(application.focus_task(), Some((0, 1)));
+		assert_eq!(application.courses[0].tasks[0].total_minutes(), 50);
+	}
+
+	#[test]
+	fn reminders() {
+		let application = application();
+		let wednesday = date(2020, 1, 8);
+
+		// Reminders are turned off, but these still go off, since the session was started on purpose
+		let reminders =
+			application.reminders_between((wednesday, time(9, 0)), (wednesday, time(12, 0)));
+		let titles: Vec<&str> = reminders
+			.iter()
+			.map(|reminder| reminder.title.as_str())
+			.collect();
+		assert_eq!(
+			titles,
+			vec![
+				"Time for a 5 minute break",
+				"Back to Essay",
+				"Time for a 15 minute break",
+				"Focus session on Essay is over"
+			]
+		);
+		assert_eq!(reminders[0].time, time(9, 25));
+		assert_eq!(
+			reminders[0].body,
+			"1 of 2 rounds of Essay done, back at 09:30"
+		);
+		assert_eq!(reminders[3].time, time(10, 10));
+		assert_eq!(reminders[3].id(), "focus");
+
+		assert!(application
+			.focus_reminders_between((wednesday, time(9, 25)), (wednesday, time(9, 29)))
+			.is_empty());
+	}
+}
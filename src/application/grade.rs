@@ -0,0 +1,486 @@
+use super::*;
+
+// Grades are kept as a score out of a maximum, like 45 out of 50, and everything else is worked out from them
+// An assessment's weight is how much it counts compared to the others in its category
+// If a course has category weights (homework 20%, exams 50%...), each category's average counts that much
+// Without any, every assessment just counts by its own weight
+// Assessments that haven't been graded yet don't count at all, so a course's average is what it is so far
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+/// Something in a course that's graded, like a test or a piece of homework.
+pub struct Assessment {
+	pub name: String,
+	/// What kind of assessment it is, like "Homework", which is what category weights go by.
+	pub category: String,
+	/// What it scored, or `None` if it hasn't been graded yet.
+	pub score: Option<f64>,
+	/// The most it can score. Scoring more than that (extra credit) is fine.
+	pub max: f64,
+	/// How much it counts compared to the others in its category, or the course if there aren't category weights.
+	pub weight: f64,
+	/// The task it's for, by its ID, if there is one.
+	pub task_id: Option<u64>,
+}
+
+impl Assessment {
+	pub fn new<T: Into<String>>(name: T, category: T, max: f64) -> Self {
+		Self {
+			name: name.into(),
+			category: category.into(),
+			score: None,
+			max,
+			weight: 1.0,
+			task_id: None,
+		}
+	}
+
+	/// Checks if the assessment is in `category`, ignoring case.
+	pub fn is_in(&self, category: &str) -> bool {
+		self.category.to_lowercase() == category.to_lowercase()
+	}
+
+	/// Returns the score as a percentage of the maximum, if it's been graded.
+	pub fn percent(&self) -> Option<f64> {
+		self.score.map(|score| score / self.max * 100.0)
+	}
+
+	/// Checks that the numbers make sense, returning what's wrong with them if they don't.
+	pub fn check(&self) -> Result<(), String> {
+		if !(self.max.is_finite() && self.max > 0.0) {
+			return Err(format!("'{}' needs a maximum score above 0.", self.name));
+		}
+		if !(self.weight.is_finite() && self.weight >= 0.0) {
+			return Err(format!("'{}' can't have a negative weight.", self.name));
+		}
+		match self.score {
+			Some(score) if !(score.is_finite() && score >= 0.0) => {
+				Err(format!("'{}' can't have a negative score.", self.name))
+			}
+			_ => Ok(()),
+		}
+	}
+}
+
+impl Default for Assessment {
+	fn default() -> Self {
+		Self::new("Assessment", "", 100.0)
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+/// One grade on a grading scale, like an A.
+pub struct Grade {
+	pub name: String,
+	/// The lowest percentage that gets this grade.
+	pub min: f64,
+	/// What it's worth towards a GPA.
+	pub points: f64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+/// The grades a course's average can get, best first.
+pub struct GradingScale {
+	pub grades: Vec<Grade>,
+}
+
+impl GradingScale {
+	/// Makes a scale out of (name, lowest percentage, points), in any order.
+	pub fn new(grades: &[(&str, f64, f64)]) -> Self {
+		let mut grades: Vec<Grade> = grades
+			.iter()
+			.map(|&(name, min, points)| Grade {
+				name: name.to_string(),
+				min,
+				points,
+			})
+			.collect();
+		// Every grade has a real number, so unwrap() is fine
+		grades.sort_by(|a, b| b.min.partial_cmp(&a.min).unwrap());
+		Self { grades }
+	}
+
+	/// A to F, 10% apart, on a 4 point scale.
+	pub fn letters() -> Self {
+		Self::new(&[
+			("A", 90.0, 4.0),
+			("B", 80.0, 3.0),
+			("C", 70.0, 2.0),
+			("D", 60.0, 1.0),
+			("F", 0.0, 0.0),
+		])
+	}
+
+	/// A to F with pluses and minuses, on a 4 point scale. An A+ is worth the same as an A.
+	pub fn plus_minus() -> Self {
+		Self::new(&[
+			("A+", 97.0, 4.0),
+			("A", 93.0, 4.0),
+			("A-", 90.0, 3.7),
+			("B+", 87.0, 3.3),
+			("B", 83.0, 3.0),
+			("B-", 80.0, 2.7),
+			("C+", 77.0, 2.3),
+			("C", 73.0, 2.0),
+			("C-", 70.0, 1.7),
+			("D+", 67.0, 1.3),
+			("D", 63.0, 1.0),
+			("D-", 60.0, 0.7),
+			("F", 0.0, 0.0),
+		])
+	}
+
+	/// Parses a scale written like "A 90 4, B 80 3, F 0 0": each grade's name, lowest percentage and points.
+	/// The names can't have spaces in them, but the grades can be in any order.
+	pub fn parse(text: &str) -> Result<Self, ()> {
+		let mut grades = Vec::new();
+		for grade in text.split(',') {
+			match grade.split_whitespace().collect::<Vec<&str>>().as_slice() {
+				[name, min, points] => {
+					let min: f64 = min.trim_end_matches('%').parse().map_err(|_| ())?;
+					let points: f64 = points.parse().map_err(|_| ())?;
+					if !(min.is_finite() && points.is_finite()) {
+						return Err(());
+					}
+					grades.push((*name, min, points));
+				}
+				_ => return Err(()),
+			}
+		}
+		Ok(Self::new(&grades))
+	}
+
+	/// Writes the scale the same way `parse` reads it.
+	pub fn as_string(&self) -> String {
+		self.grades
+			.iter()
+			.map(|grade| format!("{} {} {}", grade.name, grade.min, grade.points))
+			.collect::<Vec<String>>()
+			.join(", ")
+	}
+
+	/// Returns the grade that `percent` gets, or `None` if it's below every grade on the scale.
+	pub fn grade(&self, percent: f64) -> Option<&Grade> {
+		self.grades.iter().find(|grade| percent >= grade.min)
+	}
+}
+
+impl Default for GradingScale {
+	fn default() -> Self {
+		Self::letters()
+	}
+}
+
+/// Parses category weights written like "Homework 20, Exams 80", where the names can have spaces in them.
+/// Blank text means no category weights.
+pub fn parse_category_weights(text: &str) -> Result<Vec<(String, f64)>, ()> {
+	let mut weights = Vec::new();
+	for part in text.split(',').filter(|part| !part.trim().is_empty()) {
+		let part = part.trim();
+		let split = part.rfind(char::is_whitespace).ok_or(())?;
+		let weight: f64 = part[split..]
+			.trim()
+			.trim_end_matches('%')
+			.parse()
+			.map_err(|_| ())?;
+		if !(weight.is_finite() && weight >= 0.0) {
+			return Err(());
+		}
+		weights.push((part[..split].trim().to_string(), weight));
+	}
+	Ok(weights)
+}
+
+/// Writes category weights the same way `parse_category_weights` reads them.
+pub fn format_category_weights(weights: &[(String, f64)]) -> String {
+	weights
+		.iter()
+		.map(|(category, weight)| format!("{} {}", category, weight))
+		.collect::<Vec<String>>()
+		.join(", ")
+}
+
+/// Returns the average percentage of the graded assessments in `assessments`, each counting by its weight.
+fn weighted_average<'a, I: Iterator<Item = &'a Assessment>>(assessments: I) -> Option<f64> {
+	let mut total = 0.0;
+	let mut weights = 0.0;
+	for assessment in assessments {
+		if let Some(percent) = assessment.percent() {
+			total += percent * assessment.weight;
+			weights += assessment.weight;
+		}
+	}
+	if weights > 0.0 {
+		Some(total / weights)
+	} else {
+		None
+	}
+}
+
+impl Course {
+	/// Returns the weight of `category`, ignoring case, if it has one.
+	pub fn category_weight(&self, category: &str) -> Option<f64> {
+		self.category_weights
+			.iter()
+			.find(|(name, _)| name.to_lowercase() == category.to_lowercase())
+			.map(|(_, weight)| *weight)
+	}
+
+	/// Returns the average percentage of the graded assessments in `category`, ignoring case.
+	pub fn category_average(&self, category: &str) -> Option<f64> {
+		weighted_average(
+			self.assessments
+				.iter()
+				.filter(|assessment| assessment.is_in(category)),
+		)
+	}
+
+	/// Returns the average percentage of `assessments` as if they were this course's.
+	fn average_of(&self, assessments: &[Assessment]) -> Option<f64> {
+		if self.category_weights.is_empty() {
+			return weighted_average(assessments.iter());
+		}
+
+		// Categories without anything graded yet don't count, rather than counting as 0%
+		let mut total = 0.0;
+		let mut weights = 0.0;
+		for (category, weight) in &self.category_weights {
+			let average = weighted_average(
+				assessments
+					.iter()
+					.filter(|assessment| assessment.is_in(category)),
+			);
+			if let Some(average) = average {
+				total += average * weight;
+				weights += weight;
+			}
+		}
+		if weights > 0.0 {
+			Some(total / weights)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the course's average percentage so far, or `None` if nothing that counts has been graded.
+	/// With category weights, assessments in a category without a weight don't count.
+	pub fn average(&self) -> Option<f64> {
+		self.average_of(&self.assessments)
+	}
+
+	/// Returns the percentage needed on the assessment at `index` for the course's average to come out at `target`.
+	/// It's worked out as if the other assessments that haven't been graded yet weren't there.
+	/// Above 100 means it can't be done without extra credit, and below 0 means it's done already.
+	/// Returns `None` if the assessment makes no difference to the average, like if it has no weight.
+	pub fn needed_on(&self, index: usize, target: f64) -> Option<f64> {
+		// The average goes up in a straight line with the score, so two points are enough to find where it hits `target`
+		let average_with = |percent: f64| {
+			let mut assessments = self.assessments.clone();
+			let assessment = &mut assessments[index];
+			assessment.score = Some(percent / 100.0 * assessment.max);
+			self.average_of(&assessments)
+		};
+		let (low, high) = (average_with(0.0)?, average_with(100.0)?);
+		if (high - low).abs() < 1e-9 {
+			return None;
+		}
+		Some((target - low) / (high - low) * 100.0)
+	}
+
+	/// Checks that the credits, category weights and assessments make sense, like `Assessment::check`.
+	/// The GUI and CLI never let these go negative, but anything sent to the server has to be checked.
+	pub fn check_grading(&self) -> Result<(), String> {
+		if !(self.credits.is_finite() && self.credits >= 0.0) {
+			return Err(format!("'{}' can't have negative credits.", self.name));
+		}
+		for (category, weight) in &self.category_weights {
+			if !(weight.is_finite() && *weight >= 0.0) {
+				return Err(format!("'{}' can't have a negative weight.", category));
+			}
+		}
+		self.assessments.iter().try_for_each(Assessment::check)
+	}
+
+	/// Returns the index of the assessment for the task with `id`, if there is one.
+	pub fn assessment_for(&self, id: u64) -> Option<usize> {
+		self.assessments
+			.iter()
+			.position(|assessment| assessment.task_id == Some(id))
+	}
+}
+
+impl Application {
+	/// Returns the grade the course at `index` has so far, by the grading scale.
+	pub fn grade(&self, index: usize) -> Option<&Grade> {
+		self.courses[index]
+			.average()
+			.and_then(|average| self.grading_scale.grade(average))
+	}
+
+	/// Returns the GPA across every course that has a grade so far, each counting by its credits.
+	pub fn gpa(&self) -> Option<f64> {
+		let mut total = 0.0;
+		let mut credits = 0.0;
+		for (index, course) in self.courses.iter().enumerate() {
+			if let Some(grade) = self.grade(index) {
+				total += grade.points * course.credits;
+				credits += course.credits;
+			}
+		}
+		if credits > 0.0 {
+			Some(total / credits)
+		} else {
+			None
+		}
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assessment(name: &str, category: &str, score: Option<f64>, max: f64) -> Assessment {
+		Assessment {
+			score,
+			..Assessment::new(name, category, max)
+		}
+	}
+
+	// Two pieces of homework (80% and 100%), a midterm (70%), and a final that hasn't happened yet
+	fn course() -> Course {
+		let mut course = Course::new("Math", "Willis", "S37");
+		course.assessments = vec![
+			assessment("Homework 1", "Homework", Some(8.0), 10.0),
+			assessment("Homework 2", "Homework", Some(20.0), 20.0),
+			assessment("Midterm", "Exams", Some(35.0), 50.0),
+			assessment("Final", "Exams", None, 100.0),
+		];
+		course
+	}
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn averages() {
+		let mut course = course();
+		// Without category weights, everything graded counts the same
+		assert!(close(
+			course.average().unwrap(),
+			(80.0 + 100.0 + 70.0) / 3.0
+		));
+
+		// Homework counts for 20%, and exams for 80%
+		course.category_weights = vec![("Homework".to_string(), 20.0), ("exams".to_string(), 80.0)];
+		assert!(close(course.category_average("homework").unwrap(), 90.0));
+		assert!(close(course.average().unwrap(), 0.2 * 90.0 + 0.8 * 70.0));
+
+		// Weights inside a category count too
+		course.assessments[1].weight = 3.0;
+		assert!(close(course.category_average("Homework").unwrap(), 95.0));
+
+		// Nothing graded, nothing to average
+		assert_eq!(Course::new("Art", "", "").average(), None);
+	}
+
+	#[test]
+	fn needed_on_final() {
+		let mut course = course();
+		course.category_weights = vec![("Homework".to_string(), 20.0), ("Exams".to_string(), 80.0)];
+		course.assessments[3].weight = 2.0;
+
+		// The exams are 70% and x% (counting twice), so 80 = 0.2 * 90 + 0.8 * (70 + 2x) / 3
+		let needed = course.needed_on(3, 80.0).unwrap();
+		assert!(close(needed, 81.25));
+		let mut check = course.clone();
+		check.assessments[3].score = Some(needed);
+		assert!(close(check.average().unwrap(), 80.0));
+
+		// Something that doesn't count can't help
+		course.assessments[3].weight = 0.0;
+		assert_eq!(course.needed_on(3, 80.0), None);
+	}
+
+	#[test]
+	fn scales() {
+		let scale = GradingScale::letters();
+		assert_eq!(scale.grade(90.0).unwrap().name, "A");
+		assert_eq!(scale.grade(89.9).unwrap().name, "B");
+		assert_eq!(scale.grade(-5.0), None);
+
+		let parsed = GradingScale::parse("P 50 1, F 0 0, D 75% 2").unwrap();
+		assert_eq!(parsed.as_string(), "D 75 2, P 50 1, F 0 0");
+		assert_eq!(GradingScale::parse(&parsed.as_string()), Ok(parsed));
+		assert!(GradingScale::parse("A 90").is_err());
+		assert!(GradingScale::parse("A ninety 4").is_err());
+	}
+
+	#[test]
+	fn category_weights() {
+		let weights = parse_category_weights("Homework 20%, Final Exam 80").unwrap();
+		assert_eq!(
+			weights,
+			vec![
+				("Homework".to_string(), 20.0),
+				("Final Exam".to_string(), 80.0)
+			]
+		);
+		assert_eq!(
+			format_category_weights(&weights),
+			"Homework 20, Final Exam 80"
+		);
+		assert_eq!(parse_category_weights(" "), Ok(Vec::new()));
+		assert!(parse_category_weights("Homework").is_err());
+		assert!(parse_category_weights("Homework -5").is_err());
+	}
+
+	#[test]
+	fn gpa() {
+		let mut application = Application::default();
+		application.add_course(course());
+		let mut physics = Course::new("Physics", "Moore", "L2");
+		physics.assessments = vec![assessment("Lab", "", Some(95.0), 100.0)];
+		physics.credits = 3.0;
+		application.add_course(physics);
+		application.add_course(Course::new("Art", "", ""));
+
+		// Math is a B (83.3%), Physics is an A and counts three times as much, and Art has nothing yet
+		assert_eq!(application.grade(0).unwrap().name, "B");
+		assert_eq!(application.grade(2), None);
+		assert!(close(application.gpa().unwrap(), (3.0 + 4.0 * 3.0) / 4.0));
+
+		application.grading_scale = GradingScale::plus_minus();
+		assert_eq!(application.grade(0).unwrap().name, "B");
+		assert!(close(application.gpa().unwrap(), (3.0 + 4.0 * 3.0) / 4.0));
+	}
+
+	#[test]
+	fn checking() {
+		assert!(assessment("Quiz", "", Some(5.0), 10.0).check().is_ok());
+		assert!(assessment("Quiz", "", Some(12.0), 10.0).check().is_ok());
+		assert!(assessment("Quiz", "", Some(-1.0), 10.0).check().is_err());
+		assert!(assessment("Quiz", "", None, 0.0).check().is_err());
+
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.assessments
+			.push(assessment("Quiz", "", Some(5.0), 10.0));
+		math.category_weights.push(("Exams".to_string(), 50.0));
+		assert!(math.check_grading().is_ok());
+		math.credits = f64::NAN;
+		assert!(math.check_grading().is_err());
+		math.credits = 1.0;
+		math.category_weights.push(("Homework".to_string(), -10.0));
+		assert!(math.check_grading().is_err());
+		math.category_weights.pop();
+		math.assessments
+			.push(assessment("Test", "", Some(-1.0), 10.0));
+		assert!(math.check_grading().is_err());
+	}
+}
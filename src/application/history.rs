@@ -0,0 +1,378 @@
+use super::*;
+
+/// How many edits are remembered. The oldest ones are forgotten first.
+const MAX_HISTORY: usize = 100;
+
+// Every change to the application goes through one of these commands
+// The trick is that applying a command gives back the command that would undo it
+// For example, applying `RemoveCourse(2)` gives back `InsertCourse(2, <the course that was removed>)`
+// So undoing is just applying the command we got back, and that in turn gives back the command to redo it
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+/// A single change to the application, which can be undone.
+pub enum Command {
+	InsertCourse(usize, Course),
+	RemoveCourse(usize),
+	/// Replaces the course at the index. Used for any change inside a course, including its tasks.
+	SetCourse(usize, Course),
+	/// Replaces every holiday, since adding one can merge it with others.
+	SetHolidays(Vec<(Date, Date)>),
+	SetTimezone(Option<Zone>),
+	SetLocale(Locale),
+	SetReminders(ReminderSettings),
+	SetFocusSettings(FocusSettings),
+	/// Starts or ends a focus session, or changes how much of it has been logged.
+	SetFocusSession(Option<FocusSession>),
+	SetGradingScale(GradingScale),
+	/// Several commands applied together, which are undone together too.
+	Batch(Vec<Command>),
+}
+
+impl Command {
+	/// Returns what part of the application this command changes, for the observers.
+	fn change(&self) -> Change {
+		match self {
+			Command::InsertCourse(..) | Command::RemoveCourse(_) => Change::Courses,
+			Command::SetCourse(index, _) => Change::Course(*index),
+			Command::SetHolidays(_) => Change::Holidays,
+			Command::SetTimezone(_)
+			| Command::SetLocale(_)
+			| Command::SetReminders(_)
+			| Command::SetFocusSettings(_)
+			| Command::SetFocusSession(_)
+			| Command::SetGradingScale(_) => Change::Settings,
+			// The commands could be about anything, so it's safest to say all the courses changed
+			Command::Batch(_) => Change::Courses,
+		}
+	}
+
+	/// Applies this command to `application`, and returns the command that undoes it.
+	fn apply(self, application: &mut Application) -> Command {
+		match self {
+			Command::InsertCourse(index, course) => {
+				application.courses.insert(index, course);
+				Command::RemoveCourse(index)
+			}
+			Command::RemoveCourse(index) => {
+				let course = application.courses.remove(index);
+				Command::InsertCourse(index, course)
+			}
+			Command::SetCourse(index, course) => {
+				let old = std::mem::replace(&mut application.courses[index], course);
+				Command::SetCourse(index, old)
+			}
+			Command::SetHolidays(holidays) => {
+				let old = std::mem::replace(&mut application.holidays, holidays);
+				Command::SetHolidays(old)
+			}
+			Command::SetTimezone(timezone) => {
+				let old = std::mem::replace(&mut application.timezone, timezone);
+				Command::SetTimezone(old)
+			}
+			Command::SetLocale(locale) => {
+				let old = std::mem::replace(&mut application.locale, locale);
+				Command::SetLocale(old)
+			}
+			Command::SetReminders(reminders) => {
+				let old = std::mem::replace(&mut application.reminders, reminders);
+				Command::SetReminders(old)
+			}
+			Command::SetFocusSettings(focus) => {
+				let old = std::mem::replace(&mut application.focus, focus);
+				Command::SetFocusSettings(old)
+			}
+			Command::SetFocusSession(session) => {
+				let old = std::mem::replace(&mut application.focus_session, session);
+				Command::SetFocusSession(old)
+			}
+			Command::SetGradingScale(scale) => {
+				let old = std::mem::replace(&mut application.grading_scale, scale);
+				Command::SetGradingScale(old)
+			}
+			Command::Batch(commands) => {
+				// Undoing has to happen in the opposite order, like taking off socks and then shoes
+				let mut inverses: Vec<Command> = commands
+					.into_iter()
+					.map(|command| command.apply(application))
+					.collect();
+				inverses.reverse();
+				Command::Batch(inverses)
+			}
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// The undo and redo stacks.
+/// Each entry is (a description of the edit, the command that undoes or redoes it).
+pub struct History {
+	undo: Vec<(String, Command)>,
+	redo: Vec<(String, Command)>,
+}
+
+impl History {
+	/// Returns the description of the edit that would be undone next.
+	pub fn next_undo(&self) -> Option<&str> {
+		self.undo.last().map(|(label, _)| label.as_str())
+	}
+
+	/// Returns the description of the edit that would be redone next.
+	pub fn next_redo(&self) -> Option<&str> {
+		self.redo.last().map(|(label, _)| label.as_str())
+	}
+
+	/// Returns the descriptions of every edit that can be undone, most recent first.
+	pub fn undo_list(&self) -> Vec<&str> {
+		self.undo.iter().rev().map(|(label, _)| label.as_str()).collect()
+	}
+
+	/// Returns where the history for the data file at `location` is kept.
+	fn location(location: &str) -> String {
+		format!("{}.history", location)
+	}
+
+	/// Saves the history next to the data file at `location`.
+	pub(super) fn save(&self, location: &str) {
+		if let Ok(buf) = bincode::serialize(self) {
+			// Losing the history isn't the end of the world, so we don't panic if this fails
+			let _ = std::fs::write(Self::location(location), application::with_header(buf));
+		}
+	}
+
+	/// Loads the history saved next to the data file at `location`, or an empty one if there isn't any.
+	/// History saved by another version is forgotten, since its edits are made of courses and tasks laid out differently.
+	pub(super) fn load(location: &str) -> Self {
+		std::fs::read(Self::location(location))
+			.ok()
+			.and_then(|buf| match application::split_header(&buf) {
+				(Some(version), buf) if version == application::FORMAT_VERSION => {
+					bincode::deserialize(buf).ok()
+				}
+				_ => None,
+			})
+			.unwrap_or_default()
+	}
+
+	/// Deletes the saved history for the data file at `location`, if there is any.
+	pub(super) fn forget(location: &str) {
+		let _ = std::fs::remove_file(Self::location(location));
+	}
+}
+
+impl Application {
+	/// Applies `command`, remembering how to undo it.
+	/// `label` describes the edit for the user, like "Remove course 'Math'".
+	pub fn apply<T: Into<String>>(&mut self, label: T, command: Command) {
+		let change = command.change();
+		let inverse = command.apply(self);
+		self.mark_changed();
+
+		self.history.undo.push((label.into(), inverse));
+		if self.history.undo.len() > MAX_HISTORY {
+			self.history.undo.remove(0);
+		}
+		// Once you've done something new, the things you undid before can't be redone anymore
+		self.history.redo.clear();
+		self.notify(change);
+	}
+
+	/// Undoes the last edit, returning its description, or `None` if there's nothing to undo.
+	pub fn undo(&mut self) -> Option<String> {
+		let (label, command) = self.history.undo.pop()?;
+		let change = command.change();
+		let inverse = command.apply(self);
+		self.mark_changed();
+		self.history.redo.push((label.clone(), inverse));
+		self.notify(change);
+		Some(label)
+	}
+
+	/// Redoes the last undone edit, returning its description, or `None` if there's nothing to redo.
+	pub fn redo(&mut self) -> Option<String> {
+		let (label, command) = self.history.redo.pop()?;
+		let change = command.change();
+		let inverse = command.apply(self);
+		self.mark_changed();
+		self.history.undo.push((label.clone(), inverse));
+		self.notify(change);
+		Some(label)
+	}
+
+	/// Changes the course at `index` with `edit`, as one edit that can be undone.
+	///
+	/// This is how anything inside a course (including its tasks and steps) should be changed:
+	/// `application.edit_course(0, "Add time", |course| course.new_time());`
+	pub fn edit_course<T, F>(&mut self, index: usize, label: T, edit: F)
+	where
+		T: Into<String>,
+		F: FnOnce(&mut Course),
+	{
+		let mut course = self.courses[index].clone();
+		edit(&mut course);
+		self.apply(label, Command::SetCourse(index, course));
+	}
+
+	/// Changes the task at `task_index` in the course at `course_index`, as one edit that can be undone.
+	pub fn edit_task<T, F>(&mut self, course_index: usize, task_index: usize, label: T, edit: F)
+	where
+		T: Into<String>,
+		F: FnOnce(&mut Task),
+	{
+		self.edit_course(course_index, label, |course| edit(&mut course.tasks[task_index]));
+	}
+
+	/// Changes several tasks at once, as one edit that can be undone.
+	/// `tasks` are (course index, task index), and `edit` is given each course with the indexes of its tasks
+	/// that are in `tasks`, smallest first.
+	pub fn edit_tasks<T, F>(&mut self, tasks: &[(usize, usize)], label: T, edit: F)
+	where
+		T: Into<String>,
+		F: Fn(&mut Course, &[usize]),
+	{
+		let mut tasks = tasks.to_vec();
+		tasks.sort();
+		tasks.dedup();
+
+		let mut commands = Vec::new();
+		for (course_index, course) in self.courses.iter().enumerate() {
+			let task_indexes: Vec<usize> = tasks
+				.iter()
+				.filter(|(index, _)| *index == course_index)
+				.map(|(_, task_index)| *task_index)
+				.collect();
+			if !task_indexes.is_empty() {
+				let mut course = course.clone();
+				edit(&mut course, &task_indexes);
+				commands.push(Command::SetCourse(course_index, course));
+			}
+		}
+
+		if !commands.is_empty() {
+			self.apply(label, Command::Batch(commands));
+		}
+	}
+
+	/// Changes the holidays with `edit`, as one edit that can be undone.
+	pub fn edit_holidays<T, F>(&mut self, label: T, edit: F)
+	where
+		T: Into<String>,
+		F: FnOnce(&mut Vec<(Date, Date)>),
+	{
+		let mut holidays = self.holidays.clone();
+		edit(&mut holidays);
+		self.apply(label, Command::SetHolidays(holidays));
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn names(application: &Application) -> Vec<&str> {
+		application
+			.courses
+			.iter()
+			.map(|course| course.name.as_str())
+			.collect()
+	}
+
+	#[test]
+	fn undo_and_redo() {
+		let mut application = Application::default();
+		application.add_course(Course::new("Math", "Willis", "S37"));
+		application.add_course(Course::new("Science", "Curie", "L2"));
+
+		application.rm_course(0);
+		assert_eq!(names(&application), vec!["Science"]);
+
+		assert_eq!(application.undo(), Some("Remove course 'Math'".to_string()));
+		assert_eq!(names(&application), vec!["Math", "Science"]);
+
+		application.redo();
+		assert_eq!(names(&application), vec!["Science"]);
+
+		application.undo();
+		application.undo();
+		application.undo();
+		assert!(application.courses.is_empty());
+		assert_eq!(application.undo(), None);
+	}
+
+	#[test]
+	fn new_edits_clear_redo() {
+		let mut application = Application::default();
+		application.add_course(Course::new("Math", "Willis", "S37"));
+		application.undo();
+		assert_eq!(application.history.next_redo(), Some("Add course 'Math'"));
+
+		application.new_course();
+		assert_eq!(application.history.next_redo(), None);
+	}
+
+	#[test]
+	fn edits_inside_courses() {
+		let mut application = Application::default();
+		application.add_course(Course::new("Math", "Willis", "S37"));
+		application.edit_course(0, "Add task", |course| course.new_task());
+		application.edit_task(0, 0, "Rename task", |task| task.name = "Essay".to_string());
+
+		application.undo();
+		assert_eq!(application.courses[0].tasks[0].name, "Name");
+		application.undo();
+		assert!(application.courses[0].tasks.is_empty());
+	}
+
+	#[test]
+	fn batch() {
+		let mut application = Application::default();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.new_task();
+		math.new_task();
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.new_task();
+		application.add_course(math);
+		application.add_course(science);
+
+		application.edit_tasks(&[(1, 0), (0, 1)], "Rename tasks", |course, task_indexes| {
+			for index in task_indexes {
+				course.tasks[*index].name = "Renamed".to_string();
+			}
+		});
+		assert_eq!(application.courses[0].tasks[1].name, "Renamed");
+		assert_eq!(application.courses[1].tasks[0].name, "Renamed");
+		assert_eq!(application.courses[0].tasks[0].name, "Name");
+
+		// Both courses go back with one undo
+		assert_eq!(application.undo(), Some("Rename tasks".to_string()));
+		assert_eq!(application.courses[0].tasks[1].name, "Name");
+		assert_eq!(application.courses[1].tasks[0].name, "Name");
+	}
+
+	#[test]
+	fn holidays() {
+		let date = |day| Date::new(2020, 1, day).unwrap();
+		let mut application = Application::default();
+		application.add_holiday(date(1), date(5));
+		application.add_holiday(date(3), date(9));
+		assert_eq!(application.holidays, vec![(date(1), date(9))]);
+
+		application.undo();
+		assert_eq!(application.holidays, vec![(date(1), date(5))]);
+	}
+
+	#[test]
+	fn limited() {
+		let mut application = Application::default();
+		for _ in 0..MAX_HISTORY + 10 {
+			application.new_course();
+		}
+		assert_eq!(application.history.undo_list().len(), MAX_HISTORY);
+	}
+}
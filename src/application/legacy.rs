@@ -0,0 +1,71 @@
+use super::*;
+
+// Before saved files had a version at the start, an application was saved as just its courses and holidays
+// Bincode doesn't write down the names of fields, only their values one after the other
+// So those files can only be read with structs laid out exactly like the old ones, which are kept here
+// Nothing else should use these, they're only turned into the real thing as soon as they're read
+
+#[derive(Deserialize)]
+/// An application as it was saved before the format had a version.
+pub(super) struct LegacyApplication {
+	courses: Vec<LegacyCourse>,
+	holidays: Vec<(Date, Date)>,
+}
+
+#[derive(Deserialize)]
+/// A course as it was saved before the format had a version.
+struct LegacyCourse {
+	name: String,
+	teacher: String,
+	room: String,
+	times: Vec<(Day, Time, Time)>,
+	tasks: Vec<LegacyTask>,
+}
+
+#[derive(Deserialize)]
+/// A task as it was saved before the format had a version.
+/// Back then, every task started with a dummy step called "Completed?", which stood in for tasks without steps.
+struct LegacyTask {
+	name: String,
+	desc: String,
+	due: Date,
+	steps: Vec<(bool, String)>,
+}
+
+impl From<LegacyTask> for Task {
+	fn from(legacy: LegacyTask) -> Self {
+		let mut task = Task::new(legacy.name, legacy.desc, legacy.due);
+		let mut steps = legacy.steps;
+		// The dummy step is always the first one, and it's only there because tasks couldn't be done without steps
+		// If it's the only step, whether it was ticked is whether the task is done
+		if matches!(steps.first(), Some((_, desc)) if desc == "Completed?") {
+			task.complete = steps.remove(0).0;
+		}
+		task.steps = steps
+			.into_iter()
+			.map(|(complete, desc)| Step {
+				complete,
+				..Step::new(desc)
+			})
+			.collect();
+		task
+	}
+}
+
+impl From<LegacyCourse> for Course {
+	fn from(legacy: LegacyCourse) -> Self {
+		let mut course = Course::new(legacy.name, legacy.teacher, legacy.room);
+		course.times = legacy.times;
+		course.tasks = legacy.tasks.into_iter().map(Task::from).collect();
+		course
+	}
+}
+
+impl From<LegacyApplication> for Application {
+	fn from(legacy: LegacyApplication) -> Self {
+		let mut application = Self::default();
+		application.courses = legacy.courses.into_iter().map(Course::from).collect();
+		application.holidays = legacy.holidays;
+		application
+	}
+}
@@ -10,10 +10,46 @@ mod application;
 mod course;
 /// Contains the task struct and related functions.
 mod task;
+/// Contains the step struct, and the functions for moving steps around and inside each other.
+mod step;
+/// Contains the task query, for filtering and sorting tasks across every course.
+mod query;
+/// Contains the dependencies between tasks, and the order they can be done in.
+mod dependency;
+/// Contains the time spent on tasks, the timer that records it, and the totals for each course and week.
+mod time_log;
+/// Contains the focus sessions (pomodoros) on tasks, and the rounds of work and breaks they're made of.
+mod focus;
+/// Contains the grade book: assessments, grading scales, and the averages and GPA worked out from them.
+mod grade;
+/// Contains the full-text search across courses, tasks and steps.
+mod search;
+/// Contains the undo/redo history, and the commands every edit is made of.
+mod history;
+/// Contains the reminders for upcoming lessons and deadlines, and the notifiers that show them.
+mod reminder;
+/// Contains the agenda of a day, for calendars.
+mod agenda;
+/// Contains the observers that are told whenever the application changes.
+mod observer;
+/// Contains the way applications used to be saved, so old files can still be loaded.
+mod legacy;
 
 // However, since `Application` is the only thing that's needed, we're making it public here.
-pub use application::Application;
+pub use agenda::Agenda;
+pub use application::{Application, Conflict};
 // We're making these public so that you can read the documentation!
 // These aren't actually meant to be public.
 pub use course::Course;
-pub use task::Task;
+pub use focus::{FocusSession, FocusSettings, Phase, MAX_FOCUS_MINUTES, MAX_FOCUS_ROUNDS};
+pub use grade::{format_category_weights, parse_category_weights, Assessment, Grade, GradingScale};
+pub use history::{Command, History};
+pub use observer::{Change, Observers};
+pub use query::{TaskEntry, TaskQuery, TaskSort};
+pub use reminder::{
+	Notifier, PrintNotifier, Reminder, ReminderSettings, MAX_REMINDER_DAYS, MAX_REMINDER_MINUTES,
+};
+pub use search::{Field, SearchResult};
+pub use step::{Step, StepPlace};
+pub use task::{parse_optional_number, parse_tags, Priority, Task};
+pub use time_log::{format_minutes, parse_minutes, TimeEntry};
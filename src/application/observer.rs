@@ -0,0 +1,141 @@
+use super::*;
+use std::fmt;
+
+// Anything showing the application (like a list in the GUI) can watch it, and gets told whenever it changes
+// That way, a course edited in one window shows up straight away in every other window that has it
+// Observers are called right after every command (see history.rs), with the application as it is afterwards
+// They're given the application instead of borrowing it themselves, since it's still in the middle of changing
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// What part of the application changed.
+pub enum Change {
+	/// A course was added or removed (so the indexes of the ones after it may have moved too),
+	/// or several courses changed at once.
+	Courses,
+	/// Something in the course at this index changed, including its times, tasks and steps.
+	Course(usize),
+	Holidays,
+	/// The time zone, locale, reminders or other settings changed.
+	Settings,
+}
+
+impl Change {
+	/// Returns whether this change could have changed the course at `index`.
+	pub fn affects_course(self, index: usize) -> bool {
+		match self {
+			Change::Courses => true,
+			Change::Course(changed) => changed == index,
+			// The times of every course are shown in the application's time zone
+			Change::Settings => true,
+			Change::Holidays => false,
+		}
+	}
+}
+
+/// Something watching the application. It returns false once it doesn't want to be told about changes anymore.
+type Observer = Box<dyn Fn(&Application, Change) -> bool>;
+
+#[derive(Default)]
+/// Everyone watching the application.
+/// They aren't saved, and aren't copied along with the application either, since they're watching the original.
+pub struct Observers(Vec<Observer>);
+
+impl Clone for Observers {
+	fn clone(&self) -> Self {
+		Self::default()
+	}
+}
+
+impl fmt::Debug for Observers {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Observers({})", self.0.len())
+	}
+}
+
+impl Application {
+	/// Calls `observer` after every change from now on, with the application and what changed.
+	/// It keeps being called until it returns false, like when the window it's updating has closed.
+	pub fn observe<F>(&mut self, observer: F)
+	where
+		F: Fn(&Application, Change) -> bool + 'static,
+	{
+		self.observers.0.push(Box::new(observer));
+	}
+
+	/// Tells every observer about `change`, forgetting the ones that are done.
+	pub(super) fn notify(&mut self, change: Change) {
+		// The observers are taken out while they're called, since they need to see the rest of the application
+		let mut observers = std::mem::take(&mut self.observers.0);
+		observers.retain(|observer| observer(self, change));
+		self.observers.0 = observers;
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	#[test]
+	fn observe() {
+		let changes = Rc::new(RefCell::new(Vec::new()));
+		let mut application = Application::default();
+
+		let seen = changes.clone();
+		application.observe(move |application, change| {
+			seen.borrow_mut().push((change, application.courses.len()));
+			true
+		});
+		// This one stops watching after the first change
+		let counted = Rc::new(RefCell::new(0));
+		let count = counted.clone();
+		application.observe(move |_, _| {
+			*count.borrow_mut() += 1;
+			false
+		});
+
+		application.add_course(Course::new("Math", "Willis", "S37"));
+		application.edit_course(0, "Add task", |course| course.new_task());
+		application.add_holiday(
+			Date::new(2020, 1, 1).unwrap(),
+			Date::new(2020, 1, 5).unwrap(),
+		);
+		application.undo();
+		application.undo();
+		application.redo();
+
+		// Observers see the application after the change, so the course is already there
+		assert_eq!(
+			*changes.borrow(),
+			vec![
+				(Change::Courses, 1),
+				(Change::Course(0), 1),
+				(Change::Holidays, 1),
+				(Change::Holidays, 1),
+				(Change::Course(0), 1),
+				(Change::Course(0), 1),
+			]
+		);
+		assert_eq!(*counted.borrow(), 1);
+
+		// Copies of the application aren't watched
+		let mut copy = application.clone();
+		copy.new_course();
+		assert_eq!(changes.borrow().len(), 6);
+	}
+
+	#[test]
+	fn affects_course() {
+		assert!(Change::Course(1).affects_course(1));
+		assert!(!Change::Course(1).affects_course(0));
+		assert!(Change::Courses.affects_course(0));
+		assert!(!Change::Holidays.affects_course(0));
+	}
+}
@@ -0,0 +1,401 @@
+use super::*;
+
+// See application.rs to understand #[derive()]
+#[derive(PartialEq, Clone, Copy, Debug)]
+/// What order a query returns its tasks in.
+pub enum TaskSort {
+	/// Soonest due first, then most important first.
+	Due,
+	/// Most important first, then soonest due first.
+	Priority,
+	/// Alphabetical by task name.
+	Name,
+	/// In the order of the courses, then soonest due first.
+	Course,
+}
+
+#[derive(Clone, Debug)]
+/// A query picks out tasks from every course, like "incomplete exam tasks due this week".
+/// Every filter is optional, and a new query matches every task.
+///
+/// It's built up by chaining, like so:
+/// `TaskQuery::new().tag("exam").complete(false).sort_by(TaskSort::Priority)`
+pub struct TaskQuery {
+	/// Only tasks in the course at this index.
+	pub course: Option<usize>,
+	/// Only tasks with this tag, or that are in a course with this tag.
+	pub tag: Option<String>,
+	/// Only tasks at least this important.
+	pub min_priority: Option<Priority>,
+	/// Only tasks due on or after this date.
+	pub due_from: Option<Date>,
+	/// Only tasks due on or before this date.
+	pub due_to: Option<Date>,
+	/// Only tasks that are (or aren't) complete.
+	pub complete: Option<bool>,
+	/// Only tasks that are (or aren't) waiting on other tasks to be done first (see `Application::is_blocked`).
+	pub blocked: Option<bool>,
+	pub sort: TaskSort,
+}
+
+/// A task found by a query, along with where it is, so it can be opened or changed.
+pub struct TaskEntry<'a> {
+	pub course_index: usize,
+	pub task_index: usize,
+	pub course: &'a Course,
+	pub task: &'a Task,
+}
+
+impl TaskQuery {
+	pub fn new() -> Self {
+		Self {
+			course: None,
+			tag: None,
+			min_priority: None,
+			due_from: None,
+			due_to: None,
+			complete: None,
+			blocked: None,
+			sort: TaskSort::Due,
+		}
+	}
+
+	pub fn course(mut self, index: usize) -> Self {
+		self.course = Some(index);
+		self
+	}
+
+	pub fn tag<T: Into<String>>(mut self, tag: T) -> Self {
+		self.tag = Some(tag.into());
+		self
+	}
+
+	pub fn min_priority(mut self, priority: Priority) -> Self {
+		self.min_priority = Some(priority);
+		self
+	}
+
+	/// Only tasks due between `from` and `to`, including both.
+	pub fn due_between(mut self, from: Date, to: Date) -> Self {
+		self.due_from = Some(from);
+		self.due_to = Some(to);
+		self
+	}
+
+	/// Only tasks that were due before `today`, and still aren't complete.
+	pub fn overdue(mut self, today: Date) -> Self {
+		// Nothing can be due before the first date there is, so then nothing is overdue
+		self.due_to = Some(today.offset(-1).unwrap_or(Date::FIRST));
+		if today == Date::FIRST {
+			self.due_from = Some(Date::LAST);
+		}
+		self.complete = Some(false);
+		self
+	}
+
+	pub fn complete(mut self, complete: bool) -> Self {
+		self.complete = Some(complete);
+		self
+	}
+
+	pub fn blocked(mut self, blocked: bool) -> Self {
+		self.blocked = Some(blocked);
+		self
+	}
+
+	pub fn sort_by(mut self, sort: TaskSort) -> Self {
+		self.sort = sort;
+		self
+	}
+
+	/// Checks whether a single task (in `course`, at `course_index`) passes every filter.
+	fn matches(&self, course_index: usize, course: &Course, task: &Task) -> bool {
+		// Each check is a filter, and a filter that isn't set lets everything through
+		if let Some(index) = self.course {
+			if index != course_index {
+				return false;
+			}
+		}
+		if let Some(tag) = &self.tag {
+			if !task.has_tag(tag) && !course.has_tag(tag) {
+				return false;
+			}
+		}
+		if let Some(min) = self.min_priority {
+			if task.priority < min {
+				return false;
+			}
+		}
+		if let Some(complete) = self.complete {
+			if task.is_complete() != complete {
+				return false;
+			}
+		}
+
+		let from = self.due_from.unwrap_or(task.due);
+		let to = self.due_to.unwrap_or(task.due);
+		task.due.is_between(from, to)
+	}
+}
+
+impl Default for TaskQuery {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Application {
+	/// Returns every task that matches `query`, in the order it asks for.
+	pub fn tasks(&self, query: &TaskQuery) -> Vec<TaskEntry<'_>> {
+		let mut entries: Vec<TaskEntry> = self
+			.courses
+			.iter()
+			.enumerate()
+			.flat_map(|(course_index, course)| {
+				course
+					.tasks
+					.iter()
+					.enumerate()
+					.map(move |(task_index, task)| TaskEntry {
+						course_index,
+						task_index,
+						course,
+						task,
+					})
+			})
+			.filter(|entry| query.matches(entry.course_index, entry.course, entry.task))
+			// Whether a task is blocked depends on other tasks, so the query can't check it by itself
+			.filter(|entry| match query.blocked {
+				Some(blocked) => self.is_blocked(entry.course_index, entry.task_index) == blocked,
+				None => true,
+			})
+			.collect();
+
+		// Dates only implement PartialOrd, but every real date can be compared, so unwrap() is fine
+		// sort_by is stable, so tasks that compare equal stay in the order they were added
+		let by_due = |a: &TaskEntry, b: &TaskEntry| a.task.due.partial_cmp(&b.task.due).unwrap();
+		let by_priority = |a: &TaskEntry, b: &TaskEntry| b.task.priority.cmp(&a.task.priority);
+
+		match query.sort {
+			TaskSort::Due => entries.sort_by(|a, b| by_due(a, b).then(by_priority(a, b))),
+			TaskSort::Priority => entries.sort_by(|a, b| by_priority(a, b).then(by_due(a, b))),
+			TaskSort::Name => entries.sort_by_key(|entry| entry.task.name.to_lowercase()),
+			TaskSort::Course => entries.sort_by(|a, b| {
+				a.course_index
+					.cmp(&b.course_index)
+					.then(by_due(a, b))
+			}),
+		}
+
+		entries
+	}
+
+	/// Returns every task that's still not complete after it was due, on `today`, the longest overdue first.
+	pub fn overdue_tasks(&self, today: Date) -> Vec<TaskEntry<'_>> {
+		self.tasks(&TaskQuery::new().overdue(today))
+	}
+
+	/// Returns how many tasks are overdue on `today` in each course, in the same order as the courses.
+	pub fn overdue_counts(&self, today: Date) -> Vec<usize> {
+		self.courses
+			.iter()
+			.map(|course| {
+				course
+					.tasks
+					.iter()
+					.filter(|task| task.is_overdue(today))
+					.count()
+			})
+			.collect()
+	}
+
+	/// Gives every task at (course index, task index) in `tasks` the new due date `due`, as one edit that can be undone.
+	/// Each task remembers when it was first due (see `Task::snooze`).
+	pub fn snooze_tasks(&mut self, tasks: &[(usize, usize)], due: Date) {
+		let label = format!(
+			"Snooze {} until {}",
+			self.describe_tasks(tasks),
+			self.locale.format_date(due)
+		);
+		self.edit_tasks(tasks, label, |course, task_indexes| {
+			for index in task_indexes {
+				course.tasks[*index].snooze(due);
+			}
+		});
+	}
+
+	/// Marks every task at (course index, task index) in `tasks` as complete (or not), as one edit that can be undone.
+	pub fn complete_tasks(&mut self, tasks: &[(usize, usize)], complete: bool) {
+		let label = format!(
+			"Mark {} as {}",
+			self.describe_tasks(tasks),
+			if complete { "done" } else { "not done" }
+		);
+		self.edit_tasks(tasks, label, |course, task_indexes| {
+			for index in task_indexes {
+				course.tasks[*index].set_complete(complete);
+			}
+		});
+	}
+
+	/// Removes every task at (course index, task index) in `tasks`, as one edit that can be undone.
+	pub fn rm_tasks(&mut self, tasks: &[(usize, usize)]) {
+		let label = format!("Remove {}", self.describe_tasks(tasks));
+		self.edit_tasks(tasks, label, |course, task_indexes| {
+			// The last ones go first, so the indexes of the others don't move
+			for index in task_indexes.iter().rev() {
+				course.rm_task(*index);
+			}
+		});
+	}
+
+	/// Describes `tasks` for an edit's label, like "task 'Essay'" or "3 tasks".
+	pub(super) fn describe_tasks(&self, tasks: &[(usize, usize)]) -> String {
+		match tasks {
+			[(course_index, task_index)] => {
+				format!(
+					"task '{}'",
+					self.courses[*course_index].tasks[*task_index].name
+				)
+			}
+			_ => format!("{} tasks", tasks.len()),
+		}
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn date(year: u16, month: u8, day: u8) -> Date {
+		Date::new(year, month, day).unwrap()
+	}
+
+	fn task(name: &str, due: Date, priority: Priority, tags: &str) -> Task {
+		let mut task = Task::new(name, "", due);
+		task.priority = priority;
+		task.tags = parse_tags(tags);
+		task
+	}
+
+	// Math: Essay (due 10th, High, exam), Worksheet (due 5th, Low, done)
+	// Science (tagged lab): Report (due 7th, Normal), Poster (due 20th, Urgent)
+	fn application() -> Application {
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.tasks.push(task("Essay", date(2020, 1, 10), Priority::High, "exam"));
+		math.tasks.push(task("Worksheet", date(2020, 1, 5), Priority::Low, ""));
+		math.tasks[1].set_complete(true);
+
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.tags = parse_tags("lab");
+		science.tasks.push(task("Report", date(2020, 1, 7), Priority::Normal, ""));
+		science.tasks.push(task("Poster", date(2020, 1, 20), Priority::Urgent, ""));
+
+		let mut application = Application::default();
+		application.add_course(math);
+		application.add_course(science);
+		application
+	}
+
+	fn names(entries: Vec<TaskEntry<'_>>) -> Vec<&str> {
+		entries.iter().map(|entry| entry.task.name.as_str()).collect()
+	}
+
+	#[test]
+	fn sorting() {
+		let application = application();
+
+		assert_eq!(
+			names(application.tasks(&TaskQuery::new())),
+			vec!["Worksheet", "Report", "Essay", "Poster"]
+		);
+		assert_eq!(
+			names(application.tasks(&TaskQuery::new().sort_by(TaskSort::Priority))),
+			vec!["Poster", "Essay", "Report", "Worksheet"]
+		);
+		assert_eq!(
+			names(application.tasks(&TaskQuery::new().sort_by(TaskSort::Course))),
+			vec!["Worksheet", "Essay", "Report", "Poster"]
+		);
+	}
+
+	#[test]
+	fn filters() {
+		let application = application();
+
+		assert_eq!(names(application.tasks(&TaskQuery::new().course(0).complete(false))), vec!["Essay"]);
+		assert_eq!(names(application.tasks(&TaskQuery::new().tag("EXAM"))), vec!["Essay"]);
+		// Tags on the course count for all its tasks
+		assert_eq!(names(application.tasks(&TaskQuery::new().tag("lab"))), vec!["Report", "Poster"]);
+		assert_eq!(
+			names(application.tasks(&TaskQuery::new().min_priority(Priority::High))),
+			vec!["Essay", "Poster"]
+		);
+		assert_eq!(
+			names(application.tasks(&TaskQuery::new().due_between(date(2020, 1, 6), date(2020, 1, 10)))),
+			vec!["Report", "Essay"]
+		);
+	}
+
+	#[test]
+	fn overdue() {
+		let application = application();
+		// The worksheet is due on the 5th too, but it's done
+		assert_eq!(names(application.tasks(&TaskQuery::new().overdue(date(2020, 1, 8)))), vec!["Report"]);
+	}
+
+	#[test]
+	fn overdue_counts() {
+		let application = application();
+		assert_eq!(application.overdue_counts(date(2020, 1, 8)), vec![0, 1]);
+		assert_eq!(application.overdue_counts(date(2020, 1, 21)), vec![1, 2]);
+	}
+
+	#[test]
+	fn snooze() {
+		let mut application = application();
+		let today = date(2020, 1, 21);
+
+		application.snooze_tasks(&[(0, 0), (1, 0)], date(2020, 1, 24));
+		assert_eq!(application.history.next_undo(), Some("Snooze 2 tasks until 2020-01-24"));
+		assert_eq!(names(application.overdue_tasks(today)), vec!["Poster"]);
+		assert_eq!(application.courses[1].tasks[0].original_due, Some(date(2020, 1, 7)));
+
+		application.undo();
+		assert_eq!(names(application.overdue_tasks(today)), vec!["Report", "Essay", "Poster"]);
+		assert_eq!(application.courses[1].tasks[0].original_due, None);
+	}
+
+	#[test]
+	fn bulk_actions() {
+		let mut application = application();
+
+		application.complete_tasks(&[(0, 0), (1, 1)], true);
+		assert_eq!(application.history.next_undo(), Some("Mark 2 tasks as done"));
+		assert_eq!(names(application.tasks(&TaskQuery::new().complete(false))), vec!["Report"]);
+
+		application.rm_tasks(&[(0, 0), (0, 1), (1, 0)]);
+		assert_eq!(names(application.tasks(&TaskQuery::new())), vec!["Poster"]);
+
+		application.undo();
+		assert_eq!(application.tasks(&TaskQuery::new()).len(), 4);
+
+		application.rm_tasks(&[(1, 1)]);
+		assert_eq!(application.history.next_undo(), Some("Remove task 'Poster'"));
+	}
+
+	#[test]
+	fn entries_point_back_to_the_task() {
+		let application = application();
+		let entries = application.tasks(&TaskQuery::new().tag("lab").sort_by(TaskSort::Priority));
+
+		assert_eq!((entries[0].course_index, entries[0].task_index), (1, 1));
+		assert_eq!(entries[0].course.name, "Science");
+	}
+}
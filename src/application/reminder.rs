@@ -0,0 +1,480 @@
+use super::*;
+
+// Reminders are worked out from the courses and tasks whenever they're needed, they're never stored
+// Whoever wants them (the GUI, for example) asks for every reminder that went off since it last checked
+// That way, nothing gets reminded twice, and if the computer was asleep, the missed ones still show up
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// When reminders go off, unless a course or task says otherwise.
+pub struct ReminderSettings {
+	/// Whether any reminders go off at all.
+	pub enabled: bool,
+	/// How many minutes before a lesson to remind about it.
+	pub lesson_minutes: u32,
+	/// How many days before a task is due to remind about it.
+	pub task_days: u32,
+	/// What time of day reminders about tasks go off.
+	pub task_time: Time,
+}
+
+/// The most days before a task is due it can be reminded about.
+/// Anything longer isn't much of a reminder, and every day of it has to be looked through each time.
+pub const MAX_REMINDER_DAYS: u32 = 365;
+/// The most minutes before a lesson it can be reminded about, which is a week.
+pub const MAX_REMINDER_MINUTES: u32 = 7 * 24 * 60;
+
+impl ReminderSettings {
+	/// Returns how many minutes before `course`'s lessons to remind about them.
+	/// Anything over `MAX_REMINDER_MINUTES` (which could only come from an old file) counts as that.
+	pub fn lesson_minutes_for(&self, course: &Course) -> u32 {
+		course
+			.reminder_minutes
+			.unwrap_or(self.lesson_minutes)
+			.min(MAX_REMINDER_MINUTES)
+	}
+
+	/// Returns how many days before `task` is due to remind about it.
+	/// Anything over `MAX_REMINDER_DAYS` counts as that, like `lesson_minutes_for`.
+	pub fn task_days_for(&self, task: &Task) -> u32 {
+		task.reminder_days
+			.unwrap_or(self.task_days)
+			.min(MAX_REMINDER_DAYS)
+	}
+}
+
+impl Default for ReminderSettings {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			lesson_minutes: 10,
+			task_days: 1,
+			task_time: Time::new(8, 0).unwrap(),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+/// A reminder about an upcoming lesson or deadline.
+pub struct Reminder {
+	/// When the reminder goes off.
+	pub date: Date,
+	pub time: Time,
+	pub course_index: usize,
+	/// Set if it's about a task being due, rather than a lesson.
+	pub task_index: Option<usize>,
+	/// Set if it's about a focus session on the task moving along (see focus.rs), rather than it being due.
+	pub focus: bool,
+	/// What it's about, like "task-1234" or "lesson-Math".
+	/// Unlike the indexes, this stays the same when other courses and tasks are removed.
+	pub about: String,
+	/// A one-line summary, like "Math in 10 minutes".
+	pub title: String,
+	/// The details, like the room and the time.
+	pub body: String,
+}
+
+impl Reminder {
+	/// Returns a name that's the same for the same reminder every time it's worked out.
+	/// Notifications with the same name replace each other instead of piling up.
+	pub fn id(&self) -> String {
+		// There's only ever one focus session, so each of its reminders replaces the one before
+		if self.focus {
+			return "focus".to_string();
+		}
+		// A task only has one reminder a day, so if the time reminders go off at changes, it still replaces the old one
+		match self.task_index {
+			Some(_) => format!("{}-{}", self.about, self.date),
+			None => format!("{}-{}-{}", self.about, self.date, self.time),
+		}
+	}
+}
+
+/// Anything that can show reminders to the user, like desktop notifications.
+/// Implement this to send reminders somewhere new.
+pub trait Notifier {
+	fn notify(&self, reminder: &Reminder);
+}
+
+/// The simplest notifier, which prints reminders to the terminal.
+pub struct PrintNotifier;
+
+impl Notifier for PrintNotifier {
+	fn notify(&self, reminder: &Reminder) {
+		println!(
+			"[{} {}] {}: {}",
+			reminder.date, reminder.time, reminder.title, reminder.body
+		);
+	}
+}
+
+impl Application {
+	/// Returns the date and time right now, in the application's time zone.
+	pub fn now(&self) -> (Date, Time) {
+		(self.today(), Time::now_in(self.timezone))
+	}
+
+	/// Returns every reminder that goes off after `from`, up to and including `to`, in the order they go off.
+	/// Lessons on holidays and tasks that are already complete don't get reminders.
+	/// Tasks that are overdue get a reminder every day, until they're complete.
+	/// A focus session's reminders go off even if reminders are turned off, since it was started on purpose.
+	pub fn reminders_between(&self, from: (Date, Time), to: (Date, Time)) -> Vec<Reminder> {
+		let settings = self.reminders;
+		if to <= from {
+			return Vec::new();
+		}
+		let mut reminders = self.focus_reminders_between(from, to);
+		if !settings.enabled {
+			return reminders;
+		}
+
+		// A reminder goes off before the thing it's about, so we have to look a bit past `to` for those things
+		// The furthest ahead we need to look is the longest lead time out of everything
+		let lesson_days = self
+			.courses
+			.iter()
+			.map(|course| settings.lesson_minutes_for(course) / (24 * 60) + 1)
+			.max()
+			.unwrap_or(0);
+		let task_days = self
+			.courses
+			.iter()
+			.flat_map(|course| course.tasks.iter())
+			.map(|task| settings.task_days_for(task))
+			.max()
+			.unwrap_or(0);
+		// There's nothing to remind about past the last date we can hold, so that's as far as we look
+		let ahead = lesson_days.max(task_days) as i64;
+		let last = to.0.offset(ahead).unwrap_or(Date::LAST);
+
+		let mut date = from.0;
+		while date <= last {
+			if !self.is_holiday(date) {
+				for (course_index, course) in self.courses.iter().enumerate() {
+					let minutes = settings.lesson_minutes_for(course);
+					for (start, end) in course.lessons_on(date, self.timezone) {
+						// A reminder that would go off before 1970 is long gone anyway
+						let when = start.offset(date, -(minutes as i64));
+						let (remind_date, remind_time) = match when {
+							Ok(when) => when,
+							Err(()) => continue,
+						};
+						reminders.push(Reminder {
+							date: remind_date,
+							time: remind_time,
+							course_index,
+							task_index: None,
+							focus: false,
+							// Courses don't have IDs like tasks do, so lessons go by their course's name
+							about: format!("lesson-{}", course.name),
+							title: format!("{} in {} minutes", course.name, minutes),
+							body: format!(
+								"{} to {} in {}, with {}",
+								self.locale.format_time(start),
+								self.locale.format_time(end),
+								course.room,
+								course.teacher
+							),
+						});
+					}
+				}
+			}
+
+			for (course_index, course) in self.courses.iter().enumerate() {
+				for (task_index, task) in course.tasks.iter().enumerate() {
+					if task.is_complete() || task.due > date {
+						continue;
+					}
+					// Overdue tasks get reminded about every day until they're done (or given a new due date)
+					if task.due < date {
+						let late = task.due.days_until(date);
+						reminders.push(Reminder {
+							date,
+							time: settings.task_time,
+							course_index,
+							task_index: Some(task_index),
+							focus: false,
+							about: format!("task-{}", task.id),
+							title: match late {
+								1 => format!("{} is 1 day overdue", task.name),
+								late => format!("{} is {} days overdue", task.name, late),
+							},
+							body: format!(
+								"{}, was due {}",
+								course.name,
+								self.locale.format_date(task.due)
+							),
+						});
+						continue;
+					}
+					let days = settings.task_days_for(task);
+					let remind_date = match date.offset(-(days as i64)) {
+						Ok(remind_date) => remind_date,
+						Err(()) => continue,
+					};
+					let title = match days {
+						0 => format!("{} is due today", task.name),
+						1 => format!("{} is due tomorrow", task.name),
+						days => format!("{} is due in {} days", task.name, days),
+					};
+					reminders.push(Reminder {
+						date: remind_date,
+						time: settings.task_time,
+						course_index,
+						task_index: Some(task_index),
+						focus: false,
+						about: format!("task-{}", task.id),
+						title,
+						body: format!("{}, due {}", course.name, self.locale.format_date(date)),
+					});
+				}
+			}
+
+			date = match date.offset(1) {
+				Ok(next) => next,
+				Err(()) => break,
+			};
+		}
+
+		reminders.retain(|reminder| {
+			let when = (reminder.date, reminder.time);
+			from < when && when <= to
+		});
+		// Every date and time can be compared, so unwrap() is fine
+		reminders.sort_by(|a, b| (a.date, a.time).partial_cmp(&(b.date, b.time)).unwrap());
+		reminders
+	}
+
+	/// Sends every reminder between `from` and `to` (see `reminders_between`) to `notifier`.
+	/// Returns how many were sent.
+	pub fn send_reminders(
+		&self,
+		from: (Date, Time),
+		to: (Date, Time),
+		notifier: &dyn Notifier,
+	) -> usize {
+		let reminders = self.reminders_between(from, to);
+		for reminder in &reminders {
+			notifier.notify(reminder);
+		}
+		reminders.len()
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+
+	fn date(year: u16, month: u8, day: u8) -> Date {
+		Date::new(year, month, day).unwrap()
+	}
+
+	fn time(hour: u8, minute: u8) -> Time {
+		Time::new(hour, minute).unwrap()
+	}
+
+	// 2020-01-08 is a Wednesday
+	// Math is at 09:00 every Wednesday, and has an essay due on Friday the 10th
+	fn application() -> Application {
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Wednesday, time(9, 0), time(10, 0));
+		math.add_task("Essay", "", date(2020, 1, 10));
+
+		let mut application = Application::default();
+		application.add_course(math);
+		application
+	}
+
+	struct Recorder(RefCell<Vec<String>>);
+
+	impl Notifier for Recorder {
+		fn notify(&self, reminder: &Reminder) {
+			self.0.borrow_mut().push(reminder.title.clone());
+		}
+	}
+
+	#[test]
+	fn lessons_and_tasks() {
+		let application = application();
+		let reminders = application.reminders_between(
+			(date(2020, 1, 8), time(0, 0)),
+			(date(2020, 1, 9), time(23, 59)),
+		);
+
+		assert_eq!(reminders.len(), 2);
+		assert_eq!(
+			(reminders[0].date, reminders[0].time),
+			(date(2020, 1, 8), time(8, 50))
+		);
+		assert_eq!(reminders[0].title, "Math in 10 minutes");
+		assert_eq!(
+			(reminders[1].date, reminders[1].time),
+			(date(2020, 1, 9), time(8, 0))
+		);
+		assert_eq!(reminders[1].title, "Essay is due tomorrow");
+		assert_eq!(reminders[1].task_index, Some(0));
+	}
+
+	#[test]
+	fn only_in_range() {
+		let application = application();
+		let wednesday = date(2020, 1, 8);
+
+		// The lesson's reminder is at 08:50, so just after that is too late, and exactly then is fine
+		assert!(application
+			.reminders_between((wednesday, time(8, 50)), (wednesday, time(12, 0)))
+			.is_empty());
+		assert_eq!(
+			application
+				.reminders_between((wednesday, time(8, 49)), (wednesday, time(8, 50)))
+				.len(),
+			1
+		);
+	}
+
+	#[test]
+	fn lead_times() {
+		let mut application = application();
+		application.courses[0].reminder_minutes = Some(24 * 60);
+		application.courses[0].tasks[0].reminder_days = Some(0);
+
+		// A whole day before the lesson is Tuesday, and the essay reminder is on the day it's due
+		let reminders = application.reminders_between(
+			(date(2020, 1, 7), time(0, 0)),
+			(date(2020, 1, 10), time(23, 59)),
+		);
+		assert_eq!(
+			(reminders[0].date, reminders[0].time),
+			(date(2020, 1, 7), time(9, 0))
+		);
+		assert_eq!(reminders[1].date, date(2020, 1, 10));
+		assert_eq!(reminders[1].title, "Essay is due today");
+	}
+
+	#[test]
+	fn lead_times_are_bounded() {
+		let mut application = application();
+		// These could only come from an old file, but they used to look through 30000 days and overflow
+		application.courses[0].reminder_minutes = Some(u32::MAX);
+		application.courses[0].tasks[0].reminder_days = Some(30000);
+		let settings = application.reminders;
+		assert_eq!(
+			settings.lesson_minutes_for(&application.courses[0]),
+			MAX_REMINDER_MINUTES
+		);
+		assert_eq!(
+			settings.task_days_for(&application.courses[0].tasks[0]),
+			MAX_REMINDER_DAYS
+		);
+
+		// The essay is reminded about a year before it's due, and no earlier
+		let essay: Vec<Date> = application
+			.reminders_between(
+				(date(2019, 1, 1), time(0, 0)),
+				(date(2019, 1, 10), time(23, 59)),
+			)
+			.into_iter()
+			.filter(|reminder| reminder.task_index.is_some())
+			.map(|reminder| reminder.date)
+			.collect();
+		assert_eq!(essay, vec![date(2019, 1, 10)]);
+	}
+
+	#[test]
+	fn overdue() {
+		let mut application = application();
+		application.courses[0].times.clear();
+		let range = (
+			(date(2020, 1, 11), time(0, 0)),
+			(date(2020, 1, 12), time(23, 59)),
+		);
+
+		let titles: Vec<String> = application
+			.reminders_between(range.0, range.1)
+			.into_iter()
+			.map(|reminder| reminder.title)
+			.collect();
+		assert_eq!(
+			titles,
+			vec!["Essay is 1 day overdue", "Essay is 2 days overdue"]
+		);
+
+		// Once it's snoozed, it's not overdue anymore, and it's reminded about before it's due again
+		application.courses[0].tasks[0].snooze(date(2020, 1, 13));
+		let reminders = application.reminders_between(range.0, range.1);
+		assert_eq!(reminders.len(), 1);
+		assert_eq!(reminders[0].title, "Essay is due tomorrow");
+	}
+
+	#[test]
+	fn skipped() {
+		let mut application = application();
+		let range = (
+			(date(2020, 1, 1), time(0, 0)),
+			(date(2020, 1, 31), time(0, 0)),
+		);
+
+		application.add_holiday(date(2020, 1, 8), date(2020, 1, 8));
+		application.courses[0].tasks[0].set_complete(true);
+		// Every other Wednesday still has a lesson
+		assert!(application
+			.reminders_between(range.0, range.1)
+			.iter()
+			.all(|reminder| reminder.task_index.is_none() && reminder.date != date(2020, 1, 8)));
+
+		application.reminders.enabled = false;
+		assert!(application.reminders_between(range.0, range.1).is_empty());
+	}
+
+	#[test]
+	fn ids() {
+		let mut application = application();
+		let range = (
+			(date(2020, 1, 8), time(0, 0)),
+			(date(2020, 1, 9), time(23, 59)),
+		);
+		let ids = |application: &Application| {
+			application
+				.reminders_between(range.0, range.1)
+				.iter()
+				.map(Reminder::id)
+				.collect::<Vec<_>>()
+		};
+
+		let before = ids(&application);
+		let task = application.courses[0].tasks[0].id;
+		assert_eq!(
+			before,
+			vec![
+				"lesson-Math-2020-01-08-08:50".to_string(),
+				format!("task-{}-2020-01-09", task)
+			]
+		);
+		// Adding a course in front moves Math along, but its reminders keep their names
+		application
+			.courses
+			.insert(0, Course::new("Art", "Hill", "A1"));
+		assert_eq!(ids(&application), before);
+	}
+
+	#[test]
+	fn notifiers() {
+		let application = application();
+		let recorder = Recorder(RefCell::new(Vec::new()));
+
+		let sent = application.send_reminders(
+			(date(2020, 1, 8), time(0, 0)),
+			(date(2020, 1, 8), time(9, 0)),
+			&recorder,
+		);
+		assert_eq!(sent, 1);
+		assert_eq!(recorder.0.into_inner(), vec!["Math in 10 minutes"]);
+	}
+}
@@ -0,0 +1,315 @@
+use super::*;
+
+// Searching works on words: "lab report" finds anything containing both "lab" and "report", in any order
+// Every course, task and step is one result at most, and results are ranked by how well they matched
+// Matching ignores case for English letters only, because lowercasing other letters can change their length
+// That would throw off the positions we use for highlighting, and nobody wants a panic in their search bar
+
+/// How much a match counts for, depending on where it was found.
+/// A match in a course's name is much more likely to be what you're after than one in a step.
+const WEIGHT_NAME: u32 = 10;
+const WEIGHT_TASK_NAME: u32 = 8;
+const WEIGHT_DETAIL: u32 = 4;
+const WEIGHT_STEP: u32 = 2;
+
+/// How many characters to show either side of the first match in a snippet.
+const SNIPPET_CONTEXT: usize = 30;
+
+/// A list of (start, end) byte positions of matches in some text.
+type Ranges = Vec<(usize, usize)>;
+
+// See application.rs to understand #[derive()]
+#[derive(PartialEq, Clone, Copy, Debug)]
+/// Which part of a course or task a search result was found in.
+pub enum Field {
+	CourseName,
+	Teacher,
+	Room,
+	TaskName,
+	TaskDesc,
+	Step,
+}
+
+impl Field {
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::CourseName => "Course",
+			Self::Teacher => "Teacher",
+			Self::Room => "Room",
+			Self::TaskName => "Task",
+			Self::TaskDesc => "Description",
+			Self::Step => "Step",
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+/// A course, task or step that matched a search.
+pub struct SearchResult {
+	pub course_index: usize,
+	/// Set if the result is a task, or a step in a task.
+	pub task_index: Option<usize>,
+	/// Set if the result is a step.
+	pub step_index: Option<usize>,
+	/// Where the best match was found.
+	pub field: Field,
+	/// Higher is better.
+	pub score: u32,
+	/// A short piece of the text around the match.
+	pub snippet: String,
+	/// The (start, end) byte positions in `snippet` that matched, for highlighting.
+	pub highlights: Ranges,
+}
+
+impl SearchResult {
+	/// Returns the snippet with every matching part passed through `matched` and everything else through `plain`.
+	/// For example, the GUI escapes everything and puts `<b>` tags around the matches.
+	pub fn highlighted<F, G>(&self, plain: F, matched: G) -> String
+	where
+		F: Fn(&str) -> String,
+		G: Fn(&str) -> String,
+	{
+		let mut out = String::new();
+		let mut last = 0;
+		for &(start, end) in &self.highlights {
+			out.push_str(&plain(&self.snippet[last..start]));
+			out.push_str(&matched(&self.snippet[start..end]));
+			last = end;
+		}
+		out.push_str(&plain(&self.snippet[last..]));
+		out
+	}
+}
+
+/// Finds every term in `text`, returning the merged (start, end) ranges and a score.
+/// Returns `None` if any term is missing, since every word has to be found.
+fn match_text(text: &str, terms: &[String], phrase: &str, weight: u32) -> Option<(u32, Ranges)> {
+	let haystack = text.to_ascii_lowercase();
+	let mut ranges = Vec::new();
+	let mut score = weight;
+
+	for term in terms {
+		let mut found = false;
+		for (start, _) in haystack.match_indices(term.as_str()) {
+			found = true;
+			ranges.push((start, start + term.len()));
+
+			// A word that starts with the term is a better match than one that just contains it
+			// "lab" should rank "Lab report" above "collaborate"
+			let at_word_start = haystack[..start]
+				.chars()
+				.last()
+				.filter(|c| c.is_alphanumeric())
+				.is_none();
+			if at_word_start {
+				score += 1;
+			}
+		}
+		if !found {
+			return None;
+		}
+	}
+
+	// Finding the whole search in one piece is better than finding the words scattered around
+	if terms.len() > 1 && haystack.contains(phrase) {
+		score += weight;
+	}
+	if haystack.starts_with(phrase) {
+		score += weight;
+	}
+
+	// Overlapping ranges are merged, so highlighting doesn't go wrong
+	ranges.sort();
+	let mut merged: Ranges = Vec::new();
+	for (start, end) in ranges {
+		match merged.last_mut() {
+			Some(last) if start <= last.1 => last.1 = last.1.max(end),
+			_ => merged.push((start, end)),
+		}
+	}
+
+	Some((score, merged))
+}
+
+/// Cuts `text` down to a snippet around the first range, and moves the ranges to match.
+fn snippet(text: &str, ranges: &[(usize, usize)]) -> (String, Ranges) {
+	let first = ranges.first().map_or(0, |range| range.0);
+
+	// We can only cut strings in between characters, not halfway through one
+	let mut start = first.saturating_sub(SNIPPET_CONTEXT);
+	while !text.is_char_boundary(start) {
+		start -= 1;
+	}
+	let mut end = (first + SNIPPET_CONTEXT * 2).min(text.len());
+	while !text.is_char_boundary(end) {
+		end += 1;
+	}
+
+	let prefix = if start > 0 { "..." } else { "" };
+	let suffix = if end < text.len() { "..." } else { "" };
+	let snippet = format!("{}{}{}", prefix, &text[start..end], suffix);
+
+	// Ranges that were cut off get trimmed, and ranges that were cut out get dropped
+	let moved = ranges
+		.iter()
+		.filter(|&&(range_start, range_end)| range_start < end && range_end > start)
+		.map(|&(range_start, range_end)| {
+			(
+				range_start.max(start) - start + prefix.len(),
+				range_end.min(end) - start + prefix.len(),
+			)
+		})
+		.collect();
+
+	(snippet, moved)
+}
+
+impl Application {
+	/// Searches every course, task and step for `text`, returning the results best first.
+	pub fn search(&self, text: &str) -> Vec<SearchResult> {
+		let phrase = text.trim().to_ascii_lowercase();
+		let terms: Vec<String> = phrase.split_whitespace().map(String::from).collect();
+		if terms.is_empty() {
+			return Vec::new();
+		}
+
+		let mut results = Vec::new();
+
+		// Adds the best matching field out of `fields` as a result, with the scores of all of them added up
+		let mut add = |course_index, task_index, step_index, fields: &[(Field, &str, u32)]| {
+			let mut total = 0;
+			let mut best: Option<(u32, Field, &str, Ranges)> = None;
+
+			for &(field, text, weight) in fields {
+				if let Some((score, ranges)) = match_text(text, &terms, &phrase, weight) {
+					total += score;
+					match &best {
+						Some(best) if best.0 >= score => (),
+						_ => best = Some((score, field, text, ranges)),
+					}
+				}
+			}
+
+			if let Some((_, field, text, ranges)) = best {
+				let (snippet, highlights) = snippet(text, &ranges);
+				results.push(SearchResult {
+					course_index,
+					task_index,
+					step_index,
+					field,
+					score: total,
+					snippet,
+					highlights,
+				});
+			}
+		};
+
+		for (course_index, course) in self.courses.iter().enumerate() {
+			add(
+				course_index,
+				None,
+				None,
+				&[
+					(Field::CourseName, &course.name, WEIGHT_NAME),
+					(Field::Teacher, &course.teacher, WEIGHT_DETAIL),
+					(Field::Room, &course.room, WEIGHT_DETAIL),
+				],
+			);
+
+			for (task_index, task) in course.tasks.iter().enumerate() {
+				add(
+					course_index,
+					Some(task_index),
+					None,
+					&[
+						(Field::TaskName, &task.name, WEIGHT_TASK_NAME),
+						(Field::TaskDesc, &task.desc, WEIGHT_DETAIL),
+					],
+				);
+
+				for (step_index, step) in task.steps.iter().enumerate() {
+					add(
+						course_index,
+						Some(task_index),
+						Some(step_index),
+						&[(Field::Step, &step.desc, WEIGHT_STEP)],
+					);
+				}
+			}
+		}
+
+		// sort_by is stable, so equally good results stay in the order they appear in the application
+		results.sort_by_key(|result| std::cmp::Reverse(result.score));
+		results
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn application() -> Application {
+		let due = Date::new(2020, 1, 1).unwrap();
+
+		let mut chemistry = Course::new("Chemistry", "Dr. Lab", "L2");
+		chemistry.add_task("Lab report", "Write up the titration", due);
+		chemistry.add_task("Revision", "Collaborate on the report for the lab", due);
+		chemistry.tasks[1].add_step("Read the lab manual");
+
+		let mut application = Application::default();
+		application.add_course(Course::new("Math", "Willis", "S37"));
+		application.add_course(chemistry);
+		application
+	}
+
+	#[test]
+	fn ranking() {
+		let results = application().search("lab report");
+
+		// The task called "Lab report" is the best match, and Math doesn't match at all
+		assert_eq!(results[0].task_index, Some(0));
+		assert_eq!(results[0].field, Field::TaskName);
+		assert!(results.iter().all(|result| result.course_index == 1));
+		// The step only mentions "lab", so it doesn't match both words
+		assert!(results.iter().all(|result| result.step_index.is_none()));
+	}
+
+	#[test]
+	fn every_field() {
+		let application = application();
+
+		assert_eq!(application.search("willis")[0].field, Field::Teacher);
+		assert_eq!(application.search("S37")[0].field, Field::Room);
+		assert_eq!(application.search("titration")[0].field, Field::TaskDesc);
+		assert_eq!(application.search("manual")[0].step_index, Some(0));
+		assert!(application.search("   ").is_empty());
+	}
+
+	#[test]
+	fn highlighting() {
+		let results = application().search("TITRATION up");
+		let marked = results[0].highlighted(|text| text.to_string(), |text| format!("[{}]", text));
+
+		assert_eq!(marked, "Write [up] the [titration]");
+	}
+
+	#[test]
+	fn long_snippets() {
+		let mut application = Application::default();
+		let mut course = Course::default();
+		let desc = format!("{} needle {}", "é".repeat(40), "x".repeat(80));
+		course.add_task("", desc.as_str(), Date::new(2020, 1, 1).unwrap());
+		application.add_course(course);
+
+		let result = &application.search("needle")[0];
+		assert!(result.snippet.starts_with("..."));
+		assert!(result.snippet.ends_with("..."));
+		assert_eq!(result.highlighted(|_| String::new(), |text| text.to_string()), "needle");
+	}
+}
@@ -0,0 +1,367 @@
+use super::*;
+use task::Task;
+
+// Steps can have sub-steps, which can have sub-steps of their own, like an outline
+// They're still kept in one flat list, with each step knowing how deep it is, so every step still has one index
+// A step's sub-steps are the ones right after it that are deeper than it, like this:
+//
+// 0  Research          (depth 0)
+// 1    Find sources    (depth 1, a sub-step of 0)
+// 2    Take notes      (depth 1, a sub-step of 0)
+// 3  Write             (depth 0)
+//
+// A step with sub-steps is done once all of them are, which `roll_up` keeps up to date
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+/// One step of a task, like "Write the introduction".
+pub struct Step {
+	pub desc: String,
+	pub complete: bool,
+	/// How much this step counts towards the task's progress, compared to its other steps, or `None` for 1.
+	pub weight: Option<u32>,
+	/// When this step should be done by, if it's earlier than when the whole task is due.
+	pub due: Option<Date>,
+	/// How many steps this is inside of. 0 is a step of the task itself.
+	pub depth: usize,
+}
+
+impl Step {
+	pub fn new<T: Into<String>>(desc: T) -> Self {
+		Self {
+			desc: desc.into(),
+			complete: false,
+			weight: None,
+			due: None,
+			depth: 0,
+		}
+	}
+
+	/// Returns how much this step counts towards the task's progress.
+	pub fn weight(&self) -> u32 {
+		self.weight.unwrap_or(1)
+	}
+
+	/// Returns whether it's still not complete after the day it should have been done by, on `today`.
+	pub fn is_overdue(&self, today: Date) -> bool {
+		match self.due {
+			Some(due) => !self.complete && due < today,
+			None => false,
+		}
+	}
+}
+
+impl Default for Step {
+	fn default() -> Self {
+		Self::new("Description")
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// Where to move a step to, next to or inside the step at this index.
+pub enum StepPlace {
+	/// Just before it, as deep as it is.
+	Before(usize),
+	/// Just after it (and its sub-steps), as deep as it is.
+	After(usize),
+	/// As its first sub-step.
+	Into(usize),
+}
+
+impl Task {
+	/// Returns the indexes of the step at `index`'s sub-steps, and theirs, which always come right after it.
+	pub fn sub_steps(&self, index: usize) -> std::ops::Range<usize> {
+		let depth = self.steps[index].depth;
+		let end = self.steps[index + 1..]
+			.iter()
+			.position(|step| step.depth <= depth)
+			.map(|position| index + 1 + position)
+			.unwrap_or_else(|| self.steps.len());
+		index + 1..end
+	}
+
+	/// Returns the indexes of the steps directly inside the step at `parent`, or of the task itself if it's `None`.
+	pub fn children(&self, parent: Option<usize>) -> Vec<usize> {
+		let (range, depth) = match parent {
+			Some(index) => (self.sub_steps(index), self.steps[index].depth + 1),
+			None => (0..self.steps.len(), 0),
+		};
+		range
+			.filter(|&index| self.steps[index].depth == depth)
+			.collect()
+	}
+
+	/// Returns the index of the step that the step at `index` is inside of, if it's a sub-step.
+	pub fn parent_step(&self, index: usize) -> Option<usize> {
+		let depth = self.steps[index].depth;
+		self.steps[..index]
+			.iter()
+			.rposition(|step| step.depth < depth)
+	}
+
+	/// Returns the index of the step before the one at `index` that's inside the same step, if there is one.
+	fn previous_sibling(&self, index: usize) -> Option<usize> {
+		let depth = self.steps[index].depth;
+		let before = self.steps[..index]
+			.iter()
+			.rposition(|step| step.depth <= depth)?;
+		if self.steps[before].depth == depth {
+			Some(before)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the index of the step after the one at `index` that's inside the same step, if there is one.
+	fn next_sibling(&self, index: usize) -> Option<usize> {
+		let after = self.sub_steps(index).end;
+		match self.steps.get(after) {
+			Some(step) if step.depth == self.steps[index].depth => Some(after),
+			_ => None,
+		}
+	}
+
+	/// Adds a new step as the last sub-step of the step at `index`.
+	pub fn add_sub_step<T: Into<String>>(&mut self, index: usize, description: T) {
+		let mut step = Step::new(description);
+		step.depth = self.steps[index].depth + 1;
+		self.steps.insert(self.sub_steps(index).end, step);
+		self.roll_up();
+	}
+
+	/// Moves the step at `index`, along with its sub-steps, to `place`, and returns where it ended up.
+	/// A step can't be moved into itself, so that's an error.
+	pub fn move_step(&mut self, index: usize, place: StepPlace) -> Result<usize, ()> {
+		let block = index..self.sub_steps(index).end;
+		let target = match place {
+			StepPlace::Before(target) | StepPlace::After(target) | StepPlace::Into(target) => {
+				target
+			}
+		};
+		if target >= self.steps.len() || block.contains(&target) {
+			return Err(());
+		}
+
+		let mut moved: Vec<Step> = self.steps.drain(block.clone()).collect();
+		// Taking the steps out moves everything after them back
+		let target = if target > index {
+			target - moved.len()
+		} else {
+			target
+		};
+		let (position, depth) = match place {
+			StepPlace::Before(_) => (target, self.steps[target].depth),
+			StepPlace::After(_) => (self.sub_steps(target).end, self.steps[target].depth),
+			StepPlace::Into(_) => (target + 1, self.steps[target].depth + 1),
+		};
+
+		// The sub-steps stay inside the step, however deep it ends up
+		let old_depth = moved[0].depth;
+		for step in &mut moved {
+			step.depth = step.depth - old_depth + depth;
+		}
+		self.steps.splice(position..position, moved);
+		self.roll_up();
+		Ok(position)
+	}
+
+	/// Moves the step at `index` (and its sub-steps) before the step before it, if there's one inside the same step.
+	pub fn move_step_up(&mut self, index: usize) -> Result<usize, ()> {
+		let before = self.previous_sibling(index).ok_or(())?;
+		self.move_step(index, StepPlace::Before(before))
+	}
+
+	/// Moves the step at `index` (and its sub-steps) after the step after it, if there's one inside the same step.
+	pub fn move_step_down(&mut self, index: usize) -> Result<usize, ()> {
+		let after = self.next_sibling(index).ok_or(())?;
+		self.move_step(index, StepPlace::After(after))
+	}
+
+	/// Makes the step at `index` a sub-step of the step before it, which has to be as deep as it is.
+	/// Like moving, it returns where the step is, which never changes here.
+	pub fn indent_step(&mut self, index: usize) -> Result<usize, ()> {
+		self.previous_sibling(index).ok_or(())?;
+		// It's already right after the step before it, so it only has to go one deeper
+		for step_index in index..self.sub_steps(index).end {
+			self.steps[step_index].depth += 1;
+		}
+		self.roll_up();
+		Ok(index)
+	}
+
+	/// Takes the step at `index` out of the step it's inside, putting it right after that one.
+	pub fn outdent_step(&mut self, index: usize) -> Result<usize, ()> {
+		let parent = self.parent_step(index).ok_or(())?;
+		self.move_step(index, StepPlace::After(parent))
+	}
+
+	/// Ticks off every step with sub-steps if all of them are done, and un-ticks it if they aren't.
+	/// Call this after changing steps directly, since everything else does.
+	pub fn roll_up(&mut self) {
+		// Going backwards, a step's sub-steps are always rolled up before it is
+		for index in (0..self.steps.len()).rev() {
+			let children = self.children(Some(index));
+			if !children.is_empty() {
+				self.steps[index].complete =
+					children.iter().all(|&child| self.steps[child].complete);
+			}
+		}
+	}
+
+	/// Returns how much of the steps at `indexes` are done, from 0 to 1, counting each one as much as its weight.
+	/// Returns `None` if they don't weigh anything, so there's nothing to count.
+	pub(super) fn done_fraction(&self, indexes: &[usize]) -> Option<f64> {
+		let total: u32 = indexes
+			.iter()
+			.map(|&index| self.steps[index].weight())
+			.sum();
+		if total == 0 {
+			return None;
+		}
+		let done: f64 = indexes
+			.iter()
+			.map(|&index| {
+				let step = &self.steps[index];
+				// A step with sub-steps is as done as they are
+				let children = self.children(Some(index));
+				let done =
+					self.done_fraction(&children)
+						.unwrap_or(if step.complete { 1.0 } else { 0.0 });
+				done * step.weight() as f64
+			})
+			.sum();
+		Some(done / total as f64)
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Research > (Find sources, Take notes), Write, Proofread
+	fn task() -> Task {
+		let mut task = Task::new("Essay", "", Date::new(2020, 1, 10).unwrap());
+		task.add_step("Research");
+		task.add_step("Write");
+		task.add_step("Proofread");
+		task.add_sub_step(0, "Find sources");
+		task.add_sub_step(0, "Take notes");
+		task
+	}
+
+	fn outline(task: &Task) -> Vec<(usize, &str)> {
+		task.steps
+			.iter()
+			.map(|step| (step.depth, step.desc.as_str()))
+			.collect()
+	}
+
+	#[test]
+	fn nesting() {
+		let task = task();
+		assert_eq!(
+			outline(&task),
+			vec![
+				(0, "Research"),
+				(1, "Find sources"),
+				(1, "Take notes"),
+				(0, "Write"),
+				(0, "Proofread"),
+			]
+		);
+		assert_eq!(task.sub_steps(0), 1..3);
+		assert_eq!(task.children(None), vec![0, 3, 4]);
+		assert_eq!(task.children(Some(0)), vec![1, 2]);
+		assert_eq!(task.parent_step(2), Some(0));
+		assert_eq!(task.parent_step(3), None);
+	}
+
+	#[test]
+	fn rolling_up() {
+		let mut task = task();
+
+		// Ticking both sub-steps ticks the step they're in, and a third of the task is done
+		task.toggle_step(1);
+		assert!(!task.steps[0].complete);
+		assert_eq!(task.progress(), 16);
+		task.toggle_step(2);
+		assert!(task.steps[0].complete);
+		assert_eq!(task.progress(), 33);
+
+		// Un-ticking a step with sub-steps un-ticks all of them
+		task.toggle_step(0);
+		assert!(task.steps[..3].iter().all(|step| !step.complete));
+
+		// A new sub-step isn't done yet, so neither is the step it's in
+		task.toggle_step(3);
+		task.add_sub_step(3, "Introduction");
+		assert!(!task.steps[3].complete);
+	}
+
+	#[test]
+	fn moving() {
+		let mut task = task();
+
+		// Research moves with its sub-steps
+		task.move_step(0, StepPlace::After(4)).unwrap();
+		assert_eq!(
+			outline(&task),
+			vec![
+				(0, "Write"),
+				(0, "Proofread"),
+				(0, "Research"),
+				(1, "Find sources"),
+				(1, "Take notes"),
+			]
+		);
+
+		// A step can't go inside itself
+		assert!(task.move_step(2, StepPlace::Into(3)).is_err());
+
+		assert_eq!(task.move_step(1, StepPlace::Into(4)), Ok(4));
+		assert_eq!(
+			task.steps[4],
+			Step {
+				depth: 2,
+				..Step::new("Proofread")
+			}
+		);
+
+		// Take notes is moved with Proofread inside it, but only ever among the other steps inside Research
+		task.move_step_up(3).unwrap();
+		assert_eq!(task.steps[2].desc, "Take notes");
+		assert!(task.move_step_up(2).is_err());
+		task.move_step_down(2).unwrap();
+		assert_eq!(task.steps[2].desc, "Find sources");
+	}
+
+	#[test]
+	fn indenting() {
+		let mut task = task();
+
+		// Write goes inside Research, after its other sub-steps
+		task.indent_step(3).unwrap();
+		assert_eq!(task.children(Some(0)), vec![1, 2, 3]);
+		// Find sources is first inside Research, so there's nothing to go inside
+		assert!(task.indent_step(1).is_err());
+
+		task.outdent_step(1).unwrap();
+		assert_eq!(
+			outline(&task),
+			vec![
+				(0, "Research"),
+				(1, "Take notes"),
+				(1, "Write"),
+				(0, "Find sources"),
+				(0, "Proofread"),
+			]
+		);
+		assert!(task.outdent_step(0).is_err());
+	}
+}
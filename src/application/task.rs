@@ -1,67 +1,229 @@
 use super::*;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use step::Step;
+use time_log::TimeEntry;
 
 // See application.rs to understand #[derive()]
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Eq and Ord are like PartialEq and PartialOrd, but promise that every priority can be compared
+// That lets us use them for sorting, since Urgent > High > Normal > Low
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+/// How important a task is.
+pub enum Priority {
+	Low,
+	Normal,
+	High,
+	Urgent,
+}
+
+impl Priority {
+	pub fn all() -> [Self; 4] {
+		[Self::Low, Self::Normal, Self::High, Self::Urgent]
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Low => "Low",
+			Self::Normal => "Normal",
+			Self::High => "High",
+			Self::Urgent => "Urgent",
+		}
+	}
+}
+
+// See the same implementation for Day in the clock module
+impl TryFrom<String> for Priority {
+	type Error = ();
+	fn try_from(string: String) -> Result<Self, ()> {
+		match string.trim().to_lowercase().as_str() {
+			"low" => Ok(Self::Low),
+			"normal" => Ok(Self::Normal),
+			"high" => Ok(Self::High),
+			"urgent" => Ok(Self::Urgent),
+			_ => Err(()),
+		}
+	}
+}
+
+impl fmt::Display for Priority {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 /// This struct contains all the data necessary for a task (in a course).
 pub struct Task {
 	pub name: String,
 	pub desc: String,
 	// "due: Date" sounds nice, doesn't it?
 	pub due: Date,
-	/// A list of the steps for that task, as in (is it completed, name of step).
-	pub steps: Vec<(bool, String)>,
+	/// The steps for that task, if it's been split up into any.
+	pub steps: Vec<Step>,
+	/// Whether the task is done, for tasks without any steps. Tasks with steps are done when all of them are.
+	pub complete: bool,
+	pub priority: Priority,
+	/// Free-form labels, like "exam" or "group project".
+	pub tags: Vec<String>,
+	/// How many days before it's due to remind about it, or `None` for the application's setting.
+	pub reminder_days: Option<u32>,
+	/// When it was first due, if it's been given a new due date since (see `snooze`).
+	pub original_due: Option<Date>,
+	/// What other tasks use to point at this one, which (unlike its index) doesn't change when tasks are removed.
+	pub id: u64,
+	/// The IDs of the tasks that have to be done before this one can be started, in any course.
+	pub depends_on: Vec<u64>,
+	/// How long it's been worked on, one entry per timer run or time logged by hand.
+	pub time_spent: Vec<TimeEntry>,
+	/// When its timer was started, if it's running (see `start_timer`).
+	pub timer: Option<(Date, Time)>,
+	/// How many minutes it's expected to take, if anyone's guessed.
+	pub estimate: Option<u32>,
 }
 
 impl Task {
 	pub fn new<T: Into<String>>(name: T, desc: T, due: Date) -> Self {
-		// Creates a dummy step, because is_complete() depends on there being steps in the task
-		let steps = vec![(false, "Completed?".to_string())];
 		Self {
 			name: name.into(),
 			desc: desc.into(),
 			due,
-			steps,
+			steps: Vec::new(),
+			complete: false,
+			priority: Priority::Normal,
+			tags: Vec::new(),
+			reminder_days: None,
+			original_due: None,
+			id: new_id(),
+			depends_on: Vec::new(),
+			time_spent: Vec::new(),
+			timer: None,
+			estimate: None,
 		}
 	}
 
+	/// Checks if the task has a tag, ignoring case.
+	pub fn has_tag(&self, tag: &str) -> bool {
+		has_tag(&self.tags, tag)
+	}
+
 	/// Returns whether the entire task is complete.
-	/// Checks all the steps and returns whether or not there are any incomplete steps.
+	/// Without any steps, it's whatever it was marked as, otherwise it's whether all the steps are complete.
 	pub fn is_complete(&self) -> bool {
-		// Functional programming for the win!
-		// Checks if any() steps are !complete
-		// If even one step is incomplete, then this function will return false
-		!self.steps.iter().any(|(complete, _)| !complete)
+		if self.steps.is_empty() {
+			self.complete
+		} else {
+			// Functional programming for the win!
+			// If even one step is incomplete, then this function will return false
+			self.steps.iter().all(|step| step.complete)
+		}
+	}
+
+	/// Returns how much of the task is done, as a percentage from 0 to 100.
+	/// Each step counts as much as its weight, so a step with a weight of 2 is worth two normal ones,
+	/// and a step with sub-steps is as done as they are.
+	pub fn progress(&self) -> u32 {
+		match self.done_fraction(&self.children(None)) {
+			// A tiny bit is added, so rounding errors like 56.99999 still count as 57
+			Some(done) => (done * 100.0 + 1e-9) as u32,
+			// Without steps (or if every step weighs nothing), there's only done or not done
+			None if self.is_complete() => 100,
+			None => 0,
+		}
+	}
+
+	/// Marks the task, and every step, as done or as not done.
+	pub fn set_complete(&mut self, complete: bool) {
+		self.complete = complete;
+		for step in &mut self.steps {
+			step.complete = complete;
+		}
+	}
+
+	/// Returns the steps that aren't complete after the day they should have been done by, on `today`.
+	pub fn overdue_steps(&self, today: Date) -> Vec<&Step> {
+		self.steps
+			.iter()
+			.filter(|step| step.is_overdue(today))
+			.collect()
+	}
+
+	/// Returns whether it's still not complete after the day it was due, on `today`.
+	pub fn is_overdue(&self, today: Date) -> bool {
+		!self.is_complete() && self.due < today
+	}
+
+	/// Returns how many days late it is on `today`, or `None` if it isn't overdue.
+	pub fn days_overdue(&self, today: Date) -> Option<i64> {
+		if self.is_overdue(today) {
+			Some(self.due.days_until(today))
+		} else {
+			None
+		}
+	}
+
+	/// Gives the task a new due date, remembering when it was first due.
+	/// Snoozing it again keeps the first date, and snoozing it back to that date forgets it.
+	pub fn snooze(&mut self, due: Date) {
+		let original = self.original_due.unwrap_or(self.due);
+		self.original_due = if original == due {
+			None
+		} else {
+			Some(original)
+		};
+		self.due = due;
+	}
+
+	/// Returns a word or two about how the task's going on `today`, like "Overdue".
+	/// It's "Behind" if one of its steps is overdue, even if the whole task isn't yet.
+	pub fn status(&self, today: Date) -> &str {
+		if self.is_complete() {
+			"Done"
+		} else if self.is_overdue(today) {
+			"Overdue"
+		} else if self.due == today {
+			"Due today"
+		} else if !self.overdue_steps(today).is_empty() {
+			"Behind"
+		} else {
+			"Not Done"
+		}
 	}
 
 	/// Sets a step as complete or not complete.
+	/// Ticking a step with sub-steps ticks all of them too, and un-ticking it un-ticks them.
 	pub fn toggle_step(&mut self, index: usize) {
-		if let Some((complete, _)) = self.steps.get_mut(index) {
-			*complete = !*complete;
+		if index >= self.steps.len() {
+			return;
+		}
+		let complete = !self.steps[index].complete;
+		for step_index in index..self.sub_steps(index).end {
+			self.steps[step_index].complete = complete;
 		}
+		self.roll_up();
 	}
 
 	/// Add a step to the task, incomplete by default.
-	/// If it's the first step in the task, removes the dummy step.
 	pub fn add_step<T: Into<String>>(&mut self, description: T) {
-		// Causes a bug! GUI module is limited here
-		// if self.steps[0] == (false, "Completed?".to_string()) {
-		// 	self.steps.remove(0);
-		// }
-		self.steps.push((false, description.into()));
+		self.steps.push(Step::new(description));
 	}
 
 	/// Create a new, default step
 	pub fn new_step(&mut self) {
-		self.add_step("Description");
+		self.steps.push(Step::default());
 	}
 
-	/// Removes a step to the task.
-	/// If it's the last step in the task, adds a new dummy step.
+	/// Removes a step from the task, along with its sub-steps.
+	/// If it was the last step, the task stays as complete as it was with it.
 	pub fn rm_step(&mut self, index: usize) {
-		self.steps.remove(index);
+		let complete = self.is_complete();
+		self.steps.drain(index..self.sub_steps(index).end);
 		if self.steps.is_empty() {
-			self.steps.push((false, "Completed?".to_string()))
+			self.complete = complete;
 		}
+		self.roll_up();
 	}
 
 	/// Returns a string based on whether it's complete or not.
@@ -75,6 +237,45 @@ impl Task {
 	}
 }
 
+/// Splits text like "exam, Group Project" into tags, ignoring empty ones.
+/// Used wherever tags are typed in, so they're always stored the same way.
+pub fn parse_tags(text: &str) -> Vec<String> {
+	text.split(',')
+		.map(str::trim)
+		.filter(|tag| !tag.is_empty())
+		.map(String::from)
+		.collect()
+}
+
+/// Parses a number typed in, where nothing typed means `None`, and anything over `max` is an error.
+/// Used for settings like `Task::reminder_days`, where blank means "use the default".
+pub fn parse_optional_number(text: &str, max: u32) -> Result<Option<u32>, ()> {
+	let text = text.trim();
+	if text.is_empty() {
+		return Ok(None);
+	}
+	match text.parse() {
+		Ok(number) if number <= max => Ok(Some(number)),
+		_ => Err(()),
+	}
+}
+
+/// Checks if `tags` contains `tag`, ignoring case.
+pub(super) fn has_tag(tags: &[String], tag: &str) -> bool {
+	tags.iter().any(|other| other.to_lowercase() == tag.to_lowercase())
+}
+
+/// Returns an ID for a new task, which no other task has.
+fn new_id() -> u64 {
+	// The time keeps IDs from different runs apart, and the counter keeps tasks made at the same moment apart
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	let nanos = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_nanos() as u64)
+		.unwrap_or_default();
+	nanos.wrapping_add(COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
 impl Default for Task {
 	fn default() -> Self {
 		Self::new("Name", "Description", Date::today())
@@ -95,13 +296,89 @@ mod tests {
 	fn is_complete() {
 		let mut task = Task::new("", "", Date::new(2000, 1, 1).unwrap());
 		assert!(!task.is_complete());
-		task.steps = vec![(true, "".to_string())];
+		task.set_complete(true);
+		assert!(task.is_complete());
+
+		// With steps, it's only done once they all are
+		task.add_step("Research");
+		assert!(!task.is_complete());
+		task.toggle_step(0);
+		assert!(task.is_complete());
+
+		// Without them again, it's as done as it was
+		task.rm_step(0);
 		assert!(task.is_complete());
 	}
 
+	#[test]
+	fn tags() {
+		let mut task = Task::new("", "", Date::new(2000, 1, 1).unwrap());
+		task.tags = parse_tags("exam,  Group Project ,,");
+		assert_eq!(task.tags, vec!["exam", "Group Project"]);
+		assert!(task.has_tag("group project"));
+		assert!(!task.has_tag("homework"));
+	}
+
+	#[test]
+	fn progress() {
+		let mut task = Task::new("", "", Date::new(2000, 1, 5).unwrap());
+		assert_eq!(task.progress(), 0);
+		task.add_step("Research");
+		task.add_step("Write");
+		task.add_step("Proofread");
+		task.steps[1].weight = Some(2);
+		task.toggle_step(1);
+		assert_eq!(task.progress(), 50);
+		task.toggle_step(2);
+		assert_eq!(task.progress(), 75);
+
+		let today = Date::new(2000, 1, 6).unwrap();
+		assert!(task.is_overdue(today));
+		assert_eq!(task.status(today), "Overdue");
+		assert_eq!(task.status(task.due), "Due today");
+
+		task.set_complete(true);
+		assert_eq!(task.progress(), 100);
+		assert!(!task.is_overdue(today));
+		assert_eq!(task.status(today), "Done");
+	}
+
+	#[test]
+	fn step_due_dates() {
+		let mut task = Task::new("", "", Date::new(2000, 1, 10).unwrap());
+		task.add_step("Research");
+		task.add_step("Write");
+		task.steps[0].due = Some(Date::new(2000, 1, 3).unwrap());
+
+		let today = Date::new(2000, 1, 4).unwrap();
+		assert_eq!(task.overdue_steps(today), vec![&task.steps[0]]);
+		assert_eq!(task.status(today), "Behind");
+		task.toggle_step(0);
+		assert!(task.overdue_steps(today).is_empty());
+		assert_eq!(task.status(today), "Not Done");
+	}
+
+	#[test]
+	fn snooze() {
+		let first = Date::new(2000, 1, 5).unwrap();
+		let mut task = Task::new("", "", first);
+		assert_eq!(task.days_overdue(Date::new(2000, 1, 8).unwrap()), Some(3));
+
+		task.snooze(first + 7);
+		task.snooze(first + 14);
+		assert_eq!(task.due, first + 14);
+		assert_eq!(task.original_due, Some(first));
+		assert_eq!(task.days_overdue(Date::new(2000, 1, 8).unwrap()), None);
+
+		// Back to when it was first due, so it was never really snoozed
+		task.snooze(first);
+		assert_eq!(task.original_due, None);
+	}
+
 	#[test]
 	fn toggle() {
 		let mut task = Task::new("", "", Date::new(2000, 1, 1).unwrap());
+		task.add_step("Research");
 		assert!(!task.is_complete());
 		task.toggle_step(0);
 		assert!(task.is_complete());
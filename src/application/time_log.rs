@@ -0,0 +1,354 @@
+use super::*;
+use std::convert::TryFrom;
+
+// Time is kept in whole minutes, which is as exact as anyone needs for homework
+// A timer is just the moment it was started, saved with the task, so it keeps running between runs of the CLI
+// Only one timer runs at once, since nobody works on two tasks at the same time (however much they'd like to)
+
+// See application.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+/// Some time spent on a task.
+pub struct TimeEntry {
+	/// The day it was spent on. For a timer that ran past midnight, it's the day it started.
+	pub date: Date,
+	pub minutes: u32,
+}
+
+impl Task {
+	/// Returns how many minutes have been spent on the task altogether, not counting a timer that's still running.
+	pub fn total_minutes(&self) -> u32 {
+		sum_minutes(self.time_spent.iter().map(|entry| entry.minutes))
+	}
+
+	/// Returns how many minutes were spent on the task between `from` and `to`, including both.
+	pub fn minutes_between(&self, from: Date, to: Date) -> u32 {
+		sum_minutes(
+			self.time_spent
+				.iter()
+				.filter(|entry| entry.date.is_between(from, to))
+				.map(|entry| entry.minutes),
+		)
+	}
+
+	/// Returns how many minutes are left of the estimate, which is negative once it's taken longer than that.
+	pub fn minutes_left(&self) -> Option<i64> {
+		self.estimate
+			.map(|estimate| estimate as i64 - self.total_minutes() as i64)
+	}
+
+	/// Starts the timer at `now`, unless it's already running.
+	pub fn start_timer(&mut self, now: (Date, Time)) {
+		if self.timer.is_none() {
+			self.timer = Some(now);
+		}
+	}
+
+	/// Stops the timer at `now`, and adds the time since it started, which it returns.
+	/// A timer stopped straight away doesn't add anything.
+	pub fn stop_timer(&mut self, now: (Date, Time)) -> Option<TimeEntry> {
+		let (date, time) = self.timer.take()?;
+		let minutes = time.minutes_until(date, now.1, now.0);
+		if minutes <= 0 {
+			return None;
+		}
+		// A timer left running for years is still only as much as fits
+		let entry = TimeEntry {
+			date,
+			minutes: u32::try_from(minutes).unwrap_or(u32::MAX),
+		};
+		self.time_spent.push(entry);
+		Some(entry)
+	}
+}
+
+impl Application {
+	/// Returns (course index, task index) of the task whose timer is running, if there is one.
+	pub fn running_timer(&self) -> Option<(usize, usize)> {
+		self.tasks(&TaskQuery::new().sort_by(TaskSort::Course))
+			.iter()
+			.find(|entry| entry.task.timer.is_some())
+			.map(|entry| (entry.course_index, entry.task_index))
+	}
+
+	/// Starts the timer of the task at (course index, task index) at `now`, as an edit that can be undone.
+	/// If another task's timer is running, it's stopped first, in the same edit.
+	pub fn start_timer(&mut self, course_index: usize, task_index: usize, now: (Date, Time)) {
+		let mut tasks = vec![(course_index, task_index)];
+		match self.running_timer() {
+			Some(running) if running == (course_index, task_index) => return,
+			Some(running) => tasks.push(running),
+			None => (),
+		}
+		let label = format!(
+			"Start timer on '{}'",
+			self.courses[course_index].tasks[task_index].name
+		);
+		self.edit_tasks(&tasks, label, |course, task_indexes| {
+			for index in task_indexes {
+				let task = &mut course.tasks[*index];
+				if task.timer.is_some() {
+					task.stop_timer(now);
+				} else {
+					task.start_timer(now);
+				}
+			}
+		});
+	}
+
+	/// Stops whichever timer is running at `now`, as an edit that can be undone.
+	/// Returns where the task is and the time that was added to it, or `None` if no timer was running.
+	pub fn stop_timer(&mut self, now: (Date, Time)) -> Option<((usize, usize), TimeEntry)> {
+		let (course_index, task_index) = self.running_timer()?;
+		// Working out the entry on a copy first, so it can be returned
+		let mut task = self.courses[course_index].tasks[task_index].clone();
+		let entry = task.stop_timer(now);
+		let label = format!("Stop timer on '{}'", task.name);
+		self.edit_task(course_index, task_index, label, |old| *old = task);
+		entry.map(|entry| ((course_index, task_index), entry))
+	}
+
+	/// Adds `minutes` spent on `date` to the task at (course index, task index), as an edit that can be undone.
+	pub fn log_time(&mut self, course_index: usize, task_index: usize, date: Date, minutes: u32) {
+		let label = format!(
+			"Log {} on '{}'",
+			format_minutes(minutes),
+			self.courses[course_index].tasks[task_index].name
+		);
+		self.edit_task(course_index, task_index, label, |task| {
+			task.time_spent.push(TimeEntry { date, minutes })
+		});
+	}
+
+	/// Returns how many minutes were spent on each course between `from` and `to`, in the same order as the courses.
+	pub fn minutes_per_course(&self, from: Date, to: Date) -> Vec<u32> {
+		self.courses
+			.iter()
+			.map(|course| {
+				sum_minutes(
+					course
+						.tasks
+						.iter()
+						.map(|task| task.minutes_between(from, to)),
+				)
+			})
+			.collect()
+	}
+
+	/// Returns the minutes spent on each course in every week from the one `from` is in to the one `to` is in,
+	/// as (the first day of the week, the minutes for each course).
+	pub fn minutes_per_week(&self, from: Date, to: Date) -> Vec<(Date, Vec<u32>)> {
+		let mut weeks = Vec::new();
+		// The very first week there is started before 1970, so it's cut short
+		let mut start = self.locale.start_of_week(from).unwrap_or(Date::FIRST);
+		while start <= to {
+			let end = self.locale.end_of_week(start);
+			let minutes = self.minutes_per_course(start, end.unwrap_or(Date::LAST));
+			weeks.push((start, minutes));
+			start = match end.and_then(|end| end.offset(1)) {
+				Ok(next) => next,
+				Err(()) => break,
+			};
+		}
+		weeks
+	}
+}
+
+/// The longest time that can be typed in at once, which is a thousand hours.
+/// Nobody spends longer than that on one piece of homework, so anything more is a typo.
+pub const MAX_MINUTES: u32 = 1000 * 60;
+
+/// Adds up minutes, stopping at the most a `u32` can hold instead of overflowing.
+/// Each entry is at most `MAX_MINUTES` when it's typed in, but there's no limit to how many there are.
+fn sum_minutes<I: Iterator<Item = u32>>(minutes: I) -> u32 {
+	minutes.fold(0, u32::saturating_add)
+}
+
+/// Reads a length of time, like "90", "45m", "1h 30m", "1.5h" or "1:30", as minutes.
+/// A number on its own is minutes. Anything over `MAX_MINUTES` is an error.
+pub fn parse_minutes(text: &str) -> Result<u32, ()> {
+	let text: String = text
+		.to_lowercase()
+		.chars()
+		.filter(|c| !c.is_whitespace())
+		.collect();
+	if text.is_empty() {
+		return Err(());
+	}
+
+	// "1:30" is hours and minutes, like on a clock
+	if let Some(colon) = text.find(':') {
+		let hours: u32 = text[..colon].parse().map_err(|_| ())?;
+		let minutes: u32 = text[colon + 1..].parse().map_err(|_| ())?;
+		// "1:75" isn't a time on any clock
+		if minutes >= 60 {
+			return Err(());
+		}
+		return hours
+			.checked_mul(60)
+			.and_then(|hours| hours.checked_add(minutes))
+			.filter(|&total| total <= MAX_MINUTES)
+			.ok_or(());
+	}
+
+	// Otherwise it's hours before an 'h' (which can have a decimal point), then minutes with or without an 'm'
+	let (hours, minutes) = match text.find('h') {
+		Some(h) => (&text[..h], &text[h + 1..]),
+		None => ("", text.as_str()),
+	};
+	let minutes = minutes.trim_end_matches('m');
+	let hours: f64 = if hours.is_empty() {
+		0.0
+	} else {
+		hours.parse().map_err(|_| ())?
+	};
+	let minutes: u32 = if minutes.is_empty() {
+		0
+	} else {
+		minutes.parse().map_err(|_| ())?
+	};
+	// "inf" and "NaN" parse as numbers too, so they have to be ruled out here
+	// Checking against the limit before turning it into a u32 means it can't overflow either
+	if !hours.is_finite() || hours < 0.0 || hours * 60.0 > MAX_MINUTES as f64 {
+		return Err(());
+	}
+	((hours * 60.0).round() as u32)
+		.checked_add(minutes)
+		.filter(|&total| total <= MAX_MINUTES)
+		.ok_or(())
+}
+
+/// Shows a number of minutes like "2h 30m", "2h" or "45m".
+pub fn format_minutes(minutes: u32) -> String {
+	match (minutes / 60, minutes % 60) {
+		(0, minutes) => format!("{}m", minutes),
+		(hours, 0) => format!("{}h", hours),
+		(hours, minutes) => format!("{}h {}m", hours, minutes),
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(day: u8, hour: u8, minute: u8) -> (Date, Time) {
+		(
+			Date::new(2020, 1, day).unwrap(),
+			Time::new(hour, minute).unwrap(),
+		)
+	}
+
+	// Math: Essay, Worksheet. Science: Report
+	fn application() -> Application {
+		let due = Date::new(2020, 1, 20).unwrap();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_task("Essay", "", due);
+		math.add_task("Worksheet", "", due);
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_task("Report", "", due);
+
+		let mut application = Application::default();
+		application.add_course(math);
+		application.add_course(science);
+		application
+	}
+
+	#[test]
+	fn parsing() {
+		assert_eq!(parse_minutes("90"), Ok(90));
+		assert_eq!(parse_minutes("45m"), Ok(45));
+		assert_eq!(parse_minutes("1h 30m"), Ok(90));
+		assert_eq!(parse_minutes("2H"), Ok(120));
+		assert_eq!(parse_minutes("1.5h"), Ok(90));
+		assert_eq!(parse_minutes("1:05"), Ok(65));
+		assert!(parse_minutes("").is_err());
+		assert!(parse_minutes("a while").is_err());
+
+		// Nothing that overflows, isn't a number, or is longer than a thousand hours
+		assert!(parse_minutes("1:75").is_err());
+		assert!(parse_minutes("99999999:00").is_err());
+		assert!(parse_minutes("infh").is_err());
+		assert!(parse_minutes("NaNh").is_err());
+		assert!(parse_minutes("1e300h").is_err());
+		assert!(parse_minutes("-1h").is_err());
+		assert!(parse_minutes("4294967295").is_err());
+		assert!(parse_minutes("1h 4294967295m").is_err());
+		assert_eq!(parse_minutes("1000h"), Ok(MAX_MINUTES));
+		assert!(parse_minutes("1000h 1m").is_err());
+
+		assert_eq!(format_minutes(45), "45m");
+		assert_eq!(format_minutes(120), "2h");
+		assert_eq!(format_minutes(150), "2h 30m");
+	}
+
+	#[test]
+	fn timer() {
+		let mut application = application();
+
+		application.start_timer(0, 0, at(6, 23, 30));
+		assert_eq!(application.running_timer(), Some((0, 0)));
+
+		// Starting another timer stops the first one, past midnight here
+		application.start_timer(1, 0, at(7, 0, 15));
+		assert_eq!(application.running_timer(), Some((1, 0)));
+		let essay = &application.courses[0].tasks[0];
+		assert_eq!(
+			essay.time_spent,
+			vec![TimeEntry {
+				date: Date::new(2020, 1, 6).unwrap(),
+				minutes: 45
+			}]
+		);
+
+		let (position, entry) = application.stop_timer(at(7, 1, 15)).unwrap();
+		assert_eq!((position, entry.minutes), ((1, 0), 60));
+		assert!(application.running_timer().is_none());
+		assert!(application.stop_timer(at(7, 2, 0)).is_none());
+
+		// Stopping can be undone, which starts the timer again
+		application.undo();
+		assert_eq!(application.running_timer(), Some((1, 0)));
+	}
+
+	#[test]
+	fn totals() {
+		let mut application = application();
+		// The 5th of January 2020 was a Sunday, which starts the week by default
+		application.log_time(0, 0, Date::new(2020, 1, 5).unwrap(), 60);
+		application.log_time(0, 1, Date::new(2020, 1, 8).unwrap(), 30);
+		application.log_time(1, 0, Date::new(2020, 1, 12).unwrap(), 45);
+
+		let from = Date::new(2020, 1, 5).unwrap();
+		let to = Date::new(2020, 1, 18).unwrap();
+		assert_eq!(application.minutes_per_course(from, to), vec![90, 45]);
+
+		let weeks = application.minutes_per_week(from, to);
+		assert_eq!(weeks.len(), 2);
+		assert_eq!(weeks[0], (from, vec![90, 0]));
+		assert_eq!(weeks[1], (from + 7, vec![0, 45]));
+	}
+
+	#[test]
+	fn estimates() {
+		let mut task = Task::new("Essay", "", Date::new(2020, 1, 20).unwrap());
+		assert_eq!(task.minutes_left(), None);
+		task.estimate = Some(120);
+		task.time_spent.push(TimeEntry {
+			date: Date::new(2020, 1, 6).unwrap(),
+			minutes: 150,
+		});
+		assert_eq!(task.minutes_left(), Some(-30));
+
+		// Old entries could add up to more than fits, which stops at the most there can be
+		task.time_spent.push(TimeEntry {
+			date: Date::new(2020, 1, 7).unwrap(),
+			minutes: u32::MAX,
+		});
+		assert_eq!(task.total_minutes(), u32::MAX);
+	}
+}
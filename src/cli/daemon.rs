@@ -0,0 +1,120 @@
+use super::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use crate::application::{Notifier, PrintNotifier, Reminder};
+
+// The daemon is meant to be left running in the background, like from a startup script or a systemd service
+// It never changes the data file, only reads it again whenever it's been changed (by the GUI, or another command)
+// Reminders are worked out the same way the GUI does it: everything that went off since the last check
+
+/// How often to check for reminders, and whether the data file changed.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Runs a shell command for every reminder.
+/// The details are passed in environment variables, so the command can use them however it likes, e.g.
+/// `notify-send "$SCHEDULER_TITLE" "$SCHEDULER_BODY"`
+struct CommandNotifier(String);
+
+impl Notifier for CommandNotifier {
+	fn notify(&self, reminder: &Reminder) {
+		let status = Command::new("sh")
+			.arg("-c")
+			.arg(&self.0)
+			.env("SCHEDULER_TITLE", &reminder.title)
+			.env("SCHEDULER_BODY", &reminder.body)
+			.env("SCHEDULER_DATE", reminder.date.to_string())
+			.env("SCHEDULER_TIME", reminder.time.to_string())
+			.env("SCHEDULER_ID", reminder.id())
+			.status();
+
+		// One broken reminder shouldn't stop the rest, so we just complain about it
+		match status {
+			Ok(status) if status.success() => (),
+			Ok(status) => eprintln!("'{}' failed ({}).", self.0, status),
+			Err(error) => eprintln!("Couldn't run '{}': {}", self.0, error),
+		}
+	}
+}
+
+/// Adds a line to a log file for every reminder.
+struct LogNotifier(String);
+
+impl Notifier for LogNotifier {
+	fn notify(&self, reminder: &Reminder) {
+		let written = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.0)
+			.and_then(|mut file| {
+				writeln!(
+					file,
+					"[{} {}] {}: {}",
+					reminder.date, reminder.time, reminder.title, reminder.body
+				)
+			});
+
+		if let Err(error) = written {
+			eprintln!("Couldn't write to '{}': {}", self.0, error);
+		}
+	}
+}
+
+/// Runs the daemon with options like `--exec <COMMAND>` and `--log <FILE>`. Never returns, unless the options are wrong.
+pub(super) fn run(
+	mut application: Application,
+	location: &str,
+	options: &[&str],
+) -> Result<(), String> {
+	let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+	let mut options = options.iter();
+	while let Some(option) = options.next() {
+		let mut value = || {
+			options
+				.next()
+				.map(|value| value.to_string())
+				.ok_or_else(|| format!("'{}' needs a value after it.", option))
+		};
+		match *option {
+			"--exec" => notifiers.push(Box::new(CommandNotifier(value()?))),
+			"--log" => notifiers.push(Box::new(LogNotifier(value()?))),
+			other => return Err(format!("Unknown option: '{}'. Try 'help'.", other)),
+		}
+	}
+	// Without anywhere else to send them, reminders are printed
+	if notifiers.is_empty() {
+		notifiers.push(Box::new(PrintNotifier));
+	}
+
+	let mut last_modified = Application::last_modified(location);
+	let mut last_checked = application.now();
+	println!(
+		"Watching '{}' for reminders. Press Ctrl+C to stop.",
+		location
+	);
+
+	loop {
+		thread::sleep(POLL_INTERVAL);
+
+		// If the file changed, we load it again
+		// If that fails, it's probably halfway through being saved, so we keep the old one and try again next time
+		let now_modified = Application::last_modified(location);
+		if now_modified != last_modified {
+			if let Ok(loaded) = Application::load(location) {
+				application = loaded;
+				last_modified = now_modified;
+			}
+		}
+
+		let now = application.now();
+		for reminder in application.reminders_between(last_checked, now) {
+			for notifier in &notifiers {
+				notifier.notify(&reminder);
+			}
+		}
+		last_checked = now;
+	}
+}
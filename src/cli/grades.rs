@@ -0,0 +1,256 @@
+use super::*;
+use task::find_task;
+
+/// Runs a `grades` subcommand. Returns whether the application was changed and needs saving.
+pub(super) fn run(application: &mut Application, args: &[&str]) -> Result<bool, String> {
+	match args {
+		[] => {
+			summary(application);
+			Ok(false)
+		}
+		["add", course, name, max, options @ ..] => {
+			let course_index = find_course(application, course)?;
+			let mut assessment = Assessment::new(*name, "", number(max)?);
+			let mut options = options.iter();
+			while let Some(option) = options.next() {
+				let value = options
+					.next()
+					.ok_or_else(|| format!("'{}' needs a value after it.", option))?;
+				match *option {
+					"--category" => assessment.category = value.to_string(),
+					"--weight" => assessment.weight = number(value)?,
+					"--task" => {
+						let (_, task_index) = find_task(application, course, value)?;
+						assessment.task_id =
+							Some(application.courses[course_index].tasks[task_index].id);
+					}
+					other => return Err(format!("Unknown option: '{}'. Try 'help'.", other)),
+				}
+			}
+			assessment.check()?;
+
+			let label = format!("Add assessment '{}'", name);
+			application.edit_course(course_index, label, |course| {
+				course.assessments.push(assessment)
+			});
+			println!(
+				"Added '{}' to {}.",
+				name, application.courses[course_index].name
+			);
+			Ok(true)
+		}
+		["score", course, assessment, score] => {
+			let (course_index, index) = find_assessment(application, course, assessment)?;
+			let mut assessment = application.courses[course_index].assessments[index].clone();
+			assessment.score = match *score {
+				"none" => None,
+				score => Some(number(score)?),
+			};
+			assessment.check()?;
+
+			let label = format!("Grade '{}'", assessment.name);
+			application.edit_course(course_index, label, |course| {
+				course.assessments[index] = assessment
+			});
+			let course = &application.courses[course_index];
+			match course.average() {
+				Some(average) => println!("{} is now at {}.", course.name, percent(average)),
+				None => println!("{} has nothing graded yet.", course.name),
+			}
+			Ok(true)
+		}
+		["rm", course, assessment] => {
+			let (course_index, index) = find_assessment(application, course, assessment)?;
+			let name = application.courses[course_index].assessments[index].name.clone();
+			application.edit_course(course_index, format!("Remove assessment '{}'", name), |course| {
+				course.assessments.remove(index);
+			});
+			println!("Removed '{}'.", name);
+			Ok(true)
+		}
+		["weight", course, category, weight] => {
+			let course_index = find_course(application, course)?;
+			let weight = match *weight {
+				"none" => None,
+				weight => Some(number(weight.trim_end_matches('%'))?),
+			};
+
+			let label = format!("Change the weight of '{}'", category);
+			application.edit_course(course_index, label, |course| {
+				course
+					.category_weights
+					.retain(|(name, _)| name.to_lowercase() != category.to_lowercase());
+				if let Some(weight) = weight {
+					course.category_weights.push((category.to_string(), weight));
+				}
+			});
+			match weight {
+				Some(weight) => println!("'{}' now counts for {}%.", category, weight),
+				None => println!("'{}' doesn't have a weight anymore, so it doesn't count.", category),
+			}
+			Ok(true)
+		}
+		["credits", course, credits] => {
+			let course_index = find_course(application, course)?;
+			let credits = number(credits)?;
+			let label = format!("Change the credits of {}", application.courses[course_index].name);
+			application.edit_course(course_index, label, |course| course.credits = credits);
+			println!("{} now counts for {} credits.", application.courses[course_index].name, credits);
+			Ok(true)
+		}
+		["need", course, assessment, target] => {
+			let (course_index, index) = find_assessment(application, course, assessment)?;
+			let target = parse_target(application, target)?;
+			let course = &application.courses[course_index];
+			let name = &course.assessments[index].name;
+			match course.needed_on(index, target) {
+				Some(needed) if needed > 100.0 => println!(
+					"You'd need {} on '{}' to get {} in {}, so it can't be done without extra credit.",
+					percent(needed),
+					name,
+					percent(target),
+					course.name
+				),
+				Some(needed) if needed <= 0.0 => println!(
+					"You'll get at least {} in {}, whatever you get on '{}'.",
+					percent(target),
+					course.name,
+					name
+				),
+				Some(needed) => println!(
+					"You need {} on '{}' to get {} in {}.",
+					percent(needed),
+					name,
+					percent(target),
+					course.name
+				),
+				None => println!("'{}' doesn't make a difference to {}'s average.", name, course.name),
+			}
+			Ok(false)
+		}
+		["scale"] => {
+			println!("{}", application.grading_scale.as_string());
+			Ok(false)
+		}
+		["scale", scale @ ..] => {
+			let scale = match scale.join(" ").as_str() {
+				"letters" => GradingScale::letters(),
+				"plus-minus" => GradingScale::plus_minus(),
+				text => GradingScale::parse(text).map_err(|_| {
+					format!(
+						"Couldn't understand the scale '{}'. Try 'letters', 'plus-minus' or something like 'A 90 4, B 80 3, F 0 0'.",
+						text
+					)
+				})?,
+			};
+			application.apply("Change grading scale", Command::SetGradingScale(scale));
+			println!("{}", application.grading_scale.as_string());
+			Ok(true)
+		}
+		[course] => {
+			course_grades(application, find_course(application, course)?);
+			Ok(false)
+		}
+		_ => Err(format!("Unknown grades command: '{}'. Try 'help'.", args.join(" "))),
+	}
+}
+
+/// Parses a number that can't be negative, like a score or a weight.
+fn number(text: &str) -> Result<f64, String> {
+	match text.parse::<f64>() {
+		Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+		_ => Err(format!("'{}' has to be a number that isn't negative.", text)),
+	}
+}
+
+/// Parses the average to aim for, either a percentage like '80' or '80%', or a grade on the scale like 'B'.
+fn parse_target(application: &Application, text: &str) -> Result<f64, String> {
+	let grade = application
+		.grading_scale
+		.grades
+		.iter()
+		.find(|grade| grade.name.to_lowercase() == text.to_lowercase());
+	match grade {
+		Some(grade) => Ok(grade.min),
+		None => number(text.trim_end_matches('%')),
+	}
+}
+
+/// Shows a percentage the same way everywhere, like '83.3%'.
+fn percent(percent: f64) -> String {
+	format!("{:.1}%", percent)
+}
+
+/// Finds an assessment by its course and its number or name, as (course index, assessment index).
+fn find_assessment(application: &Application, course: &str, assessment: &str) -> Result<(usize, usize), String> {
+	let course_index = find_course(application, course)?;
+	let course = &application.courses[course_index];
+	let index = find(course.assessments.iter().map(|assessment| assessment.name.as_str()), assessment)
+		.ok_or_else(|| format!("No assessment called '{}' in {}.", assessment, course.name))?;
+	Ok((course_index, index))
+}
+
+/// Prints every course's average and grade so far, and the GPA.
+fn summary(application: &Application) {
+	for (index, course) in application.courses.iter().enumerate() {
+		match (course.average(), application.grade(index)) {
+			(Some(average), Some(grade)) => {
+				println!("{}: {} ({})", course.name, percent(average), grade.name)
+			}
+			(Some(average), None) => println!("{}: {}", course.name, percent(average)),
+			_ => println!("{}: nothing graded yet", course.name),
+		}
+	}
+	match application.gpa() {
+		Some(gpa) => println!("GPA: {:.2}", gpa),
+		None => println!("GPA: nothing graded yet"),
+	}
+}
+
+/// Prints a course's assessments, how each category is doing, and its average so far.
+fn course_grades(application: &Application, course_index: usize) {
+	let course = &application.courses[course_index];
+	for (i, assessment) in course.assessments.iter().enumerate() {
+		print!("{}. {}", i + 1, assessment.name);
+		if !assessment.category.is_empty() {
+			print!(" [{}]", assessment.category);
+		}
+		match assessment.score {
+			Some(score) => print!(
+				"  {} / {} ({})",
+				score,
+				assessment.max,
+				percent(assessment.percent().unwrap_or(0.0))
+			),
+			None => print!("  not graded, out of {}", assessment.max),
+		}
+		if assessment.weight != 1.0 {
+			print!("  weight {}", assessment.weight);
+		}
+		let task = assessment
+			.task_id
+			.and_then(|id| application.find_task(id))
+			.map(|(c, t)| &application.courses[c].tasks[t]);
+		if let Some(task) = task {
+			print!("  for '{}'", task.name);
+		}
+		println!();
+	}
+
+	if !course.category_weights.is_empty() {
+		println!();
+	}
+	for (category, weight) in &course.category_weights {
+		match course.category_average(category) {
+			Some(average) => println!("{} ({}%): {}", category, weight, percent(average)),
+			None => println!("{} ({}%): nothing graded yet", category, weight),
+		}
+	}
+
+	println!();
+	match (course.average(), application.grade(course_index)) {
+		(Some(average), Some(grade)) => println!("Average: {} ({})", percent(average), grade.name),
+		(Some(average), None) => println!("Average: {}", percent(average)),
+		_ => println!("Average: nothing graded yet"),
+	}
+}
@@ -0,0 +1,369 @@
+//! This module contains the command-line interface, used when the scheduler is run with arguments.
+
+use std::convert::TryFrom;
+
+use crate::application::{
+	format_minutes, parse_minutes, Application, Assessment, Command, GradingScale, Phase, Priority,
+	TaskQuery, TaskSort, MAX_FOCUS_MINUTES, MAX_FOCUS_ROUNDS,
+};
+use crate::clock::*;
+use crate::server::Server;
+
+/// For the `task` commands.
+mod task;
+/// For the `daemon` command, which sends reminders in the background.
+mod daemon;
+/// For the `timer`, `focus` and `time` commands, which track how long tasks take.
+mod time;
+/// For the `grades` commands, which keep the grade book.
+mod grades;
+
+// The CLI is much simpler than the GUI, thankfully
+// Every command is one run of the program: load, do one thing, save if something changed, exit
+// Courses and tasks are referred to either by their number (as shown by `courses` and `task list`) or by name
+// A task's number is its place in its course, so it always goes with the course, like `task done Math 2`
+
+/// Runs the command in `args`, saving the application to `location` if it was changed.
+/// Returns a message for the user if something went wrong.
+pub fn run(mut application: Application, location: &str, args: &[String]) -> Result<(), String> {
+	let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+	match args.as_slice() {
+		["help"] | ["--help"] | ["-h"] => {
+			println!("{}", HELP);
+			Ok(())
+		}
+		["courses"] => {
+			let overdue = application.overdue_counts(application.today());
+			for (i, course) in application.courses.iter().enumerate() {
+				print!("{}. {} ({}, {})", i + 1, course.name, course.teacher, course.room);
+				if overdue[i] > 0 {
+					print!("  {} overdue", overdue[i]);
+				}
+				println!();
+			}
+			Ok(())
+		}
+		["overdue"] => {
+			overdue(&application);
+			Ok(())
+		}
+		["order"] => {
+			order(&application);
+			Ok(())
+		}
+		["date", text @ ..] => {
+			let date = parse_date(&application, &text.join(" "))?;
+			println!("{} ({})", application.locale.format_date(date), date.weekday());
+			Ok(())
+		}
+		["search", text @ ..] if !text.is_empty() => {
+			search(&application, &text.join(" "));
+			Ok(())
+		}
+		["task", command @ ..] => {
+			if task::run(&mut application, command)? {
+				application.save(location);
+			}
+			Ok(())
+		}
+		["timer", command @ ..] => {
+			if time::timer(&mut application, command)? {
+				application.save(location);
+			}
+			Ok(())
+		}
+		["focus", command @ ..] => {
+			if time::focus(&mut application, command)? {
+				application.save(location);
+			}
+			Ok(())
+		}
+		["time", options @ ..] => time::report(&application, options),
+		["grades", command @ ..] => {
+			if grades::run(&mut application, command)? {
+				application.save(location);
+			}
+			Ok(())
+		}
+		["daemon", options @ ..] => daemon::run(application, location, options),
+		["tui"] => crate::tui::run(application, location),
+		["serve", options @ ..] => serve(application, location, options),
+		["caldav", options @ ..] => match options {
+			[] => Server::new(application, location).run_caldav(5232),
+			["--port", port] => {
+				let port = port.parse().map_err(|_| "The port has to be a number.".to_string())?;
+				Server::new(application, location).run_caldav(port)
+			}
+			_ => Err(format!("Unknown options: '{}'. Try 'help'.", options.join(" "))),
+		},
+		["undo"] => {
+			let label = application.undo().ok_or_else(|| nothing_to("undo", &application))?;
+			println!("Undid: {}", label);
+			application.save(location);
+			Ok(())
+		}
+		["redo"] => {
+			let label = application.redo().ok_or_else(|| nothing_to("redo", &application))?;
+			println!("Redid: {}", label);
+			application.save(location);
+			Ok(())
+		}
+		["history"] => {
+			for label in application.history.undo_list() {
+				println!("{}", label);
+			}
+			Ok(())
+		}
+		["history", setting] => {
+			application.set_keep_history(match *setting {
+				"on" => true,
+				"off" => false,
+				other => return Err(format!("History can be 'on' or 'off', not '{}'.", other)),
+			});
+			application.save(location);
+			Ok(())
+		}
+		_ => Err(format!("Unknown command: '{}'. Try 'help'.", args.join(" "))),
+	}
+}
+
+/// Starts the HTTP server with options like `--port 8080`. Doesn't return unless something goes wrong.
+fn serve(application: Application, location: &str, options: &[&str]) -> Result<(), String> {
+	let mut port = 8080;
+	let mut allow_origin = None;
+	let mut options = options.iter();
+	while let Some(option) = options.next() {
+		let mut value = || {
+			options
+				.next()
+				.ok_or_else(|| format!("'{}' needs a value after it.", option))
+		};
+		match *option {
+			"--port" => port = value()?.parse().map_err(|_| "The port has to be a number.".to_string())?,
+			"--allow-origin" => allow_origin = Some(value()?.to_string()),
+			other => return Err(format!("Unknown option: '{}'. Try 'help'.", other)),
+		}
+	}
+
+	Server::new(application, location).run(port, allow_origin.as_deref())
+}
+
+/// The message for when there's nothing to undo or redo.
+/// Every run of the CLI starts fresh, so without the history being kept there's never anything there.
+fn nothing_to(action: &str, application: &Application) -> String {
+	if application.keep_history {
+		format!("Nothing to {}.", action)
+	} else {
+		format!("Nothing to {}. Run 'history on' to keep edits between runs.", action)
+	}
+}
+
+const HELP: &str = "\
+Usage: scheduler [COMMAND]
+Without a command, the graphical interface is opened.
+
+Commands:
+  help                                   Show this message
+  courses                                List all courses
+  overdue                                List the tasks that are past due, and how many each course has
+  order                                  List the unfinished tasks in an order they can be done in,
+                                         with every task after the ones it depends on
+  date <DATE>                            Show what a date like 'next friday' means
+  search <TEXT>                          Search every course, task and step
+  task list [OPTIONS]                    List tasks from every course
+  task add <COURSE> <NAME> <DUE>         Add a task to a course
+  task due <COURSE> <TASK> <DUE>         Change when a task is due
+  task snooze <COURSE> <TASK> <DUE>      Put off a task, remembering when it was first due
+  task depend <COURSE> <TASK> <COURSE> <TASK>
+                                         Make the first task wait until the second one is done
+  task undepend <COURSE> <TASK> <COURSE> <TASK>
+                                         Stop the first task waiting for the second one
+  task log <COURSE> <TASK> <TIME> [DATE] Add time spent on a task, like '1h 30m' (default date: today)
+  task estimate <COURSE> <TASK> <TIME>   Set how long a task should take, or 'none'
+  timer                                  Show which task is being timed
+  timer start <COURSE> <TASK>            Start timing a task, stopping any other timer
+  timer stop                             Stop the timer, adding the time to its task
+  focus                                  Show how the focus session is going, and log the rounds
+                                         of work it's finished
+  focus start <COURSE> <TASK>            Start a focus session (a pomodoro) on a task
+  focus stop                             End the focus session early, logging the rounds it finished
+  focus settings [OPTIONS]               Show or change how long focus sessions last
+  time [--weeks <N>]                     Show the time spent on each course this week and in the
+                                         last N weeks (default: 4), and on tasks with estimates
+  grades                                 Show every course's average and grade so far, and the GPA
+  grades <COURSE>                        Show a course's assessments, and how each category is doing
+  grades add <COURSE> <NAME> <MAX> [OPTIONS]
+                                         Add something graded, like a test out of 50
+  grades score <COURSE> <NAME> <SCORE>   Set what an assessment scored, or 'none'
+  grades rm <COURSE> <NAME>              Remove an assessment
+  grades weight <COURSE> <CATEGORY> <PERCENT>
+                                         Set how much a category counts, like 'Exams 50', or 'none'
+  grades credits <COURSE> <CREDITS>      Set how much a course counts towards the GPA (default: 1)
+  grades need <COURSE> <NAME> <TARGET>   Show what's needed on an assessment for an average like
+                                         '80' or a grade like 'B'
+  grades scale [SCALE]                   Show or change the grading scale: 'letters', 'plus-minus',
+                                         or each grade's name, lowest percentage and points, like
+                                         'A 90 4, B 80 3, C 70 2, F 0 0'
+  undo, redo                             Undo or redo the last edit
+  history                                List the edits that can be undone, most recent first
+  history <on|off>                       Whether to keep the undo history between runs
+  tui                                    Open the terminal interface, with the same menus as the GUI
+  daemon [OPTIONS]                       Keep running, and send reminders when they go off
+  serve [OPTIONS]                        Run a JSON API on this computer, for other programs to use
+  caldav [--port <PORT>]                 Run a CalDAV server on this computer (default port: 5232),
+                                         so calendar apps can show the timetable and tick off tasks
+
+Options for 'daemon' (reminders are printed if neither is given):
+  --exec <COMMAND>                       Run a shell command for each reminder, with the details in
+                                         $SCHEDULER_TITLE, $SCHEDULER_BODY, $SCHEDULER_DATE and $SCHEDULER_TIME
+  --log <FILE>                           Add a line to a file for each reminder
+
+Options for 'focus settings' (run 'daemon' or the GUI to be told when each part ends):
+  --work <MINUTES>                       How long each round of work lasts (default: 25)
+  --short-break <MINUTES>                How long the breaks between rounds last (default: 5)
+  --long-break <MINUTES>                 How long the break at the end lasts (default: 15)
+  --rounds <N>                           How many rounds of work there are (default: 4)
+Each part can last up to a day, and there can be up to 100 rounds.
+
+Options for 'grades add':
+  --category <CATEGORY>                  What kind of assessment it is, like 'Homework'
+  --weight <WEIGHT>                      How much it counts compared to the rest of its category (default: 1)
+  --task <TASK>                          The task in the same course it's for
+
+Options for 'serve' (see server/routes.rs for the API):
+  --port <PORT>                          The port to listen on (default: 8080)
+  --allow-origin <URL>                   Let web pages from this address use the API, like 'http://localhost:3000'
+
+Options for 'task list':
+  --course <COURSE>                      Only tasks in this course
+  --tag <TAG>                            Only tasks with this tag (or in a course with it)
+  --priority <low|normal|high|urgent>    Only tasks at least this important
+  --from <DATE>, --to <DATE>             Only tasks due in this range
+  --done, --todo                         Only complete or incomplete tasks
+  --overdue                              Only incomplete tasks that were due before today
+  --blocked                              Only incomplete tasks waiting for other tasks to be done
+  --sort <due|priority|name|course>      The order to list them in (default: due)
+
+Dates can be written as 'YYYY-MM-DD', 'today', 'tomorrow', 'friday', 'next friday',
+'in 3 days', 'in 2 weeks', 'end of month' or 'DD/MM'.";
+
+/// Prints the results of searching for `text`, with the matches in bold.
+fn search(application: &Application, text: &str) {
+	let results = application.search(text);
+	if results.is_empty() {
+		println!("Nothing found.");
+	}
+
+	for result in results {
+		let course = &application.courses[result.course_index];
+		let location = match result.task_index {
+			Some(task_index) => format!("{} > {}", course.name, course.tasks[task_index].name),
+			None => course.name.clone(),
+		};
+		// "\x1b[1m" turns on bold in the terminal, and "\x1b[0m" turns it off again
+		let snippet = result.highlighted(|text| text.to_string(), |text| format!("\x1b[1m{}\x1b[0m", text));
+		println!("{} ({}): {}", location, result.field.as_str(), snippet);
+	}
+}
+
+/// Prints every overdue task, longest overdue first, then how many each course has.
+fn overdue(application: &Application) {
+	let today = application.today();
+	let tasks = application.overdue_tasks(today);
+	if tasks.is_empty() {
+		println!("Nothing's overdue.");
+		return;
+	}
+
+	for entry in &tasks {
+		let task = entry.task;
+		print!(
+			"{}  [{}]  {}: {} ({} days late)",
+			application.locale.format_date(task.due),
+			task.priority,
+			entry.course.name,
+			task.name,
+			task.days_overdue(today).unwrap_or(0)
+		);
+		if let Some(original) = task.original_due {
+			print!("  first due {}", application.locale.format_date(original));
+		}
+		println!();
+	}
+
+	println!();
+	let counts = application.overdue_counts(today);
+	for (course, count) in application.courses.iter().zip(counts) {
+		if count > 0 {
+			println!("{}: {} overdue", course.name, count);
+		}
+	}
+}
+
+/// Prints every unfinished task in the order they can be done in, and what each one is waiting for.
+fn order(application: &Application) {
+	let tasks = application.work_order();
+	if tasks.is_empty() {
+		println!("Everything's done.");
+		return;
+	}
+
+	let name = |(course_index, task_index): (usize, usize)| {
+		let course = &application.courses[course_index];
+		format!("{}: {}", course.name, course.tasks[task_index].name)
+	};
+	for (i, &(course_index, task_index)) in tasks.iter().enumerate() {
+		let task = &application.courses[course_index].tasks[task_index];
+		print!(
+			"{}. {}  (due {})",
+			i + 1,
+			name((course_index, task_index)),
+			application.locale.format_date(task.due)
+		);
+		let blockers = application.blockers(course_index, task_index);
+		if !blockers.is_empty() {
+			let names: Vec<String> = blockers.into_iter().map(name).collect();
+			print!("  after {}", names.join(", "));
+		}
+		println!();
+	}
+
+	if let Some(tasks) = application.dependency_loop() {
+		let names: Vec<String> = tasks.into_iter().map(name).collect();
+		println!();
+		println!("These tasks are waiting for each other, so none of them can be started: {}", names.join(", "));
+	}
+}
+
+/// Finds a course by its number (starting from 1) or by its name, ignoring case.
+fn find_course(application: &Application, text: &str) -> Result<usize, String> {
+	find(
+		application.courses.iter().map(|course| course.name.as_str()),
+		text,
+	)
+	.ok_or_else(|| format!("No course called '{}'.", text))
+}
+
+/// Finds the index of `text` in `names`, either as a number starting from 1 or as a name, ignoring case.
+fn find<'a>(names: impl Iterator<Item = &'a str>, text: &str) -> Option<usize> {
+	let names: Vec<&str> = names.collect();
+
+	if let Ok(number) = text.parse::<usize>() {
+		if number >= 1 && number <= names.len() {
+			return Some(number - 1);
+		}
+	}
+
+	names
+		.iter()
+		.position(|name| name.to_lowercase() == text.to_lowercase())
+}
+
+/// Parses a date relative to today, with a helpful message if it can't be understood.
+fn parse_date(application: &Application, text: &str) -> Result<Date, String> {
+	application
+		.locale
+		.parse_date(text, application.today())
+		.map_err(|_| format!("Couldn't understand the date '{}'.", text))
+}
@@ -0,0 +1,206 @@
+use super::*;
+
+/// Runs a `task` subcommand. Returns whether the application was changed and needs saving.
+pub(super) fn run(application: &mut Application, args: &[&str]) -> Result<bool, String> {
+	match args {
+		["list", options @ ..] => {
+			list(application, options)?;
+			Ok(false)
+		}
+		["add", course, name, due @ ..] if !due.is_empty() => {
+			let course_index = find_course(application, course)?;
+			let due = parse_date(application, &due.join(" "))?;
+
+			let due_text = application.locale.format_date(due);
+			application.edit_course(course_index, format!("Add task '{}'", name), |course| {
+				course.add_task(*name, "", due)
+			});
+			println!(
+				"Added '{}' to {}, due {}.",
+				name, application.courses[course_index].name, due_text
+			);
+			Ok(true)
+		}
+		["due", course, task, due @ ..] if !due.is_empty() => {
+			let course_index = find_course(application, course)?;
+			let due = parse_date(application, &due.join(" "))?;
+
+			let due_text = application.locale.format_date(due);
+			let course = &application.courses[course_index];
+			let task_index = find(course.tasks.iter().map(|task| task.name.as_str()), task)
+				.ok_or_else(|| format!("No task called '{}' in {}.", task, course.name))?;
+			let name = course.tasks[task_index].name.clone();
+
+			application.edit_task(course_index, task_index, format!("Change due date of '{}'", name), |task| {
+				task.due = due
+			});
+			println!("'{}' is now due {}.", name, due_text);
+			Ok(true)
+		}
+		["snooze", course, task, due @ ..] if !due.is_empty() => {
+			let course_index = find_course(application, course)?;
+			let due = parse_date(application, &due.join(" "))?;
+
+			let due_text = application.locale.format_date(due);
+			let course = &application.courses[course_index];
+			let task_index = find(course.tasks.iter().map(|task| task.name.as_str()), task)
+				.ok_or_else(|| format!("No task called '{}' in {}.", task, course.name))?;
+			let name = course.tasks[task_index].name.clone();
+
+			application.snooze_tasks(&[(course_index, task_index)], due);
+			let task = &application.courses[course_index].tasks[task_index];
+			match task.original_due {
+				Some(original) => println!(
+					"'{}' is now due {} (first due {}).",
+					name,
+					due_text,
+					application.locale.format_date(original)
+				),
+				None => println!("'{}' is now due {}.", name, due_text),
+			}
+			Ok(true)
+		}
+		["depend", course, task, on_course, on_task] => {
+			let task = find_task(application, course, task)?;
+			let prerequisite = find_task(application, on_course, on_task)?;
+			application.add_dependency(task, prerequisite)?;
+			println!(
+				"'{}' now has to wait for '{}'.",
+				application.courses[task.0].tasks[task.1].name,
+				application.courses[prerequisite.0].tasks[prerequisite.1].name
+			);
+			Ok(true)
+		}
+		["undepend", course, task, on_course, on_task] => {
+			let task = find_task(application, course, task)?;
+			let prerequisite = find_task(application, on_course, on_task)?;
+			if !application.prerequisites(task.0, task.1).contains(&prerequisite) {
+				return Err("That task doesn't depend on that one.".to_string());
+			}
+			application.rm_dependency(task, prerequisite);
+			println!(
+				"'{}' doesn't have to wait for '{}' anymore.",
+				application.courses[task.0].tasks[task.1].name,
+				application.courses[prerequisite.0].tasks[prerequisite.1].name
+			);
+			Ok(true)
+		}
+		["log", course, task, time, date @ ..] => {
+			let (course_index, task_index) = find_task(application, course, task)?;
+			let minutes = parse_minutes(time).map_err(|_| time_invalid(time))?;
+			let date = if date.is_empty() {
+				application.today()
+			} else {
+				parse_date(application, &date.join(" "))?
+			};
+
+			application.log_time(course_index, task_index, date, minutes);
+			let task = &application.courses[course_index].tasks[task_index];
+			println!(
+				"Logged {} on '{}', which has taken {} so far.",
+				format_minutes(minutes),
+				task.name,
+				format_minutes(task.total_minutes())
+			);
+			Ok(true)
+		}
+		["estimate", course, task, time] => {
+			let (course_index, task_index) = find_task(application, course, task)?;
+			let estimate = match *time {
+				"none" => None,
+				time => Some(parse_minutes(time).map_err(|_| time_invalid(time))?),
+			};
+
+			let name = application.courses[course_index].tasks[task_index].name.clone();
+			application.edit_task(course_index, task_index, format!("Estimate '{}'", name), |task| {
+				task.estimate = estimate
+			});
+			match estimate {
+				Some(estimate) => println!("'{}' should take {}.", name, format_minutes(estimate)),
+				None => println!("'{}' doesn't have an estimate anymore.", name),
+			}
+			Ok(true)
+		}
+		_ => Err(format!("Unknown task command: '{}'. Try 'help'.", args.join(" "))),
+	}
+}
+
+/// The message for a length of time that can't be understood.
+fn time_invalid(text: &str) -> String {
+	format!("Couldn't understand the time '{}'. Try '45m', '1h 30m' or '1:30', up to 1000 hours.", text)
+}
+
+/// Finds a task by its course and its number or name, as (course index, task index).
+pub(super) fn find_task(application: &Application, course: &str, task: &str) -> Result<(usize, usize), String> {
+	let course_index = find_course(application, course)?;
+	let course = &application.courses[course_index];
+	let task_index = find(course.tasks.iter().map(|task| task.name.as_str()), task)
+		.ok_or_else(|| format!("No task called '{}' in {}.", task, course.name))?;
+	Ok((course_index, task_index))
+}
+
+/// Prints the tasks matching the options, like `--tag exam --todo --sort priority`.
+fn list(application: &Application, options: &[&str]) -> Result<(), String> {
+	let mut query = TaskQuery::new();
+	let mut options = options.iter();
+
+	// Most options take a value after them, so we take the next one along when they do
+	while let Some(option) = options.next() {
+		let mut value = || {
+			options
+				.next()
+				.ok_or_else(|| format!("'{}' needs a value after it.", option))
+		};
+
+		query = match *option {
+			"--course" => query.course(find_course(application, value()?)?),
+			"--tag" => query.tag(*value()?),
+			"--priority" => query.min_priority(
+				Priority::try_from(value()?.to_string())
+					.map_err(|_| "Priorities are 'low', 'normal', 'high' or 'urgent'.".to_string())?,
+			),
+			"--from" => {
+				query.due_from = Some(parse_date(application, value()?)?);
+				query
+			}
+			"--to" => {
+				query.due_to = Some(parse_date(application, value()?)?);
+				query
+			}
+			"--done" => query.complete(true),
+			"--todo" => query.complete(false),
+			"--overdue" => query.overdue(application.today()),
+			"--blocked" => query.blocked(true),
+			"--sort" => query.sort_by(match *value()? {
+				"due" => TaskSort::Due,
+				"priority" => TaskSort::Priority,
+				"name" => TaskSort::Name,
+				"course" => TaskSort::Course,
+				other => return Err(format!("Can't sort by '{}'.", other)),
+			}),
+			other => return Err(format!("Unknown option: '{}'. Try 'help'.", other)),
+		};
+	}
+
+	// The number before each task is its number in its course, which is what other commands take
+	// So "Math: 2. Essay" can be done with `task done Math 2`, however the list is sorted or filtered
+	let today = application.today();
+	for entry in application.tasks(&query) {
+		let task = entry.task;
+		print!(
+			"{}  [{}]  {}: {}. {} ({})",
+			application.locale.format_date(task.due),
+			task.priority,
+			entry.course.name,
+			entry.task_index + 1,
+			task.name,
+			application.task_status(entry.course_index, entry.task_index, today)
+		);
+		if !task.tags.is_empty() {
+			print!("  #{}", task.tags.join(" #"));
+		}
+		println!();
+	}
+
+	Ok(())
+}
@@ -0,0 +1,229 @@
+use super::*;
+use task::find_task;
+
+/// Runs a `timer` subcommand. Returns whether the application was changed and needs saving.
+pub(super) fn timer(application: &mut Application, args: &[&str]) -> Result<bool, String> {
+	let now = application.now();
+	match args {
+		[] => {
+			match application.running_timer() {
+				Some((course_index, task_index)) => {
+					let course = &application.courses[course_index];
+					let task = &course.tasks[task_index];
+					// The timer's always running when it's found, so there's always a start
+					let (date, time) = task.timer.unwrap();
+					let minutes = time.minutes_until(date, now.1, now.0).max(0) as u32;
+					println!(
+						"Timing '{}' ({}) since {}, {} so far.",
+						task.name,
+						course.name,
+						application.locale.format_time(time),
+						format_minutes(minutes)
+					);
+				}
+				None => println!("No timer is running."),
+			}
+			Ok(false)
+		}
+		["start", course, task] => {
+			let (course_index, task_index) = find_task(application, course, task)?;
+			if let Some((running_course, running_task)) = application.running_timer() {
+				if (running_course, running_task) == (course_index, task_index) {
+					return Err("That task is already being timed.".to_string());
+				}
+				println!(
+					"Stopped timing '{}'.",
+					application.courses[running_course].tasks[running_task].name
+				);
+			}
+			application.start_timer(course_index, task_index, now);
+			println!(
+				"Started timing '{}'. Run 'timer stop' when you're done.",
+				application.courses[course_index].tasks[task_index].name
+			);
+			Ok(true)
+		}
+		["stop"] => {
+			if application.running_timer().is_none() {
+				return Err("No timer is running.".to_string());
+			}
+			match application.stop_timer(now) {
+				Some(((course_index, task_index), entry)) => {
+					let task = &application.courses[course_index].tasks[task_index];
+					println!(
+						"Stopped timing '{}' after {}. It's taken {} so far.",
+						task.name,
+						format_minutes(entry.minutes),
+						format_minutes(task.total_minutes())
+					);
+				}
+				// A timer stopped in the same minute it was started still stops, it just doesn't add anything
+				None => println!("Stopped the timer before a minute went by, so no time was added."),
+			}
+			Ok(true)
+		}
+		_ => Err(format!("Unknown timer command: '{}'. Try 'help'.", args.join(" "))),
+	}
+}
+
+/// Runs a `focus` subcommand. Returns whether the application was changed and needs saving.
+pub(super) fn focus(application: &mut Application, args: &[&str]) -> Result<bool, String> {
+	let now = application.now();
+	match args {
+		[] => {
+			// Checking on the session is as good a time as any to log the rounds it's finished
+			let logged = application.log_focus(now);
+			if logged > 0 {
+				println!("Logged {} of focus.", format_minutes(logged));
+			}
+			let (session, (course_index, task_index)) =
+				match (application.focus_session, application.focus_task()) {
+					(Some(session), Some(task)) => (session, task),
+					_ => {
+						println!("No focus session is going.");
+						return Ok(logged > 0);
+					}
+				};
+			let course = &application.courses[course_index];
+			let task = &course.tasks[task_index];
+			// A session that's over has already been ended by logging it, so there's always a phase
+			if let Some((phase, round, (_, end))) = session.phase_at(now) {
+				println!(
+					"Focusing on '{}' ({}): {} in round {} of {}, until {}.",
+					task.name,
+					course.name,
+					phase.as_str().to_lowercase(),
+					round,
+					session.settings.rounds.max(1),
+					application.locale.format_time(end)
+				);
+			}
+			Ok(logged > 0)
+		}
+		["start", course, task] => {
+			let (course_index, task_index) = find_task(application, course, task)?;
+			if let Some(running) = application.running_timer() {
+				println!(
+					"Stopped timing '{}'.",
+					application.courses[running.0].tasks[running.1].name
+				);
+			}
+			application.start_focus(course_index, task_index, now);
+			let session = application.focus_session.unwrap();
+			println!(
+				"Started a focus session on '{}': {} rounds of {}, until {}.",
+				application.courses[course_index].tasks[task_index].name,
+				session.settings.rounds.max(1),
+				format_minutes(session.settings.minutes(Phase::Work)),
+				application.locale.format_time(session.at(session.length()).1)
+			);
+			Ok(true)
+		}
+		["stop"] => match application.stop_focus(now) {
+			Some(logged) => {
+				println!("Ended the focus session, and logged {}.", format_minutes(logged));
+				Ok(true)
+			}
+			None => Err("No focus session is going.".to_string()),
+		},
+		["settings", options @ ..] => {
+			let mut settings = application.focus;
+			let mut options = options.iter();
+			while let Some(option) = options.next() {
+				let value = options
+					.next()
+					.ok_or_else(|| format!("'{}' needs a value after it.", option))?;
+				let max = if *option == "--rounds" {
+					MAX_FOCUS_ROUNDS
+				} else {
+					MAX_FOCUS_MINUTES
+				};
+				let value = match value.parse() {
+					Ok(value) if value > 0 && value <= max => value,
+					_ => return Err(format!("'{}' has to be a whole number from 1 to {}.", option, max)),
+				};
+				match *option {
+					"--work" => settings.work_minutes = value,
+					"--short-break" => settings.short_break_minutes = value,
+					"--long-break" => settings.long_break_minutes = value,
+					"--rounds" => settings.rounds = value,
+					other => return Err(format!("Unknown option: '{}'. Try 'help'.", other)),
+				}
+			}
+			println!(
+				"{} rounds of {} work, with {} breaks and a {} break at the end.",
+				settings.rounds,
+				format_minutes(settings.work_minutes),
+				format_minutes(settings.short_break_minutes),
+				format_minutes(settings.long_break_minutes)
+			);
+			if settings == application.focus {
+				return Ok(false);
+			}
+			application.apply("Change focus settings", Command::SetFocusSettings(settings));
+			Ok(true)
+		}
+		_ => Err(format!("Unknown focus command: '{}'. Try 'help'.", args.join(" "))),
+	}
+}
+
+/// Prints the time spent on each course this week and in the last few weeks, and on every task with an estimate.
+pub(super) fn report(application: &Application, options: &[&str]) -> Result<(), String> {
+	let weeks = match options {
+		[] => 4,
+		["--weeks", weeks] => weeks
+			.parse::<u32>()
+			.map_err(|_| "The number of weeks has to be a number.".to_string())?,
+		_ => return Err(format!("Unknown options: '{}'. Try 'help'.", options.join(" "))),
+	};
+	let today = application.today();
+	let locale = &application.locale;
+
+	println!("This week:");
+	// The first week there is started before 1970, so it's cut short
+	let week_start = locale.start_of_week(today).unwrap_or(Date::FIRST);
+	let week_end = locale.end_of_week(today).unwrap_or(Date::LAST);
+	let this_week = application.minutes_per_course(week_start, week_end);
+	for (course, minutes) in application.courses.iter().zip(this_week) {
+		println!("  {}: {}", course.name, format_minutes(minutes));
+	}
+
+	println!();
+	println!("Each week:");
+	let from = week_start
+		.offset(-7 * (weeks as i64 - 1).max(0))
+		.map_err(|_| format!("{} weeks goes back further than 1970.", weeks))?;
+	for (start, minutes) in application.minutes_per_week(from, today) {
+		let total: u32 = minutes.iter().sum();
+		println!("  Week of {}: {}", locale.format_date(start), format_minutes(total));
+	}
+
+	// Only tasks with estimates can be compared against them
+	let estimated: Vec<_> = application
+		.tasks(&TaskQuery::new().sort_by(TaskSort::Course))
+		.into_iter()
+		.filter(|entry| entry.task.estimate.is_some())
+		.collect();
+	if !estimated.is_empty() {
+		println!();
+		println!("Estimates:");
+	}
+	for entry in estimated {
+		let task = entry.task;
+		let left = match task.minutes_left() {
+			Some(left) if left < 0 => format!("over by {}", format_minutes(-left as u32)),
+			Some(left) => format!("{} left", format_minutes(left as u32)),
+			None => String::new(),
+		};
+		println!(
+			"  {}: {}  {} of {} ({})",
+			entry.course.name,
+			task.name,
+			format_minutes(task.total_minutes()),
+			format_minutes(task.estimate.unwrap_or(0)),
+			left
+		);
+	}
+
+	Ok(())
+}
@@ -13,11 +13,26 @@ pub struct Date {
 }
 
 impl Date {
+	/// The first date there can be, since everything before 1970 is turned away.
+	pub const FIRST: Date = Date {
+		year: 1970,
+		month: 1,
+		day: 1,
+	};
+	/// The last date there can be, since the year has to fit in a u16.
+	pub const LAST: Date = Date {
+		year: 65535,
+		month: 12,
+		day: 31,
+	};
+
 	pub fn new(year: u16, month: u8, day: u8) -> Result<Self, ()> {
 		// Returns the number of days in a specific month
 		// This array is 1-indexed, so you can access it with the normal month number
 		// Example, if self.month is 2 (February), then there is 28 days
-		let days_in_months = [0, 31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+		// February gets its extra day on leap years, which are every 4 years except most centuries
+		let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+		let days_in_months = [0, 31, 28 + leap as u8, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
 
 		// 1970 is the base year because of the unix epoch
 		// I assume my client is not a time traveller
@@ -36,16 +51,90 @@ impl Date {
 		start <= self && self <= end
 	}
 
-	/// Returns the date of today.
+	/// Returns the date of today, in the system's time zone.
 	pub fn today() -> Self {
+		Self::today_in(None)
+	}
+
+	/// Returns the date of today in `zone`, or in the system's time zone if `zone` is `None`.
+	pub fn today_in(zone: Option<Zone>) -> Self {
 		// We don't want to mess with different OS interfaces, so I'm just going to depend on Chrono for this one
-		let localtime = Local::now();
-		Self::new(
-			localtime.year() as u16,
-			localtime.month() as u8,
-			localtime.day() as u8,
-		)
-		.unwrap()
+		// The clock would have to be set to before 1970 for this to fail
+		Self::from_naive(Zone::now_in(zone).date()).unwrap()
+	}
+
+	pub fn year(self) -> u16 {
+		self.year
+	}
+
+	pub fn month(self) -> u8 {
+		self.month
+	}
+
+	pub fn day(self) -> u8 {
+		self.day
+	}
+
+	/// Returns which day of the week this date falls on.
+	pub fn weekday(self) -> Day {
+		Day::from(self.naive().weekday())
+	}
+
+	/// Returns the date `days` days after this one (or before, if `days` is negative).
+	/// Returns an error if that's before 1970, or too far in the future for a date to hold.
+	pub fn offset(self, days: i64) -> Result<Self, ()> {
+		// Chrono panics on a duration of more than about 100 trillion days, so anything that big is turned away first
+		// 100 million days is over 270,000 years, which is already further than any date can go
+		if !(-100_000_000..=100_000_000).contains(&days) {
+			return Err(());
+		}
+		self.naive()
+			.checked_add_signed(Duration::days(days))
+			.ok_or(())
+			.and_then(Self::from_naive)
+	}
+
+	/// Returns how many days it is from this date to `other`, which is negative if `other` is before it.
+	pub fn days_until(self, other: Date) -> i64 {
+		(other.naive() - self.naive()).num_days()
+	}
+
+	/// Returns the first day of this date's month.
+	pub fn start_of_month(self) -> Self {
+		Self {
+			year: self.year,
+			month: self.month,
+			day: 1,
+		}
+	}
+
+	/// Returns the same day `months` months later (or earlier, if `months` is negative).
+	/// If that month is too short (like going from January 31st to February), it's the last day of that month.
+	/// Returns an error if that's before 1970, or too far in the future for a date to hold.
+	pub fn offset_months(self, months: i64) -> Result<Self, ()> {
+		// Counting months from year 0 makes going over the end of a year just a division
+		let total = (self.year as i64 * 12 + (self.month as i64 - 1))
+			.checked_add(months)
+			.ok_or(())?;
+		let year = u16::try_from(total.div_euclid(12)).map_err(|_| ())?;
+		let first = Self::new(year, (total.rem_euclid(12) + 1) as u8, 1)?;
+		let last = first.end_of_month();
+		Ok(Self {
+			day: self.day.min(last.day),
+			..first
+		})
+	}
+
+	/// Converts this date into Chrono's representation, so we can do calendar maths with it.
+	pub(super) fn naive(self) -> NaiveDate {
+		NaiveDate::from_ymd(self.year as i32, self.month as u32, self.day as u32)
+	}
+
+	/// Converts a date from Chrono's representation.
+	/// Chrono goes much further back and forward than we do, so this returns an error for dates we can't hold.
+	pub(super) fn from_naive(naive: NaiveDate) -> Result<Self, ()> {
+		let year = u16::try_from(naive.year()).map_err(|_| ())?;
+		Self::new(year, naive.month() as u8, naive.day() as u8)
 	}
 
 	pub fn as_string(self) -> String {
@@ -85,21 +174,14 @@ impl TryFrom<String> for Date {
 	}
 }
 
-// Implementing Add for Date, so we can do Date + Date
-// This function isn't that useful and is used rarely, so I won't comment it
+// Implementing Add for Date, so we can do Date + 7
+// The old hand-written version broke on the last day of a month and at the end of the year, so it's just `offset` now
+// Like adding numbers, this panics if it goes too far, so use `offset` for anything that came from the user
 impl std::ops::Add<usize> for Date {
 	type Output = Date;
 
-	fn add(mut self, other: usize) -> Date {
-		let day_in_month = [0, 31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31][self.month as usize];
-
-		self.day += other as u8;
-		if self.day > day_in_month {
-			self.month += 1;
-		}
-		self.day %= day_in_month;
-
-		self
+	fn add(self, other: usize) -> Date {
+		self.offset(other as i64).expect("Date overflowed")
 	}
 }
 
@@ -126,6 +208,9 @@ mod tests {
 		assert!(Date::new(2000, 1, 1).is_ok());
 		assert!(!Date::new(2019, 10, 32).is_ok());
 		assert!(!Date::new(2019, 13, 1).is_ok()); // Array out of bounds error!, fixed
+		assert!(Date::new(2020, 2, 29).is_ok());
+		assert!(!Date::new(2019, 2, 29).is_ok());
+		assert!(!Date::new(2100, 2, 29).is_ok());
 	}
 
 	#[test]
@@ -135,6 +220,53 @@ mod tests {
 		assert_eq!(date1 + 7, date2);
 	}
 
+	#[test]
+	fn addition_across_months_and_years() {
+		let date1 = Date::new(2000, 1, 24).unwrap();
+		assert_eq!(date1 + 7, Date::new(2000, 1, 31).unwrap());
+
+		let date2 = Date::new(2019, 12, 30).unwrap();
+		assert_eq!(date2 + 3, Date::new(2020, 1, 2).unwrap());
+		assert_eq!(date2.offset(-30), Date::new(2019, 11, 30));
+	}
+
+	#[test]
+	fn days_until() {
+		let date = Date::new(2019, 12, 30).unwrap();
+		assert_eq!(date.days_until(Date::new(2020, 1, 2).unwrap()), 3);
+		assert_eq!(date.days_until(date.offset(-30).unwrap()), -30);
+		assert_eq!(date.days_until(date), 0);
+	}
+
+	#[test]
+	fn months() {
+		let date = Date::new(2020, 1, 31).unwrap();
+		assert_eq!(date.start_of_month(), Date::new(2020, 1, 1).unwrap());
+		assert_eq!(date.offset_months(1), Date::new(2020, 2, 29));
+		assert_eq!(date.offset_months(-1), Date::new(2019, 12, 31));
+		assert_eq!(date.offset_months(14), Date::new(2021, 3, 31));
+		assert!(date.offset_months(-601).is_err());
+		assert!(date.offset_months(i64::MAX).is_err());
+	}
+
+	#[test]
+	fn offset_out_of_range() {
+		// Before 1970, past the year 65535, and far past what Chrono can count are all errors instead of panics
+		let first = Date::new(1970, 1, 1).unwrap();
+		let last = Date::new(65535, 12, 31).unwrap();
+		assert!(first.offset(-1).is_err());
+		assert!(last.offset(1).is_err());
+		assert!(first.offset(99_999_999_999).is_err());
+		assert!(first.offset(i64::MIN).is_err());
+		assert_eq!(last.offset(-365), Date::new(65534, 12, 31));
+	}
+
+	#[test]
+	fn weekday() {
+		assert_eq!(Date::new(2020, 1, 1).unwrap().weekday(), Day::Wednesday);
+		assert_eq!(Date::new(2019, 11, 5).unwrap().weekday(), Day::Tuesday);
+	}
+
 	#[test]
 	fn comparison() {
 		let date1 = Date::new(1990, 1, 1).unwrap();
@@ -15,23 +15,14 @@ pub enum Day {
 }
 
 impl Day {
-	/// Gets what day it is today.
-	/// It's basically a wrapper for an external library, making it nicer for internal use.
+	/// Gets what day it is today, in the system's time zone.
 	pub fn today() -> Self {
-		// We're depending on a foreign library for this
-		// Because, turns out, it's super difficult finding the day of a certain date
-		// This small function would be 10x longer if I had to implement this myself
-		// Thanks, developers of Chrono!
-		let localtime = Local::now();
-		match localtime.weekday() {
-			Sun => Self::Sunday,
-			Mon => Self::Monday,
-			Tue => Self::Tuesday,
-			Wed => Self::Wednesday,
-			Thu => Self::Thursday,
-			Fri => Self::Friday,
-			Sat => Self::Saturday,
-		}
+		Self::today_in(None)
+	}
+
+	/// Gets what day it is today in `zone`, or in the system's time zone if `zone` is `None`.
+	pub fn today_in(zone: Option<Zone>) -> Self {
+		Date::today_in(zone).weekday()
 	}
 
 	pub fn as_str(&self) -> &str {
@@ -46,13 +37,17 @@ impl Day {
 		}
 	}
 
-	pub fn weekdays() -> [Self; 5] {
+	/// Returns every day of the week, from Sunday to Saturday.
+	/// For the days that have school on them, see `Locale::school_week`.
+	pub fn all() -> [Self; 7] {
 		[
 			Day::Sunday,
 			Day::Monday,
 			Day::Tuesday,
 			Day::Wednesday,
 			Day::Thursday,
+			Day::Friday,
+			Day::Saturday,
 		]
 	}
 }
@@ -76,6 +71,24 @@ impl TryFrom<String> for Day {
 	}
 }
 
+// We're depending on a foreign library for finding the day of a certain date
+// Because, turns out, it's super difficult
+// This conversion lets the rest of the module use Chrono's answer
+// Thanks, developers of Chrono!
+impl From<chrono::Weekday> for Day {
+	fn from(weekday: chrono::Weekday) -> Self {
+		match weekday {
+			Sun => Self::Sunday,
+			Mon => Self::Monday,
+			Tue => Self::Tuesday,
+			Wed => Self::Wednesday,
+			Thu => Self::Thursday,
+			Fri => Self::Friday,
+			Sat => Self::Saturday,
+		}
+	}
+}
+
 impl fmt::Display for Day {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(f, "{}", self.as_str())
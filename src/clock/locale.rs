@@ -0,0 +1,267 @@
+use super::*;
+
+// See date.rs to understand #[derive()]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+/// Whether times are shown like '15:30' or '3:30 PM'.
+pub enum ClockStyle {
+	TwentyFourHour,
+	TwelveHour,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+/// Which way around dates are written.
+pub enum DateOrder {
+	/// 2020-03-11
+	YearMonthDay,
+	/// 11/03/2020
+	DayMonthYear,
+	/// 03/11/2020
+	MonthDayYear,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+/// The locale contains everything about how the calendar looks where the user lives.
+/// The defaults are the way this app always worked: school from Sunday to Thursday, ISO dates, 24hr times.
+pub struct Locale {
+	/// The days that have lessons on them.
+	pub school_days: Vec<Day>,
+	/// The day shown first in a week.
+	pub week_start: Day,
+	pub clock: ClockStyle,
+	pub date_order: DateOrder,
+}
+
+impl Locale {
+	/// Returns all seven days of the week, starting from `week_start`.
+	pub fn week(&self) -> Vec<Day> {
+		let start = self.week_start as usize;
+		(0..7).map(|i| Day::all()[(start + i) % 7]).collect()
+	}
+
+	/// Returns the school days, in the order they happen in the week.
+	pub fn school_week(&self) -> Vec<Day> {
+		self.week()
+			.into_iter()
+			.filter(|day| self.school_days.contains(day))
+			.collect()
+	}
+
+	/// Returns the first day of the week that `date` is in.
+	/// Returns an error if that's before 1970, which is only the case in the very first week.
+	pub fn start_of_week(&self, date: Date) -> Result<Date, ()> {
+		let behind = (date.weekday() as i64 - self.week_start as i64 + 7) % 7;
+		date.offset(-behind)
+	}
+
+	/// Returns the last day of the week that `date` is in.
+	/// Returns an error if that's after the last date we can hold, which is only the case in the very last week.
+	pub fn end_of_week(&self, date: Date) -> Result<Date, ()> {
+		self.date_in_week(date, self.week()[6])
+	}
+
+	/// Returns the date that `day` falls on, in the same week as `date`.
+	/// Returns an error if that's outside the dates we can hold, like `start_of_week`.
+	pub fn date_in_week(&self, date: Date, day: Day) -> Result<Date, ()> {
+		let start = self.start_of_week(date)?;
+		let ahead = (day as i64 - self.week_start as i64 + 7) % 7;
+		start.offset(ahead)
+	}
+
+	/// Returns every week that has a day of `date`'s month in it, as seven dates starting on `week_start`.
+	/// This is the grid of a month calendar, including the end of last month and the start of next.
+	/// Returns an error for the first and last months, where the grid goes outside the dates we can hold.
+	pub fn month_weeks(&self, date: Date) -> Result<Vec<Vec<Date>>, ()> {
+		let last = date.end_of_month();
+		let mut start = self.start_of_week(date.start_of_month())?;
+		let mut weeks = Vec::new();
+		while start <= last {
+			let week = (0..7).map(|day| start.offset(day));
+			weeks.push(week.collect::<Result<_, ()>>()?);
+			start = start.offset(7)?;
+		}
+		Ok(weeks)
+	}
+
+	/// Returns the month and year of `date`, like "March 2020".
+	pub fn format_month(&self, date: Date) -> String {
+		const MONTHS: [&str; 12] = [
+			"January",
+			"February",
+			"March",
+			"April",
+			"May",
+			"June",
+			"July",
+			"August",
+			"September",
+			"October",
+			"November",
+			"December",
+		];
+		format!("{} {}", MONTHS[date.month() as usize - 1], date.year())
+	}
+
+	pub fn format_date(&self, date: Date) -> String {
+		match self.date_order {
+			DateOrder::YearMonthDay => date.to_string(),
+			DateOrder::DayMonthYear => {
+				format!("{:02}/{:02}/{:04}", date.day(), date.month(), date.year())
+			}
+			DateOrder::MonthDayYear => {
+				format!("{:02}/{:02}/{:04}", date.month(), date.day(), date.year())
+			}
+		}
+	}
+
+	pub fn format_time(&self, time: Time) -> String {
+		match self.clock {
+			ClockStyle::TwentyFourHour => time.to_string(),
+			ClockStyle::TwelveHour => {
+				let suffix = if time.hour() < 12 { "AM" } else { "PM" };
+				let hour = match time.hour() % 12 {
+					0 => 12,
+					hour => hour,
+				};
+				format!("{}:{:02} {}", hour, time.minute(), suffix)
+			}
+		}
+	}
+
+	/// Parses a date the same way as `Date::parse`, but reads 'XX/YY' the way around this locale writes it.
+	/// This way, anything from `format_date` can be read back in.
+	pub fn parse_date(&self, text: &str, reference: Date) -> Result<Date, ()> {
+		if self.date_order == DateOrder::MonthDayYear && text.contains('/') {
+			// Date::parse reads day first, so swap the first two parts around
+			let mut segments: Vec<&str> = text.trim().split('/').collect();
+			if segments.len() >= 2 {
+				segments.swap(0, 1);
+			}
+			Date::parse(&segments.join("/"), reference)
+		} else {
+			Date::parse(text, reference)
+		}
+	}
+}
+
+impl Default for Locale {
+	fn default() -> Self {
+		Self {
+			school_days: vec![
+				Day::Sunday,
+				Day::Monday,
+				Day::Tuesday,
+				Day::Wednesday,
+				Day::Thursday,
+			],
+			week_start: Day::Sunday,
+			clock: ClockStyle::TwentyFourHour,
+			date_order: DateOrder::YearMonthDay,
+		}
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use crate::clock::*;
+
+	fn european() -> Locale {
+		Locale {
+			school_days: vec![
+				Day::Friday,
+				Day::Monday,
+				Day::Tuesday,
+				Day::Wednesday,
+				Day::Thursday,
+			],
+			week_start: Day::Monday,
+			clock: ClockStyle::TwelveHour,
+			date_order: DateOrder::DayMonthYear,
+		}
+	}
+
+	#[test]
+	fn weeks() {
+		let locale = european();
+		assert_eq!(locale.week()[0], Day::Monday);
+		assert_eq!(locale.week()[6], Day::Sunday);
+		assert_eq!(
+			locale.school_week(),
+			vec![
+				Day::Monday,
+				Day::Tuesday,
+				Day::Wednesday,
+				Day::Thursday,
+				Day::Friday
+			]
+		);
+
+		// 2020-01-05 is a Sunday, so with Monday starting the week, it belongs to the week of the 30th
+		let sunday = Date::new(2020, 1, 5).unwrap();
+		assert_eq!(locale.start_of_week(sunday), Date::new(2019, 12, 30));
+		assert_eq!(Locale::default().start_of_week(sunday), Ok(sunday));
+		assert_eq!(
+			locale.date_in_week(sunday, Day::Friday),
+			Date::new(2020, 1, 3)
+		);
+		// 1970-01-01 is a Thursday, so its week started before any date we can hold
+		assert!(locale.start_of_week(Date::new(1970, 1, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn month_weeks() {
+		// March 2020 starts on a Sunday and ends on a Tuesday
+		let date = Date::new(2020, 3, 11).unwrap();
+
+		let weeks = Locale::default().month_weeks(date).unwrap();
+		assert_eq!(weeks.len(), 5);
+		assert_eq!(weeks[0][0], Date::new(2020, 3, 1).unwrap());
+		assert_eq!(weeks[4][6], Date::new(2020, 4, 4).unwrap());
+
+		// Starting the week on Monday pushes the 1st to the end of the first week, and the 30th into a sixth
+		let weeks = european().month_weeks(date).unwrap();
+		assert_eq!(weeks.len(), 6);
+		assert_eq!(weeks[0][0], Date::new(2020, 2, 24).unwrap());
+		assert_eq!(weeks[0][6], Date::new(2020, 3, 1).unwrap());
+		assert_eq!(weeks[5][0], Date::new(2020, 3, 30).unwrap());
+		assert!(weeks.iter().all(|week| week.len() == 7));
+
+		assert_eq!(european().format_month(date), "March 2020");
+	}
+
+	#[test]
+	fn formatting() {
+		let locale = european();
+		let date = Date::new(2020, 3, 11).unwrap();
+
+		assert_eq!(locale.format_date(date), "11/03/2020");
+		assert_eq!(Locale::default().format_date(date), "2020-03-11");
+		assert_eq!(locale.format_time(Time::new(15, 5).unwrap()), "3:05 PM");
+		assert_eq!(locale.format_time(Time::new(0, 30).unwrap()), "12:30 AM");
+		assert_eq!(locale.format_time(Time::new(12, 0).unwrap()), "12:00 PM");
+		assert_eq!(Locale::default().format_time(Time::new(15, 5).unwrap()), "15:05");
+	}
+
+	#[test]
+	fn round_trip() {
+		let date = Date::new(2020, 3, 11).unwrap();
+		let mut locale = european();
+
+		for order in &[
+			DateOrder::YearMonthDay,
+			DateOrder::DayMonthYear,
+			DateOrder::MonthDayYear,
+		] {
+			locale.date_order = *order;
+			assert_eq!(locale.parse_date(&locale.format_date(date), date), Ok(date));
+		}
+
+		let time = Time::new(15, 5).unwrap();
+		assert_eq!(Time::parse(&locale.format_time(time)), Ok(time));
+	}
+}
@@ -1,7 +1,7 @@
 //! This module defines all the time-related structures, such as for dates and 24hr times.
 
 use chrono::prelude::Local;
-use chrono::Datelike;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
@@ -10,9 +10,17 @@ use std::fmt;
 mod date;
 /// Contains the Day struct and related functions.
 mod day;
+/// Contains the Locale struct, for how dates and weeks are shown.
+mod locale;
+/// Contains the parsers for dates and times as people type them, like "next friday" or "3pm".
+mod parse;
 /// Contains the Time struct and related functions.
 mod time;
+/// Contains the Zone struct and time zone conversions.
+mod zone;
 
 pub use date::Date;
 pub use day::Day;
+pub use locale::{ClockStyle, DateOrder, Locale};
 pub use time::Time;
+pub use zone::{Transition, Zone};
@@ -0,0 +1,259 @@
+use super::*;
+
+// The `TryFrom<String>` implementations only accept the strict formats, which is what we want when loading data
+// People typing into a box don't think in 'YYYY-MM-DD' though, so this file is for understanding them
+// Everything is lowercased and trimmed first, so "Next Friday " is the same as "next friday"
+
+impl Date {
+	/// Understands a date the way a person would type it, relative to `reference` (usually today).
+	///
+	/// Accepts, as well as `YYYY-MM-DD`:
+	/// - `today`, `tomorrow`, `yesterday`
+	/// - `friday` (the next Friday, or today if it's Friday) and `next friday` (never today)
+	/// - `in 3 days`, `in 2 weeks`, `in a week`
+	/// - `end of month`
+	/// - `11/03` (the next 11th of March) and `11/03/2020`, day first
+	pub fn parse(text: &str, reference: Date) -> Result<Self, ()> {
+		let text = text.trim().to_lowercase();
+		let words: Vec<&str> = text.split_whitespace().collect();
+
+		match words.as_slice() {
+			["today"] => Ok(reference),
+			["tomorrow"] => reference.offset(1),
+			["yesterday"] => reference.offset(-1),
+			["end", "of", "month"] | ["end", "of", "the", "month"] => Ok(reference.end_of_month()),
+			["in", amount, unit] => {
+				// Parsing into a u32 turns away negative numbers, and `offset` turns away anything too far ahead
+				let amount: u32 = match *amount {
+					"a" | "an" | "one" => 1,
+					number => number.parse().map_err(|_| ())?,
+				};
+				match unit.trim_end_matches('s') {
+					"day" => reference.offset(amount as i64),
+					"week" => reference.offset(amount as i64 * 7),
+					_ => Err(()),
+				}
+			}
+			["next", day] => reference.next_weekday(Day::parse(day)?, false),
+			[day] if Day::parse(day).is_ok() => reference.next_weekday(Day::parse(day)?, true),
+			[date] if date.contains('/') => Self::parse_slashed(date, reference),
+			_ => Date::try_from(text.clone()),
+		}
+	}
+
+	/// Parses `DD/MM` or `DD/MM/YYYY`.
+	/// Without a year, picks the first one that isn't before `reference`, since you don't set things due in the past.
+	fn parse_slashed(text: &str, reference: Date) -> Result<Self, ()> {
+		let segments: Vec<&str> = text.split('/').collect();
+
+		match segments.as_slice() {
+			[day, month] => {
+				let (day, month) = (day.parse().map_err(|_| ())?, month.parse().map_err(|_| ())?);
+				// The 29th of February only comes every 4 years (or 8, around 2100), so we look that far ahead
+				(0..=8)
+					.filter_map(|ahead| reference.year().checked_add(ahead))
+					.filter_map(|year| Date::new(year, month, day).ok())
+					.find(|date| *date >= reference)
+					.ok_or(())
+			}
+			[day, month, year] => Date::new(
+				year.parse().map_err(|_| ())?,
+				month.parse().map_err(|_| ())?,
+				day.parse().map_err(|_| ())?,
+			),
+			_ => Err(()),
+		}
+	}
+
+	/// Returns the last day of this date's month.
+	pub fn end_of_month(self) -> Self {
+		// December always ends on the 31st, and other months end the day before the next one starts
+		// Neither can go past the last date we can hold, so unwrap() is fine
+		if self.month() == 12 {
+			Date::new(self.year(), 12, 31).unwrap()
+		} else {
+			let first_of_next = Date::new(self.year(), self.month() + 1, 1).unwrap();
+			first_of_next.offset(-1).unwrap()
+		}
+	}
+
+	/// Returns the next date that falls on `day`.
+	/// If `inclusive` is true and this date is already on `day`, returns this date.
+	/// Returns an error if that's too far in the future for a date to hold.
+	pub fn next_weekday(self, day: Day, inclusive: bool) -> Result<Self, ()> {
+		let mut ahead = (day as i64 - self.weekday() as i64 + 7) % 7;
+		if ahead == 0 && !inclusive {
+			ahead = 7;
+		}
+		self.offset(ahead)
+	}
+}
+
+impl Time {
+	/// Understands a time the way a person would type it.
+	///
+	/// Accepts, as well as `HH:MM`:
+	/// - `3pm`, `3 pm`, `3:30pm`, `12am`
+	/// - `15h30`, `15h`
+	/// - `noon`, `midnight`
+	pub fn parse(text: &str) -> Result<Self, ()> {
+		let text: String = text.trim().to_lowercase().split_whitespace().collect();
+
+		match text.as_str() {
+			"noon" | "midday" => return Time::new(12, 0),
+			"midnight" => return Time::new(0, 0),
+			_ => (),
+		}
+
+		// 'am' and 'pm' just decide what to add to the hour, and 12 is the odd one out
+		if text.ends_with("am") || text.ends_with("pm") {
+			let (clock, suffix) = text.split_at(text.len() - 2);
+			let (hour, minute) = Self::parse_parts(clock, ':')?;
+			if hour == 0 || hour > 12 {
+				return Err(());
+			}
+			let hour = match (suffix, hour) {
+				("am", 12) => 0,
+				("pm", 12) => 12,
+				("pm", hour) => hour + 12,
+				(_, hour) => hour,
+			};
+			return Time::new(hour, minute);
+		}
+
+		let separator = if text.contains('h') { 'h' } else { ':' };
+		let (hour, minute) = Self::parse_parts(&text, separator)?;
+		Time::new(hour, minute)
+	}
+
+	/// Splits `text` into an hour and an optional minute, like "3:30" or "15h".
+	fn parse_parts(text: &str, separator: char) -> Result<(u8, u8), ()> {
+		let mut segments = text.splitn(2, separator);
+		let hour = segments.next().unwrap().parse().map_err(|_| ())?;
+		let minute = match segments.next() {
+			Some("") | None => 0,
+			Some(minute) => minute.parse().map_err(|_| ())?,
+		};
+		Ok((hour, minute))
+	}
+}
+
+impl Day {
+	/// Understands a day of the week, either in full or abbreviated, like "fri" or "Friday".
+	pub fn parse(text: &str) -> Result<Self, ()> {
+		let text = text.trim().to_lowercase();
+		if text.len() < 2 {
+			return Err(());
+		}
+
+		Day::all()
+			.iter()
+			.find(|day| day.as_str().to_lowercase().starts_with(&text))
+			.copied()
+			.ok_or(())
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use crate::clock::*;
+
+	fn date(year: u16, month: u8, day: u8) -> Date {
+		Date::new(year, month, day).unwrap()
+	}
+
+	fn time(hour: u8, minute: u8) -> Time {
+		Time::new(hour, minute).unwrap()
+	}
+
+	// 2020-01-08 is a Wednesday
+	fn reference() -> Date {
+		date(2020, 1, 8)
+	}
+
+	#[test]
+	fn relative_dates() {
+		assert_eq!(Date::parse("today", reference()), Ok(reference()));
+		assert_eq!(Date::parse("Tomorrow", reference()), Ok(date(2020, 1, 9)));
+		assert_eq!(Date::parse("in 3 days", reference()), Ok(date(2020, 1, 11)));
+		assert_eq!(Date::parse("in a week", reference()), Ok(date(2020, 1, 15)));
+		assert_eq!(Date::parse("in 4 weeks", reference()), Ok(date(2020, 2, 5)));
+		assert_eq!(Date::parse("end of month", reference()), Ok(date(2020, 1, 31)));
+		assert_eq!(date(2020, 2, 3).end_of_month(), date(2020, 2, 29));
+		assert_eq!(date(2019, 12, 3).end_of_month(), date(2019, 12, 31));
+	}
+
+	#[test]
+	fn relative_dates_out_of_range() {
+		// Negative counts and ones that go past the last date we can hold are errors, not panics
+		assert!(Date::parse("in -30000 days", reference()).is_err());
+		assert!(Date::parse("in -1 weeks", reference()).is_err());
+		assert!(Date::parse("in 99999999999 days", reference()).is_err());
+		assert!(Date::parse("in 4000000000 weeks", reference()).is_err());
+		assert!(Date::parse("in 40000000 days", reference()).is_err());
+		assert_eq!(Date::parse("in 0 days", reference()), Ok(reference()));
+
+		assert!(Date::parse("tomorrow", date(65535, 12, 31)).is_err());
+		assert!(Date::parse("yesterday", date(1970, 1, 1)).is_err());
+		assert!(Date::parse("friday", date(65535, 12, 31)).is_err());
+		assert_eq!(Date::parse("end of month", date(65535, 12, 3)), Ok(date(65535, 12, 31)));
+	}
+
+	#[test]
+	fn weekdays() {
+		assert_eq!(Date::parse("friday", reference()), Ok(date(2020, 1, 10)));
+		assert_eq!(Date::parse("next friday", reference()), Ok(date(2020, 1, 10)));
+		assert_eq!(Date::parse("wed", reference()), Ok(reference()));
+		assert_eq!(Date::parse("next wednesday", reference()), Ok(date(2020, 1, 15)));
+		assert_eq!(Date::parse("next sunday", reference()), Ok(date(2020, 1, 12)));
+	}
+
+	#[test]
+	fn slashed_dates() {
+		assert_eq!(Date::parse("11/03", reference()), Ok(date(2020, 3, 11)));
+		// Already passed this year, so it must be next year
+		assert_eq!(Date::parse("1/1", reference()), Ok(date(2021, 1, 1)));
+		assert_eq!(Date::parse("11/03/2019", reference()), Ok(date(2019, 3, 11)));
+		assert!(Date::parse("31/02", reference()).is_err());
+		// 2021 isn't a leap year, so the next 29th of February is in 2024
+		assert_eq!(Date::parse("29/02", date(2021, 5, 1)), Ok(date(2024, 2, 29)));
+		assert_eq!(Date::parse("29/02", date(2096, 3, 1)), Ok(date(2104, 2, 29)));
+		assert!(Date::parse("1/1", date(65535, 6, 1)).is_err());
+	}
+
+	#[test]
+	fn strict_dates_still_work() {
+		assert_eq!(Date::parse("2020-05-01", reference()), Ok(date(2020, 5, 1)));
+		assert!(Date::parse("someday", reference()).is_err());
+		assert!(Date::parse("in three fortnights", reference()).is_err());
+	}
+
+	#[test]
+	fn times() {
+		assert_eq!(Time::parse("09:30"), Ok(time(9, 30)));
+		assert_eq!(Time::parse("3pm"), Ok(time(15, 0)));
+		assert_eq!(Time::parse("3:45 PM"), Ok(time(15, 45)));
+		assert_eq!(Time::parse("12am"), Ok(time(0, 0)));
+		assert_eq!(Time::parse("12pm"), Ok(time(12, 0)));
+		assert_eq!(Time::parse("15h30"), Ok(time(15, 30)));
+		assert_eq!(Time::parse("15h"), Ok(time(15, 0)));
+		assert_eq!(Time::parse("noon"), Ok(time(12, 0)));
+		assert!(Time::parse("13pm").is_err());
+		assert!(Time::parse("25h").is_err());
+		assert!(Time::parse("teatime").is_err());
+	}
+
+	#[test]
+	fn days() {
+		assert_eq!(Day::parse("Fri"), Ok(Day::Friday));
+		assert_eq!(Day::parse("thursday"), Ok(Day::Thursday));
+		assert!(Day::parse("t").is_err());
+		assert!(Day::parse("funday").is_err());
+	}
+}
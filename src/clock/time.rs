@@ -18,6 +18,43 @@ impl Time {
 		}
 	}
 
+	pub fn hour(self) -> u8 {
+		self.hour
+	}
+
+	pub fn minute(self) -> u8 {
+		self.minute
+	}
+
+	/// Returns the time right now in `zone`, or in the system's time zone if `zone` is `None`.
+	pub fn now_in(zone: Option<Zone>) -> Self {
+		let now = Zone::now_in(zone);
+		Self::new(now.hour() as u8, now.minute() as u8).unwrap()
+	}
+
+	/// Moves this time, on `date`, by `minutes` (which can be negative).
+	/// Returns the new date along with the new time, since going past midnight changes the day.
+	/// Returns an error if the date goes further than a date can, see `Date::offset`.
+	pub fn offset(self, date: Date, minutes: i64) -> Result<(Date, Self), ()> {
+		let total = (self.hour as i64 * 60 + self.minute as i64)
+			.checked_add(minutes)
+			.ok_or(())?;
+		// div_euclid and rem_euclid round towards negative infinity, so -30 minutes is 23:30 the day before
+		let days = total.div_euclid(24 * 60);
+		let total = total.rem_euclid(24 * 60);
+		Ok((
+			date.offset(days)?,
+			Self::new((total / 60) as u8, (total % 60) as u8).unwrap(),
+		))
+	}
+
+	/// Returns how many minutes it is from this time on `date` to `other` on `other_date`.
+	/// It's negative if `other` comes first, like `Date::days_until`.
+	pub fn minutes_until(self, date: Date, other: Time, other_date: Date) -> i64 {
+		let minutes = |time: Time| time.hour as i64 * 60 + time.minute as i64;
+		date.days_until(other_date) * 24 * 60 + minutes(other) - minutes(self)
+	}
+
 	pub fn as_string(self) -> String {
 		format!("{:02}:{:02}", self.hour, self.minute)
 	}
@@ -96,4 +133,36 @@ mod tests {
 
 		assert!(!other_time.is_between(start, end));
 	}
+
+	#[test]
+	fn offset() {
+		let date = Date::new(2020, 1, 1).unwrap();
+		let time = Time::new(0, 10).unwrap();
+
+		assert_eq!(time.offset(date, 20), Ok((date, Time::new(0, 30).unwrap())));
+		assert_eq!(
+			time.offset(date, -20),
+			Ok((Date::new(2019, 12, 31).unwrap(), Time::new(23, 50).unwrap()))
+		);
+		assert_eq!(
+			time.offset(date, 2 * 24 * 60),
+			Ok((Date::new(2020, 1, 3).unwrap(), time))
+		);
+		assert!(time.offset(date, i64::MAX).is_err());
+	}
+
+	#[test]
+	fn minutes_until() {
+		let date = Date::new(2020, 1, 1).unwrap();
+		let time = Time::new(23, 30).unwrap();
+
+		assert_eq!(
+			time.minutes_until(date, Time::new(23, 45).unwrap(), date),
+			15
+		);
+		// Going past midnight
+		let (next_date, next_time) = time.offset(date, 45).unwrap();
+		assert_eq!(time.minutes_until(date, next_time, next_date), 45);
+		assert_eq!(next_time.minutes_until(next_date, time, date), -45);
+	}
 }
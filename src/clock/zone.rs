@@ -0,0 +1,261 @@
+use super::*;
+use chrono::Offset;
+use chrono_tz::{OffsetComponents, Tz};
+
+// See date.rs to understand #[derive()]
+// Tz is Chrono's list of every time zone in the world (the IANA database), e.g. "Europe/London"
+// It knows about daylight saving time, which is the whole reason we're not doing this ourselves
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+/// A time zone, such as Asia/Tokyo.
+/// Anywhere an `Option<Zone>` is taken, `None` means "whatever zone the computer is set to".
+pub struct Zone(Tz);
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+/// A change in a zone's offset from UTC, like the clocks going forward for summer time.
+pub struct Transition {
+	/// The wall clock date and time it happens at, as it would be read just before the change.
+	pub date: Date,
+	pub time: Time,
+	/// How many minutes ahead of UTC the zone is before the change, and after it.
+	pub from: i32,
+	pub to: i32,
+	/// Whether summer time starts here, rather than ends.
+	pub summer: bool,
+}
+
+impl Zone {
+	/// Returns the current date and time in `zone`, as it would be shown on a wall clock there.
+	pub(super) fn now_in(zone: Option<Zone>) -> NaiveDateTime {
+		match zone {
+			Some(Zone(tz)) => Utc::now().with_timezone(&tz).naive_local(),
+			None => Local::now().naive_local(),
+		}
+	}
+
+	/// Converts a wall clock date and time in `from` into the wall clock date and time in `to`.
+	///
+	/// The date matters, not just the time: 09:00 in New York is 14:00 in London in January,
+	/// but 13:00 in the few weeks of March where only the US has switched to summer time.
+	/// The date can also change, if the time is pushed past midnight.
+	pub fn convert(date: Date, time: Time, from: Option<Zone>, to: Option<Zone>) -> (Date, Time) {
+		if from == to {
+			return (date, time);
+		}
+
+		let wall = date
+			.naive()
+			.and_hms(time.hour() as u32, time.minute() as u32, 0);
+		let converted = Self::from_utc(Self::to_utc(wall, from), to);
+
+		(
+			// Only the very first and last days we can hold could be pushed past what a date can hold
+			// It's much better to be a few hours off there than to crash, so those stay on the same date
+			Date::from_naive(converted.date()).unwrap_or(date),
+			Time::new(converted.hour() as u8, converted.minute() as u8).unwrap(),
+		)
+	}
+
+	/// Turns a wall clock time in `zone` into UTC.
+	fn to_utc(wall: NaiveDateTime, zone: Option<Zone>) -> NaiveDateTime {
+		// Twice a year, wall clocks are weird
+		// When the clocks go back, a time like 01:30 happens twice, so we take the first one
+		// When the clocks go forward, a time like 02:30 never happens
+		// Chrono-tz reads those with the summer offset, but if the system zone refuses, we try an hour later
+		let resolve = |wall: NaiveDateTime| match zone {
+			Some(Zone(tz)) => tz
+				.from_local_datetime(&wall)
+				.earliest()
+				.map(|datetime| datetime.naive_utc()),
+			None => Local
+				.from_local_datetime(&wall)
+				.earliest()
+				.map(|datetime| datetime.naive_utc()),
+		};
+
+		resolve(wall)
+			.or_else(|| resolve(wall + Duration::hours(1)))
+			.unwrap_or(wall)
+	}
+
+	/// Turns a UTC time into the wall clock time in `zone`.
+	fn from_utc(utc: NaiveDateTime, zone: Option<Zone>) -> NaiveDateTime {
+		match zone {
+			Some(Zone(tz)) => tz.from_utc_datetime(&utc).naive_local(),
+			None => Local.from_utc_datetime(&utc).naive_local(),
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		self.0.name()
+	}
+
+	/// Returns how many minutes ahead of UTC the zone is at `utc`, and whether that's summer time.
+	fn offset_at(self, utc: NaiveDateTime) -> (i32, bool) {
+		let offset = self.0.offset_from_utc_datetime(&utc);
+		(
+			offset.fix().local_minus_utc() / 60,
+			offset.dst_offset() != Duration::zero(),
+		)
+	}
+
+	/// Returns how many minutes ahead of UTC the zone is at the start of `date` (in UTC), and whether that's summer time.
+	pub fn offset_on(self, date: Date) -> (i32, bool) {
+		self.offset_at(date.naive().and_hms(0, 0, 0))
+	}
+
+	/// Returns every change in the zone's offset from the start of `from` until the start of `to` (in UTC), in order.
+	/// This is for telling calendar apps about the zone, since they can't be relied on to know it themselves.
+	pub fn transitions(self, from: Date, to: Date) -> Vec<Transition> {
+		// Clocks never change more than once in a day, so we only look at midnight every day
+		// When the offset is different to the day before, we narrow down on the minute it changed, by halving
+		let mut transitions = Vec::new();
+		let mut day = from.naive().and_hms(0, 0, 0);
+		let end = to.naive().and_hms(0, 0, 0);
+		let mut offset = self.offset_at(day);
+		while day < end {
+			let next = day + Duration::days(1);
+			let next_offset = self.offset_at(next);
+			if next_offset != offset {
+				// The change is after `before`, and at or before `after`
+				let (mut before, mut after) = (day, next);
+				while after - before > Duration::minutes(1) {
+					let middle = before + (after - before) / 2;
+					if self.offset_at(middle) == offset {
+						before = middle;
+					} else {
+						after = middle;
+					}
+				}
+				let wall = after + Duration::minutes(offset.0 as i64);
+				transitions.push(Transition {
+					// Only the very last day we can hold could be pushed past it, like in `convert`
+					date: Date::from_naive(wall.date()).unwrap_or(to),
+					time: Time::new(wall.hour() as u8, wall.minute() as u8).unwrap(),
+					from: offset.0,
+					to: next_offset.0,
+					summer: next_offset.1,
+				});
+				offset = next_offset;
+			}
+			day = next;
+		}
+		transitions
+	}
+}
+
+// See the same implementation in date.rs for more information
+impl TryFrom<String> for Zone {
+	type Error = ();
+	/// The correct format here is the IANA name, like "Europe/London" or "UTC".
+	fn try_from(string: String) -> Result<Self, ()> {
+		string.trim().parse().map(Zone).map_err(|_| ())
+	}
+}
+
+impl fmt::Display for Zone {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use crate::clock::*;
+
+	fn zone(name: &str) -> Option<Zone> {
+		Some(Zone::try_from(name.to_string()).unwrap())
+	}
+
+	fn date(year: u16, month: u8, day: u8) -> Date {
+		Date::new(year, month, day).unwrap()
+	}
+
+	fn time(hour: u8, minute: u8) -> Time {
+		Time::new(hour, minute).unwrap()
+	}
+
+	#[test]
+	fn parsing() {
+		assert!(Zone::try_from("Europe/London".to_string()).is_ok());
+		assert!(Zone::try_from(" UTC ".to_string()).is_ok());
+		assert!(Zone::try_from("Mars/Olympus_Mons".to_string()).is_err());
+	}
+
+	#[test]
+	fn daylight_saving() {
+		let new_york = zone("America/New_York");
+		let london = zone("Europe/London");
+
+		// Neither has switched to summer time
+		assert_eq!(
+			Zone::convert(date(2020, 1, 10), time(9, 0), new_york, london),
+			(date(2020, 1, 10), time(14, 0))
+		);
+		// The US switched on the 8th of March, the UK only on the 29th
+		assert_eq!(
+			Zone::convert(date(2020, 3, 10), time(9, 0), new_york, london),
+			(date(2020, 3, 10), time(13, 0))
+		);
+		// Both have switched
+		assert_eq!(
+			Zone::convert(date(2020, 4, 10), time(9, 0), new_york, london),
+			(date(2020, 4, 10), time(14, 0))
+		);
+	}
+
+	#[test]
+	fn across_midnight() {
+		assert_eq!(
+			Zone::convert(date(2020, 1, 1), time(8, 0), zone("Asia/Tokyo"), zone("UTC")),
+			(date(2019, 12, 31), time(23, 0))
+		);
+	}
+
+	#[test]
+	fn skipped_hour() {
+		// 02:30 never happened in London on the 29th of March 2020, so it's read as summer time
+		assert_eq!(
+			Zone::convert(date(2020, 3, 29), time(2, 30), zone("Europe/London"), zone("UTC")),
+			(date(2020, 3, 29), time(1, 30))
+		);
+	}
+
+	#[test]
+	fn transitions() {
+		let london = zone("Europe/London").unwrap();
+		assert_eq!(london.offset_on(date(2020, 1, 1)), (0, false));
+		assert_eq!(
+			london.transitions(date(2020, 1, 1), date(2021, 1, 1)),
+			vec![
+				// The clocks go forward at 01:00, and back at 02:00 summer time
+				Transition {
+					date: date(2020, 3, 29),
+					time: time(1, 0),
+					from: 0,
+					to: 60,
+					summer: true,
+				},
+				Transition {
+					date: date(2020, 10, 25),
+					time: time(2, 0),
+					from: 60,
+					to: 0,
+					summer: false,
+				},
+			]
+		);
+
+		// Tokyo doesn't have summer time at all
+		let tokyo = zone("Asia/Tokyo").unwrap();
+		assert_eq!(tokyo.offset_on(date(2020, 1, 1)), (9 * 60, false));
+		assert!(tokyo
+			.transitions(date(2020, 1, 1), date(2030, 1, 1))
+			.is_empty());
+	}
+}
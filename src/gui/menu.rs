@@ -1,87 +1,390 @@
 use super::*;
 
-/// Open the "main" menu with today's courses and this week's tasks.
+/// Open the "main" menu with overdue tasks, today's courses and this week's tasks.
+/// Every list keeps itself up to date, so edits made in pop-ups show up straight away.
 pub(super) fn main(grid: &Grid, application: Rc<RefCell<Application>>) {
 	clear(grid);
 
-	let courses = ListBox::new();
-	if application.borrow().is_holiday(Date::today()) {
-		courses.insert(&Label::new(Some("Today's a holiday - no lessons!")), -1);
-	} else {
-		for course in &application.borrow().courses {
-			for time in &course.times {
-				if time.0 == Day::today() {
-					courses.insert(
-						&Label::new(Some(
-							format!("{}: {} to {}", course.name, time.1, time.2).as_str(),
-						)),
-						-1,
+	// Overdue tasks aren't in this week's tasks anymore, so they get their own list right at the top
+	let overdue = live_list(
+		&application,
+		|change| change != Change::Holidays,
+		|application| {
+			let today = application.today();
+			let locale = &application.locale;
+			let tasks = application.overdue_tasks(today);
+			if tasks.is_empty() {
+				return vec!["Nothing's overdue!".to_string()];
+			}
+			tasks
+				.iter()
+				.map(|entry| {
+					let mut text = format!(
+						"[{}] {}: {} ({} days late",
+						entry.task.priority,
+						entry.course.name,
+						entry.task.name,
+						entry.task.days_overdue(today).unwrap_or(0)
 					);
-				}
+					if let Some(original) = entry.task.original_due {
+						text.push_str(&format!(", first due {}", locale.format_date(original)));
+					}
+					text.push(')');
+					text
+				})
+				.collect()
+		},
+	);
+	let overdue_frame = FrameBuilder::new()
+		.label("Overdue Tasks (give them a new due date in the Tasks menu)")
+		.build();
+	overdue_frame.add(&overdue);
+	overdue_frame.set_hexpand(true);
+
+	let courses = live_list(
+		&application,
+		|_| true,
+		|application| {
+			let today = application.today();
+			let locale = &application.locale;
+			if application.is_holiday(today) {
+				return vec!["Today's a holiday - no lessons!".to_string()];
 			}
-		}
-	}
+			application
+				.lessons_on(today)
+				.into_iter()
+				.map(|(course, start, end)| {
+					format!(
+						"{}: {} to {}",
+						course.name,
+						locale.format_time(start),
+						locale.format_time(end)
+					)
+				})
+				.collect()
+		},
+	);
 	let courses_frame = FrameBuilder::new().label("Today's Courses").build();
 	courses_frame.add(&courses);
 	courses_frame.set_hexpand(true);
 	courses_frame.set_vexpand(true);
 
-	let hw = ListBox::new();
-	for course in &application.borrow().courses {
-		for task in &course.tasks {
-			if Date::today() <= task.due && task.due <= Date::today() + 7 
-                            && !task.is_complete() {
-				hw.insert(
-					&Label::new(Some(format!("{}: {}", task.name, task.desc).as_str())),
-					-1,
-				);
-			}
-		}
-	}
+	let hw = live_list(
+		&application,
+		|change| change != Change::Holidays,
+		|application| {
+			let today = application.today();
+			let query = TaskQuery::new()
+				.due_between(today, today + 7)
+				.complete(false)
+				.sort_by(TaskSort::Due);
+			application
+				.tasks(&query)
+				.iter()
+				.map(|entry| {
+					format!(
+						"[{}] {}: {}",
+						entry.task.priority, entry.task.name, entry.task.desc
+					)
+				})
+				.collect()
+		},
+	);
 	let hw_frame = FrameBuilder::new().label("Week's Tasks").build();
 	hw_frame.add(&hw);
 	hw_frame.set_hexpand(true);
 	hw_frame.set_vexpand(true);
 
-	grid.attach(&courses_frame, 1, 0, 1, 1);
-	grid.attach(&hw_frame, 2, 0, 1, 1);
+	grid.attach(&overdue_frame, 1, 0, 2, 1);
+	grid.attach(&courses_frame, 1, 1, 1, 1);
+	grid.attach(&hw_frame, 2, 1, 1, 1);
+}
+
+/// Open the weekly courses page. Has the school days set in the locale, Sunday-Thursday by default.
+/// The lessons are drawn on a timeline (see timeline.rs), and clicking one opens its course.
+pub(super) fn weekly(
+	grid: &Grid,
+	gui_app: &gtk::Application,
+	window: &gtk::ApplicationWindow,
+	application: Rc<RefCell<Application>>,
+) {
+	clear(grid);
+
+	let frame = FrameBuilder::new()
+		.label("This Week (drag a lesson to move it, or its bottom edge to change when it ends)")
+		.build();
+	frame.add(&timeline::timeline(gui_app, application));
+	frame.set_hexpand(true);
+	frame.set_vexpand(true);
+	grid.attach(&frame, 1, 0, 1, 1);
+
+	window.show_all();
 }
 
-/// Open the weekly courses page. Has the weekdays Sunday-Thursday.
-pub(super) fn weekly(grid: &Grid, application: Rc<RefCell<Application>>) {
+/// How many lessons and tasks fit in a day of the month view, before it says how many more there are.
+const MONTH_CELL_LINES: usize = 3;
+/// How many characters fit on a line in a day of the month view.
+const MONTH_CELL_WIDTH: usize = 18;
+
+/// Shows `shown`'s month as a calendar, with the lessons, tasks and holidays on each day.
+/// Clicking a day shows everything happening on it underneath.
+pub(super) fn month(
+	grid: &Grid,
+	gui_app: &gtk::Application,
+	window: &gtk::ApplicationWindow,
+	application: Rc<RefCell<Application>>,
+	shown: Date,
+) {
 	clear(grid);
 
-	let mut vec = Vec::new();
-	vec.push(ListBox::new());
-	vec.push(ListBox::new());
-	vec.push(ListBox::new());
-	vec.push(ListBox::new());
-	vec.push(ListBox::new());
-
-	for course in &application.borrow().courses {
-		for (day, start, end) in &course.times {
-			if *day as usize > 4 {
-				continue;
-			}
-			vec[*day as usize].insert(
-				&Label::new(Some(
-					format!("{}: {} to {}", course.name, start, end).as_str(),
-				)),
-				-1,
-			);
+	let today = application.borrow().today();
+	let locale = application.borrow().locale.clone();
+
+	// There's nothing before 1970, so the buttons stop at the first (and last) month that can be shown
+	let previous = shown
+		.offset_months(-1)
+		.ok()
+		.filter(|previous| locale.month_weeks(*previous).is_ok());
+	let next = shown
+		.offset_months(1)
+		.ok()
+		.filter(|next| locale.month_weeks(*next).is_ok());
+	let button_previous = Button::new_with_label("<");
+	button_previous.set_sensitive(previous.is_some());
+	button_previous.connect_clicked(
+		clone!(@weak grid, @weak gui_app, @weak window, @weak application => move |_| {
+			if let Some(previous) = previous {
+				month(&grid, &gui_app, &window, application, previous);
+			}
+		}),
+	);
+	let button_next = Button::new_with_label(">");
+	button_next.set_sensitive(next.is_some());
+	button_next.connect_clicked(
+		clone!(@weak grid, @weak gui_app, @weak window, @weak application => move |_| {
+			if let Some(next) = next {
+				month(&grid, &gui_app, &window, application, next);
+			}
+		}),
+	);
+	let button_today = Button::new_with_label("Today");
+	button_today.connect_clicked(
+		clone!(@weak grid, @weak gui_app, @weak window, @weak application => move |_| {
+			month(&grid, &gui_app, &window, application, today);
+		}),
+	);
+	let title = Label::new(Some(&locale.format_month(shown)));
+	title.set_hexpand(true);
+
+	let navigation = Box::new(Orientation::Horizontal, 5);
+	navigation.pack_start(&button_previous, false, false, 0);
+	navigation.pack_start(&title, true, true, 0);
+	navigation.pack_start(&button_today, false, false, 0);
+	navigation.pack_start(&button_next, false, false, 0);
+
+	// The day that's clicked on has its agenda shown here, starting with today if it's in this month
+	let agenda_list = ListBox::new();
+	agenda_list.set_selection_mode(SelectionMode::None);
+	let agenda_frame = frame_with_text("", &agenda_list);
+	agenda_frame.set_hexpand(true);
+	let first_shown = if today.start_of_month() == shown.start_of_month() {
+		today
+	} else {
+		shown.start_of_month()
+	};
+	show_agenda(
+		&agenda_frame,
+		&agenda_list,
+		gui_app,
+		application.clone(),
+		first_shown,
+	);
+
+	// Holidays are shaded, with a bit of CSS that only applies to the days it's added to
+	let holiday_style = CssProvider::new();
+	let _ = holiday_style.load_from_data(
+		b"button { background-image: none; background-color: alpha(@theme_selected_bg_color, 0.25); }",
+	);
+
+	let calendar = Grid::new();
+	calendar.set_row_homogeneous(true);
+	calendar.set_column_homogeneous(true);
+	calendar.set_hexpand(true);
+	calendar.set_vexpand(true);
+	for (column, day) in locale.week().iter().enumerate() {
+		calendar.attach(&Label::new(Some(day.as_str())), column as i32, 0, 1, 1);
+	}
+
+	// The very first month there is (if the clock was reset to 1970) can't be shown as whole weeks, so it's empty
+	let weeks = locale.month_weeks(shown).unwrap_or_default();
+	let agendas = match (weeks.first(), weeks.last()) {
+		(Some(first), Some(last)) => application.borrow().agendas(first[0], last[6]),
+		_ => Vec::new(),
+	};
+	for (index, agenda) in agendas.iter().enumerate() {
+		let date = agenda.date;
+
+		let mut lines = Vec::new();
+		{
+			let application = application.borrow();
+			if agenda.holiday {
+				lines.push("Holiday".to_string());
+			}
+			for (course_index, start, _) in &agenda.lessons {
+				lines.push(format!(
+					"{} {}",
+					locale.format_time(*start),
+					application.courses[*course_index].name
+				));
+			}
+			for (course_index, task_index) in &agenda.tasks {
+				lines.push(format!(
+					"Due: {}",
+					application.courses[*course_index].tasks[*task_index].name
+				));
+			}
+		}
+		// Long names would make every day as wide as the longest one, so they're cut short
+		for line in lines.iter_mut() {
+			if line.chars().count() > MONTH_CELL_WIDTH {
+				*line = line.chars().take(MONTH_CELL_WIDTH - 1).collect::<String>() + "…";
+			}
+		}
+		let more = lines.len().saturating_sub(MONTH_CELL_LINES);
+		lines.truncate(MONTH_CELL_LINES);
+		if more > 0 {
+			lines.push(format!("+{} more", more));
+		}
+
+		// Labels understand a small bit of HTML, so everything typed in has to be escaped
+		let number = if date == today {
+			format!("<b><u>{}</u></b>", date.day())
+		} else {
+			format!("<b>{}</b>", date.day())
+		};
+		let mut markup = number;
+		for line in &lines {
+			markup.push('\n');
+			markup.push_str(&glib::markup_escape_text(line));
+		}
+		// Days from the months either side are greyed out
+		if date.month() != shown.month() {
+			markup = format!("<span foreground=\"gray\">{}</span>", markup);
+		}
+
+		let label = Label::new(None);
+		label.set_markup(&markup);
+		label.set_halign(Align::Start);
+		label.set_valign(Align::Start);
+
+		let cell = Button::new();
+		cell.add(&label);
+		if agenda.holiday {
+			cell.get_style_context()
+				.add_provider(&holiday_style, STYLE_PROVIDER_PRIORITY_APPLICATION);
 		}
+		cell.connect_clicked(
+			clone!(@weak agenda_frame, @weak agenda_list, @weak gui_app, @weak application => move |_| {
+				show_agenda(&agenda_frame, &agenda_list, &gui_app, application, date);
+			}),
+		);
+		calendar.attach(&cell, (index % 7) as i32, (index / 7) as i32 + 1, 1, 1);
+	}
+
+	grid.attach(&navigation, 1, 0, 1, 1);
+	grid.attach(&calendar, 1, 1, 1, 1);
+	grid.attach(&agenda_frame, 1, 2, 1, 1);
+
+	// Edits made anywhere (like in a task's pop-up) change what's on the calendar, so it's built again after them
+	// It can't be built while the application is still in the middle of changing, so that waits until GTK is idle
+	// Once the calendar's gone (another menu was opened, or it was built again), this stops watching
+	let calendar_weak = calendar.downgrade();
+	application.borrow_mut().observe(
+		clone!(@weak grid, @weak gui_app, @weak window, @weak application => @default-return false, move |_, _| {
+			if calendar_weak.upgrade().is_none() {
+				return false;
+			}
+			let calendar_weak = calendar_weak.clone();
+			glib::idle_add_local(
+				clone!(@weak grid, @weak gui_app, @weak window, @weak application => @default-return Continue(false), move || {
+					if calendar_weak.upgrade().is_some() {
+						month(&grid, &gui_app, &window, application, shown);
+					}
+					Continue(false)
+				}),
+			);
+			true
+		}),
+	);
+
+	window.show_all();
+}
+
+/// Fills `list` with everything happening on `date`, for the month view.
+/// Tasks can be clicked on to open them.
+fn show_agenda(
+	frame: &Frame,
+	list: &ListBox,
+	gui_app: &gtk::Application,
+	application: Rc<RefCell<Application>>,
+	date: Date,
+) {
+	for child in list.get_children() {
+		list.remove(&child);
 	}
 
-	for ((i, day), listbox) in (1..6).zip(Day::weekdays().iter()).zip(vec.drain(..)) {
-		let hw_frame = frame_with_text(day.as_str(), &listbox);
-		listbox.set_selection_mode(SelectionMode::None);
-		hw_frame.set_hexpand(true);
-		hw_frame.set_vexpand(true);
-		grid.attach(&hw_frame, i, 0, 1, 1);
+	let agenda = application.borrow().agenda(date);
+	let locale = application.borrow().locale.clone();
+	frame.set_label(Some(&format!(
+		"{} {}",
+		date.weekday(),
+		locale.format_date(date)
+	)));
+
+	if agenda.holiday {
+		list.insert(&Label::new(Some("Holiday - no lessons!")), -1);
+	} else if agenda.is_empty() {
+		list.insert(&Label::new(Some("Nothing on this day.")), -1);
+	}
+	for (course_index, start, end) in &agenda.lessons {
+		let application = application.borrow();
+		let course = &application.courses[*course_index];
+		list.insert(
+			&Label::new(Some(&format!(
+				"{}: {} to {} ({})",
+				course.name,
+				locale.format_time(*start),
+				locale.format_time(*end),
+				course.room
+			))),
+			-1,
+		);
+	}
+	for &(course_index, task_index) in &agenda.tasks {
+		let text = {
+			let application = application.borrow();
+			let course = &application.courses[course_index];
+			let task = &course.tasks[task_index];
+			format!(
+				"Due: [{}] {} ({}, {})",
+				task.priority,
+				task.name,
+				course.name,
+				task.is_complete_str()
+			)
+		};
+		let button = Button::new_with_label(&text);
+		button.connect_clicked(clone!(@weak gui_app, @weak application => move |_| {
+			window::task(course_index, task_index, &gui_app, application);
+		}));
+		list.insert(&button, -1);
 	}
+
+	list.show_all();
 }
 
-/// Shows the list of all courses.
+/// Shows the list of all courses, with how many overdue tasks each has.
+/// The list keeps itself up to date, so adding, removing or renaming a course shows up straight away.
 pub(super) fn courses(
 	grid: &Grid,
 	gui_app: &gtk::Application,
@@ -90,101 +393,386 @@ pub(super) fn courses(
 ) {
 	clear(grid);
 
-	let listbox = ListBox::new();
-	for course in &application.borrow().courses {
-		listbox.insert(&Label::new(Some(&course.name)), -1);
-	}
-	let frame2 = frame_with_text("Courses", &listbox);
+	let list = live_list(
+		&application,
+		|change| change != Change::Holidays,
+		|application| {
+			let overdue = application.overdue_counts(application.today());
+			application
+				.courses
+				.iter()
+				.zip(overdue)
+				.enumerate()
+				.map(|(index, (course, overdue))| {
+					let mut text = course.name.clone();
+					if let Some(grade) = application.grade(index) {
+						text.push_str(&format!(": {}", grade.name));
+					}
+					if overdue > 0 {
+						text.push_str(&format!(" ({} overdue)", overdue));
+					}
+					text
+				})
+				.collect()
+		},
+	);
+
+	let gpa = Label::new(None);
+	show_gpa(&gpa, &application.borrow());
+	let gpa_weak = gpa.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |application, change| match gpa_weak.upgrade() {
+			Some(gpa) => {
+				if change != Change::Holidays {
+					show_gpa(&gpa, application);
+				}
+				true
+			}
+			None => false,
+		});
+	let frame2 = frame_with_text("Courses", &list);
 	frame2.set_hexpand(true);
 	frame2.set_vexpand(true);
 
 	let add_button = Button::new_with_label("Add");
-	add_button.connect_clicked(
-		clone!( @weak gui_app, @weak grid, @weak application, @weak window => move |_| {
-			application.borrow_mut().new_course();
-			// let len = application.borrow().courses.len();
-			// window::course(&gui_app_weak.upgrade().unwrap(), len - 1, application.clone());
-			courses(&grid, &gui_app, &window, application);
-		}),
-	);
+	add_button.connect_clicked(clone!(@weak application => move |_| {
+		application.borrow_mut().new_course();
+	}));
 
 	let remove_button = Button::new_with_label("Remove");
-	remove_button.connect_clicked(
-		clone!( @weak listbox, @weak application, @weak grid, @weak gui_app, @weak window => move |_| {
-			if let Some(row) = listbox.get_selected_row() {
-				let index = row.get_index();
-				application.borrow_mut().rm_course(index as usize);
-				courses(&grid, &gui_app, &window, application);
+	remove_button.connect_clicked(clone!(@weak list, @weak application => move |_| {
+		if let Some(index) = selected_index(&list) {
+			let name = application.borrow().courses[index].name.clone();
+			if !confirm_dialog(&format!("Remove '{}' and all of its tasks? (Ctrl+Z undoes this)", name)) {
+				return;
 			}
-		}),
-	);
-
-	let edit_button = Button::new_with_label("View / Edit");
-	edit_button.connect_clicked(clone!( @weak listbox, @weak gui_app => move |_| {
-		if let Some(row) = listbox.get_selected_row() {
-			let index = row.get_index();
-			window::course(&gui_app, index as usize, application.clone());
+			application.borrow_mut().rm_course(index);
 		}
 	}));
 
+	let edit_button = Button::new_with_label("View / Edit");
+	edit_button.connect_clicked(
+		clone!(@weak list, @weak gui_app, @weak application => move |_| {
+			if let Some(index) = selected_index(&list) {
+				window::course(&gui_app, index, application);
+			}
+		}),
+	);
+
 	grid.attach(&frame2, 1, 0, 2, 1);
 	grid.attach(&add_button, 1, 1, 1, 1);
 	grid.attach(&remove_button, 2, 1, 1, 1);
 	grid.attach(&edit_button, 1, 2, 2, 1);
+	grid.attach(&gpa, 1, 3, 2, 1);
 
 	window.show_all();
 }
 
-/// Shows a list of all holidays.
-pub(super) fn holidays(
-	gui_app: &gtk::Application,
+/// Shows the GPA across every course with a grade so far.
+fn show_gpa(gpa: &Label, application: &Application) {
+	match application.gpa() {
+		Some(points) => gpa.set_text(&format!("GPA: {:.2}", points)),
+		None => gpa.set_text("GPA: nothing graded yet"),
+	}
+}
+
+/// What the tasks menu can show. The names are in the same order as the drop-down list.
+const TASK_FILTERS: [&str; 7] = [
+	"All tasks",
+	"Overdue",
+	"Due this week",
+	"Not done",
+	"Done",
+	"Blocked",
+	"In work order",
+];
+
+// The columns of the tasks menu's store
+// Some columns are only there to sort by, since (for example) dates shown as text don't sort properly
+const TASK_NAME: u32 = 0;
+const TASK_COURSE: u32 = 1;
+const TASK_DUE: u32 = 2;
+const TASK_DUE_SORT: u32 = 3;
+const TASK_PRIORITY: u32 = 4;
+const TASK_PRIORITY_SORT: u32 = 5;
+const TASK_PROGRESS: u32 = 6;
+const TASK_PROGRESS_SORT: u32 = 7;
+const TASK_STATUS: u32 = 8;
+const TASK_COURSE_INDEX: u32 = 9;
+const TASK_INDEX: u32 = 10;
+
+/// Shows every task from every course, with when it's due, what course it's in and how far along it is.
+/// Clicking a column's title sorts by it, and several tasks can be selected to be marked done, given a new due date
+/// or removed at once.
+pub(super) fn tasks(
 	grid: &Grid,
+	gui_app: &gtk::Application,
 	window: &gtk::ApplicationWindow,
 	application: Rc<RefCell<Application>>,
 ) {
 	clear(grid);
 
-	let listbox = ListBox::new();
-	for (start, end) in &application.borrow().holidays {
-		listbox.insert(
-			&Label::new(Some(format!("{} to {}", start, end).as_str())),
-			-1,
-		);
+	let store = ListStore::new(&[
+		glib::Type::String,
+		glib::Type::String,
+		glib::Type::String,
+		glib::Type::String,
+		glib::Type::String,
+		glib::Type::U32,
+		glib::Type::String,
+		glib::Type::U32,
+		glib::Type::String,
+		glib::Type::U32,
+		glib::Type::U32,
+	]);
+	store.set_sort_column_id(SortColumn::Index(TASK_DUE_SORT), SortType::Ascending);
+
+	let filter = Rc::new(Cell::new(0));
+	fill_tasks(&store, &application.borrow(), filter.get());
+
+	// Like the other lists, it's filled again after every change for as long as it's shown
+	let store_weak = store.downgrade();
+	let observed_filter = filter.clone();
+	application
+		.borrow_mut()
+		.observe(move |application, _| match store_weak.upgrade() {
+			Some(store) => {
+				fill_tasks(&store, application, observed_filter.get());
+				true
+			}
+			None => false,
+		});
+
+	let view = TreeView::new_with_model(&store);
+	view.get_selection().set_mode(SelectionMode::Multiple);
+	for (title, column, sort_column) in &[
+		("Task", TASK_NAME, TASK_NAME),
+		("Course", TASK_COURSE, TASK_COURSE),
+		("Due", TASK_DUE, TASK_DUE_SORT),
+		("Priority", TASK_PRIORITY, TASK_PRIORITY_SORT),
+		("Progress", TASK_PROGRESS, TASK_PROGRESS_SORT),
+		("Status", TASK_STATUS, TASK_STATUS),
+	] {
+		let cell = CellRendererText::new();
+		let tree_column = TreeViewColumn::new();
+		tree_column.set_title(title);
+		tree_column.pack_start(&cell, true);
+		tree_column.add_attribute(&cell, "text", *column as i32);
+		tree_column.set_sort_column_id(*sort_column as i32);
+		tree_column.set_resizable(true);
+		view.append_column(&tree_column);
 	}
-	let frame2 = frame_with_text("Holidays", &listbox);
-	frame2.set_hexpand(true);
-	frame2.set_vexpand(true);
+	view.connect_row_activated(
+		clone!(@weak gui_app, @weak application => move |view, path, _| {
+			if let Some(model) = view.get_model() {
+				if let Some((course_index, task_index)) = task_at(&model, path) {
+					window::task(course_index, task_index, &gui_app, application);
+				}
+			}
+		}),
+	);
 
-	let add_button = Button::new_with_label("Add");
-	add_button.connect_clicked(
-		clone!(@weak gui_app, @weak grid, @weak window, @weak application => move |_| {
-			application.borrow_mut().new_holiday();
-			// let len = application.borrow().holidays.len();
-			// window::holiday(&gui_app_weak.upgrade().unwrap(), len - 1, application.clone());
-			holidays(&gui_app, &grid, &window, application);
+	let scrolled = ScrolledWindow::new(None::<&Adjustment>, None::<&Adjustment>);
+	scrolled.add(&view);
+	let frame = frame_with_text("Tasks (double-click one to open it)", &scrolled);
+	frame.set_hexpand(true);
+	frame.set_vexpand(true);
+
+	let filter_box = ComboBoxText::new();
+	for name in TASK_FILTERS.iter() {
+		filter_box.append_text(name);
+	}
+	filter_box.set_active(Some(0));
+	filter_box.connect_changed(
+		clone!(@weak store, @weak application, @strong filter => move |filter_box| {
+			filter.set(filter_box.get_active().unwrap_or(0));
+			fill_tasks(&store, &application.borrow(), filter.get());
 		}),
 	);
 
-	let remove_button = Button::new_with_label("Remove");
-	remove_button.connect_clicked(
-		clone!(@weak gui_app, @weak grid, @weak listbox, @weak window, @weak application => move |_| {
-			if let Some(row) = listbox.get_selected_row() {
-				let index = row.get_index();
-				application.borrow_mut()
-					.rm_holiday(index as usize);
-				holidays(&gui_app, &grid, &window, application);
+	let button_done = Button::new_with_label("Mark Done");
+	button_done.connect_clicked(clone!(@weak view, @weak application => move |_| {
+		let selected = selected_tasks(&view);
+		application.borrow_mut().complete_tasks(&selected, true);
+	}));
+	let button_not_done = Button::new_with_label("Mark Not Done");
+	button_not_done.connect_clicked(clone!(@weak view, @weak application => move |_| {
+		let selected = selected_tasks(&view);
+		application.borrow_mut().complete_tasks(&selected, false);
+	}));
+	let button_snooze = Button::new_with_label("New Due Date");
+	button_snooze.connect_clicked(
+		clone!(@weak view, @weak gui_app, @weak application => move |_| {
+			let selected = selected_tasks(&view);
+			if !selected.is_empty() {
+				window::snooze_dialog(selected, &gui_app, application);
 			}
 		}),
 	);
+	let button_remove = Button::new_with_label("Remove");
+	button_remove.connect_clicked(clone!(@weak view, @weak application => move |_| {
+		let selected = selected_tasks(&view);
+		if selected.is_empty()
+			|| !confirm_dialog(&format!("Remove {} task(s)? (Ctrl+Z undoes this)", selected.len()))
+		{
+			return;
+		}
+		application.borrow_mut().rm_tasks(&selected);
+	}));
 
-	let edit_button = Button::new_with_label("View / Edit");
-	edit_button.connect_clicked(clone!(@weak listbox, @weak gui_app => move |_| {
-		if let Some(row) = listbox.get_selected_row() {
-			let index = row.get_index();
-			window::holiday(&gui_app, index as usize, application.clone());
+	let buttons = Box::new(Orientation::Horizontal, 5);
+	buttons.pack_start(&filter_box, false, false, 0);
+	buttons.pack_end(&button_remove, false, false, 0);
+	buttons.pack_end(&button_snooze, false, false, 0);
+	buttons.pack_end(&button_not_done, false, false, 0);
+	buttons.pack_end(&button_done, false, false, 0);
+
+	grid.attach(&frame, 1, 0, 1, 1);
+	grid.attach(&buttons, 1, 1, 1, 1);
+
+	window.show_all();
+}
+
+/// Fills the tasks menu's `store` with the tasks that pass the `filter`th of `TASK_FILTERS`.
+fn fill_tasks(store: &ListStore, application: &Application, filter: u32) {
+	let today = application.today();
+	let locale = &application.locale;
+	let week_start = locale.start_of_week(today).unwrap_or(Date::FIRST);
+	let week_end = locale.end_of_week(today).unwrap_or(Date::LAST);
+
+	let query = match filter {
+		1 => TaskQuery::new().overdue(today),
+		2 => TaskQuery::new().due_between(week_start, week_end),
+		3 => TaskQuery::new().complete(false),
+		4 => TaskQuery::new().complete(true),
+		5 => TaskQuery::new().blocked(true),
+		_ => TaskQuery::new(),
+	};
+	// The work order isn't a query, since it's about how the tasks depend on each other, not about each one
+	let tasks: Vec<(usize, usize)> = if filter == 6 {
+		application.work_order()
+	} else {
+		application
+			.tasks(&query)
+			.iter()
+			.map(|entry| (entry.course_index, entry.task_index))
+			.collect()
+	};
+
+	store.clear();
+	for (course_index, task_index) in tasks {
+		let course = &application.courses[course_index];
+		let task = &course.tasks[task_index];
+		store.insert_with_values(
+			None,
+			&[
+				TASK_NAME,
+				TASK_COURSE,
+				TASK_DUE,
+				TASK_DUE_SORT,
+				TASK_PRIORITY,
+				TASK_PRIORITY_SORT,
+				TASK_PROGRESS,
+				TASK_PROGRESS_SORT,
+				TASK_STATUS,
+				TASK_COURSE_INDEX,
+				TASK_INDEX,
+			],
+			&[
+				&task.name,
+				&course.name,
+				&locale.format_date(task.due),
+				&task.due.to_string(),
+				&task.priority.to_string(),
+				&(task.priority as u32),
+				&format!("{}%", task.progress()),
+				&task.progress(),
+				&application
+					.task_status(course_index, task_index, today)
+					.to_string(),
+				&(course_index as u32),
+				&(task_index as u32),
+			],
+		);
+	}
+}
+
+/// Returns (course index, task index) of the task in the row at `path` of the tasks menu.
+fn task_at(model: &TreeModel, path: &TreePath) -> Option<(usize, usize)> {
+	let iter = model.get_iter(path)?;
+	let course_index = model
+		.get_value(&iter, TASK_COURSE_INDEX as i32)
+		.get::<u32>()
+		.ok()??;
+	let task_index = model
+		.get_value(&iter, TASK_INDEX as i32)
+		.get::<u32>()
+		.ok()??;
+	Some((course_index as usize, task_index as usize))
+}
+
+/// Returns (course index, task index) of every task selected in the tasks menu.
+fn selected_tasks(view: &TreeView) -> Vec<(usize, usize)> {
+	let (paths, model) = view.get_selection().get_selected_rows();
+	paths
+		.iter()
+		.filter_map(|path| task_at(&model, path))
+		.collect()
+}
+
+/// Shows a list of all holidays, which keeps itself up to date.
+pub(super) fn holidays(
+	gui_app: &gtk::Application,
+	grid: &Grid,
+	window: &gtk::ApplicationWindow,
+	application: Rc<RefCell<Application>>,
+) {
+	clear(grid);
+
+	let list = live_list(
+		&application,
+		|change| change == Change::Holidays || change == Change::Settings,
+		|application| {
+			let locale = &application.locale;
+			application
+				.holidays
+				.iter()
+				.map(|(start, end)| {
+					format!(
+						"{} to {}",
+						locale.format_date(*start),
+						locale.format_date(*end)
+					)
+				})
+				.collect()
+		},
+	);
+	let frame2 = frame_with_text("Holidays", &list);
+	frame2.set_hexpand(true);
+	frame2.set_vexpand(true);
+
+	let add_button = Button::new_with_label("Add");
+	add_button.connect_clicked(clone!(@weak application => move |_| {
+		application.borrow_mut().new_holiday();
+	}));
+
+	let remove_button = Button::new_with_label("Remove");
+	remove_button.connect_clicked(clone!(@weak list, @weak application => move |_| {
+		if let Some(index) = selected_index(&list) {
+			application.borrow_mut().rm_holiday(index);
 		}
 	}));
 
+	let edit_button = Button::new_with_label("View / Edit");
+	edit_button.connect_clicked(
+		clone!(@weak list, @weak gui_app, @weak application => move |_| {
+			if let Some(index) = selected_index(&list) {
+				window::holiday(&gui_app, index, application);
+			}
+		}),
+	);
+
 	grid.attach(&frame2, 1, 0, 2, 1);
 	grid.attach(&add_button, 1, 1, 1, 1);
 	grid.attach(&remove_button, 2, 1, 1, 1);
@@ -192,3 +780,283 @@ pub(super) fn holidays(
 
 	window.show_all();
 }
+
+/// Shows the results of searching for `text`, best first, with the matching words in bold.
+pub(super) fn search(
+	grid: &Grid,
+	gui_app: &gtk::Application,
+	window: &gtk::ApplicationWindow,
+	application: Rc<RefCell<Application>>,
+	text: &str,
+) {
+	clear(grid);
+
+	let results = application.borrow().search(text);
+
+	let listbox = ListBox::new();
+	if results.is_empty() {
+		listbox.insert(&Label::new(Some("Nothing found.")), -1);
+	}
+	for result in &results {
+		let application = application.borrow();
+		let course = &application.courses[result.course_index];
+		let location = match result.task_index {
+			Some(task_index) => format!("{} > {}", course.name, course.tasks[task_index].name),
+			None => course.name.clone(),
+		};
+
+		// Labels understand a small bit of HTML, so everything has to be escaped before we add <b> tags
+		let snippet = result.highlighted(
+			|text| glib::markup_escape_text(text).to_string(),
+			|text| format!("<b>{}</b>", glib::markup_escape_text(text)),
+		);
+
+		let label = Label::new(None);
+		label.set_markup(&format!(
+			"{} ({}): {}",
+			glib::markup_escape_text(&location),
+			result.field.as_str(),
+			snippet
+		));
+		label.set_halign(Align::Start);
+		listbox.insert(&label, -1);
+	}
+	let frame = frame_with_text(&format!("Results for '{}'", text), &listbox);
+	frame.set_hexpand(true);
+	frame.set_vexpand(true);
+
+	// Opens whatever the selected result is in, the task if there is one, otherwise the course
+	let places: Vec<(usize, Option<usize>)> = results
+		.iter()
+		.map(|result| (result.course_index, result.task_index))
+		.collect();
+	let open_button = Button::new_with_label("View / Edit");
+	open_button.connect_clicked(
+		clone!(@weak listbox, @weak gui_app, @weak application => move |_| {
+			if let Some(row) = listbox.get_selected_row() {
+				if let Some(&(course_index, task_index)) = places.get(row.get_index() as usize) {
+					match task_index {
+						Some(task_index) => window::task(course_index, task_index, &gui_app, application),
+						None => window::course(&gui_app, course_index, application),
+					}
+				}
+			}
+		}),
+	);
+
+	grid.attach(&frame, 1, 0, 1, 1);
+	grid.attach(&open_button, 1, 1, 1, 1);
+
+	window.show_all();
+}
+
+/// Shows the application-wide settings.
+pub(super) fn settings(
+	grid: &Grid,
+	window: &gtk::ApplicationWindow,
+	application: Rc<RefCell<Application>>,
+) {
+	clear(grid);
+
+	let timezone = match application.borrow().timezone {
+		Some(zone) => zone.to_string(),
+		None => String::new(),
+	};
+	let t1 = text_with_default(&timezone, None);
+	let f1 = frame_with_text(
+		"Time Zone (e.g. 'Europe/London', blank for this computer's)",
+		&t1,
+	);
+	f1.set_hexpand(true);
+
+	let locale = application.borrow().locale.clone();
+
+	let school_days = ListBox::new();
+	school_days.set_selection_mode(SelectionMode::None);
+	let mut day_buttons = Vec::new();
+	for day in Day::all().iter() {
+		let check = CheckButton::new_with_label(day.as_str());
+		check.set_active(locale.school_days.contains(day));
+		school_days.insert(&check, -1);
+		day_buttons.push((*day, check));
+	}
+	let f2 = frame_with_text("School Days", &school_days);
+
+	let week_start = ComboBoxText::new();
+	for day in Day::all().iter() {
+		week_start.append_text(day.as_str());
+	}
+	week_start.set_active(Some(locale.week_start as u32));
+	let f3 = frame_with_text("First Day of the Week", &week_start);
+
+	let date_order = ComboBoxText::new();
+	date_order.append_text("2020-03-11 (Year-Month-Day)");
+	date_order.append_text("11/03/2020 (Day/Month/Year)");
+	date_order.append_text("03/11/2020 (Month/Day/Year)");
+	date_order.set_active(Some(match locale.date_order {
+		DateOrder::YearMonthDay => 0,
+		DateOrder::DayMonthYear => 1,
+		DateOrder::MonthDayYear => 2,
+	}));
+	let f4 = frame_with_text("Date Format", &date_order);
+
+	let twelve_hour = CheckButton::new_with_label("12-hour clock (3:30 PM)");
+	twelve_hour.set_active(locale.clock == ClockStyle::TwelveHour);
+
+	let keep_history = CheckButton::new_with_label("Remember undo history after closing");
+	keep_history.set_active(application.borrow().keep_history);
+
+	let reminders = application.borrow().reminders;
+	let reminders_enabled = CheckButton::new_with_label("Remind me about lessons and deadlines");
+	reminders_enabled.set_active(reminders.enabled);
+	let t2 = text_with_default(&reminders.lesson_minutes.to_string(), None);
+	let t3 = text_with_default(&reminders.task_days.to_string(), None);
+	let t4 = text_with_default(&locale.format_time(reminders.task_time), None);
+	let f5 = frame_with_text("Minutes Before Lessons", &t2);
+	let f6 = frame_with_text("Days Before Tasks Are Due", &t3);
+	let f7 = frame_with_text("Time of Day for Task Reminders", &t4);
+
+	// A focus session that's already going keeps the settings it started with
+	let focus = application.borrow().focus;
+	let t5 = text_with_default(&focus.work_minutes.to_string(), None);
+	let t6 = text_with_default(&focus.short_break_minutes.to_string(), None);
+	let t7 = text_with_default(&focus.long_break_minutes.to_string(), None);
+	let t8 = text_with_default(&focus.rounds.to_string(), None);
+	let focus_box = Box::new(Orientation::Horizontal, 5);
+	focus_box.pack_start(&frame_with_text("Focus Minutes", &t5), true, true, 0);
+	focus_box.pack_start(&frame_with_text("Break Minutes", &t6), true, true, 0);
+	focus_box.pack_start(&frame_with_text("Long Break Minutes", &t7), true, true, 0);
+	focus_box.pack_start(&frame_with_text("Rounds", &t8), true, true, 0);
+	let f8 = frame_with_text("Focus Sessions", &focus_box);
+
+	let t9 = text_with_default(&application.borrow().grading_scale.as_string(), None);
+	let f9 = frame_with_text(
+		"Grading Scale (each grade's name, lowest percentage and points, like 'A 90 4, B 80 3, F 0 0')",
+		&t9,
+	);
+
+	let button_save = Button::new_with_label("Save");
+	button_save.connect_clicked(
+		clone!(@weak t1, @weak week_start, @weak date_order, @weak twelve_hour, @weak keep_history,
+		@weak reminders_enabled, @weak t2, @weak t3, @weak t4, @weak t5, @weak t6, @weak t7, @weak t8,
+		@weak t9, @weak application => move |_| {
+			let text = get_string_from_text!(t1);
+			let timezone = if text.trim().is_empty() {
+				Ok(None)
+			} else {
+				Zone::try_from(text).map(Some)
+			};
+			match timezone {
+				Ok(timezone) if timezone != application.borrow().timezone => {
+					application
+						.borrow_mut()
+						.apply("Change time zone", Command::SetTimezone(timezone));
+				}
+				Ok(_) => (),
+				Err(_) => message_dialog("Time zone invalid. Use a name like 'Europe/London' or 'UTC'."),
+			}
+
+			let mut locale = application.borrow().locale.clone();
+			locale.school_days = day_buttons
+				.iter()
+				.filter(|(_, check)| check.get_active())
+				.map(|(day, _)| *day)
+				.collect();
+			if let Some(index) = week_start.get_active() {
+				locale.week_start = Day::all()[index as usize];
+			}
+			locale.date_order = match date_order.get_active() {
+				Some(1) => DateOrder::DayMonthYear,
+				Some(2) => DateOrder::MonthDayYear,
+				_ => DateOrder::YearMonthDay,
+			};
+			locale.clock = if twelve_hour.get_active() {
+				ClockStyle::TwelveHour
+			} else {
+				ClockStyle::TwentyFourHour
+			};
+			if locale != application.borrow().locale {
+				application
+					.borrow_mut()
+					.apply("Change calendar settings", Command::SetLocale(locale));
+			}
+
+			application.borrow_mut().set_keep_history(keep_history.get_active());
+
+			let mut reminders = application.borrow().reminders;
+			reminders.enabled = reminders_enabled.get_active();
+			match (
+				get_string_from_text!(t2).trim().parse(),
+				get_string_from_text!(t3).trim().parse(),
+				Time::parse(&get_string_from_text!(t4)),
+			) {
+				(Ok(lesson_minutes), Ok(task_days), Ok(task_time))
+					if lesson_minutes <= MAX_REMINDER_MINUTES && task_days <= MAX_REMINDER_DAYS =>
+				{
+					reminders.lesson_minutes = lesson_minutes;
+					reminders.task_days = task_days;
+					reminders.task_time = task_time;
+				}
+				_ => message_dialog("Reminder settings invalid. Use whole numbers (up to a week of minutes, and 365 days), and a time like '08:00' or '8am'."),
+			}
+			if reminders != application.borrow().reminders {
+				application
+					.borrow_mut()
+					.apply("Change reminders", Command::SetReminders(reminders));
+			}
+
+			let mut focus = application.borrow().focus;
+			let number = |text: &TextView, max: u32| -> Result<u32, ()> {
+				match get_string_from_text!(text).trim().parse() {
+					Ok(number) if number > 0 && number <= max => Ok(number),
+					_ => Err(()),
+				}
+			};
+			match (
+				number(&t5, MAX_FOCUS_MINUTES),
+				number(&t6, MAX_FOCUS_MINUTES),
+				number(&t7, MAX_FOCUS_MINUTES),
+				number(&t8, MAX_FOCUS_ROUNDS),
+			) {
+				(Ok(work_minutes), Ok(short_break_minutes), Ok(long_break_minutes), Ok(rounds)) => {
+					focus.work_minutes = work_minutes;
+					focus.short_break_minutes = short_break_minutes;
+					focus.long_break_minutes = long_break_minutes;
+					focus.rounds = rounds;
+				}
+				_ => message_dialog("Focus settings invalid. Use whole numbers above 0, with minutes up to a day (1440) and up to 100 rounds."),
+			}
+			if focus != application.borrow().focus {
+				application
+					.borrow_mut()
+					.apply("Change focus settings", Command::SetFocusSettings(focus));
+			}
+
+			match GradingScale::parse(&get_string_from_text!(t9)) {
+				Ok(scale) if scale != application.borrow().grading_scale => {
+					application
+						.borrow_mut()
+						.apply("Change grading scale", Command::SetGradingScale(scale));
+				}
+				Ok(_) => (),
+				Err(_) => message_dialog("Grading scale invalid. Write each grade's name, lowest percentage and points, like 'A 90 4, B 80 3, F 0 0'."),
+			}
+		}),
+	);
+
+	grid.attach(&f1, 1, 0, 1, 1);
+	grid.attach(&f2, 1, 1, 1, 1);
+	grid.attach(&f3, 1, 2, 1, 1);
+	grid.attach(&f4, 1, 3, 1, 1);
+	grid.attach(&twelve_hour, 1, 4, 1, 1);
+	grid.attach(&keep_history, 1, 5, 1, 1);
+	grid.attach(&reminders_enabled, 1, 6, 1, 1);
+	grid.attach(&f5, 1, 7, 1, 1);
+	grid.attach(&f6, 1, 8, 1, 1);
+	grid.attach(&f7, 1, 9, 1, 1);
+	grid.attach(&f8, 1, 10, 1, 1);
+	grid.attach(&f9, 1, 11, 1, 1);
+	grid.attach(&button_save, 1, 12, 1, 1);
+
+	window.show_all();
+}
@@ -1,14 +1,22 @@
 //! This module contains all the GUI-related code.
 
+use gio::prelude::*;
 use glib::clone;
 use gtk::prelude::*;
 use gtk::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::convert::TryFrom;
 use std::rc::Rc;
+use std::time::Duration;
 
-use crate::application::Application;
+use crate::application::{
+	format_category_weights, format_minutes, parse_category_weights, parse_minutes,
+	parse_optional_number, parse_tags, Application, Assessment, Change, Command, Course,
+	GradingScale, Notifier, Priority, Reminder, StepPlace, Task, TaskQuery, TaskSort,
+	MAX_FOCUS_MINUTES, MAX_FOCUS_ROUNDS, MAX_REMINDER_DAYS, MAX_REMINDER_MINUTES,
+};
 use crate::clock::*;
+use picker::*;
 use util::*;
 
 #[macro_use]
@@ -18,6 +26,10 @@ mod util;
 mod menu;
 /// For functions that open new windows.
 mod window;
+/// For drawing the weekly timeline.
+mod timeline;
+/// For the date, time and day pickers in pop-ups.
+mod picker;
 
 // Welcome brave soul to the `gui` module.
 // This module is split into 3 different components
@@ -35,8 +47,25 @@ mod window;
 // I sincerely hope I will never have to see this module ever again in my life
 // That is all.
 
+/// How long to wait after the last change before autosaving.
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(3);
+
+/// Shows reminders as desktop notifications, which GTK calls GNotifications.
+/// They only show up while the app is open.
+struct DesktopNotifier(gtk::Application);
+
+impl Notifier for DesktopNotifier {
+	fn notify(&self, reminder: &Reminder) {
+		let notification = gio::Notification::new(&reminder.title);
+		notification.set_body(Some(&reminder.body));
+		// Sending one with the same ID replaces the old one, so the same reminder never shows up twice
+		self.0.send_notification(Some(&reminder.id()), &notification);
+	}
+}
+
 /// The function used to create the UI. Calls a whole lot of other functions in the GUI module.
-pub fn build_ui(app: &gtk::Application, application: Rc<RefCell<Application>>) {
+/// `location` is where the application is saved to.
+pub fn build_ui(app: &gtk::Application, application: Rc<RefCell<Application>>, location: &'static str) {
 	let window = ApplicationWindow::new(app);
 	let grid = Grid::new();
 
@@ -91,12 +120,20 @@ pub fn build_ui(app: &gtk::Application, application: Rc<RefCell<Application>>) {
 	);
 
 	let button_weekly = Button::new_with_label("Weekly");
-	button_weekly.connect_clicked(clone!(@weak grid, @weak window, @weak application
+	button_weekly.connect_clicked(clone!(@weak grid, @weak window, @weak application, @weak app
 	=> move |_| {
-		menu::weekly(&grid, application);
-		window.show_all();
+		menu::weekly(&grid, &app, &window, application);
 	}));
 
+	let button_month = Button::new_with_label("Month");
+	button_month.connect_clicked(
+		clone!(@weak grid, @weak window, @weak application, @weak app
+		=> move |_| {
+			let today = application.borrow().today();
+			menu::month(&grid, &app, &window, application, today);
+		}),
+	);
+
 	let button_courses = Button::new_with_label("Courses");
 	button_courses.connect_clicked(
 		clone!(@weak grid, @weak window, @weak application, @weak app
@@ -105,6 +142,14 @@ pub fn build_ui(app: &gtk::Application, application: Rc<RefCell<Application>>) {
 		}),
 	);
 
+	let button_tasks = Button::new_with_label("Tasks");
+	button_tasks.connect_clicked(
+		clone!(@weak grid, @weak window, @weak application, @weak app
+		=> move |_| {
+			menu::tasks(&grid, &app, &window, application);
+		}),
+	);
+
 	let button_holidays = Button::new_with_label("Holidays");
 	button_holidays.connect_clicked(
 		clone!(@weak grid, @weak window, @weak application, @weak app
@@ -113,18 +158,121 @@ pub fn build_ui(app: &gtk::Application, application: Rc<RefCell<Application>>) {
 		}),
 	);
 
+	let button_settings = Button::new_with_label("Settings");
+	button_settings.connect_clicked(clone!(@weak grid, @weak window, @weak application
+	=> move |_| {
+		menu::settings(&grid, &window, application);
+	}));
+
 	let button_save = Button::new_with_label("Save");
 	button_save.connect_clicked(clone!(@weak application => move |_| {
-		application.borrow().save("scheduler.bin");
+		application.borrow_mut().save(location);
 	}));
 
+	// Every second, we check whether it's time to autosave, and show whether everything's saved
+	// try_borrow_mut is used because this can run while a pop-up is open and has the application borrowed
+	// In that case, we just try again next second
+	let save_status = Label::new(Some("All changes saved"));
+	glib::timeout_add_seconds_local(
+		1,
+		clone!(@weak application, @weak save_status => @default-return Continue(false), move || {
+			if let Ok(mut application) = application.try_borrow_mut() {
+				if application.should_autosave(AUTOSAVE_DELAY) {
+					application.save(location);
+				}
+				save_status.set_text(if application.is_dirty() {
+					"Unsaved changes"
+				} else {
+					"All changes saved"
+				});
+			}
+			Continue(true)
+		}),
+	);
+
+	// Every 30 seconds, we send any reminders that went off since the last time we checked
+	// That includes the focus session moving on, so that's when the rounds it finished are logged too
+	let notifier = DesktopNotifier(app.clone());
+	let last_checked = Cell::new(application.borrow().now());
+	glib::timeout_add_seconds_local(
+		30,
+		clone!(@weak application => @default-return Continue(false), move || {
+			if let Ok(mut application) = application.try_borrow_mut() {
+				let now = application.now();
+				application.send_reminders(last_checked.get(), now, &notifier);
+				application.log_focus(now);
+				last_checked.set(now);
+			}
+			Continue(true)
+		}),
+	);
+
+	// Closing the window with unsaved changes asks what to do with them first
+	window.connect_delete_event(clone!(@weak application => @default-return Inhibit(false), move |_, _| {
+		if !application.borrow().is_dirty() {
+			return Inhibit(false);
+		}
+		match save_changes_dialog() {
+			ResponseType::Yes => {
+				application.borrow_mut().save(location);
+				Inhibit(false)
+			}
+			ResponseType::No => Inhibit(false),
+			_ => Inhibit(true),
+		}
+	}));
+
+	// Searching happens as you type, and clearing the search goes back to the main menu
+	let search_entry = SearchEntry::new();
+	search_entry.connect_search_changed(
+		clone!(@weak grid, @weak window, @weak application, @weak app
+		=> move |entry| {
+			let text = entry.get_text().map(|text| text.to_string()).unwrap_or_default();
+			if text.trim().is_empty() {
+				menu::main(&grid, application);
+				window.show_all();
+			} else {
+				menu::search(&grid, &app, &window, application, &text);
+			}
+		}),
+	);
+
+	// Undo and redo are actions, so they can have keyboard shortcuts
+	// Every list watches the application for changes, so whatever's open shows the change by itself
+	// Pop-ups watch it too, and close themselves if what they were showing is gone (see `close_when_gone` in window.rs)
+	let undo = gio::SimpleAction::new("undo", None);
+	undo.connect_activate(clone!(@weak application => move |_, _| {
+		application.borrow_mut().undo();
+	}));
+	app.add_action(&undo);
+	app.set_accels_for_action("app.undo", &["<Primary>z"]);
+
+	let redo = gio::SimpleAction::new("redo", None);
+	redo.connect_activate(clone!(@weak application => move |_, _| {
+		application.borrow_mut().redo();
+	}));
+	app.add_action(&redo);
+	app.set_accels_for_action("app.redo", &["<Primary><Shift>z"]);
+
+	let button_undo = Button::new_with_label("Undo");
+	button_undo.set_action_name(Some("app.undo"));
+	let button_redo = Button::new_with_label("Redo");
+	button_redo.set_action_name(Some("app.redo"));
+
 	let left_menu = ListBox::new();
 	left_menu.set_selection_mode(SelectionMode::None);
+	left_menu.insert(&search_entry, -1);
 	left_menu.insert(&button_main, -1);
 	left_menu.insert(&button_weekly, -1);
+	left_menu.insert(&button_month, -1);
 	left_menu.insert(&button_courses, -1);
+	left_menu.insert(&button_tasks, -1);
 	left_menu.insert(&button_holidays, -1);
+	left_menu.insert(&button_settings, -1);
+	left_menu.insert(&button_undo, -1);
+	left_menu.insert(&button_redo, -1);
 	left_menu.insert(&button_save, -1);
+	left_menu.insert(&save_status, -1);
 
 	let left_menu_frame = frame_with_text("Menu", &left_menu);
 	left_menu_frame.set_vexpand(true);
@@ -0,0 +1,156 @@
+use super::*;
+
+// Pickers are for choosing dates, times and days, instead of typing them into a text box and hoping
+// Each one keeps what's picked in a shared cell, which the pop-up reads when Save is pressed
+// The cell is `None` while what's typed can't be understood, and `on_change` is called every time it changes,
+// so the pop-up can say what's wrong (and stop it from being saved) straight away
+
+/// What's been picked so far, or `None` if what's typed in isn't valid.
+pub(super) type Picked<T> = Rc<Cell<Option<T>>>;
+
+const DATE_HELP: &str = "Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'.";
+
+/// Creates a date picker: a box to type the date into, with a calendar under it to click on instead.
+/// Anything `Locale::parse_date` understands can be typed, like "next friday", and the calendar follows along.
+/// It starts at whatever's in `picked`, or `today` if there's nothing.
+pub(super) fn date_picker<F: Fn() + 'static>(
+	locale: &Locale,
+	today: Date,
+	picked: &Picked<Date>,
+	on_change: F,
+) -> Box {
+	let date = picked.get().unwrap_or(today);
+
+	let entry = Entry::new();
+	entry.set_text(&locale.format_date(date));
+
+	let calendar = Calendar::new();
+	select_date(&calendar, date);
+
+	// Typing moves the calendar, and clicking the calendar changes the text
+	// While the text is moving the calendar, the calendar mustn't change the text, or it'd be replaced mid-word
+	let typing = Rc::new(Cell::new(false));
+
+	let entry_locale = locale.clone();
+	entry.connect_changed(
+		clone!(@weak calendar, @strong picked, @strong typing => move |entry| {
+			let text = entry.get_text().map(|text| text.to_string()).unwrap_or_default();
+			match entry_locale.parse_date(&text, today) {
+				Ok(date) => {
+					entry.set_icon_from_icon_name(EntryIconPosition::Secondary, None);
+					typing.set(true);
+					select_date(&calendar, date);
+					typing.set(false);
+					picked.set(Some(date));
+				}
+				Err(_) => {
+					entry.set_icon_from_icon_name(EntryIconPosition::Secondary, Some("dialog-warning"));
+					entry.set_icon_tooltip_text(
+						EntryIconPosition::Secondary,
+						Some(&format!("Date entry invalid. {}", DATE_HELP)),
+					);
+					picked.set(None);
+				}
+			}
+			on_change();
+		}),
+	);
+
+	let calendar_locale = locale.clone();
+	calendar.connect_day_selected(clone!(@weak entry, @strong typing => move |calendar| {
+		if typing.get() {
+			return;
+		}
+		if let Some(date) = calendar_date(calendar) {
+			// Changing the text picks the date, through the handler above
+			entry.set_text(&calendar_locale.format_date(date));
+		}
+	}));
+
+	let picker = Box::new(Orientation::Vertical, 3);
+	picker.pack_start(&entry, false, false, 0);
+	picker.pack_start(&calendar, false, false, 0);
+	picker
+}
+
+/// Shows `date` on `calendar`.
+fn select_date(calendar: &Calendar, date: Date) {
+	// GTK counts months from 0, but days from 1
+	calendar.select_month(date.month() as u32 - 1, date.year() as u32);
+	calendar.select_day(date.day() as u32);
+}
+
+/// Returns the date picked on `calendar`.
+fn calendar_date(calendar: &Calendar) -> Option<Date> {
+	let (year, month, day) = calendar.get_date();
+	Date::new(year as u16, month as u8 + 1, day as u8).ok()
+}
+
+/// Creates a time picker, with one spin button for the hour and one for the minute.
+/// Spin buttons only take numbers in range, so the time is always valid.
+pub(super) fn time_picker<F: Fn() + 'static>(picked: &Picked<Time>, on_change: F) -> Box {
+	let time = picked.get().unwrap_or_else(|| Time::new(0, 0).unwrap());
+	let on_change = Rc::new(on_change);
+
+	let hour = SpinButton::new_with_range(0.0, 23.0, 1.0);
+	hour.set_value(time.hour() as f64);
+	let minute = SpinButton::new_with_range(0.0, 59.0, 5.0);
+	minute.set_value(time.minute() as f64);
+
+	for spin in &[&hour, &minute] {
+		spin.set_numeric(true);
+		spin.set_wrap(true);
+		// Shows "09" instead of "9", like a clock
+		spin.connect_output(|spin| {
+			spin.set_text(&format!("{:02}", spin.get_value_as_int()));
+			Inhibit(true)
+		});
+	}
+
+	// Both spin buttons need to see each other, so they only have weak references, or they'd never be freed
+	let hour_weak = hour.downgrade();
+	let minute_weak = minute.downgrade();
+	let picked = picked.clone();
+	let update = move |_: &SpinButton| {
+		if let (Some(hour), Some(minute)) = (hour_weak.upgrade(), minute_weak.upgrade()) {
+			picked.set(
+				Time::new(
+					hour.get_value_as_int() as u8,
+					minute.get_value_as_int() as u8,
+				)
+				.ok(),
+			);
+			on_change();
+		}
+	};
+	hour.connect_value_changed(update.clone());
+	minute.connect_value_changed(update);
+
+	let picker = Box::new(Orientation::Horizontal, 3);
+	picker.pack_start(&hour, false, false, 0);
+	picker.pack_start(&Label::new(Some(":")), false, false, 0);
+	picker.pack_start(&minute, false, false, 0);
+	picker
+}
+
+/// Creates a drop-down list of the days of the week.
+pub(super) fn day_picker<F: Fn() + 'static>(picked: &Picked<Day>, on_change: F) -> ComboBoxText {
+	let picker = ComboBoxText::new();
+	for day in Day::all().iter() {
+		picker.append_text(day.as_str());
+	}
+	if let Some(day) = picked.get() {
+		picker.set_active(Some(day as u32));
+	}
+	picker.connect_changed(clone!(@strong picked => move |picker| {
+		picked.set(picker.get_active().map(|index| Day::all()[index as usize]));
+		on_change();
+	}));
+	picker
+}
+
+/// Shows what's wrong with what's been picked under the pickers, and only lets it be saved if nothing is.
+pub(super) fn show_problem(label: &Label, button_save: &Button, problem: Option<&str>) {
+	label.set_text(problem.unwrap_or(""));
+	button_save.set_sensitive(problem.is_none());
+}
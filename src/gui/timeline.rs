@@ -0,0 +1,567 @@
+use super::*;
+
+// The weekly timeline is drawn by hand with Cairo, which is GTK's drawing library
+// Days go across, hours go down, and each lesson is a block as tall as it is long
+// Lessons that overlap are put side by side in the same day, each getting a share of its width
+// The same layout is worked out again for clicks, so a click can find the block it landed on
+// Blocks can be dragged to another day or time, or stretched from their bottom edge to change when they end
+// While dragging, a see-through copy shows where it'll go, and the lesson only really moves when it's let go
+
+/// The space on the left for the hours, and at the top for the days.
+const LEFT: f64 = 50.0;
+const TOP: f64 = 30.0;
+
+/// How close to the bottom of a block (in pixels) a press has to be to stretch it instead of moving it.
+const RESIZE_EDGE: f64 = 6.0;
+/// How far (in pixels) the mouse has to move before a press counts as a drag rather than a click.
+const DRAG_THRESHOLD: f64 = 3.0;
+/// Dragged lessons snap to this many minutes.
+const SNAP_MINUTES: i64 = 15;
+
+/// Every course gets one of these colors, picked by its index, so it's the same color everywhere.
+const COLORS: [(f64, f64, f64); 8] = [
+	(0.36, 0.61, 0.84),
+	(0.93, 0.49, 0.19),
+	(0.44, 0.68, 0.28),
+	(0.80, 0.33, 0.40),
+	(0.55, 0.42, 0.75),
+	(0.25, 0.70, 0.68),
+	(0.85, 0.65, 0.13),
+	(0.55, 0.55, 0.55),
+];
+
+/// One lesson on the timeline.
+struct Block {
+	course_index: usize,
+	/// Which of the course's `times` it comes from, so it can be moved.
+	time_index: Option<usize>,
+	/// Which day (column) it's on.
+	day: usize,
+	start: Time,
+	end: Time,
+	/// Which of the side-by-side lessons it is, out of `lanes`.
+	lane: usize,
+	lanes: usize,
+}
+
+/// Where everything goes, for a drawing area of a certain size.
+struct Layout {
+	/// (day, date, whether it's a holiday) for each column.
+	days: Vec<(Day, Date, bool)>,
+	blocks: Vec<Block>,
+	/// The first and last hours shown.
+	first_hour: u8,
+	last_hour: u8,
+	width: f64,
+	height: f64,
+}
+
+impl Layout {
+	/// Works out the layout of `application`'s lessons this week, to fit in `width` by `height`.
+	fn new(application: &Application, width: f64, height: f64) -> Self {
+		let today = application.today();
+		let locale = &application.locale;
+
+		let mut days = Vec::new();
+		let mut blocks = Vec::new();
+		// Only the very first week there is has days that can't be dates (before 1970), and those are left out
+		let week = locale
+			.school_week()
+			.into_iter()
+			.filter_map(|day| Some((day, locale.date_in_week(today, day).ok()?)));
+		for (index, (day, date)) in week.enumerate() {
+			let agenda = application.agenda(date);
+			days.push((day, date, agenda.holiday));
+
+			let mut lessons: Vec<Block> = agenda
+				.lessons
+				.iter()
+				.map(|&(course_index, start, end)| Block {
+					course_index,
+					time_index: application.courses[course_index].time_index_on(
+						date,
+						start,
+						application.timezone,
+					),
+					day: index,
+					start,
+					end,
+					lane: 0,
+					lanes: 1,
+				})
+				.collect();
+			place_side_by_side(&mut lessons);
+			blocks.extend(lessons);
+		}
+
+		// The usual school day is always shown, and stretched if there's anything earlier or later
+		let first_hour = blocks
+			.iter()
+			.map(|block| block.start.hour())
+			.min()
+			.unwrap_or(8)
+			.min(8);
+		let last_hour = blocks
+			.iter()
+			.map(|block| block.end.hour() + if block.end.minute() > 0 { 1 } else { 0 })
+			.max()
+			.unwrap_or(17)
+			.clamp(17, 24);
+
+		Self {
+			days,
+			blocks,
+			first_hour,
+			last_hour,
+			width,
+			height,
+		}
+	}
+
+	fn column_width(&self) -> f64 {
+		(self.width - LEFT) / self.days.len().max(1) as f64
+	}
+
+	/// How many minutes each pixel down is.
+	fn minutes_per_pixel(&self) -> f64 {
+		(self.last_hour - self.first_hour) as f64 * 60.0 / (self.height - TOP)
+	}
+
+	/// How far down `time` is.
+	fn y(&self, time: Time) -> f64 {
+		TOP + (minutes(time) - self.first_hour as i64 * 60) as f64 / self.minutes_per_pixel()
+	}
+
+	/// Returns which day (column) `x` is in, or `None` if it's in the hours on the left.
+	/// Anything past the last column counts as the last day.
+	fn day_at(&self, x: f64) -> Option<usize> {
+		if x < LEFT || self.days.is_empty() {
+			return None;
+		}
+		Some((((x - LEFT) / self.column_width()) as usize).min(self.days.len() - 1))
+	}
+
+	/// The rectangle of `block`, as (x, y, width, height).
+	fn rectangle(&self, block: &Block) -> (f64, f64, f64, f64) {
+		let lane_width = self.column_width() / block.lanes as f64;
+		let x = LEFT + block.day as f64 * self.column_width() + block.lane as f64 * lane_width;
+		let y = self.y(block.start);
+		// A little gap all around, so blocks next to each other don't look like one
+		(
+			x + 1.0,
+			y + 1.0,
+			lane_width - 2.0,
+			self.y(block.end) - y - 2.0,
+		)
+	}
+
+	/// Returns the block at (`x`, `y`), if there is one.
+	fn block_at(&self, x: f64, y: f64) -> Option<&Block> {
+		self.blocks.iter().find(|block| {
+			let (left, top, width, height) = self.rectangle(block);
+			x >= left && x <= left + width && y >= top && y <= top + height
+		})
+	}
+}
+
+/// A lesson being dragged (or stretched) with the mouse.
+struct Drag {
+	course_index: usize,
+	time_index: usize,
+	/// Where the lesson was before the drag, as (day, start, end).
+	day: usize,
+	start: Time,
+	end: Time,
+	/// Whether the bottom edge was grabbed, so only the end changes.
+	resize: bool,
+	/// Where the mouse was pressed, and where it is now.
+	from: (f64, f64),
+	to: (f64, f64),
+}
+
+impl Drag {
+	/// Returns whether the mouse has moved far enough for this to be a drag, not a click.
+	fn moved(&self) -> bool {
+		(self.to.0 - self.from.0).abs() > DRAG_THRESHOLD
+			|| (self.to.1 - self.from.1).abs() > DRAG_THRESHOLD
+	}
+
+	/// Returns where the lesson would go if it was let go now, as (day, start, end).
+	/// Returns `None` if that's off the top or bottom of the timeline.
+	fn target(&self, layout: &Layout) -> Option<(usize, Time, Time)> {
+		let moved = (self.to.1 - self.from.1) * layout.minutes_per_pixel();
+		let snap = |minutes: f64| (minutes / SNAP_MINUTES as f64).round() as i64 * SNAP_MINUTES;
+		let shown = |minutes: i64| {
+			if (layout.first_hour as i64 * 60..=layout.last_hour as i64 * 60).contains(&minutes) {
+				from_minutes(minutes)
+			} else {
+				None
+			}
+		};
+
+		if self.resize {
+			// The start stays where it is, and the lesson can't get shorter than one snap
+			let end =
+				snap(minutes(self.end) as f64 + moved).max(minutes(self.start) + SNAP_MINUTES);
+			return Some((self.day, self.start, shown(end)?));
+		}
+
+		let day = layout.day_at(self.to.0).unwrap_or(self.day);
+		let start = snap(minutes(self.start) as f64 + moved);
+		let end = start + minutes(self.end) - minutes(self.start);
+		Some((day, shown(start)?, shown(end)?))
+	}
+}
+
+/// Returns how many minutes past midnight `time` is.
+fn minutes(time: Time) -> i64 {
+	time.hour() as i64 * 60 + time.minute() as i64
+}
+
+/// Returns the time `minutes` past midnight, or `None` if that's not in the same day.
+fn from_minutes(minutes: i64) -> Option<Time> {
+	if !(0..24 * 60).contains(&minutes) {
+		return None;
+	}
+	Time::new((minutes / 60) as u8, (minutes % 60) as u8).ok()
+}
+
+/// Gives every lesson in one day a lane, so lessons that overlap are put next to each other.
+/// `lessons` must be sorted by when they start.
+fn place_side_by_side(lessons: &mut [Block]) {
+	// Lessons are put into groups that overlap each other (even if only through another lesson)
+	// Within a group, each lesson takes the first lane that's free by the time it starts
+	// Every lesson in a group then shares the width between as many lanes as the group needed
+	let mut group_start = 0;
+	let mut group_end: Option<Time> = None;
+	let mut lane_ends: Vec<Time> = Vec::new();
+
+	for index in 0..=lessons.len() {
+		let starts_new_group = match (lessons.get(index), group_end) {
+			(Some(lesson), Some(end)) => lesson.start >= end,
+			_ => true,
+		};
+		if starts_new_group {
+			for lesson in &mut lessons[group_start..index] {
+				lesson.lanes = lane_ends.len().max(1);
+			}
+			group_start = index;
+			group_end = None;
+			lane_ends.clear();
+		}
+
+		if let Some(lesson) = lessons.get_mut(index) {
+			lesson.lane = match lane_ends.iter().position(|end| *end <= lesson.start) {
+				Some(lane) => lane,
+				None => {
+					lane_ends.push(lesson.end);
+					lane_ends.len() - 1
+				}
+			};
+			lane_ends[lesson.lane] = lesson.end;
+			group_end = Some(match group_end {
+				Some(end) if end > lesson.end => end,
+				_ => lesson.end,
+			});
+		}
+	}
+}
+
+/// Creates the drawing area for this week's timeline.
+/// Clicking on a lesson opens its course, and dragging it moves it to another day or time.
+pub(super) fn timeline(
+	gui_app: &gtk::Application,
+	application: Rc<RefCell<Application>>,
+) -> DrawingArea {
+	let area = DrawingArea::new();
+	area.set_hexpand(true);
+	area.set_vexpand(true);
+	area.set_size_request(500, 400);
+
+	let drag: Rc<RefCell<Option<Drag>>> = Rc::new(RefCell::new(None));
+
+	area.connect_draw(
+		clone!(@weak application, @weak drag => @default-return Inhibit(false), move |area, cr| {
+			// If a pop-up is busy changing the application, it'll be drawn again once it's done
+			if let Ok(application) = application.try_borrow() {
+				let layout = Layout::new(
+					&application,
+					area.get_allocated_width() as f64,
+					area.get_allocated_height() as f64,
+				);
+				draw(cr, &layout, &application, drag.borrow().as_ref());
+			}
+			Inhibit(false)
+		}),
+	);
+
+	area.add_events(
+		gdk::EventMask::BUTTON_PRESS_MASK
+			| gdk::EventMask::BUTTON_RELEASE_MASK
+			| gdk::EventMask::BUTTON1_MOTION_MASK,
+	);
+
+	area.connect_button_press_event(
+		clone!(@weak application, @weak drag => @default-return Inhibit(false), move |area, event| {
+			let (x, y) = event.get_position();
+			let application = application.borrow();
+			let layout = Layout::new(
+				&application,
+				area.get_allocated_width() as f64,
+				area.get_allocated_height() as f64,
+			);
+			*drag.borrow_mut() = layout.block_at(x, y).and_then(|block| {
+				let (_, top, _, height) = layout.rectangle(block);
+				Some(Drag {
+					course_index: block.course_index,
+					time_index: block.time_index?,
+					day: block.day,
+					start: block.start,
+					end: block.end,
+					resize: y >= top + height - RESIZE_EDGE,
+					from: (x, y),
+					to: (x, y),
+				})
+			});
+			Inhibit(true)
+		}),
+	);
+
+	area.connect_motion_notify_event(
+		clone!(@weak drag => @default-return Inhibit(false), move |area, event| {
+			if let Some(drag) = drag.borrow_mut().as_mut() {
+				drag.to = event.get_position();
+				area.queue_draw();
+			}
+			Inhibit(true)
+		}),
+	);
+
+	area.connect_button_release_event(
+		clone!(@weak application, @weak gui_app, @weak drag => @default-return Inhibit(false), move |area, event| {
+			let drag = match drag.borrow_mut().take() {
+				Some(mut drag) => {
+					drag.to = event.get_position();
+					drag
+				}
+				None => return Inhibit(true),
+			};
+			area.queue_draw();
+
+			if !drag.moved() {
+				window::course(&gui_app, drag.course_index, application);
+				return Inhibit(true);
+			}
+
+			let target = {
+				let application = application.borrow();
+				let layout = Layout::new(
+					&application,
+					area.get_allocated_width() as f64,
+					area.get_allocated_height() as f64,
+				);
+				match drag.target(&layout) {
+					// Dropped back where it was, so there's nothing to do
+					Some((day, start, end)) if (day, start, end) == (drag.day, drag.start, drag.end) => None,
+					Some((day, start, end)) => Some((layout.days[day].1, start, end)),
+					// Dropped off the top or bottom, so it stays where it was
+					None => None,
+				}
+			};
+			let (date, start, end) = match target {
+				Some(target) => target,
+				None => return Inhibit(true),
+			};
+
+			// The result is kept so the borrow ends before a dialog pops up, and the timeline can still be drawn behind it
+			let result = application
+				.borrow_mut()
+				.move_lesson(drag.course_index, drag.time_index, date, start, end);
+			match result {
+				Ok(conflicts) if !conflicts.is_empty() => {
+					let application = application.borrow();
+					let locale = &application.locale;
+					let clashes: Vec<String> = conflicts
+						.iter()
+						.map(|conflict| {
+							format!(
+								"{} and {}, from {} to {}",
+								application.courses[conflict.first].name,
+								application.courses[conflict.second].name,
+								locale.format_time(conflict.start),
+								locale.format_time(conflict.end)
+							)
+						})
+						.collect();
+					let text = format!(
+						"The lesson was moved, but it now clashes:\n{}",
+						clashes.join("\n")
+					);
+					drop(application);
+					message_dialog(&text);
+				}
+				Ok(_) => (),
+				Err(_) => message_dialog(
+					"The lesson can't be moved there, because it would go past midnight in the course's time zone.",
+				),
+			}
+			Inhibit(true)
+		}),
+	);
+
+	// Edits made anywhere (like dragging, or in a pop-up) are drawn straight away, until the timeline's closed
+	let area_weak = area.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |_, _| match area_weak.upgrade() {
+			Some(area) => {
+				area.queue_draw();
+				true
+			}
+			None => false,
+		});
+
+	// The "now" line moves, so it's drawn again every minute too
+	glib::timeout_add_seconds_local(
+		60,
+		clone!(@weak area => @default-return Continue(false), move || {
+			area.queue_draw();
+			Continue(true)
+		}),
+	);
+
+	area
+}
+
+/// Draws the whole timeline, and where `drag` would put its lesson if there is one.
+fn draw(cr: &cairo::Context, layout: &Layout, application: &Application, drag: Option<&Drag>) {
+	let locale = &application.locale;
+	let column_width = layout.column_width();
+
+	// White background, with holidays shaded
+	cr.set_source_rgb(1.0, 1.0, 1.0);
+	cr.paint();
+	for (index, (_, _, holiday)) in layout.days.iter().enumerate() {
+		if *holiday {
+			cr.set_source_rgb(0.92, 0.92, 0.92);
+			cr.rectangle(
+				LEFT + index as f64 * column_width,
+				TOP,
+				column_width,
+				layout.height - TOP,
+			);
+			cr.fill();
+		}
+	}
+
+	// A line and a label for every hour
+	cr.set_font_size(11.0);
+	cr.set_line_width(1.0);
+	for hour in layout.first_hour..=layout.last_hour {
+		// 24:00 isn't a valid time, so the bottom line is worked out from 23:00
+		let y = match Time::new(hour, 0) {
+			Ok(time) => layout.y(time),
+			Err(_) => layout.height,
+		};
+		cr.set_source_rgb(0.85, 0.85, 0.85);
+		cr.move_to(LEFT, y);
+		cr.line_to(layout.width, y);
+		cr.stroke();
+		if let Ok(time) = Time::new(hour, 0) {
+			cr.set_source_rgb(0.3, 0.3, 0.3);
+			cr.move_to(4.0, y + 4.0);
+			cr.show_text(&locale.format_time(time));
+		}
+	}
+
+	// The days along the top, with a line between each
+	for (index, (day, date, holiday)) in layout.days.iter().enumerate() {
+		let x = LEFT + index as f64 * column_width;
+		cr.set_source_rgb(0.85, 0.85, 0.85);
+		cr.move_to(x, 0.0);
+		cr.line_to(x, layout.height);
+		cr.stroke();
+
+		cr.set_source_rgb(0.1, 0.1, 0.1);
+		cr.move_to(x + 4.0, TOP - 10.0);
+		let title = if *holiday {
+			format!("{} {} (Holiday)", day, date.day())
+		} else {
+			format!("{} {}", day, date.day())
+		};
+		cr.show_text(&title);
+	}
+
+	// The lessons, with their name, room and time on them
+	for block in &layout.blocks {
+		let (x, y, width, height) = layout.rectangle(block);
+		let course = &application.courses[block.course_index];
+		let (r, g, b) = COLORS[block.course_index % COLORS.len()];
+
+		cr.set_source_rgb(r, g, b);
+		cr.rectangle(x, y, width, height);
+		cr.fill();
+
+		// Text that doesn't fit is cut off at the edge of the block
+		cr.save();
+		cr.rectangle(x, y, width, height);
+		cr.clip();
+		cr.set_source_rgb(1.0, 1.0, 1.0);
+		let lines = [
+			course.name.clone(),
+			course.room.clone(),
+			format!(
+				"{} to {}",
+				locale.format_time(block.start),
+				locale.format_time(block.end)
+			),
+		];
+		for (line, text) in lines.iter().enumerate() {
+			cr.move_to(x + 4.0, y + 14.0 + line as f64 * 13.0);
+			cr.show_text(text);
+		}
+		cr.restore();
+	}
+
+	// A see-through copy of the dragged lesson where it'd go, with its new times
+	if let Some(drag) = drag.filter(|drag| drag.moved()) {
+		if let Some((day, start, end)) = drag.target(layout) {
+			let x = LEFT + day as f64 * column_width + 1.0;
+			let y = layout.y(start) + 1.0;
+			let height = layout.y(end) - y - 1.0;
+			let (r, g, b) = COLORS[drag.course_index % COLORS.len()];
+
+			cr.set_source_rgba(r, g, b, 0.5);
+			cr.rectangle(x, y, column_width - 2.0, height);
+			cr.fill_preserve();
+			cr.set_source_rgb(r * 0.6, g * 0.6, b * 0.6);
+			cr.set_line_width(2.0);
+			cr.stroke();
+
+			cr.set_source_rgb(0.1, 0.1, 0.1);
+			cr.move_to(x + 4.0, y + 14.0);
+			cr.show_text(&format!(
+				"{} to {}",
+				locale.format_time(start),
+				locale.format_time(end)
+			));
+		}
+	}
+
+	// A red line at the time it is now, across today
+	let now = Time::now_in(application.timezone);
+	let today = application.today();
+	if let Some(index) = layout.days.iter().position(|(_, date, _)| *date == today) {
+		if now.hour() >= layout.first_hour && now.hour() < layout.last_hour {
+			let x = LEFT + index as f64 * column_width;
+			let y = layout.y(now);
+			cr.set_source_rgb(0.85, 0.1, 0.1);
+			cr.set_line_width(2.0);
+			cr.move_to(x, y);
+			cr.line_to(x + column_width, y);
+			cr.stroke();
+			cr.arc(x, y, 4.0, 0.0, 2.0 * std::f64::consts::PI);
+			cr.fill();
+		}
+	}
+}
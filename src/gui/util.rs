@@ -34,19 +34,108 @@ pub(super) fn message_dialog(text: &str) {
 	.run();
 }
 
-/// Takes a grid and clears it, very not efficient.
+/// Pops up a Yes/No dialog, and returns whether Yes was pressed.
+pub(super) fn confirm_dialog(text: &str) -> bool {
+	let dialog = MessageDialog::new(
+		None::<&Window>,
+		DialogFlags::MODAL,
+		MessageType::Question,
+		ButtonsType::YesNo,
+		text,
+	);
+	let response = dialog.run();
+	dialog.destroy();
+	response == ResponseType::Yes
+}
+
+/// Asks what to do with unsaved changes before closing.
+/// Returns `Yes` to save them, `No` to throw them away, or anything else to not close after all.
+pub(super) fn save_changes_dialog() -> ResponseType {
+	let dialog = MessageDialog::new(
+		None::<&Window>,
+		DialogFlags::MODAL,
+		MessageType::Question,
+		ButtonsType::None,
+		"There are unsaved changes. Save them before closing?",
+	);
+	dialog.add_buttons(&[
+		("Save", ResponseType::Yes),
+		("Don't save", ResponseType::No),
+		("Cancel", ResponseType::Cancel),
+	]);
+	let response = dialog.run();
+	dialog.destroy();
+	response
+}
+
+/// Clears everything off a grid, except the menu on the left.
 pub(super) fn clear(grid: &Grid) {
-	for i in 1..100 {
-		grid.remove_column(i);
-	}
-	for i in 1..100 {
-		grid.remove_column(i);
+	let menu = grid.get_child_at(0, 0);
+	for child in grid.get_children() {
+		if Some(&child) != menu.as_ref() {
+			grid.remove(&child);
+		}
 	}
-	for i in 1..100 {
-		grid.remove_column(i);
+}
+
+/// Creates a list of text that shows whatever's in `store`, one row per line.
+pub(super) fn list_view(store: &ListStore) -> TreeView {
+	let view = TreeView::new_with_model(store);
+	view.set_headers_visible(false);
+	let cell = CellRendererText::new();
+	let column = TreeViewColumn::new();
+	column.pack_start(&cell, true);
+	column.add_attribute(&cell, "text", 0);
+	view.append_column(&column);
+	view
+}
+
+/// Returns the index of the row that's selected in `view`, if there is one.
+pub(super) fn selected_index(view: &TreeView) -> Option<usize> {
+	let (model, iter) = view.get_selection().get_selected()?;
+	let path = model.get_path(&iter)?;
+	path.get_indices().first().map(|index| *index as usize)
+}
+
+/// Replaces everything in `store` with `rows`.
+pub(super) fn fill_store(store: &ListStore, rows: &[String]) {
+	store.clear();
+	for row in rows {
+		store.insert_with_values(None, &[0], &[row]);
 	}
 }
 
+/// Creates a list that's kept up to date with `rows`, which works out what's in it from the application.
+/// It's filled again after every change that `affects` says matters, in every window, for as long as it's shown.
+pub(super) fn live_list<A, R>(
+	application: &Rc<RefCell<Application>>,
+	affects: A,
+	rows: R,
+) -> TreeView
+where
+	A: Fn(Change) -> bool + 'static,
+	R: Fn(&Application) -> Vec<String> + 'static,
+{
+	let store = ListStore::new(&[glib::Type::String]);
+	fill_store(&store, &rows(&application.borrow()));
+
+	// The observer only has a weak reference, so once the list is closed, the store's gone and it stops watching
+	let store_weak = store.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |application, change| match store_weak.upgrade() {
+			Some(store) => {
+				if affects(change) {
+					fill_store(&store, &rows(application));
+				}
+				true
+			}
+			None => false,
+		});
+
+	list_view(&store)
+}
+
 /// Creates a textbox with default text in it.
 pub(super) fn text_with_default(text: &str, wrapmd: Option<WrapMode>) -> TextView {
 	let temp = TextView::new();
@@ -57,6 +146,21 @@ pub(super) fn text_with_default(text: &str, wrapmd: Option<WrapMode>) -> TextVie
 	temp
 }
 
+/// Returns what's written in each of `views`, in order.
+pub(super) fn get_texts(views: &[&TextView]) -> Vec<String> {
+	views
+		.iter()
+		.map(|view| get_string_from_text!(view))
+		.collect()
+}
+
+/// Writes each of `texts` into the textbox in the same place in `views`.
+pub(super) fn set_texts(views: &[&TextView], texts: &[String]) {
+	for (view, text) in views.iter().zip(texts) {
+		view.get_buffer().unwrap().set_text(text);
+	}
+}
+
 /// Creates a frame with text inside it.
 pub(super) fn frame_with_text<P: IsA<Widget>>(text: &str, widget: &P) -> Frame {
 	let tmp = Frame::new(Some(text));
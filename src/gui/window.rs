@@ -1,136 +1,549 @@
 use super::*;
 
 /// Pop-up window for a specific course.
+/// Its times and tasks keep themselves up to date, even when they're changed somewhere else.
 pub(super) fn course(
 	gui_app: &gtk::Application,
 	index: usize,
 	application: Rc<RefCell<Application>>,
 ) {
-	let course = &application.borrow().courses[index];
+	// The lists below need to borrow the application to start watching it, so this is a copy
+	let course = application.borrow().courses[index].clone();
 	let window = ApplicationWindow::new(gui_app);
 
-	let t1 = text_with_default(&course.name, None);
-	let t2 = text_with_default(&course.teacher, None);
-	let t3 = text_with_default(&course.room, None);
+	let texts = course_texts(&course);
+	let t1 = text_with_default(&texts[0], None);
+	let t2 = text_with_default(&texts[1], None);
+	let t3 = text_with_default(&texts[2], None);
+	let t4 = text_with_default(&texts[3], None);
+	let t5 = text_with_default(&texts[4], None);
+	let t6 = text_with_default(&texts[5], None);
+	let t7 = text_with_default(&texts[6], None);
+	let t8 = text_with_default(&texts[7], None);
 
 	let f1 = frame_with_text("Course Name", &t1);
 	let f2 = frame_with_text("Teacher Name", &t2);
 	let f3 = frame_with_text("Room ID", &t3);
+	let f6 = frame_with_text("Time Zone (blank for the application's)", &t4);
+	let f7 = frame_with_text("Tags (separated by commas)", &t5);
+	let f8 = frame_with_text("Remind Minutes Before Lessons (blank for the default)", &t6);
+	let f10 = frame_with_text(
+		"Category Weights (like 'Homework 20, Exams 80', blank for none)",
+		&t7,
+	);
+	let f11 = frame_with_text("Credits (how much it counts towards the GPA)", &t8);
 
-	let listbox_times = ListBox::new();
-	for (day, start, end) in &course.times {
-		listbox_times.insert(
-			&Label::new(Some(&format!("{}: From {} to {}", day, start, end))),
-			-1,
-		);
-	}
-	let f4 = frame_with_text("Times", &listbox_times);
+	// If the course is removed while this is open, the window closes (see `close_with_course`)
+	let list_times = live_list(
+		&application,
+		move |change| change.affects_course(index),
+		move |application| {
+			let locale = &application.locale;
+			match application.courses.get(index) {
+				Some(course) => course
+					.times
+					.iter()
+					.map(|(day, start, end)| {
+						format!(
+							"{}: From {} to {}",
+							day,
+							locale.format_time(*start),
+							locale.format_time(*end)
+						)
+					})
+					.collect(),
+				None => Vec::new(),
+			}
+		},
+	);
+	let f4 = frame_with_text("Times", &list_times);
 	f4.set_hexpand(true);
 	f4.set_vexpand(true);
 
-	let listbox_tasks = ListBox::new();
-	for task in &course.tasks {
-		listbox_tasks.insert(
-			&Label::new(Some(&format!("{}: {}", task.is_complete_str(), task.name))),
-			-1,
-		);
-	}
-	let f5 = frame_with_text("Tasks", &listbox_tasks);
+	let list_tasks = live_list(
+		&application,
+		move |change| change.affects_course(index),
+		move |application| match application.courses.get(index) {
+			Some(course) => course
+				.tasks
+				.iter()
+				.map(|task| format!("{}: {}", task.is_complete_str(), task.name))
+				.collect(),
+			None => Vec::new(),
+		},
+	);
+	let f5 = frame_with_text("Tasks", &list_tasks);
 	f5.set_vexpand(true);
 
-	let button_add_time = Button::new_with_label("Add Time");
-	button_add_time.connect_clicked(
-		clone!(@weak listbox_times, @weak application, @weak window => move |_| {
-			application.borrow_mut().courses[index].new_time();
-			let time = *application.borrow().courses[index].times.last().unwrap();
-			listbox_times.insert(
-				&Label::new(Some(&format!("{}: From {} to {}", time.0, time.1, time.2))),
-				-1,
-			);
-			window.show_all();
-		}),
+	let list_assessments = live_list(
+		&application,
+		move |change| change.affects_course(index),
+		move |application| match application.courses.get(index) {
+			Some(course) => course
+				.assessments
+				.iter()
+				.map(|assessment| {
+					let mut text = assessment.name.clone();
+					if !assessment.category.is_empty() {
+						text.push_str(&format!(" [{}]", assessment.category));
+					}
+					match (assessment.score, assessment.percent()) {
+						(Some(score), Some(percent)) => text.push_str(&format!(
+							": {} / {} ({:.1}%)",
+							score, assessment.max, percent
+						)),
+						_ => text.push_str(&format!(": not graded, out of {}", assessment.max)),
+					}
+					text
+				})
+				.collect(),
+			None => Vec::new(),
+		},
 	);
-	let button_rm_time = Button::new_with_label("Rm Time");
-	button_rm_time.connect_clicked(
-		clone!( @weak listbox_times, @weak application, @weak window => move |_| {
-			if let Some(row) = listbox_times.get_selected_row() {
-				let time_index = row.get_index();
-				application.borrow_mut().courses[index as usize].times.remove(time_index as usize);
-				listbox_times.remove(&row);
-				window.show_all();
+	let f9 = frame_with_text("Grades", &list_assessments);
+	f9.set_vexpand(true);
+
+	let average = Label::new(None);
+	show_average(&average, &application.borrow(), index);
+	let average_weak = average.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |application, change| match average_weak.upgrade() {
+			Some(average) => {
+				if change.affects_course(index) && index < application.courses.len() {
+					show_average(&average, application, index);
+				}
+				true
 			}
-		}),
-	);
+			None => false,
+		});
+
+	let button_add_time = Button::new_with_label("Add Time");
+	button_add_time.connect_clicked(clone!(@weak application => move |_| {
+		application
+			.borrow_mut()
+			.edit_course(index, "Add time", |course| course.new_time());
+	}));
+	let button_rm_time = Button::new_with_label("Rm Time");
+	button_rm_time.connect_clicked(clone!(@weak list_times, @weak application => move |_| {
+		if let Some(time_index) = selected_index(&list_times) {
+			application
+				.borrow_mut()
+				.edit_course(index, "Remove time", |course| course.rm_time(time_index));
+		}
+	}));
 	let button_edit_time = Button::new_with_label("Edit Time");
 	button_edit_time.connect_clicked(
-		clone!(@weak listbox_times, @weak application, @weak gui_app => move |_| {
-			if let Some(row) = listbox_times.get_selected_row() {
-				let time_index = row.get_index() as usize;
+		clone!(@weak list_times, @weak application, @weak gui_app => move |_| {
+			if let Some(time_index) = selected_index(&list_times) {
 				window::time_dialog(time_index, index, application, &gui_app)
 			}
 		}),
 	);
 	let button_add_task = Button::new_with_label("Add Task");
-	button_add_task.connect_clicked(
-		clone!(@weak listbox_tasks, @weak application, @weak window => move |_| {
-			let course = &mut application.borrow_mut().courses[index];
-			course.new_task();
-			let task = course.tasks.last().unwrap();
-			listbox_tasks.insert(
-				&Label::new(Some(&format!("{}: {}", task.is_complete_str(), task.name))),
-				-1,
-			);
-			window.show_all();
+	button_add_task.connect_clicked(clone!(@weak application => move |_| {
+		application
+			.borrow_mut()
+			.edit_course(index, "Add task", |course| course.new_task());
+	}));
+	let button_rm_task = Button::new_with_label("Rm Task");
+	button_rm_task.connect_clicked(clone!(@weak list_tasks, @weak application => move |_| {
+		if let Some(task_index) = selected_index(&list_tasks) {
+			let label = format!("Remove task '{}'", application.borrow().courses[index].tasks[task_index].name);
+			application
+				.borrow_mut()
+				.edit_course(index, label, |course| course.rm_task(task_index));
+		}
+	}));
+	let button_edit_task = Button::new_with_label("Edit Task");
+	button_edit_task.connect_clicked(
+		clone!(@weak list_tasks, @weak application, @weak gui_app => move |_| {
+			if let Some(task_index) = selected_index(&list_tasks) {
+				window::task(index, task_index, &gui_app, application)
+			}
 		}),
 	);
-	let button_rm_task = Button::new_with_label("Rm Task");
-	button_rm_task.connect_clicked(
-		clone!( @weak listbox_tasks, @weak application, @weak window => move |_| {
-			if let Some(row) = listbox_tasks.get_selected_row() {
-				let task_index = row.get_index();
-				application.borrow_mut().courses[index as usize].tasks.remove(task_index as usize);
-				listbox_tasks.remove(&row);
-				window.show_all();
+	let button_add_assessment = Button::new_with_label("Add Assessment");
+	button_add_assessment.connect_clicked(clone!(@weak application => move |_| {
+		application
+			.borrow_mut()
+			.edit_course(index, "Add assessment", |course| course.assessments.push(Assessment::default()));
+	}));
+	let button_rm_assessment = Button::new_with_label("Rm Assessment");
+	button_rm_assessment.connect_clicked(
+		clone!(@weak list_assessments, @weak application => move |_| {
+			if let Some(assessment_index) = selected_index(&list_assessments) {
+				let label = format!(
+					"Remove assessment '{}'",
+					application.borrow().courses[index].assessments[assessment_index].name
+				);
+				application.borrow_mut().edit_course(index, label, |course| {
+					course.assessments.remove(assessment_index);
+				});
 			}
 		}),
 	);
-	let button_edit_task = Button::new_with_label("Edit Task");
-	button_edit_task.connect_clicked(
-		clone!(@weak listbox_tasks, @weak application, @weak gui_app => move |_| {
-			if let Some(row) = listbox_tasks.get_selected_row() {
-				let task_index = row.get_index() as usize;
-				window::task(index, task_index, &gui_app, application)
+	let button_edit_assessment = Button::new_with_label("Edit Assessment");
+	button_edit_assessment.connect_clicked(
+		clone!(@weak list_assessments, @weak application, @weak gui_app => move |_| {
+			if let Some(assessment_index) = selected_index(&list_assessments) {
+				window::assessment(index, assessment_index, &gui_app, application)
 			}
 		}),
 	);
-	let button_save = Button::new_with_label("Save");
-	button_save.connect_clicked(
-		clone!(@weak t1, @weak t2, @weak t3, @weak application => move |_| {
-			let course = &mut application.borrow_mut().courses[index];
+	// Returns whether everything could be saved, since closing the window shouldn't lose what couldn't
+	let save = clone!(@weak t1, @weak t2, @weak t3, @weak t4, @weak t5, @weak t6, @weak t7, @weak t8,
+	@weak application => @default-return false, move || {
+		let timezone = get_string_from_text!(t4);
+		let timezone = if timezone.trim().is_empty() {
+			Ok(None)
+		} else {
+			Zone::try_from(timezone).map(Some)
+		};
+		if timezone.is_err() {
+			message_dialog("Time zone invalid. Use a name like 'Europe/London' or 'UTC'.");
+		}
+		let reminder = parse_optional_number(&get_string_from_text!(t6), MAX_REMINDER_MINUTES);
+		if reminder.is_err() {
+			message_dialog("Reminder invalid. Use a whole number of minutes up to a week (10080), or leave it blank.");
+		}
+		let category_weights = parse_category_weights(&get_string_from_text!(t7));
+		if category_weights.is_err() {
+			message_dialog("Category weights invalid. Write each category and its weight, like 'Homework 20, Exams 80'.");
+		}
+		let credits = match get_string_from_text!(t8).trim().parse::<f64>() {
+			Ok(credits) if credits.is_finite() && credits >= 0.0 => Ok(credits),
+			_ => Err(()),
+		};
+		if credits.is_err() {
+			message_dialog("Credits invalid. Use a number that isn't negative, like 3 or 0.5.");
+		}
+		let valid = timezone.is_ok() && reminder.is_ok() && category_weights.is_ok() && credits.is_ok();
+
+		application.borrow_mut().edit_course(index, "Edit course", |course| {
 			course.name = get_string_from_text!(t1);
 			course.teacher = get_string_from_text!(t2);
 			course.room = get_string_from_text!(t3);
-		}),
-	);
+			course.tags = parse_tags(&get_string_from_text!(t5));
+			if let Ok(timezone) = timezone {
+				course.timezone = timezone;
+			}
+			if let Ok(reminder) = reminder {
+				course.reminder_minutes = reminder;
+			}
+			if let Ok(category_weights) = category_weights {
+				course.category_weights = category_weights;
+			}
+			if let Ok(credits) = credits {
+				course.credits = credits;
+			}
+		});
+
+		// It's shown the way it was saved (like '3' for credits of '3.0'), so it doesn't look changed afterwards
+		if valid {
+			let texts = course_texts(&application.borrow().courses[index]);
+			set_texts(&[&t1, &t2, &t3, &t4, &t5, &t6, &t7, &t8], &texts);
+		}
+		valid
+	});
+	let unsaved = clone!(@weak t1, @weak t2, @weak t3, @weak t4, @weak t5, @weak t6, @weak t7, @weak t8,
+	@weak application => @default-return false, move || {
+		let texts = get_texts(&[&t1, &t2, &t3, &t4, &t5, &t6, &t7, &t8]);
+		let unsaved = match application.borrow().courses.get(index) {
+			Some(course) => texts != course_texts(course),
+			None => false,
+		};
+		unsaved
+	});
+	let button_save = Button::new_with_label("Save");
+	button_save.connect_clicked(clone!(@strong save => move |_| {
+		save();
+	}));
+	ask_before_closing(&window, unsaved, save);
 
 	let grid = Grid::new();
 	grid.attach(&f1, 0, 0, 2, 1);
 	grid.attach(&f2, 0, 1, 2, 1);
 	grid.attach(&f3, 0, 2, 2, 1);
-	grid.attach(&f4, 0, 3, 2, 1);
+	grid.attach(&f6, 0, 3, 2, 1);
+	grid.attach(&f7, 0, 4, 2, 1);
+	grid.attach(&f8, 0, 5, 2, 1);
+	grid.attach(&f4, 0, 6, 2, 1);
+
+	grid.attach(&button_add_time, 0, 7, 1, 1);
+	grid.attach(&button_rm_time, 1, 7, 1, 1);
+	grid.attach(&button_edit_time, 0, 8, 2, 1);
+
+	grid.attach(&f5, 0, 9, 2, 1);
 
-	grid.attach(&button_add_time, 0, 4, 1, 1);
-	grid.attach(&button_rm_time, 1, 4, 1, 1);
-	grid.attach(&button_edit_time, 0, 5, 2, 1);
+	grid.attach(&button_add_task, 0, 10, 1, 1);
+	grid.attach(&button_rm_task, 1, 10, 1, 1);
+	grid.attach(&button_edit_task, 0, 11, 2, 1);
 
-	grid.attach(&f5, 0, 6, 2, 1);
+	grid.attach(&f9, 0, 12, 2, 1);
+	grid.attach(&average, 0, 13, 2, 1);
 
-	grid.attach(&button_add_task, 0, 7, 1, 1);
-	grid.attach(&button_rm_task, 1, 7, 1, 1);
-	grid.attach(&button_edit_task, 0, 8, 2, 1);
+	grid.attach(&button_add_assessment, 0, 14, 1, 1);
+	grid.attach(&button_rm_assessment, 1, 14, 1, 1);
+	grid.attach(&button_edit_assessment, 0, 15, 2, 1);
+	grid.attach(&f10, 0, 16, 2, 1);
+	grid.attach(&f11, 0, 17, 2, 1);
 
-	grid.attach(&button_save, 0, 9, 2, 1);
+	grid.attach(&button_save, 0, 18, 2, 1);
 
+	close_with_course(&window, &application, index, |_| true);
+	window.add(&grid);
+	window.show_all();
+}
+
+/// Returns what a course pop-up's boxes start with, in order, so it can tell whether any were changed.
+fn course_texts(course: &Course) -> Vec<String> {
+	let timezone = match course.timezone {
+		Some(zone) => zone.to_string(),
+		None => String::new(),
+	};
+	let reminder = course
+		.reminder_minutes
+		.map(|minutes| minutes.to_string())
+		.unwrap_or_default();
+	vec![
+		course.name.clone(),
+		course.teacher.clone(),
+		course.room.clone(),
+		timezone,
+		course.tags.join(", "),
+		reminder,
+		format_category_weights(&course.category_weights),
+		course.credits.to_string(),
+	]
+}
+
+/// Asks what to do with the changes in a pop-up before it's closed, if `unsaved` says there are any.
+/// `save` saves them, and returns whether it could, so the pop-up stays open if they're not all valid.
+fn ask_before_closing<U, S>(window: &ApplicationWindow, unsaved: U, save: S)
+where
+	U: Fn() -> bool + 'static,
+	S: Fn() -> bool + 'static,
+{
+	window.connect_delete_event(move |_, _| {
+		if !unsaved() {
+			return Inhibit(false);
+		}
+		match save_changes_dialog() {
+			ResponseType::Yes => Inhibit(!save()),
+			ResponseType::No => Inhibit(false),
+			_ => Inhibit(true),
+		}
+	});
+}
+
+/// Shows the average of the course at `index` so far, and the grade it gets.
+fn show_average(average: &Label, application: &Application, index: usize) {
+	let text = match (
+		application.courses[index].average(),
+		application.grade(index),
+	) {
+		(Some(percent), Some(grade)) => format!("Average so far: {:.1}% ({})", percent, grade.name),
+		(Some(percent), None) => format!("Average so far: {:.1}%", percent),
+		_ => "Nothing graded yet".to_string(),
+	};
+	average.set_text(&text);
+}
+
+/// Closes `window` as soon as `exists` says what it's showing is gone, like when an undo takes it away.
+/// It's destroyed instead of closed, since closing can ask about unsaved changes, which needs the application,
+/// and the application is still busy telling everyone about the change.
+fn close_when_gone<F>(window: &ApplicationWindow, application: &RefCell<Application>, exists: F)
+where
+	F: Fn(&Application, Change) -> bool + 'static,
+{
+	let window_weak = window.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |application, change| match window_weak.upgrade() {
+			Some(window) => {
+				if exists(application, change) {
+					true
+				} else {
+					window.destroy();
+					false
+				}
+			}
+			None => false,
+		});
+}
+
+/// Closes `window`, which shows something in the course at `index`, once that course is gone
+/// or `exists` says the thing in it is.
+fn close_with_course<F>(
+	window: &ApplicationWindow,
+	application: &RefCell<Application>,
+	index: usize,
+	exists: F,
+) where
+	F: Fn(&Course) -> bool + 'static,
+{
+	// Courses don't have IDs like tasks do, so this remembers what the course looks like
+	// Adding or removing a course can move the others around, and then it has to be the same course at `index`
+	let course = RefCell::new(application.borrow().courses[index].clone());
+	let count = Cell::new(application.borrow().courses.len());
+	close_when_gone(window, application, move |application, change| {
+		if !change.affects_course(index) {
+			return true;
+		}
+		let now = match application.courses.get(index) {
+			Some(now) => now,
+			None => return false,
+		};
+		if application.courses.len() != count.get() && *now != *course.borrow() {
+			return false;
+		}
+		// Anything else was a change to the course itself, so that's what it looks like now
+		count.set(application.courses.len());
+		*course.borrow_mut() = now.clone();
+		exists(now)
+	});
+}
+
+/// Returns where the task with `id` is now, as (course index, task index), if it's still there.
+/// Its indexes change when tasks before it are removed, but its ID doesn't.
+fn locate_task(application: &RefCell<Application>, id: u64) -> Option<(usize, usize)> {
+	application.borrow().find_task(id)
+}
+
+/// Changes the task with `id` with `edit`, wherever it is now, as one edit that can be undone.
+/// If it's gone, nothing happens.
+fn edit_task<F: FnOnce(&mut Task)>(
+	application: &RefCell<Application>,
+	id: u64,
+	label: &str,
+	edit: F,
+) {
+	if let Some((course_index, task_index)) = locate_task(application, id) {
+		application
+			.borrow_mut()
+			.edit_task(course_index, task_index, label, edit);
+	}
+}
+
+/// Pop-up window for editing an assessment, including what's needed on it for the course to get each grade.
+pub(super) fn assessment(
+	course_index: usize,
+	index: usize,
+	gui_app: &gtk::Application,
+	application: Rc<RefCell<Application>>,
+) {
+	let course = application.borrow().courses[course_index].clone();
+	let assessment = course.assessments[index].clone();
+	let window = ApplicationWindow::new(gui_app);
+	// Assessments don't have IDs, so it's closed once the assessment at `index` isn't this one anymore
+	let shown = Rc::new(RefCell::new(assessment.clone()));
+
+	let t1 = text_with_default(&assessment.name, None);
+	let t2 = text_with_default(&assessment.category, None);
+	let score = assessment
+		.score
+		.map(|score| score.to_string())
+		.unwrap_or_default();
+	let t3 = text_with_default(&score, None);
+	let t4 = text_with_default(&assessment.max.to_string(), None);
+	let t5 = text_with_default(&assessment.weight.to_string(), None);
+
+	let f1 = frame_with_text("Name", &t1);
+	let f2 = frame_with_text("Category (like 'Homework')", &t2);
+	let f3 = frame_with_text("Score (blank if it hasn't been graded yet)", &t3);
+	let f4 = frame_with_text("Out Of", &t4);
+	let f5 = frame_with_text("Weight (compared to the rest of its category)", &t5);
+
+	// The IDs are task IDs, so the right task stays picked if the others are moved around
+	let task = ComboBoxText::new();
+	task.append(Some("none"), "None");
+	for course_task in &course.tasks {
+		task.append(Some(&course_task.id.to_string()), &course_task.name);
+	}
+	let task_id = assessment
+		.task_id
+		.map(|id| id.to_string())
+		.unwrap_or_else(|| "none".to_string());
+	if !task.set_active_id(Some(&task_id)) {
+		task.set_active_id(Some("none"));
+	}
+	let f6 = frame_with_text("For Task", &task);
+
+	// What's needed on an assessment is only worth showing before it's graded
+	let needed = Label::new(None);
+	if assessment.score.is_none() {
+		let scale = application.borrow().grading_scale.clone();
+		let lines: Vec<String> = scale
+			.grades
+			.iter()
+			.filter_map(|grade| {
+				let percent = course.needed_on(index, grade.min)?;
+				if percent > 100.0 || percent <= 0.0 {
+					return None;
+				}
+				Some(format!("{} needs {:.1}%", grade.name, percent))
+			})
+			.collect();
+		if !lines.is_empty() {
+			needed.set_text(&lines.join("\n"));
+		}
+	}
+
+	let button_save = Button::new_with_label("Save");
+	button_save.connect_clicked(
+		clone!(@weak t1, @weak t2, @weak t3, @weak t4, @weak t5, @weak task, @weak window,
+		@weak application, @strong shown => move |_| {
+			let number = |text: String| text.trim().parse::<f64>().map_err(|_| ());
+			let score = get_string_from_text!(t3);
+			let score = if score.trim().is_empty() {
+				Ok(None)
+			} else {
+				number(score).map(Some)
+			};
+			let (score, max, weight) = match (score, number(get_string_from_text!(t4)), number(get_string_from_text!(t5))) {
+				(Ok(score), Ok(max), Ok(weight)) => (score, max, weight),
+				_ => {
+					message_dialog("Score, out of and weight have to be numbers (the score can be blank).");
+					return;
+				}
+			};
+			let assessment = Assessment {
+				name: get_string_from_text!(t1),
+				category: get_string_from_text!(t2).trim().to_string(),
+				score,
+				max,
+				weight,
+				task_id: task
+					.get_active_id()
+					.and_then(|id| id.to_string().parse().ok()),
+			};
+			if let Err(problem) = assessment.check() {
+				message_dialog(&problem);
+				return;
+			}
+			let label = format!("Edit assessment '{}'", assessment.name);
+			*shown.borrow_mut() = assessment.clone();
+			application.borrow_mut().edit_course(course_index, label, |course| {
+				course.assessments[index] = assessment
+			});
+			window.close();
+		}),
+	);
+
+	let grid = Grid::new();
+	grid.attach(&f1, 0, 0, 2, 1);
+	grid.attach(&f2, 0, 1, 2, 1);
+	grid.attach(&f3, 0, 2, 1, 1);
+	grid.attach(&f4, 1, 2, 1, 1);
+	grid.attach(&f5, 0, 3, 2, 1);
+	grid.attach(&f6, 0, 4, 2, 1);
+	grid.attach(&needed, 0, 5, 2, 1);
+	grid.attach(&button_save, 0, 6, 2, 1);
+
+	close_with_course(&window, &application, course_index, move |course| {
+		course.assessments.get(index) == Some(&*shown.borrow())
+	});
 	window.add(&grid);
 	window.show_all();
 }
@@ -141,45 +554,78 @@ pub(super) fn holiday(
 	index: usize,
 	application: Rc<RefCell<Application>>,
 ) {
-	let holiday = &application.borrow().holidays[index];
+	let (start, end) = application.borrow().holidays[index];
+	let locale = application.borrow().locale.clone();
+	let today = application.borrow().today();
 	let window = ApplicationWindow::new(gui_app);
+	// Holidays don't have IDs, so it's closed once the holiday at `index` isn't this one anymore
+	let shown = Rc::new(Cell::new((start, end)));
+
+	let start: Picked<Date> = Rc::new(Cell::new(Some(start)));
+	let end: Picked<Date> = Rc::new(Cell::new(Some(end)));
+	let problem = Label::new(None);
+	let button_save = Button::new_with_label("Save");
 
-	let t1 = text_with_default(&holiday.0.as_string(), None);
-	let t2 = text_with_default(&holiday.1.as_string(), None);
+	let check = clone!(@weak problem, @weak button_save, @strong start, @strong end => move || {
+		let text = match (start.get(), end.get()) {
+			(Some(start), Some(end)) if start > end => Some("Start date greater than end date!"),
+			(Some(_), Some(_)) => None,
+			_ => Some("Date entry invalid. Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'."),
+		};
+		show_problem(&problem, &button_save, text);
+	});
 
-	let f1 = frame_with_text("Start Date", &t1);
+	let f1 = frame_with_text(
+		"Start Date",
+		&date_picker(&locale, today, &start, check.clone()),
+	);
 	f1.set_hexpand(true);
 
-	let f2 = frame_with_text("End Date", &t2);
+	let f2 = frame_with_text("End Date", &date_picker(&locale, today, &end, check));
 	f2.set_hexpand(true);
 
-	let button_save = Button::new_with_label("Save");
-	button_save.connect_clicked(clone!(@weak t1, @weak t2, @weak application => move |_| {
-		if let (Ok(start), Ok(end)) = (
-			Date::try_from(get_string_from_text!(t1)),
-			Date::try_from(get_string_from_text!(t2)),
-		) {
-			if start > end {
-				message_dialog("Start date greater than end date!");
-			} else {
-				application.borrow_mut().holidays[index] = (start, end);
+	// Returns whether it could be saved, like the course pop-up's
+	let save = clone!(@weak application, @strong shown, @strong start, @strong end
+	=> @default-return false, move || {
+		match (start.get(), end.get()) {
+			(Some(start), Some(end)) if start <= end => {
+				shown.set((start, end));
+				application
+					.borrow_mut()
+					.edit_holidays("Edit holiday", |holidays| holidays[index] = (start, end));
+				true
 			}
-		} else {
-			message_dialog("Date entry invalid. Recheck entry, and only use numbers in format: 'YYYY-MM-DD'.");
+			_ => false,
 		}
+	});
+	let unsaved = clone!(@weak application => @default-return false, move || {
+		let unsaved = match application.borrow().holidays.get(index) {
+			Some(&(saved_start, saved_end)) => (start.get(), end.get()) != (Some(saved_start), Some(saved_end)),
+			None => false,
+		};
+		unsaved
+	});
+	button_save.connect_clicked(clone!(@strong save => move |_| {
+		save();
 	}));
+	ask_before_closing(&window, unsaved, save);
 
 	let grid = Grid::new();
 	grid.attach(&f1, 0, 1, 1, 1);
-	grid.attach(&f2, 0, 2, 1, 1);
+	grid.attach(&f2, 1, 1, 1, 1);
 
-	grid.attach(&button_save, 0, 3, 1, 1);
+	grid.attach(&problem, 0, 2, 2, 1);
+	grid.attach(&button_save, 0, 3, 2, 1);
 
+	close_when_gone(&window, &application, move |application, change| {
+		change != Change::Holidays || application.holidays.get(index) == Some(&shown.get())
+	});
 	window.add(&grid);
 	window.show_all();
 }
 
 /// Pop-up window for editing a task.
+/// Its steps keep themselves up to date, even when they're changed somewhere else.
 pub(super) fn task(
 	course_index: usize,
 	task_index: usize,
@@ -188,150 +634,675 @@ pub(super) fn task(
 ) {
 	let window = ApplicationWindow::new(gui_app);
 
-	let course = &mut application.borrow_mut().courses[course_index];
-	let task = &mut course.tasks[task_index];
+	// The steps need to borrow the application to start watching it, so this is a copy
+	let locale = application.borrow().locale.clone();
+	let today = application.borrow().today();
+	let task = application.borrow().courses[course_index].tasks[task_index].clone();
+	// Tasks before it can be removed while this is open, so it's found by its ID whenever it's needed
+	let id = task.id;
 
-	let t1 = text_with_default(&task.name, None);
-	let t2 = text_with_default(&task.desc, Some(WrapMode::Word));
-	let t3 = text_with_default(&task.due.to_string(), None);
+	let texts = task_texts(&task);
+	let t1 = text_with_default(&texts[0], None);
+	let t2 = text_with_default(&texts[1], Some(WrapMode::Word));
+	let t4 = text_with_default(&texts[2], None);
+	let t5 = text_with_default(&texts[3], None);
+	let t6 = text_with_default(&texts[4], None);
+
+	let priority = ComboBoxText::new();
+	for level in Priority::all().iter() {
+		priority.append_text(level.as_str());
+	}
+	priority.set_active(Some(task.priority as u32));
 
 	let f1 = frame_with_text("Task Name", &t1);
 	let f2 = frame_with_text("Description", &t2);
 	f2.set_hexpand(true);
-	let f3 = frame_with_text("Due Date", &t3);
-
-	let listbox = ListBox::new();
-	for (step_index, (done, desc)) in task.steps.iter().enumerate() {
-		let done_desc = if *done { "Done" } else { "Undone" };
-
-		let done_button = Button::new_with_label(done_desc);
-		done_button.connect_clicked(clone!(@weak application, @weak window => move |but| {
-			let course = &mut application.borrow_mut().courses[course_index];
-			let task = &mut course.tasks[task_index];
-			task.toggle_step(step_index);
-			match but.get_label().unwrap().as_str() {
-				"Done" => but.set_label("Undone"),
-				"Undone" => but.set_label("Done"),
-				_ => ()
-			}
-			window.show_all();
-		}));
 
-		let grid = Grid::new();
-		grid.attach(&done_button, 0, 0, 1, 1);
-		grid.attach(&Label::new(Some(desc)), 1, 0, 1, 1);
-		listbox.insert(&grid, -1);
-	}
-	let f4 = frame_with_text("Task Steps", &listbox);
+	let due: Picked<Date> = Rc::new(Cell::new(Some(task.due)));
+	let problem = Label::new(None);
+	let button_save = Button::new_with_label("Save");
+	let check = clone!(@weak problem, @weak button_save, @strong due => move || {
+		let text = match due.get() {
+			Some(_) => None,
+			None => Some("Due date invalid. Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'."),
+		};
+		show_problem(&problem, &button_save, text);
+	});
+	// A snoozed task says when it was first due, so it's not forgotten how late it is
+	let due_title = match task.original_due {
+		Some(original) => format!("Due Date (first due {})", locale.format_date(original)),
+		None => "Due Date".to_string(),
+	};
+	let f3 = frame_with_text(&due_title, &date_picker(&locale, today, &due, check));
+	let f5 = frame_with_text("Priority", &priority);
+	let f6 = frame_with_text("Tags (separated by commas)", &t4);
+	let f7 = frame_with_text("Remind Days Before Due (blank for the default)", &t5);
+	let f9 = frame_with_text("Estimate (like '2h 30m', blank for none)", &t6);
+
+	let steps = steps_list(id, application.clone());
+	let f4 = frame_with_text("Task Steps", &steps);
 	f4.set_vexpand(true);
 
-	let button_add_step = Button::new_with_label("Add Step");
-	button_add_step.connect_clicked(
-		clone!(@weak listbox, @weak application, @weak window => move |_| {
-			let course = &mut application.borrow_mut().courses[course_index];
-			let task = &mut course.tasks[task_index];
-			task.new_step();
-			let step = task.steps.last().unwrap();
-			let step_index = task.steps.len() -1;
-
-			// This code is repeated above... For now I'll leave it as-is
-			let done_button = Button::new_with_label("Undone");
-			done_button.connect_clicked(clone!(@weak application, @weak window => move |but| {
-				let course = &mut application.borrow_mut().courses[course_index];
-				let task = &mut course.tasks[task_index];
-				task.toggle_step(step_index);
-				match but.get_label().unwrap().as_str() {
-					"Done" => but.set_label("Undone"),
-					"Undone" => but.set_label("Done"),
-					_ => ()
+	// A task without steps just has a box to tick, which goes away once there are steps to tick instead
+	let done = CheckButton::new_with_label("Done");
+	done.set_no_show_all(true);
+	let progress = Label::new(None);
+	let updating = Rc::new(Cell::new(false));
+	show_done(&done, &progress, &updating, &task);
+
+	let done_weak = done.downgrade();
+	let progress_weak = progress.downgrade();
+	let observed_updating = updating.clone();
+	application.borrow_mut().observe(move |application, change| {
+		match (done_weak.upgrade(), progress_weak.upgrade()) {
+			(Some(done), Some(progress)) => {
+				if let Some((course_index, task_index)) = application.find_task(id) {
+					if change.affects_course(course_index) {
+						let task = &application.courses[course_index].tasks[task_index];
+						show_done(&done, &progress, &observed_updating, task);
+					}
+				}
+				true
+			}
+			_ => false,
+		}
+	});
+	done.connect_toggled(clone!(@weak application, @strong updating => move |done| {
+		// Ticking the box to show a change made somewhere else isn't a change of its own
+		if updating.get() {
+			return;
+		}
+		let complete = done.get_active();
+		edit_task(&application, id, "Tick task", |task| task.set_complete(complete));
+	}));
+	let f8 = frame_with_text("Depends On", &dependencies(id, application.clone()));
+
+	// The timer keeps running with the window closed, or even with the scheduler closed, since it's saved with the task
+	// Focus sessions are the same, and the main window logs their rounds as they finish (see mod.rs)
+	let time_spent = Label::new(None);
+	let button_timer = Button::new_with_label("Start Timer");
+	let button_focus = Button::new_with_label("Start Focus Session");
+	show_time(&time_spent, &button_timer, &task, &locale);
+	show_focus(&button_focus, &application.borrow(), course_index, task_index);
+	let time_spent_weak = time_spent.downgrade();
+	let button_timer_weak = button_timer.downgrade();
+	let button_focus_weak = button_focus.downgrade();
+	application.borrow_mut().observe(move |application, change| {
+		match (
+			time_spent_weak.upgrade(),
+			button_timer_weak.upgrade(),
+			button_focus_weak.upgrade(),
+		) {
+			(Some(time_spent), Some(button_timer), Some(button_focus)) => {
+				if let Some((course_index, task_index)) = application.find_task(id) {
+					if change.affects_course(course_index) {
+						let task = &application.courses[course_index].tasks[task_index];
+						show_time(&time_spent, &button_timer, task, &application.locale);
+						show_focus(&button_focus, application, course_index, task_index);
+					}
 				}
-				window.show_all();
-			}));
+				true
+			}
+			_ => false,
+		}
+	});
+	button_timer.connect_clicked(clone!(@weak application => move |_| {
+		let mut application = application.borrow_mut();
+		let (course_index, task_index) = match application.find_task(id) {
+			Some(found) => found,
+			None => return,
+		};
+		let now = application.now();
+		if application.courses[course_index].tasks[task_index].timer.is_some() {
+			application.stop_timer(now);
+		} else {
+			application.start_timer(course_index, task_index, now);
+		}
+	}));
+	button_focus.connect_clicked(clone!(@weak application => move |_| {
+		let mut application = application.borrow_mut();
+		let (course_index, task_index) = match application.find_task(id) {
+			Some(found) => found,
+			None => return,
+		};
+		let now = application.now();
+		if application.focus_task() == Some((course_index, task_index)) {
+			application.stop_focus(now);
+		} else {
+			application.start_focus(course_index, task_index, now);
+		}
+	}));
+	let button_log_time = Button::new_with_label("Log Time");
+	button_log_time.connect_clicked(clone!(@weak gui_app, @weak application => move |_| {
+		log_time_dialog(id, &gui_app, application);
+	}));
+	let time_box = Box::new(Orientation::Horizontal, 5);
+	time_box.pack_start(&time_spent, false, false, 0);
+	time_box.pack_end(&button_log_time, false, false, 0);
+	time_box.pack_end(&button_timer, false, false, 0);
+	time_box.pack_end(&button_focus, false, false, 0);
 
-			let grid = Grid::new();
-			grid.attach(&done_button, 0, 0, 1, 1);
-			grid.attach(&Label::new(Some(&step.1)), 1, 0, 1, 1);
-			listbox.insert(&grid, -1);
+	let done_box = Box::new(Orientation::Horizontal, 5);
+	done_box.pack_start(&done, false, false, 0);
+	done_box.pack_end(&progress, false, false, 0);
 
-			window.show_all();
-		}),
-	);
+	let button_add_step = Button::new_with_label("Add Step");
+	button_add_step.connect_clicked(clone!(@weak application => move |_| {
+		edit_task(&application, id, "Add step", |task| task.new_step());
+	}));
+	let button_add_sub_step = Button::new_with_label("Add Sub-step");
+	button_add_sub_step.connect_clicked(clone!(@weak steps, @weak application => move |_| {
+		if let Some(step_index) = selected_step(&steps) {
+			edit_task(&application, id, "Add sub-step", |task| task.add_sub_step(step_index, "Description"));
+		}
+	}));
+	let button_indent_step = Button::new_with_label("Indent");
+	button_indent_step.connect_clicked(clone!(@weak steps, @weak application => move |_| {
+		if let Some(step_index) = selected_step(&steps) {
+			move_step(&steps, id, &application, "Indent step", |task| task.indent_step(step_index));
+		}
+	}));
+	let button_outdent_step = Button::new_with_label("Outdent");
+	button_outdent_step.connect_clicked(clone!(@weak steps, @weak application => move |_| {
+		if let Some(step_index) = selected_step(&steps) {
+			move_step(&steps, id, &application, "Outdent step", |task| task.outdent_step(step_index));
+		}
+	}));
 	let button_rm_step = Button::new_with_label("Rm Step");
-	button_rm_step.connect_clicked(
-		clone!( @weak listbox, @weak application, @weak window => move |_| {
-			if let Some(row) = listbox.get_selected_row() {
-				let step_index = row.get_index();
-				application.borrow_mut().courses[course_index as usize].tasks[task_index].rm_step(step_index as usize);
-				listbox.remove(&row);
-				window.show_all();
+	button_rm_step.connect_clicked(clone!(@weak steps, @weak application => move |_| {
+		if let Some(step_index) = selected_step(&steps) {
+			edit_task(&application, id, "Remove step", |task| task.rm_step(step_index));
+		}
+	}));
+	let button_edit_step = Button::new_with_label("Edit Step");
+	button_edit_step.connect_clicked(
+		clone!(@weak steps, @weak gui_app, @weak application => move |_| {
+			if let Some(step_index) = selected_step(&steps) {
+				window::step_dialog(id, step_index, &gui_app, application);
 			}
 		}),
 	);
-	let button_edit_step = Button::new_with_label("Edit Step");
-	button_edit_step.connect_clicked(clone!( @weak gui_app, @weak application => move |_| {
-		if let Some(row) = listbox.get_selected_row() {
-			let step_index = row.get_index() as usize;
-			window::step_dialog(course_index, task_index, step_index, &gui_app, application);
+	// Returns whether everything could be saved, like the course pop-up's
+	let save = clone!(@weak t1, @weak t2, @weak t4, @weak t5, @weak t6, @weak priority, @weak application,
+	@strong due => @default-return false, move || {
+		let reminder = parse_optional_number(&get_string_from_text!(t5), MAX_REMINDER_DAYS);
+		if reminder.is_err() {
+			message_dialog("Reminder invalid. Use a whole number of days up to 365, or leave it blank.");
+		}
+		let estimate = get_string_from_text!(t6);
+		let estimate = if estimate.trim().is_empty() {
+			Ok(None)
+		} else {
+			parse_minutes(&estimate).map(Some)
+		};
+		if estimate.is_err() {
+			message_dialog("Estimate invalid. Try '45m', '1h 30m' or '1:30' (up to 1000 hours), or leave it blank.");
 		}
-	}));
-	let button_save = Button::new_with_label("Save");
-	button_save.connect_clicked(
-		clone!(@weak t1, @weak t2, @weak t3, @weak application => move |_| {
-			let course = &mut application.borrow_mut().courses[course_index];
-			let task = &mut course.tasks[task_index];
 
+		edit_task(&application, id, "Edit task", |task| {
 			task.name = get_string_from_text!(t1);
 			task.desc = get_string_from_text!(t2);
-
-			if let Ok(due) = Date::try_from(get_string_from_text!(t3)) {
+			task.tags = parse_tags(&get_string_from_text!(t4));
+			if let Some(index) = priority.get_active() {
+				task.priority = Priority::all()[index as usize];
+			}
+			if let Some(due) = due.get() {
 				task.due = due;
-			} else {
-				message_dialog("Date entry invalid. Recheck entry, and only use numbers in format: 'YYYY-MM-DD'.");
 			}
-		}),
-	);
+			if let Ok(reminder) = reminder {
+				task.reminder_days = reminder;
+			}
+			if let Ok(estimate) = estimate {
+				task.estimate = estimate;
+			}
+		});
+
+		let valid = due.get().is_some() && reminder.is_ok() && estimate.is_ok();
+		if valid {
+			if let Some((course_index, task_index)) = locate_task(&application, id) {
+				let texts = task_texts(&application.borrow().courses[course_index].tasks[task_index]);
+				set_texts(&[&t1, &t2, &t4, &t5, &t6], &texts);
+			}
+		}
+		valid
+	});
+	let unsaved = clone!(@weak t1, @weak t2, @weak t4, @weak t5, @weak t6, @weak priority, @weak application,
+	@strong due => @default-return false, move || {
+		let texts = get_texts(&[&t1, &t2, &t4, &t5, &t6]);
+		let application = application.borrow();
+		match application.find_task(id) {
+			Some((course_index, task_index)) => {
+				let task = &application.courses[course_index].tasks[task_index];
+				texts != task_texts(task)
+					|| priority.get_active() != Some(task.priority as u32)
+					|| due.get() != Some(task.due)
+			}
+			None => false,
+		}
+	});
+	button_save.connect_clicked(clone!(@strong save => move |_| {
+		save();
+	}));
+	ask_before_closing(&window, unsaved, save);
 
 	let grid = Grid::new();
 	grid.attach(&f1, 0, 0, 2, 1);
 	grid.attach(&f2, 0, 1, 2, 1);
 	grid.attach(&f3, 0, 2, 2, 1);
-	grid.attach(&f4, 0, 3, 2, 1);
+	grid.attach(&f5, 0, 3, 2, 1);
+	grid.attach(&f6, 0, 4, 2, 1);
+	grid.attach(&f7, 0, 5, 2, 1);
+	grid.attach(&f4, 0, 6, 2, 1);
+	grid.attach(&done_box, 0, 7, 2, 1);
 
-	grid.attach(&button_add_step, 0, 5, 1, 1);
-	grid.attach(&button_rm_step, 1, 5, 1, 1);
-	grid.attach(&button_edit_step, 0, 6, 2, 1);
+	grid.attach(&button_add_step, 0, 8, 1, 1);
+	grid.attach(&button_add_sub_step, 1, 8, 1, 1);
+	grid.attach(&button_indent_step, 0, 9, 1, 1);
+	grid.attach(&button_outdent_step, 1, 9, 1, 1);
+	grid.attach(&button_rm_step, 0, 10, 1, 1);
+	grid.attach(&button_edit_step, 1, 10, 1, 1);
+	grid.attach(&f8, 0, 11, 2, 1);
+	grid.attach(&f9, 0, 12, 2, 1);
+	grid.attach(&time_box, 0, 13, 2, 1);
 
-	grid.attach(&button_save, 0, 7, 2, 1);
+	grid.attach(&problem, 0, 14, 2, 1);
+	grid.attach(&button_save, 0, 15, 2, 1);
 
+	close_when_gone(&window, &application, move |application, _| {
+		application.find_task(id).is_some()
+	});
 	window.add(&grid);
 	window.show_all();
 }
 
-/// A small dialog for editing a step.
-pub(super) fn step_dialog(
+/// Returns what a task pop-up's boxes start with, in order, so it can tell whether any were changed.
+fn task_texts(task: &Task) -> Vec<String> {
+	let reminder = task
+		.reminder_days
+		.map(|days| days.to_string())
+		.unwrap_or_default();
+	vec![
+		task.name.clone(),
+		task.desc.clone(),
+		task.tags.join(", "),
+		reminder,
+		task.estimate.map(format_minutes).unwrap_or_default(),
+	]
+}
+
+/// Creates the list of tasks that a task depends on, with a menu of every other task to add to it.
+/// Like the steps, it's kept up to date for as long as it's shown, and every change can be undone.
+fn dependencies(id: u64, application: Rc<RefCell<Application>>) -> Box {
+	// The tasks it depends on can be in any course, so any change to the courses can change the list
+	let list = live_list(
+		&application,
+		|change| change != Change::Holidays,
+		move |application| match application.find_task(id) {
+			Some((course_index, task_index)) => application
+				.prerequisites(course_index, task_index)
+				.into_iter()
+				.map(|(c, t)| {
+					let task = &application.courses[c].tasks[t];
+					let done = if task.is_complete() { " (done)" } else { "" };
+					format!("{}: {}{}", application.courses[c].name, task.name, done)
+				})
+				.collect(),
+			_ => Vec::new(),
+		},
+	);
+
+	// The IDs are task IDs, so the right task is added even if others were moved around since
+	let others = ComboBoxText::new();
+	let fill = move |others: &ComboBoxText, application: &Application| {
+		others.remove_all();
+		for course in &application.courses {
+			for task in &course.tasks {
+				if task.id != id {
+					let other = task.id.to_string();
+					others.append(Some(&other), &format!("{}: {}", course.name, task.name));
+				}
+			}
+		}
+	};
+	fill(&others, &application.borrow());
+	let others_weak = others.downgrade();
+	application
+		.borrow_mut()
+		.observe(move |application, change| match others_weak.upgrade() {
+			Some(others) => {
+				if change != Change::Holidays {
+					fill(&others, application);
+				}
+				true
+			}
+			None => false,
+		});
+
+	let button_add = Button::new_with_label("Add");
+	button_add.connect_clicked(clone!(@weak others, @weak application => move |_| {
+		let other = others
+			.get_active_id()
+			.and_then(|other| other.to_string().parse::<u64>().ok());
+		let prerequisite = other.and_then(|other| locate_task(&application, other));
+		if let (Some(task), Some(prerequisite)) = (locate_task(&application, id), prerequisite) {
+			let result = application.borrow_mut().add_dependency(task, prerequisite);
+			if let Err(message) = result {
+				message_dialog(&message);
+			}
+		}
+	}));
+	let button_rm = Button::new_with_label("Remove");
+	button_rm.connect_clicked(clone!(@weak list, @weak application => move |_| {
+		if let (Some(index), Some(task)) = (selected_index(&list), locate_task(&application, id)) {
+			let prerequisite = application.borrow().prerequisites(task.0, task.1)[index];
+			application.borrow_mut().rm_dependency(task, prerequisite);
+		}
+	}));
+
+	let buttons = Box::new(Orientation::Horizontal, 5);
+	buttons.pack_start(&others, true, true, 0);
+	buttons.pack_start(&button_add, false, false, 0);
+	buttons.pack_start(&button_rm, false, false, 0);
+
+	let dependencies = Box::new(Orientation::Vertical, 5);
+	dependencies.pack_start(&list, true, true, 0);
+	dependencies.pack_start(&buttons, false, false, 0);
+	dependencies
+}
+
+/// Shows how long has been spent on `task` compared to its estimate, and whether its timer's running.
+fn show_time(time_spent: &Label, button_timer: &Button, task: &Task, locale: &Locale) {
+	let mut text = format!("Spent {}", format_minutes(task.total_minutes()));
+	if let (Some(estimate), Some(left)) = (task.estimate, task.minutes_left()) {
+		text.push_str(&format!(" of {}", format_minutes(estimate)));
+		if left < 0 {
+			text.push_str(&format!(" (over by {})", format_minutes(-left as u32)));
+		} else {
+			text.push_str(&format!(" ({} left)", format_minutes(left as u32)));
+		}
+	}
+	match task.timer {
+		Some((_, started)) => {
+			text.push_str(&format!(", timing since {}", locale.format_time(started)));
+			button_timer.set_label("Stop Timer");
+		}
+		None => button_timer.set_label("Start Timer"),
+	}
+	time_spent.set_text(&text);
+}
+
+/// Shows whether there's a focus session on the task at (course index, task index), and when it ends.
+fn show_focus(
+	button_focus: &Button,
+	application: &Application,
 	course_index: usize,
 	task_index: usize,
+) {
+	match application.focus_session {
+		Some(session) if application.focus_task() == Some((course_index, task_index)) => {
+			let end = session.at(session.length()).1;
+			button_focus.set_label(&format!(
+				"Stop Focus Session (until {})",
+				application.locale.format_time(end)
+			));
+		}
+		_ => button_focus.set_label("Start Focus Session"),
+	}
+}
+
+/// Shows how far along `task` is, and ticks the `done` box if it's done, which is only shown if it has no steps.
+fn show_done(done: &CheckButton, progress: &Label, updating: &Cell<bool>, task: &Task) {
+	updating.set(true);
+	done.set_active(task.complete);
+	updating.set(false);
+	done.set_visible(task.steps.is_empty());
+	progress.set_text(&format!("{}% done", task.progress()));
+}
+
+/// Creates the list of a task's steps, with a box to tick each one off.
+/// Sub-steps are shown inside the step they're in, and steps can be dragged around to reorder or nest them.
+/// It's filled again whenever the task's course changes, for as long as it's shown.
+fn steps_list(id: u64, application: Rc<RefCell<Application>>) -> TreeView {
+	// Each row is (whether it's done, what it is, which step it is)
+	// The rows are nested, so the last column is the only way to tell which step a row is
+	let store = TreeStore::new(&[glib::Type::Bool, glib::Type::String, glib::Type::U32]);
+	let view = TreeView::new_with_model(&store);
+	view.set_headers_visible(false);
+
+	let fill = move |view: &TreeView, store: &TreeStore, application: &Application| {
+		store.clear();
+		if let Some((course_index, task_index)) = application.find_task(id) {
+			let task = &application.courses[course_index].tasks[task_index];
+			// The last row at each depth so far, which the next deeper step goes inside
+			let mut parents: Vec<TreeIter> = Vec::new();
+			for (step_index, step) in task.steps.iter().enumerate() {
+				let mut text = step.desc.clone();
+				if let Some(weight) = step.weight {
+					text.push_str(&format!(" (counts {}x)", weight));
+				}
+				if let Some(due) = step.due {
+					text.push_str(&format!(" (by {})", application.locale.format_date(due)));
+				}
+				parents.truncate(step.depth);
+				let iter = store.insert_with_values(
+					parents.last(),
+					None,
+					&[0, 1, 2],
+					&[&step.complete, &text, &(step_index as u32)],
+				);
+				parents.push(iter);
+			}
+		}
+		view.expand_all();
+	};
+	fill(&view, &store, &application.borrow());
+
+	let view_weak = view.downgrade();
+	let store_weak = store.downgrade();
+	application.borrow_mut().observe(move |application, change| {
+		match (view_weak.upgrade(), store_weak.upgrade()) {
+			(Some(view), Some(store)) => {
+				let affected = match application.find_task(id) {
+					Some((course_index, _)) => change.affects_course(course_index),
+					None => true,
+				};
+				if affected {
+					fill(&view, &store, application);
+				}
+				true
+			}
+			_ => false,
+		}
+	});
+
+	let done = CellRendererToggle::new();
+	done.connect_toggled(clone!(@weak store, @weak application => move |_, path| {
+		if let Some(step_index) = store.get_iter(&path).and_then(|iter| step_at(&store, &iter)) {
+			edit_task(&application, id, "Tick step", |task| task.toggle_step(step_index));
+		}
+	}));
+	let column = TreeViewColumn::new();
+	column.pack_start(&done, false);
+	column.add_attribute(&done, "active", 0);
+	view.append_column(&column);
+
+	let desc = CellRendererText::new();
+	let column = TreeViewColumn::new();
+	column.pack_start(&desc, true);
+	column.add_attribute(&desc, "text", 1);
+	view.append_column(&column);
+
+	// GTK can move the rows around by itself, but the steps have to be moved in the task, where it can be undone
+	// So the dragged step is moved in the task, and the list is filled again, instead of GTK moving the rows
+	// The dragging is set up on the list as a plain widget, since this version of gtk-rs can't set it up for the rows
+	let targets = [TargetEntry::new("step", TargetFlags::SAME_WIDGET, 0)];
+	view.drag_source_set(
+		gdk::ModifierType::BUTTON1_MASK,
+		&targets,
+		gdk::DragAction::MOVE,
+	);
+	view.drag_dest_set(DestDefaults::ALL, &targets, gdk::DragAction::MOVE);
+	// The dragged step is the selected one, so what's sent along doesn't matter, but without anything the drop fails
+	view.connect_drag_data_get(|_, _, data, _, _| {
+		data.set(&data.get_target(), 8, b"step");
+	});
+	view.connect_drag_data_received(
+		clone!(@weak store, @weak application => move |view, _, x, y, _, _, _| {
+			let dragged = selected_step(view);
+			let dropped = view.get_dest_row_at_pos(x, y).and_then(|(path, position)| {
+				let target = step_at(&store, &store.get_iter(&path?)?)?;
+				Some(match position {
+					TreeViewDropPosition::Before => StepPlace::Before(target),
+					TreeViewDropPosition::After => StepPlace::After(target),
+					_ => StepPlace::Into(target),
+				})
+			});
+			if let (Some(step_index), Some(place)) = (dragged, dropped) {
+				move_step(view, id, &application, "Move step", |task| task.move_step(step_index, place));
+			}
+			// GTK finishes the drag by itself (that's part of `DestDefaults::ALL`)
+			glib::signal::signal_stop_emission_by_name(view, "drag-data-received");
+		}),
+	);
+
+	view
+}
+
+/// Returns which step the row at `iter` in a list of steps is.
+fn step_at(model: &impl IsA<TreeModel>, iter: &TreeIter) -> Option<usize> {
+	let index = model.get_value(iter, 2).get::<u32>().ok()??;
+	Some(index as usize)
+}
+
+/// Returns which step is selected in a list of steps, if any.
+fn selected_step(view: &TreeView) -> Option<usize> {
+	let (model, iter) = view.get_selection().get_selected()?;
+	step_at(&model, &iter)
+}
+
+/// Moves a step with `how`, if it can be moved that way, and selects it again wherever it ends up.
+fn move_step(
+	view: &TreeView,
+	id: u64,
+	application: &RefCell<Application>,
+	label: &str,
+	how: impl FnOnce(&mut Task) -> Result<usize, ()>,
+) {
+	let (course_index, task_index) = match locate_task(application, id) {
+		Some(found) => found,
+		None => return,
+	};
+	// Trying it on a copy first, so a move that can't happen doesn't leave anything to undo
+	let mut task = application.borrow().courses[course_index].tasks[task_index].clone();
+	let step_index = match how(&mut task) {
+		Ok(step_index) => step_index,
+		Err(()) => {
+			message_dialog("That step can't go there.");
+			return;
+		}
+	};
+	application
+		.borrow_mut()
+		.edit_task(course_index, task_index, label, |old| *old = task);
+
+	// The list was just filled again, so the step's row has to be found
+	if let Some(model) = view.get_model() {
+		model.foreach(|model, _, iter| {
+			if step_at(model, iter) == Some(step_index) {
+				view.get_selection().select_iter(iter);
+				true
+			} else {
+				false
+			}
+		});
+	}
+}
+
+/// A small dialog for editing a step, with how much it counts for and when it has to be done by.
+pub(super) fn step_dialog(
+	id: u64,
 	step_index: usize,
 	gui_app: &gtk::Application,
 	application: Rc<RefCell<Application>>,
 ) {
-	let course = &mut application.borrow_mut().courses[course_index];
-	let task = &mut course.tasks[task_index];
-	let step = &mut task.steps[step_index];
+	let locale = application.borrow().locale.clone();
+	let today = application.borrow().today();
+	let (course_index, task_index) = match locate_task(&application, id) {
+		Some(found) => found,
+		None => return,
+	};
+	let step =
+		application.borrow().courses[course_index].tasks[task_index].steps[step_index].clone();
+	// Steps don't have IDs, so it's closed once the step at `step_index` is a different one
+	// Ticking or moving a step doesn't change what it's called, so that's how it's told apart
+	let shown = Rc::new(RefCell::new(step.desc.clone()));
+
+	let text_box = text_with_default(&step.desc, None);
+	let f1 = frame_with_text("Step", &text_box);
+
+	let weight = SpinButton::new_with_range(0.0, 100.0, 1.0);
+	weight.set_value(step.weight() as f64);
+	let f2 = frame_with_text("Counts For (how many normal steps it's worth)", &weight);
 
-	let text_box = text_with_default(&step.1, None);
+	// Steps don't need their own due date, so the picker only counts while the box is ticked
+	let has_due = CheckButton::new_with_label("Has to be done by its own date");
+	has_due.set_active(step.due.is_some());
+	let due: Picked<Date> = Rc::new(Cell::new(step.due));
+	let problem = Label::new(None);
 	let button_save = Button::new_with_label("Save");
-	button_save.connect_clicked(clone!(@weak text_box, @weak application => move |_| {
-		let course = &mut application.borrow_mut().courses[course_index];
-		let task = &mut course.tasks[task_index];
-		task.steps[step_index].1 = get_string_from_text!(text_box);
+	let check = clone!(@weak problem, @weak button_save, @weak has_due, @strong due => move || {
+		let text = match due.get() {
+			None if has_due.get_active() => Some("Due date invalid. Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'."),
+			_ => None,
+		};
+		show_problem(&problem, &button_save, text);
+	});
+	let picker = date_picker(&locale, today, &due, check.clone());
+	picker.set_sensitive(step.due.is_some());
+	has_due.connect_toggled(clone!(@weak picker => move |has_due| {
+		picker.set_sensitive(has_due.get_active());
+		check();
 	}));
+	let due_box = Box::new(Orientation::Vertical, 3);
+	due_box.pack_start(&has_due, false, false, 0);
+	due_box.pack_start(&picker, false, false, 0);
+	let f3 = frame_with_text("Step Due Date", &due_box);
+
+	button_save.connect_clicked(
+		clone!(@weak text_box, @weak weight, @weak has_due, @weak application, @strong shown => move |_| {
+			let weight = weight.get_value_as_int() as u32;
+			let due = if has_due.get_active() { due.get() } else { None };
+			let desc = get_string_from_text!(text_box);
+			*shown.borrow_mut() = desc.clone();
+			edit_task(&application, id, "Edit step", |task| {
+				let step = &mut task.steps[step_index];
+				step.desc = desc;
+				// A weight of 1 is the same as not having one
+				step.weight = if weight == 1 { None } else { Some(weight) };
+				step.due = due;
+			});
+		}),
+	);
 
 	let grid = Grid::new();
-	grid.attach(&text_box, 0, 0, 1, 1);
-	grid.attach(&button_save, 0, 1, 1, 1);
+	grid.attach(&f1, 0, 0, 1, 1);
+	grid.attach(&f2, 0, 1, 1, 1);
+	grid.attach(&f3, 0, 2, 1, 1);
+	grid.attach(&problem, 0, 3, 1, 1);
+	grid.attach(&button_save, 0, 4, 1, 1);
 
 	let window = ApplicationWindow::new(gui_app);
+	close_when_gone(
+		&window,
+		&application,
+		move |application, _| match application.find_task(id) {
+			Some((course_index, task_index)) => {
+				let steps = &application.courses[course_index].tasks[task_index].steps;
+				matches!(steps.get(step_index), Some(step) if step.desc == *shown.borrow())
+			}
+			None => false,
+		},
+	);
 	window.add(&grid);
 	window.show_all();
 }
@@ -343,50 +1314,160 @@ pub(super) fn time_dialog(
 	application: Rc<RefCell<Application>>,
 	gui_app: &gtk::Application,
 ) {
-	let course = &application.borrow().courses[course_index];
-	let (day, start, end) = course.times[time_index];
+	let (day, start, end) = application.borrow().courses[course_index].times[time_index];
+	// Times don't have IDs, so it's closed once the time at `time_index` isn't this one anymore
+	let shown = Rc::new(Cell::new((day, start, end)));
 
-	let t1 = text_with_default(day.as_str(), None);
-	t1.set_left_margin(3);
-	t1.set_right_margin(3);
+	let day: Picked<Day> = Rc::new(Cell::new(Some(day)));
+	let start: Picked<Time> = Rc::new(Cell::new(Some(start)));
+	let end: Picked<Time> = Rc::new(Cell::new(Some(end)));
+	let problem = Label::new(None);
+	let button_save = Button::new_with_label("Save");
 
-	let t2 = text_with_default(&start.to_string(), None);
-	t2.set_left_margin(3);
-	t2.set_right_margin(3);
+	let check = clone!(@weak problem, @weak button_save, @strong day, @strong start, @strong end => move || {
+		let text = match (day.get(), start.get(), end.get()) {
+			(Some(_), Some(start), Some(end)) if start > end => Some("Start time greater than end time!"),
+			(Some(_), Some(_), Some(_)) => None,
+			_ => Some("Pick a day, and the times it starts and ends."),
+		};
+		show_problem(&problem, &button_save, text);
+	});
 
-	let t3 = text_with_default(&end.to_string(), None);
-	t3.set_left_margin(3);
-	t3.set_right_margin(3);
+	let f1 = frame_with_text("Day", &day_picker(&day, check.clone()));
+	let f2 = frame_with_text("From", &time_picker(&start, check.clone()));
+	let f3 = frame_with_text("To", &time_picker(&end, check));
 
+	button_save.connect_clicked(clone!(@weak application, @strong shown => move |_| {
+		if let (Some(day), Some(start), Some(end)) = (day.get(), start.get(), end.get()) {
+			shown.set((day, start, end));
+			application.borrow_mut().edit_course(course_index, "Edit time", |course| {
+				course.times[time_index] = (day, start, end);
+			});
+		}
+	}));
+
+	let grid = Grid::new();
+	grid.attach(&f1, 0, 0, 1, 1);
+	grid.attach(&f2, 1, 0, 1, 1);
+	grid.attach(&f3, 2, 0, 1, 1);
+
+	grid.attach(&problem, 0, 1, 3, 1);
+	grid.attach(&button_save, 0, 2, 3, 1);
+
+	let window = ApplicationWindow::new(gui_app);
+	close_with_course(&window, &application, course_index, move |course| {
+		course.times.get(time_index) == Some(&shown.get())
+	});
+	window.add(&grid);
+	window.show_all();
+}
+
+/// A small dialog for adding time spent on a task by hand, like time spent before it was in the scheduler.
+pub(super) fn log_time_dialog(
+	id: u64,
+	gui_app: &gtk::Application,
+	application: Rc<RefCell<Application>>,
+) {
+	let locale = application.borrow().locale.clone();
+	let today = application.borrow().today();
+
+	let date: Picked<Date> = Rc::new(Cell::new(Some(today)));
+	let minutes: Rc<Cell<Option<u32>>> = Rc::new(Cell::new(None));
+	let problem = Label::new(None);
 	let button_save = Button::new_with_label("Save");
-	button_save.connect_clicked(
-		clone!(@weak t1, @weak t2, @weak t3, @weak application => move |_| {
-			let course = &mut application.borrow_mut().courses[course_index];
-
-			if let (Ok(day), Ok(start), Ok(end)) = (
-				Day::try_from(get_string_from_text!(t1)),
-				Time::try_from(get_string_from_text!(t2)),
-				Time::try_from(get_string_from_text!(t3)),
-			) {
-				if start > end {
-					message_dialog("Start time greater than end time!");
-				} else {
-					course.times[time_index] = (day, start, end);
-				}
-			} else {
-				message_dialog("Time/Day entry invalid. Recheck entry, and only use numbers in format: 'HH-MM'.");
-			}
-		}),
+	let check = clone!(@weak problem, @weak button_save, @strong date, @strong minutes => move || {
+		let text = match (date.get(), minutes.get()) {
+			(None, _) => Some("Date invalid. Try 'YYYY-MM-DD', 'yesterday', 'friday' or 'DD/MM'."),
+			(_, None) => Some("Time invalid. Try '45m', '1h 30m' or '1:30'."),
+			_ => None,
+		};
+		show_problem(&problem, &button_save, text);
+	});
+
+	let entry = Entry::new();
+	entry.connect_changed(clone!(@strong minutes, @strong check => move |entry| {
+		let text = entry.get_text().map(|text| text.to_string()).unwrap_or_default();
+		minutes.set(parse_minutes(&text).ok());
+		check();
+	}));
+	let f1 = frame_with_text("Time Spent (like '1h 30m')", &entry);
+	let f2 = frame_with_text(
+		"Spent On",
+		&date_picker(&locale, today, &date, check.clone()),
 	);
+	check();
+
+	let window = ApplicationWindow::new(gui_app);
+	button_save.connect_clicked(clone!(@weak window, @weak application => move |_| {
+		let task = locate_task(&application, id);
+		if let (Some(date), Some(minutes), Some((course_index, task_index))) = (date.get(), minutes.get(), task) {
+			application.borrow_mut().log_time(course_index, task_index, date, minutes);
+			window.close();
+		}
+	}));
 
 	let grid = Grid::new();
-	grid.attach(&t1, 0, 0, 1, 1);
-	grid.attach(&t2, 1, 0, 1, 1);
-	grid.attach(&t3, 2, 0, 1, 1);
+	grid.attach(&f1, 0, 0, 1, 1);
+	grid.attach(&f2, 0, 1, 1, 1);
+	grid.attach(&problem, 0, 2, 1, 1);
+	grid.attach(&button_save, 0, 3, 1, 1);
+
+	close_when_gone(&window, &application, move |application, _| {
+		application.find_task(id).is_some()
+	});
+
+	window.add(&grid);
+	window.show_all();
+}
+
+/// Pops up a window to give every task at (course index, task index) in `tasks` a new due date.
+/// Each task remembers when it was first due, so snoozing doesn't hide how late it is.
+pub(super) fn snooze_dialog(
+	tasks: Vec<(usize, usize)>,
+	gui_app: &gtk::Application,
+	application: Rc<RefCell<Application>>,
+) {
+	let locale = application.borrow().locale.clone();
+	let today = application.borrow().today();
+	// Undoing something while this is open can move the tasks around, so they're found by their IDs when it's saved
+	let ids: Vec<u64> = tasks
+		.iter()
+		.map(|&(c, t)| application.borrow().courses[c].tasks[t].id)
+		.collect();
 
-	grid.attach(&button_save, 0, 1, 3, 1);
+	// It starts a week from today, which is as good a guess as any
+	let due: Picked<Date> = Rc::new(Cell::new(today.offset(7).ok()));
+	let problem = Label::new(None);
+	let button_save = Button::new_with_label("Save");
+	let check = clone!(@weak problem, @weak button_save, @strong due => move || {
+		let text = match due.get() {
+			Some(_) => None,
+			None => Some("Due date invalid. Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'."),
+		};
+		show_problem(&problem, &button_save, text);
+	});
+	let f1 = frame_with_text(
+		&format!("New Due Date for {} Task(s)", tasks.len()),
+		&date_picker(&locale, today, &due, check),
+	);
 
 	let window = ApplicationWindow::new(gui_app);
+	button_save.connect_clicked(clone!(@weak window, @weak application => move |_| {
+		if let Some(due) = due.get() {
+			let tasks: Vec<(usize, usize)> = ids
+				.iter()
+				.filter_map(|&id| locate_task(&application, id))
+				.collect();
+			application.borrow_mut().snooze_tasks(&tasks, due);
+			window.close();
+		}
+	}));
+
+	let grid = Grid::new();
+	grid.attach(&f1, 0, 0, 1, 1);
+	grid.attach(&problem, 0, 1, 1, 1);
+	grid.attach(&button_save, 0, 2, 1, 1);
+
 	window.add(&grid);
 	window.show_all();
 }
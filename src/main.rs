@@ -11,14 +11,26 @@ use crate::application::Application;
 fn main() {
 	let location = "scheduler.bin";
 
-	let application = Application::load_or_default(location);
+	let application = match Application::load_or_default(location) {
+		Ok(application) => application,
+		Err(message) => {
+			eprintln!("{}", message);
+			std::process::exit(1);
+		}
+	};
 
-	gui_init(application);
-	// TODO: cli_init(application);
+	// If there's anything after the program's name, like `scheduler task add ...`, it's for the CLI
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if args.is_empty() {
+		gui_init(application, location);
+	} else {
+		cli_init(application, location, &args);
+	}
 }
 
 /// Initializer for our GUI app.
-pub fn gui_init(application: Application) {
+/// `location` is where the application is saved, which happens automatically a few seconds after each change.
+pub fn gui_init(application: Application, location: &'static str) {
 	// we HAVE to surround Application in an Rc and RefCell for our GUI to work
 	// Rust's ownership system makes GUI development difficult
 	// Rc means "Reference Counted", allowing us to create as many references to application as we want
@@ -30,23 +42,31 @@ pub fn gui_init(application: Application) {
 	// The code below initializes the GUI application (GTK) and launches it
 	// See the `gui` module (directory) for all the GUI-related components
 	// Otherwise, see application.rs to understand the backend of the app
-	let gui_app = gtk::Application::new(None, Default::default()).unwrap();
+	// Desktop notifications need the app to have an ID
+	let gui_app = gtk::Application::new(Some("com.github.leguu.scheduler"), Default::default()).unwrap();
 
 	gui_app.connect_activate(move |app| {
-		gui::build_ui(&app, application.clone());
+		gui::build_ui(&app, application.clone(), location);
 	});
 
 	gui_app.run(&[]);
 }
 
 /// Initializer for our CLI app.
-pub fn cli_init(_application: Application) {
-	unimplemented!()
+/// See the `cli` module for the commands.
+pub fn cli_init(application: Application, location: &str, args: &[String]) {
+	if let Err(message) = cli::run(application, location, args) {
+		eprintln!("{}", message);
+		std::process::exit(1);
+	}
 }
 
 // Making these modules accessible
 // Rust has a weird module system
 // Rust is just weird in general
 pub mod application;
+pub mod cli;
 pub mod clock;
 pub mod gui;
+pub mod server;
+pub mod tui;
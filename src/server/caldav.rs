@@ -0,0 +1,613 @@
+use super::*;
+use crate::application::Task;
+
+// CalDAV (RFC 4791) is how calendar apps on phones and computers sync with a server
+// It's WebDAV (files over HTTP, with XML to describe them) where the files are iCalendar events and to-dos
+//
+// There are two calendars (WebDAV calls them collections):
+// /calendars/timetable/    Every lesson time, as an event that repeats weekly. Read-only.
+// /calendars/tasks/        Every task, as a to-do. Ticking one off in a calendar app ticks off every step.
+//
+// Calendar apps find these by asking the root (/) where the calendars are, so "http://127.0.0.1:5232/" is all they need
+// There's no login, and the server only listens on this computer, so any username and password will do
+//
+// Calendar apps remember everything by its file name, so the names can't change when something else is removed
+// Tasks are named by their ID, like "task-1234.ics", and lessons by what they are (see `Server::lesson_names`)
+
+/// The name of the calendar with the lessons in it.
+const TIMETABLE: &str = "timetable";
+/// The name of the calendar with the tasks in it.
+const TASKS: &str = "tasks";
+
+const CALENDAR_TYPE: &str = "text/calendar; charset=utf-8";
+const XML_TYPE: &str = "application/xml; charset=utf-8";
+
+/// Escapes the characters that mean something in XML.
+fn xml_escape(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+/// One <response> in a multistatus, for `href` with the properties in `props`.
+fn prop_response(href: &str, props: &str) -> String {
+	format!(
+		"<D:response><D:href>{}</D:href><D:propstat><D:prop>{}</D:prop>\
+		<D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+		xml_escape(href),
+		props
+	)
+}
+
+/// A <response> saying there's nothing at `href`.
+fn missing_response(href: &str) -> String {
+	format!(
+		"<D:response><D:href>{}</D:href><D:status>HTTP/1.1 404 Not Found</D:status></D:response>",
+		xml_escape(href)
+	)
+}
+
+/// Puts responses together into a multistatus, which is how WebDAV answers about several things at once.
+fn multistatus(responses: &[String]) -> Response {
+	let body = format!(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+		<D:multistatus xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\" xmlns:CS=\"http://calendarserver.org/ns/\">\
+		{}</D:multistatus>",
+		responses.concat()
+	);
+	Response::text(207, XML_TYPE, body)
+}
+
+/// Finds every <href> in an XML request, which is how calendar apps ask for particular files.
+fn hrefs(body: &str) -> Vec<String> {
+	// Both <D:href> and </D:href> end in "href>", but only the text after the opening one isn't blank
+	body.match_indices("href>")
+		.filter_map(|(index, _)| {
+			let rest = &body[index + 5..];
+			let text = rest[..rest.find('<').unwrap_or(rest.len())].trim();
+			if text.is_empty() {
+				None
+			} else {
+				Some(text.to_string())
+			}
+		})
+		.collect()
+}
+
+/// Reads a file name like "task-1234.ics" into the task's ID.
+fn task_id(name: &str) -> Option<u64> {
+	if !name.starts_with("task-") || !name.ends_with(".ics") {
+		return None;
+	}
+	name["task-".len()..name.len() - ".ics".len()].parse().ok()
+}
+
+impl Server {
+	/// Does whatever a CalDAV `request` asks for, and returns the answer.
+	pub(super) fn handle_caldav(&mut self, request: &Request) -> Response {
+		let segments = request.segments();
+		// Anything other than "0" is treated as "1", since we're never more than one level deep anyway
+		let deep = request.header("depth") != Some("0");
+
+		match (request.method.as_str(), segments.as_slice()) {
+			("OPTIONS", _) => Response::text(200, "text/plain", "")
+				.header("DAV", "1, calendar-access")
+				.header("Allow", "OPTIONS, GET, PUT, PROPFIND, REPORT"),
+			(_, [".well-known", "caldav"]) => {
+				Response::text(301, "text/plain", "").header("Location", "/")
+			}
+
+			("PROPFIND", []) => multistatus(&[prop_response(
+				"/",
+				"<D:resourcetype><D:collection/></D:resourcetype>\
+				<D:displayname>Scheduler</D:displayname>\
+				<D:current-user-principal><D:href>/</D:href></D:current-user-principal>\
+				<C:calendar-home-set><D:href>/calendars/</D:href></C:calendar-home-set>",
+			)]),
+			("PROPFIND", ["calendars"]) => {
+				let mut responses = vec![prop_response(
+					"/calendars/",
+					"<D:resourcetype><D:collection/></D:resourcetype>",
+				)];
+				if deep {
+					responses.push(self.collection_response(TIMETABLE));
+					responses.push(self.collection_response(TASKS));
+				}
+				multistatus(&responses)
+			}
+			("PROPFIND", ["calendars", collection]) => match self.items(collection) {
+				Some(items) => {
+					let mut responses = vec![self.collection_response(collection)];
+					if deep {
+						for (name, text) in &items {
+							responses.push(item_response(collection, name, text, false));
+						}
+					}
+					multistatus(&responses)
+				}
+				None => not_found(),
+			},
+			("PROPFIND", ["calendars", collection, name]) => match self.item(collection, name) {
+				Some(text) => multistatus(&[item_response(collection, name, &text, false)]),
+				None => not_found(),
+			},
+
+			("REPORT", ["calendars", collection]) => match self.items(collection) {
+				Some(items) => self.report(collection, &items, &request.body),
+				None => not_found(),
+			},
+
+			// The whole calendar at once, for apps that can only subscribe to a link
+			("GET", ["calendars", collection]) => match self.items(collection) {
+				Some(items) => {
+					// Each zone is only described once, however many lessons are in it
+					let mut zones = Vec::new();
+					if *collection == TIMETABLE {
+						for (_, c, _) in self.lesson_names() {
+							let zone = ical::lesson_zone(&self.application, c);
+							if !zones.contains(&zone) {
+								zones.push(zone);
+							}
+						}
+					}
+					let components: Vec<String> = zones
+						.into_iter()
+						.flatten()
+						.map(ical::timezone)
+						.chain(
+							items
+								.iter()
+								.map(|(name, _)| self.component(collection, name).unwrap()),
+						)
+						.collect();
+					Response::text(200, CALENDAR_TYPE, ical::calendar(&components))
+				}
+				None => not_found(),
+			},
+			("GET", ["calendars", collection, name]) => match self.item(collection, name) {
+				Some(text) => Response::text(200, CALENDAR_TYPE, text.clone())
+					.header("ETag", ical::etag(&text)),
+				None => not_found(),
+			},
+
+			("PUT", ["calendars", TASKS, name]) => self.put_task(name, request),
+			("PUT", _) | ("DELETE", _) => Response::text(
+				403,
+				"text/plain",
+				"The timetable can't be changed here, and tasks can only be ticked off.",
+			),
+			_ => not_found(),
+		}
+	}
+
+	/// Returns every lesson time as (file name, course index, time index).
+	/// Courses don't have IDs like tasks do, so a lesson is named after its course's name, its day and its times.
+	/// That way it keeps its name when other courses and lessons are removed, and only gets a new one when it changes.
+	fn lesson_names(&self) -> Vec<(String, usize, usize)> {
+		let mut lessons: Vec<(String, usize, usize)> = Vec::new();
+		for (c, course) in self.application.courses.iter().enumerate() {
+			for (t, (day, start, end)) in course.times.iter().enumerate() {
+				let key = ical::hash(&format!("{}\n{}\n{}\n{}", course.name, day, start, end));
+				// Two courses with the same name at the same time would get the same name, so the later ones are numbered
+				let mut name = format!("lesson-{:016x}.ics", key);
+				let mut number = 2;
+				while lessons.iter().any(|(other, _, _)| *other == name) {
+					name = format!("lesson-{:016x}-{}.ics", key, number);
+					number += 1;
+				}
+				lessons.push((name, c, t));
+			}
+		}
+		lessons
+	}
+
+	/// Returns the (file name, calendar file) of everything in `collection`, or `None` if there's no such collection.
+	fn items(&self, collection: &str) -> Option<Vec<(String, String)>> {
+		let names: Vec<String> = match collection {
+			TIMETABLE => self
+				.lesson_names()
+				.into_iter()
+				.map(|(name, _, _)| name)
+				.collect(),
+			TASKS => self
+				.application
+				.courses
+				.iter()
+				.flat_map(|course| course.tasks.iter())
+				.map(|task| format!("task-{}.ics", task.id))
+				.collect(),
+			_ => return None,
+		};
+
+		Some(
+			names
+				.into_iter()
+				.map(|name| {
+					let text = self.item(collection, &name).unwrap();
+					(name, text)
+				})
+				.collect(),
+		)
+	}
+
+	/// Returns the event or to-do called `name` in `collection`, without the VCALENDAR around it.
+	fn component(&self, collection: &str, name: &str) -> Option<String> {
+		match collection {
+			TIMETABLE => {
+				let (name, c, t) = self
+					.lesson_names()
+					.into_iter()
+					.find(|(other, _, _)| other == name)?;
+				Some(ical::event(
+					&self.application,
+					c,
+					t,
+					name.trim_end_matches(".ics"),
+				))
+			}
+			TASKS => {
+				let (c, t) = self.application.find_task(task_id(name)?)?;
+				Some(ical::todo(&self.application, c, t))
+			}
+			_ => None,
+		}
+	}
+
+	/// Returns the calendar file called `name` in `collection`.
+	fn item(&self, collection: &str, name: &str) -> Option<String> {
+		let component = self.component(collection, name)?;
+		// A lesson in a time zone needs the zone described in the same file
+		let zone = match collection {
+			TIMETABLE => self
+				.lesson_names()
+				.into_iter()
+				.find(|(other, _, _)| other == name)
+				.and_then(|(_, c, _)| ical::lesson_zone(&self.application, c)),
+			_ => None,
+		};
+		let mut components: Vec<String> = zone.into_iter().map(ical::timezone).collect();
+		components.push(component);
+		Some(ical::calendar(&components))
+	}
+
+	/// The properties of a collection, which say it's a calendar and what's in it.
+	fn collection_response(&self, collection: &str) -> String {
+		let (display_name, component, privileges) = if collection == TIMETABLE {
+			(
+				"Timetable",
+				"VEVENT",
+				"<D:privilege><D:read/></D:privilege>",
+			)
+		} else {
+			(
+				"Tasks",
+				"VTODO",
+				"<D:privilege><D:read/></D:privilege><D:privilege><D:write-content/></D:privilege>",
+			)
+		};
+
+		// The ctag changes whenever anything in the calendar does, so apps know when to look for changes
+		let everything: String = self
+			.items(collection)
+			.unwrap_or_default()
+			.into_iter()
+			.map(|(_, text)| text)
+			.collect();
+
+		prop_response(
+			&format!("/calendars/{}/", collection),
+			&format!(
+				"<D:resourcetype><D:collection/><C:calendar/></D:resourcetype>\
+				<D:displayname>{}</D:displayname>\
+				<C:supported-calendar-component-set><C:comp name=\"{}\"/></C:supported-calendar-component-set>\
+				<D:current-user-privilege-set>{}</D:current-user-privilege-set>\
+				<CS:getctag>{}</CS:getctag>",
+				display_name,
+				component,
+				privileges,
+				xml_escape(&ical::etag(&everything))
+			),
+		)
+	}
+
+	/// Answers a REPORT, which asks for the calendar files themselves.
+	/// A "calendar-multiget" names the files it wants, and anything else (a "calendar-query") gets every one.
+	/// Queries can also filter by date, but sending everything is always a correct (if slower) answer.
+	fn report(&self, collection: &str, items: &[(String, String)], body: &str) -> Response {
+		if !body.contains("calendar-multiget") {
+			let responses: Vec<String> = items
+				.iter()
+				.map(|(name, text)| item_response(collection, name, text, true))
+				.collect();
+			return multistatus(&responses);
+		}
+
+		let responses: Vec<String> = hrefs(body)
+			.iter()
+			.map(|href| {
+				let name = href.rsplit('/').next().unwrap_or("");
+				match items.iter().find(|(item, _)| item == name) {
+					Some((name, text)) => item_response(collection, name, text, true),
+					None => missing_response(href),
+				}
+			})
+			.collect();
+		multistatus(&responses)
+	}
+
+	/// Takes a to-do sent back by a calendar app, and ticks off (or un-ticks) the task's steps to match.
+	fn put_task(&mut self, name: &str, request: &Request) -> Response {
+		let current = match self.item(TASKS, name) {
+			Some(current) => current,
+			None => return Response::text(403, "text/plain", "New tasks can't be added here."),
+		};
+		// If-Match stops an app from overwriting a change it hasn't seen yet
+		if let Some(expected) = request.header("if-match") {
+			if expected != "*" && expected != ical::etag(&current) {
+				return Response::text(
+					412,
+					"text/plain",
+					"The task has changed since it was downloaded.",
+				);
+			}
+		}
+		let completed = match ical::parse_completed(&request.body) {
+			Ok(completed) => completed,
+			Err(()) => return Response::text(400, "text/plain", "There's no VTODO in that."),
+		};
+
+		// The task was found by `item` just now, so it's definitely there
+		let (c, t) = self.application.find_task(task_id(name).unwrap()).unwrap();
+		let task: &Task = &self.application.courses[c].tasks[t];
+		// Un-ticking a finished task un-ticks every step, but a half-finished one is left alone
+		if completed != task.is_complete() {
+			let label = format!(
+				"Mark '{}' as {}",
+				task.name,
+				if completed { "done" } else { "not done" }
+			);
+			self.application
+				.edit_task(c, t, label, |task| task.set_complete(completed));
+			self.save();
+		}
+
+		let text = self.item(TASKS, name).unwrap();
+		Response::text(204, "text/plain", "").header("ETag", ical::etag(&text))
+	}
+}
+
+/// The properties of one calendar file, and the file itself if `with_data` is set.
+fn item_response(collection: &str, name: &str, text: &str, with_data: bool) -> String {
+	let mut props = format!(
+		"<D:resourcetype/><D:getetag>{}</D:getetag><D:getcontenttype>{}</D:getcontenttype>",
+		xml_escape(&ical::etag(text)),
+		CALENDAR_TYPE
+	);
+	if with_data {
+		props.push_str(&format!(
+			"<C:calendar-data>{}</C:calendar-data>",
+			xml_escape(text)
+		));
+	}
+	prop_response(&format!("/calendars/{}/{}", collection, name), &props)
+}
+
+fn not_found() -> Response {
+	Response::text(404, "text/plain", "Not found.")
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::application::Course;
+	use crate::clock::*;
+	use std::convert::TryFrom;
+
+	fn server() -> Server {
+		let time = |hour| Time::new(hour, 0).unwrap();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_time(Day::Wednesday, time(9), time(10));
+		math.add_task("Essay", "", Date::new(2020, 1, 8).unwrap());
+
+		let mut application = Application::default();
+		application.add_course(math);
+		// 2020-01-15 is a Wednesday, so that lesson is skipped
+		application.add_holiday(
+			Date::new(2020, 1, 13).unwrap(),
+			Date::new(2020, 1, 17).unwrap(),
+		);
+
+		let location = std::env::temp_dir().join(format!(
+			"scheduler-caldav-test-{:?}.bin",
+			std::thread::current().id()
+		));
+		Server::new(application, location.to_str().unwrap())
+	}
+
+	/// The path of the `task_index`th task of the first course.
+	fn task_path(server: &Server, task_index: usize) -> String {
+		format!(
+			"/calendars/tasks/task-{}.ics",
+			server.application.courses[0].tasks[task_index].id
+		)
+	}
+
+	/// The path of the `index`th lesson time in the timetable.
+	fn lesson_path(server: &Server, index: usize) -> String {
+		format!("/calendars/timetable/{}", server.lesson_names()[index].0)
+	}
+
+	fn request(
+		server: &mut Server,
+		method: &str,
+		path: &str,
+		headers: &[(&str, &str)],
+		body: &str,
+	) -> Response {
+		server.handle_caldav(&Request {
+			method: method.to_string(),
+			path: path.to_string(),
+			body: body.to_string(),
+			headers: headers
+				.iter()
+				.map(|(name, value)| (name.to_string(), value.to_string()))
+				.collect(),
+		})
+	}
+
+	#[test]
+	fn listing() {
+		let mut server = server();
+
+		let response = request(
+			&mut server,
+			"PROPFIND",
+			"/calendars/tasks/",
+			&[("depth", "1")],
+			"",
+		);
+		assert_eq!(response.status, 207);
+		let href = format!("<D:href>{}</D:href>", task_path(&server, 0));
+		assert!(response.body.contains(&href));
+		assert!(response.body.contains("<C:comp name=\"VTODO\"/>"));
+
+		let response = request(
+			&mut server,
+			"PROPFIND",
+			"/calendars/tasks/",
+			&[("depth", "0")],
+			"",
+		);
+		assert!(!response.body.contains(&href));
+	}
+
+	#[test]
+	fn stable_names() {
+		let mut server = server();
+		let lesson = lesson_path(&server, 0);
+		let mut science = Course::new("Science", "Curie", "L2");
+		science.add_time(
+			Day::Monday,
+			Time::new(9, 0).unwrap(),
+			Time::new(10, 0).unwrap(),
+		);
+		science.add_task("Report", "", Date::new(2020, 1, 9).unwrap());
+		let report = format!("/calendars/tasks/task-{}.ics", science.tasks[0].id);
+		server.application.courses.insert(0, science);
+
+		// Adding a course before Math doesn't change what Math's lesson and Essay are called
+		assert_eq!(lesson_path(&server, 1), lesson);
+		let body = request(&mut server, "GET", &lesson, &[], "").body;
+		assert!(body.contains("SUMMARY:Math"));
+		let uid = format!(
+			"UID:{}@scheduler",
+			lesson.rsplit('/').next().unwrap().trim_end_matches(".ics")
+		);
+		assert!(body.contains(&uid));
+
+		// And removing a task doesn't change the ones after it
+		server.application.courses[0]
+			.tasks
+			.insert(0, Task::new("Quiz", "", Date::new(2020, 1, 9).unwrap()));
+		let response = request(&mut server, "GET", &report, &[], "");
+		assert!(response.body.contains("SUMMARY:Report"));
+		server.application.courses[0].tasks.remove(0);
+		let response = request(&mut server, "GET", &report, &[], "");
+		assert!(response.body.contains("SUMMARY:Report"));
+	}
+
+	#[test]
+	fn time_zones() {
+		let mut server = server();
+		server.application.timezone = Some(Zone::try_from("Europe/London".to_string()).unwrap());
+		let lesson = lesson_path(&server, 0);
+
+		// The zone is described in the same file as the lesson, and only once for the whole calendar
+		let body = request(&mut server, "GET", &lesson, &[], "").body;
+		assert!(body.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/London\r\n"));
+		assert!(body.contains("DTSTART;TZID=Europe/London:20200101T090000\r\n"));
+		let body = request(&mut server, "GET", "/calendars/timetable", &[], "").body;
+		assert_eq!(body.matches("BEGIN:VTIMEZONE").count(), 1);
+	}
+
+	#[test]
+	fn events() {
+		let mut server = server();
+
+		let lesson = lesson_path(&server, 0);
+		let response = request(&mut server, "GET", &lesson, &[], "");
+		assert_eq!(response.status, 200);
+		// The first Wednesday after 2020-01-01 is the 1st itself
+		assert!(response.body.contains("DTSTART:20200101T090000\r\n"));
+		assert!(response.body.contains("RRULE:FREQ=WEEKLY\r\n"));
+		assert!(response.body.contains("EXDATE:20200115T090000\r\n"));
+		assert!(response.body.contains("SUMMARY:Math\r\n"));
+		// Without a time zone, there's nothing to describe
+		assert!(!response.body.contains("VTIMEZONE"));
+
+		assert_eq!(
+			request(
+				&mut server,
+				"GET",
+				"/calendars/timetable/lesson-0.ics",
+				&[],
+				""
+			)
+			.status,
+			404
+		);
+		assert_eq!(request(&mut server, "PUT", &lesson, &[], "").status, 403);
+	}
+
+	#[test]
+	fn multiget() {
+		let mut server = server();
+		let body = format!(
+			"<C:calendar-multiget xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+			<D:prop><D:getetag/><C:calendar-data/></D:prop>\
+			<D:href>{}</D:href>\
+			<D:href>/calendars/tasks/task-0.ics</D:href>\
+			</C:calendar-multiget>",
+			task_path(&server, 0)
+		);
+
+		let response = request(&mut server, "REPORT", "/calendars/tasks/", &[], &body);
+		assert_eq!(response.status, 207);
+		assert!(response.body.contains("SUMMARY:Essay"));
+		assert!(response.body.contains("404 Not Found"));
+	}
+
+	#[test]
+	fn ticking_off() {
+		let mut server = server();
+		let path = &task_path(&server, 0);
+		let completed = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:task-0@scheduler\r\nSTATUS:COMPLETED\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+		// An old ETag means the app hasn't seen the latest version
+		let response = request(
+			&mut server,
+			"PUT",
+			path,
+			&[("if-match", "\"old\"")],
+			completed,
+		);
+		assert_eq!(response.status, 412);
+		assert!(!server.application.courses[0].tasks[0].is_complete());
+
+		let response = request(&mut server, "PUT", path, &[], completed);
+		assert_eq!(response.status, 204);
+		assert!(server.application.courses[0].tasks[0].is_complete());
+		assert!(request(&mut server, "GET", path, &[], "")
+			.body
+			.contains("STATUS:COMPLETED"));
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+}
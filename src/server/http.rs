@@ -0,0 +1,265 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+// This is about the smallest HTTP/1.1 we can get away with
+// Every connection gets one request and one response, and then it's closed
+// That's slower than keeping connections open, but it's only ever going to be used on this computer anyway
+
+/// Requests with bodies bigger than this are refused, so nobody can make us run out of memory.
+const MAX_BODY: usize = 1024 * 1024;
+/// The same goes for everything before the body, which is the first line and the headers.
+const MAX_HEADERS: u64 = 64 * 1024;
+
+/// An HTTP request, with only the parts we care about.
+pub(super) struct Request {
+	/// Like "GET" or "POST".
+	pub method: String,
+	/// Like "/courses/0/tasks", without anything after a '?'.
+	pub path: String,
+	pub body: String,
+	/// Every header as (name, value), with the names in lowercase.
+	pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+	/// Reads a request from `stream`, returning an error message if it isn't valid HTTP.
+	pub fn read<R: Read>(stream: &mut R) -> Result<Self, String> {
+		// Nothing past `MAX_HEADERS` can be read until we know how long the body is
+		// Otherwise a header line that never ends would keep filling up memory
+		let mut reader = BufReader::new(stream.take(MAX_HEADERS));
+
+		// The first line looks like "GET /courses HTTP/1.1"
+		let line = read_line(&mut reader)?;
+		let mut parts = line.split_whitespace();
+		let method = parts.next().ok_or("Empty request")?.to_uppercase();
+		let target = parts.next().ok_or("No path in request")?;
+		let path = target.split('?').next().unwrap_or("").to_string();
+
+		// Then come the headers, one per line, until an empty line
+		let mut length = 0;
+		let mut headers = Vec::new();
+		loop {
+			let line = read_line(&mut reader)?;
+			let line = line.trim();
+			if line.is_empty() {
+				break;
+			}
+			if let Some(colon) = line.find(':') {
+				let (name, value) = (
+					line[..colon].trim().to_lowercase(),
+					line[colon + 1..].trim(),
+				);
+				if name == "content-length" {
+					length = value.parse().map_err(|_| "Bad Content-Length")?;
+				}
+				headers.push((name, value.to_string()));
+			}
+		}
+
+		if length > MAX_BODY {
+			return Err("Request body too large".to_string());
+		}
+		// Some of the body might be read already, so this only lets through as much as is left
+		reader.get_mut().set_limit(length as u64);
+		let mut body = vec![0; length];
+		reader
+			.read_exact(&mut body)
+			.map_err(|error| error.to_string())?;
+		let body = String::from_utf8(body).map_err(|_| "Request body isn't UTF-8")?;
+
+		Ok(Self {
+			method,
+			path,
+			body,
+			headers,
+		})
+	}
+
+	/// Returns the value of the header called `name` (in lowercase), if it was sent.
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|(header, _)| header == name)
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// Returns whether the request was sent to this computer on `port`, going by its Host header.
+	/// A website can make a name of its own point at 127.0.0.1 (DNS rebinding), and then the browser thinks the
+	/// server is part of that website, so Origin doesn't help. The Host header still has the website's name in it.
+	pub fn is_for(&self, port: u16) -> bool {
+		match self.header("host") {
+			Some(host) => ["127.0.0.1", "localhost"]
+				.iter()
+				.any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, port))),
+			None => false,
+		}
+	}
+
+	/// Splits the path into its parts, like ["courses", "0", "tasks"].
+	pub fn segments(&self) -> Vec<&str> {
+		self.path
+			.split('/')
+			.filter(|segment| !segment.is_empty())
+			.collect()
+	}
+}
+
+/// Reads one line of a request, which has to end before the headers get too long.
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, String> {
+	let mut line = String::new();
+	reader
+		.read_line(&mut line)
+		.map_err(|error| error.to_string())?;
+	// A line without an end means we ran out, either because the request stopped or it was too long
+	if !line.ends_with('\n') {
+		return Err("Request headers too large, or cut off".to_string());
+	}
+	Ok(line)
+}
+
+/// An HTTP response. The JSON API always sends JSON, but CalDAV sends XML and calendars too.
+pub(super) struct Response {
+	pub status: u16,
+	pub content_type: &'static str,
+	/// Any headers other than the ones every response has.
+	pub headers: Vec<(&'static str, String)>,
+	pub body: String,
+}
+
+impl Response {
+	/// A successful response, with `value` as the body.
+	pub fn ok<T: serde::Serialize>(value: &T) -> Self {
+		Self::with_status(200, value)
+	}
+
+	/// A response with `value` as the JSON body.
+	pub fn with_status<T: serde::Serialize>(status: u16, value: &T) -> Self {
+		// Everything we send can be turned into JSON, so unwrap() is fine
+		Self::text(
+			status,
+			"application/json",
+			serde_json::to_string_pretty(value).unwrap(),
+		)
+	}
+
+	/// A response with any kind of body.
+	pub fn text<T: Into<String>>(status: u16, content_type: &'static str, body: T) -> Self {
+		Self {
+			status,
+			content_type,
+			headers: Vec::new(),
+			body: body.into(),
+		}
+	}
+
+	/// Adds a header to the response.
+	pub fn header<T: Into<String>>(mut self, name: &'static str, value: T) -> Self {
+		self.headers.push((name, value.into()));
+		self
+	}
+
+	/// An error response, with the body `{"error": message}`.
+	pub fn error<T: Into<String>>(status: u16, message: T) -> Self {
+		Self::with_status(status, &serde_json::json!({ "error": message.into() }))
+	}
+
+	fn reason(&self) -> &'static str {
+		match self.status {
+			200 => "OK",
+			201 => "Created",
+			204 => "No Content",
+			207 => "Multi-Status",
+			301 => "Moved Permanently",
+			400 => "Bad Request",
+			403 => "Forbidden",
+			404 => "Not Found",
+			405 => "Method Not Allowed",
+			412 => "Precondition Failed",
+			_ => "Internal Server Error",
+		}
+	}
+
+	/// Writes the response to `stream`.
+	/// If `allow_origin` is set, browsers on that website are allowed to read it.
+	pub fn write(&self, stream: &mut TcpStream, allow_origin: Option<&str>) -> std::io::Result<()> {
+		write!(stream, "HTTP/1.1 {} {}\r\n", self.status, self.reason())?;
+		write!(stream, "Content-Type: {}\r\n", self.content_type)?;
+		write!(stream, "Content-Length: {}\r\n", self.body.len())?;
+		write!(stream, "Connection: close\r\n")?;
+		for (name, value) in &self.headers {
+			write!(stream, "{}: {}\r\n", name, value)?;
+		}
+		if let Some(origin) = allow_origin {
+			write!(stream, "Access-Control-Allow-Origin: {}\r\n", origin)?;
+			write!(
+				stream,
+				"Access-Control-Allow-Methods: GET, POST, PATCH, DELETE\r\n"
+			)?;
+			write!(stream, "Access-Control-Allow-Headers: Content-Type\r\n")?;
+		}
+		write!(stream, "\r\n{}", self.body)?;
+		stream.flush()
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn read(text: &str) -> Result<Request, String> {
+		Request::read(&mut text.as_bytes())
+	}
+
+	#[test]
+	fn reading() {
+		let request = read(
+			"POST /courses?x=1 HTTP/1.1\r\nContent-Length: 4\r\nOrigin: here\r\n\r\nbodyextra",
+		)
+		.unwrap();
+		assert_eq!(request.method, "POST");
+		assert_eq!(request.path, "/courses");
+		assert_eq!(request.header("origin"), Some("here"));
+		// Only as much as Content-Length says is read, even if more was sent
+		assert_eq!(request.body, "body");
+
+		assert!(read("").is_err());
+		assert!(read("GET / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").is_err());
+	}
+
+	#[test]
+	fn hosts() {
+		let host = |host: &str| read(&format!("GET / HTTP/1.1\r\nHost: {}\r\n\r\n", host)).unwrap();
+		assert!(host("127.0.0.1:5232").is_for(5232));
+		assert!(host("localhost:5232").is_for(5232));
+		assert!(host("LocalHost:5232").is_for(5232));
+		assert!(!host("127.0.0.1:5232").is_for(8080));
+		assert!(!host("127.0.0.1").is_for(80));
+		assert!(!host("evil.example:5232").is_for(5232));
+		assert!(!host("localhost:5232.evil.example").is_for(5232));
+		// HTTP/1.1 always sends a Host, so anything without one is up to something
+		assert!(!read("GET / HTTP/1.1\r\n\r\n").unwrap().is_for(5232));
+	}
+
+	#[test]
+	fn too_large() {
+		// Headers that never end are cut off instead of read forever
+		let endless = format!(
+			"GET / HTTP/1.1\r\nX-Long: {}",
+			"a".repeat(MAX_HEADERS as usize * 2)
+		);
+		assert!(read(&endless).is_err());
+		let many = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Header: a\r\n".repeat(10000));
+		assert!(read(&many).is_err());
+		let body = format!(
+			"POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+			MAX_BODY + 1
+		);
+		assert!(read(&body).is_err());
+	}
+}
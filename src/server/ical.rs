@@ -0,0 +1,375 @@
+use crate::application::{Application, Priority};
+use crate::clock::*;
+
+// iCalendar (RFC 5545) is the file format every calendar app understands
+// A file is a VCALENDAR with events (VEVENT) and to-dos (VTODO) in it, each being a list of "NAME:value" lines
+// Lines are ended with "\r\n", and long lines are "folded" onto the next line, which starts with a space
+
+/// Lessons repeat every week from the first one after this date.
+/// We don't know when the school year starts, so any fixed date will do, as long as it never changes.
+fn anchor() -> Date {
+	Date::new(2020, 1, 1).unwrap()
+}
+
+/// Time zones are written out from the anchor until this date, which is further ahead than anyone plans a timetable.
+/// After it, calendar apps carry on with whatever offset the zone had last.
+fn zone_end() -> Date {
+	Date::new(2050, 1, 1).unwrap()
+}
+
+/// Every event needs a DTSTAMP, which is when it was made.
+/// We don't keep track of that, and it has to stay the same or the ETag would change every time.
+const DTSTAMP: &str = "DTSTAMP:20200101T000000Z";
+
+/// Escapes the characters that mean something in iCalendar text.
+pub(super) fn escape(text: &str) -> String {
+	text.replace('\\', "\\\\")
+		.replace(';', "\\;")
+		.replace(',', "\\,")
+		// A bare '\r' would end the line early, so Windows and old Mac line endings become newlines too
+		.replace("\r\n", "\\n")
+		.replace(['\r', '\n'], "\\n")
+}
+
+/// Folds a line so that no part of it is longer than 75 bytes, without cutting a character in half.
+pub(super) fn fold(line: &str) -> String {
+	let mut folded = String::new();
+	let mut length = 0;
+	for c in line.chars() {
+		if length + c.len_utf8() > 75 {
+			folded.push_str("\r\n ");
+			// The space counts towards the next line's length
+			length = 1;
+		}
+		folded.push(c);
+		length += c.len_utf8();
+	}
+	folded
+}
+
+/// Puts components (like from `event` and `todo`) into a whole calendar file.
+pub(super) fn calendar(components: &[String]) -> String {
+	let mut lines = vec![
+		"BEGIN:VCALENDAR".to_string(),
+		"VERSION:2.0".to_string(),
+		"PRODID:-//Scheduler//EN".to_string(),
+	];
+	lines.extend(components.iter().cloned());
+	lines.push("END:VCALENDAR".to_string());
+	lines.join("\r\n") + "\r\n"
+}
+
+/// Hashes `text` with FNV-1a, which is simple enough to write out here and always gives the same answer.
+/// Rust's own hasher can change between versions of Rust, which would make every calendar app download everything again.
+pub(super) fn hash(text: &str) -> u64 {
+	text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+		(hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+/// Returns a tag for `text` that changes whenever it does, so calendar apps know when to download it again.
+pub(super) fn etag(text: &str) -> String {
+	format!("\"{:016x}\"", hash(text))
+}
+
+fn date_value(date: Date) -> String {
+	format!("{:04}{:02}{:02}", date.year(), date.month(), date.day())
+}
+
+fn date_time_value(date: Date, time: Time) -> String {
+	format!(
+		"{}T{:02}{:02}00",
+		date_value(date),
+		time.hour(),
+		time.minute()
+	)
+}
+
+fn offset_value(minutes: i32) -> String {
+	let sign = if minutes < 0 { '-' } else { '+' };
+	format!("{}{:02}{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+/// Returns the zone the `course_index`th course's lessons are in, if they're in one.
+pub(super) fn lesson_zone(application: &Application, course_index: usize) -> Option<Zone> {
+	application.courses[course_index]
+		.timezone
+		.or(application.timezone)
+}
+
+/// The VTIMEZONE that says what `zone` is, which has to go in any calendar file with an event in that zone.
+pub(super) fn timezone(zone: Zone) -> String {
+	// Every change of the clocks from the anchor to `zone_end` is written down, rather than rules like "the last Sunday in March"
+	// That's longer, but it's right for every zone, even the ones whose changes don't follow a rule
+	// Changes of the same kind (like "forward an hour into summer time") go together, with the first one as DTSTART
+	// Before any of them, there's just the offset the zone had at the anchor
+	let (offset, summer) = zone.offset_on(anchor());
+	let mut parts = vec![(
+		summer,
+		offset,
+		offset,
+		vec![date_time_value(anchor(), Time::new(0, 0).unwrap())],
+	)];
+	for transition in zone.transitions(anchor(), zone_end()) {
+		let start = date_time_value(transition.date, transition.time);
+		let kind = (transition.summer, transition.from, transition.to);
+		match parts
+			.iter_mut()
+			.skip(1)
+			.find(|(summer, from, to, _)| (*summer, *from, *to) == kind)
+		{
+			Some((_, _, _, starts)) => starts.push(start),
+			None => parts.push((kind.0, kind.1, kind.2, vec![start])),
+		}
+	}
+
+	let mut lines = vec![
+		"BEGIN:VTIMEZONE".to_string(),
+		format!("TZID:{}", zone.name()),
+	];
+	for (summer, from, to, starts) in parts {
+		let kind = if summer { "DAYLIGHT" } else { "STANDARD" };
+		lines.push(format!("BEGIN:{}", kind));
+		lines.push(format!("DTSTART:{}", starts[0]));
+		if starts.len() > 1 {
+			lines.push(format!("RDATE:{}", starts[1..].join(",")));
+		}
+		lines.push(format!("TZOFFSETFROM:{}", offset_value(from)));
+		lines.push(format!("TZOFFSETTO:{}", offset_value(to)));
+		lines.push(format!("END:{}", kind));
+	}
+	lines.push("END:VTIMEZONE".to_string());
+
+	lines
+		.iter()
+		.map(|line| fold(line))
+		.collect::<Vec<_>>()
+		.join("\r\n")
+}
+
+/// The repeating event for the `time_index`th lesson time of the `course_index`th course.
+/// `name` is what it's called on the CalDAV server (without ".ics"), which its UID is made from.
+pub(super) fn event(
+	application: &Application,
+	course_index: usize,
+	time_index: usize,
+	name: &str,
+) -> String {
+	let course = &application.courses[course_index];
+	let (day, start, end) = course.times[time_index];
+	// The anchor is in 2020, so there's always a next weekday after it
+	let first = anchor().next_weekday(day, true).unwrap();
+
+	// Times are written as wall clock times in a named zone, and the calendar app converts them itself
+	// The zone is described by a VTIMEZONE (see `timezone`), which has to go in the same calendar file
+	// Without any zone, the times are "floating", and show up as the same wall clock time wherever you are
+	let zone = match lesson_zone(application, course_index) {
+		Some(zone) => format!(";TZID={}", zone.name()),
+		None => String::new(),
+	};
+
+	// No lessons on holidays, so each one that would have happened during a holiday is excluded
+	let mut excluded = Vec::new();
+	for &(holiday_start, holiday_end) in &application.holidays {
+		let mut date = holiday_start.next_weekday(day, true);
+		while let Ok(holiday_date) = date {
+			if holiday_date > holiday_end {
+				break;
+			}
+			if holiday_date >= first {
+				excluded.push(date_time_value(holiday_date, start));
+			}
+			date = holiday_date.offset(7);
+		}
+	}
+
+	let mut lines = vec![
+		"BEGIN:VEVENT".to_string(),
+		format!("UID:{}@scheduler", name),
+		DTSTAMP.to_string(),
+		format!("DTSTART{}:{}", zone, date_time_value(first, start)),
+		format!("DTEND{}:{}", zone, date_time_value(first, end)),
+		"RRULE:FREQ=WEEKLY".to_string(),
+	];
+	if !excluded.is_empty() {
+		lines.push(format!("EXDATE{}:{}", zone, excluded.join(",")));
+	}
+	lines.push(format!("SUMMARY:{}", escape(&course.name)));
+	lines.push(format!("LOCATION:{}", escape(&course.room)));
+	lines.push(format!(
+		"DESCRIPTION:{}",
+		escape(&format!("With {}", course.teacher))
+	));
+	if !course.tags.is_empty() {
+		let tags: Vec<String> = course.tags.iter().map(|tag| escape(tag)).collect();
+		lines.push(format!("CATEGORIES:{}", tags.join(",")));
+	}
+	lines.push("END:VEVENT".to_string());
+
+	lines
+		.iter()
+		.map(|line| fold(line))
+		.collect::<Vec<_>>()
+		.join("\r\n")
+}
+
+/// The to-do for the `task_index`th task of the `course_index`th course.
+pub(super) fn todo(application: &Application, course_index: usize, task_index: usize) -> String {
+	let course = &application.courses[course_index];
+	let task = &course.tasks[task_index];
+
+	// iCalendar priorities go from 1 (most important) to 9 (least), with 0 meaning "not set"
+	let priority = match task.priority {
+		Priority::Urgent => 1,
+		Priority::High => 3,
+		Priority::Normal => 5,
+		Priority::Low => 9,
+	};
+	// Calendar apps don't know about steps, so they're listed in the description
+	let mut description = task.desc.clone();
+	for step in &task.steps {
+		let mark = if step.complete { "x" } else { " " };
+		description.push_str(&format!("\n[{}] {}", mark, step.desc));
+		if let Some(due) = step.due {
+			description.push_str(&format!(" (by {})", due));
+		}
+	}
+
+	let mut categories = vec![escape(&course.name)];
+	categories.extend(task.tags.iter().map(|tag| escape(tag)));
+
+	let lines = vec![
+		"BEGIN:VTODO".to_string(),
+		// The ID stays the same when other tasks are removed, unlike the task's index
+		format!("UID:task-{}@scheduler", task.id),
+		DTSTAMP.to_string(),
+		format!("SUMMARY:{}", escape(&task.name)),
+		format!("DESCRIPTION:{}", escape(description.trim_start())),
+		format!("DUE;VALUE=DATE:{}", date_value(task.due)),
+		format!("PRIORITY:{}", priority),
+		format!("PERCENT-COMPLETE:{}", task.progress()),
+		format!(
+			"STATUS:{}",
+			if task.is_complete() {
+				"COMPLETED"
+			} else {
+				"NEEDS-ACTION"
+			}
+		),
+		format!("CATEGORIES:{}", categories.join(",")),
+		"END:VTODO".to_string(),
+	];
+
+	lines
+		.iter()
+		.map(|line| fold(line))
+		.collect::<Vec<_>>()
+		.join("\r\n")
+}
+
+/// Reads a to-do sent back by a calendar app, and returns whether it's been ticked off.
+/// Returns an error if there's no to-do in it.
+pub(super) fn parse_completed(text: &str) -> Result<bool, ()> {
+	// Folded lines are put back together first, by removing every line break followed by a space
+	let unfolded = text
+		.replace("\r\n ", "")
+		.replace("\r\n\t", "")
+		.replace("\n ", "")
+		.replace("\n\t", "");
+
+	let mut in_todo = false;
+	let mut found = false;
+	let mut completed = false;
+	for line in unfolded.lines() {
+		// Properties can have parameters, like "STATUS;X-SOMETHING=1:COMPLETED", which we don't care about
+		let (name, value) = match line.find(':') {
+			Some(colon) => (&line[..colon], line[colon + 1..].trim()),
+			None => continue,
+		};
+		let name = name.split(';').next().unwrap_or("").to_uppercase();
+
+		match (name.as_str(), value) {
+			("BEGIN", "VTODO") => {
+				in_todo = true;
+				found = true;
+			}
+			("END", "VTODO") => in_todo = false,
+			("STATUS", status) if in_todo => completed = status.eq_ignore_ascii_case("COMPLETED"),
+			("PERCENT-COMPLETE", "100") if in_todo => completed = true,
+			_ => (),
+		}
+	}
+
+	if found {
+		Ok(completed)
+	} else {
+		Err(())
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::convert::TryFrom;
+
+	#[test]
+	fn escaping_and_folding() {
+		assert_eq!(
+			escape("Lab, room 2; bring\ngoggles"),
+			"Lab\\, room 2\\; bring\\ngoggles"
+		);
+		assert_eq!(escape("one\r\ntwo\rthree"), "one\\ntwo\\nthree");
+
+		let long = "é".repeat(100);
+		let folded = fold(&long);
+		assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+		assert_eq!(folded.replace("\r\n ", ""), long);
+	}
+
+	#[test]
+	fn hashing() {
+		// These have to stay the same forever, or calendar apps would think everything had changed
+		assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+		assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+		assert_eq!(etag("a"), "\"af63dc4c8601ec8c\"");
+	}
+
+	#[test]
+	fn timezones() {
+		let london = timezone(Zone::try_from("Europe/London".to_string()).unwrap());
+		assert!(london.starts_with("BEGIN:VTIMEZONE\r\nTZID:Europe/London\r\n"));
+		assert!(london.contains(
+			"BEGIN:STANDARD\r\nDTSTART:20200101T000000\r\nTZOFFSETFROM:+0000\r\nTZOFFSETTO:+0000\r\n"
+		));
+		assert!(
+			london.contains("BEGIN:DAYLIGHT\r\nDTSTART:20200329T010000\r\nRDATE:20210328T010000,")
+		);
+		assert!(london.contains("TZOFFSETFROM:+0100\r\nTZOFFSETTO:+0000\r\n"));
+		assert!(london.split("\r\n").all(|line| line.len() <= 75));
+
+		let new_york = timezone(Zone::try_from("America/New_York".to_string()).unwrap());
+		assert!(new_york.contains("TZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\n"));
+	}
+
+	#[test]
+	fn completed() {
+		let todo = |status: &str| {
+			format!(
+				"BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:x\r\n{}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+				status
+			)
+		};
+
+		assert_eq!(parse_completed(&todo("STATUS:COMPLETED")), Ok(true));
+		assert_eq!(parse_completed(&todo("STATUS:NEEDS-ACTION")), Ok(false));
+		assert_eq!(parse_completed(&todo("PERCENT-COMPLETE:100")), Ok(true));
+		assert_eq!(parse_completed(&todo("STA\r\n TUS:COMPLETED")), Ok(true));
+		assert_eq!(parse_completed("BEGIN:VCALENDAR\r\nEND:VCALENDAR"), Err(()));
+	}
+}
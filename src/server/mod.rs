@@ -0,0 +1,125 @@
+//! This module contains the HTTP server, used when the scheduler is run with `serve`.
+//! It lets other programs (like a web page, or a shell script with `curl`) read and change the schedule as JSON.
+
+use std::net::TcpListener;
+use std::time::{Duration, SystemTime};
+
+use crate::application::Application;
+use http::{Request, Response};
+
+/// For reading requests and writing responses.
+mod http;
+/// For working out what each request means, and doing it.
+mod routes;
+/// For the CalDAV server, which calendar apps can subscribe to.
+mod caldav;
+/// For writing and reading iCalendar files, which is what CalDAV sends around.
+mod ical;
+
+// The server only listens on this computer (127.0.0.1), since there's no password or anything on it
+// Everything is in the same shapes as the Application's own structures, so a course looks like:
+// {"name": "Math", "teacher": "Willis", "room": "S37", "times": [["Monday", {"hour": 9, "minute": 0}, ...]], ...}
+// Every change is saved straight away, the same way the GUI's Save button does it
+// See routes.rs for the list of everything the server can do
+
+/// How long a connection can go without sending or taking anything before it's given up on.
+/// Requests are answered one at a time, so one that never finishes would otherwise hold up everything after it.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The server, which owns the application while it's running.
+pub struct Server {
+	application: Application,
+	/// Where the application is saved.
+	location: String,
+	/// When the file was last changed, so we know if someone else (like the GUI) saved over it.
+	last_modified: Option<SystemTime>,
+}
+
+impl Server {
+	pub fn new<T: Into<String>>(application: Application, location: T) -> Self {
+		let location = location.into();
+		Self {
+			last_modified: Application::last_modified(&location),
+			application,
+			location,
+		}
+	}
+
+	/// Loads the application again if the file was changed since we last saw it.
+	/// If it can't be read (probably because it's halfway through being saved), we keep what we have.
+	fn reload(&mut self) {
+		let modified = Application::last_modified(&self.location);
+		if modified != self.last_modified {
+			if let Ok(application) = Application::load(&self.location) {
+				self.application = application;
+				self.last_modified = modified;
+			}
+		}
+	}
+
+	/// Saves the application, and remembers that the file is up to date.
+	fn save(&mut self) {
+		self.application.save(&self.location);
+		self.last_modified = Application::last_modified(&self.location);
+	}
+
+	/// Runs the JSON API on `port`, answering requests one at a time until the program is stopped.
+	/// If `allow_origin` is set (like "http://localhost:3000"), web pages from there can use the server too.
+	pub fn run(self, port: u16, allow_origin: Option<&str>) -> Result<(), String> {
+		self.listen(port, allow_origin, |server, request| {
+			// Browsers ask whether they're allowed to send a request first, with OPTIONS
+			if request.method == "OPTIONS" {
+				Response::ok(&())
+			} else {
+				server.handle(request)
+			}
+		})
+	}
+
+	/// Runs the CalDAV server on `port`, for calendar apps. See caldav.rs.
+	pub fn run_caldav(self, port: u16) -> Result<(), String> {
+		self.listen(port, None, Self::handle_caldav)
+	}
+
+	/// Listens on `port`, passing every request to `handle` and sending back what it returns.
+	fn listen<F>(mut self, port: u16, allow_origin: Option<&str>, mut handle: F) -> Result<(), String>
+	where
+		F: FnMut(&mut Self, &Request) -> Response,
+	{
+		let listener = TcpListener::bind(("127.0.0.1", port))
+			.map_err(|error| format!("Couldn't listen on port {}: {}", port, error))?;
+		println!("Listening on http://127.0.0.1:{}/. Press Ctrl+C to stop.", port);
+
+		for stream in listener.incoming() {
+			// A connection that goes wrong only affects that one request, so we just move on to the next
+			let mut stream = match stream {
+				Ok(stream) => stream,
+				Err(_) => continue,
+			};
+			if stream.set_read_timeout(Some(TIMEOUT)).is_err()
+				|| stream.set_write_timeout(Some(TIMEOUT)).is_err()
+			{
+				continue;
+			}
+			let response = match Request::read(&mut stream) {
+				Ok(request) => {
+					// Any website open in a browser can send requests to 127.0.0.1, so we only answer the one we trust
+					// Browsers say which website sent anything that could change things, and other programs don't say at all
+					let origin = request.header("origin");
+					if !request.is_for(port) {
+						Response::error(403, "Requests have to be sent to 127.0.0.1 or localhost.")
+					} else if origin.is_some() && origin != allow_origin {
+						Response::error(403, "Requests from this website aren't allowed. See '--allow-origin'.")
+					} else {
+						self.reload();
+						handle(&mut self, &request)
+					}
+				}
+				Err(message) => Response::error(400, message),
+			};
+			let _ = response.write(&mut stream, allow_origin);
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,1038 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::*;
+use crate::application::{
+	Assessment, Command, Course, Step, StepPlace, Task, TaskQuery, MAX_REMINDER_DAYS,
+	MAX_REMINDER_MINUTES,
+};
+use crate::clock::*;
+
+// Everything the server can do:
+//
+// GET    /today                                  Today's lessons, and the tasks due today
+// GET    /week                                   Every day this week with its lessons, and the tasks due this week
+// GET    /conflicts                              Lessons this week that happen at the same time
+// GET    /overdue                                Tasks past their due date and not done, and how many each course has
+// GET    /order                                  Unfinished tasks in an order they can be done in, and any that wait for each other
+// GET    /grades                                 Every course's average and grade so far, and the GPA
+//
+// GET    /courses                                Every course
+// POST   /courses                                Add a course, e.g. {"name": "Math"} (without its tasks)
+// GET    /courses/<c>                            One course
+// PATCH  /courses/<c>                            Change some of a course other than its tasks, e.g. {"room": "S38"}
+// DELETE /courses/<c>                            Remove a course
+//
+// GET    /courses/<c>/times                      A course's times
+// POST   /courses/<c>/times                      Add a time, e.g. ["Monday", {"hour": 9, "minute": 0}, {"hour": 10, "minute": 0}]
+// DELETE /courses/<c>/times/<t>                  Remove a time
+//
+// GET    /courses/<c>/assessments                A course's assessments
+// POST   /courses/<c>/assessments                Add an assessment, e.g. {"name": "Midterm", "category": "Exams", "max": 50}
+// PATCH  /courses/<c>/assessments/<a>            Change some of an assessment, e.g. {"score": 42}
+// DELETE /courses/<c>/assessments/<a>            Remove an assessment
+// GET    /courses/<c>/assessments/<a>/needed/<p> What an assessment needs for the course's average to be <p>%
+//
+// GET    /courses/<c>/tasks                      A course's tasks
+// POST   /courses/<c>/tasks                      Add a task, e.g. {"name": "Essay", "due": {"year": 2020, "month": 1, "day": 8}}
+// GET    /courses/<c>/tasks/<t>                  One task
+// PATCH  /courses/<c>/tasks/<t>                  Change some of a task
+// DELETE /courses/<c>/tasks/<t>                  Remove a task
+// POST   /courses/<c>/tasks/<t>/snooze           Give a task a new due date, remembering the first one, e.g. {"year": 2020, ...}
+//
+// GET    /courses/<c>/tasks/<t>/dependencies     The tasks a task has to wait for
+// POST   /courses/<c>/tasks/<t>/dependencies     Make a task wait for another, e.g. [1, 0] for course 1's task 0
+// DELETE /courses/<c>/tasks/<t>/dependencies/<d> Stop a task waiting for another, numbered as in the GET
+//
+// GET    /courses/<c>/tasks/<t>/steps            A task's steps
+// POST   /courses/<c>/tasks/<t>/steps            Add a step to the end, e.g. {"desc": "Read chapter 3", "weight": 2}
+// PATCH  /courses/<c>/tasks/<t>/steps/<s>        Change some of a step, e.g. {"complete": true}, but not how deep it is
+// DELETE /courses/<c>/tasks/<t>/steps/<s>        Remove a step, and its sub-steps
+// POST   /courses/<c>/tasks/<t>/steps/<s>/move   Move a step and its sub-steps, e.g. {"After": 2}, {"Before": 0} or {"Into": 1}
+//
+// GET    /holidays                               Every holiday
+// POST   /holidays                               Add a holiday, e.g. [{"year": 2020, ...}, {"year": 2020, ...}]
+// DELETE /holidays/<h>                          Remove a holiday
+//
+// Courses, tasks and holidays are numbered from 0, in the order they're listed in
+// Tasks also have an ID, which is what `depends_on` is made of, and which can't be changed
+
+/// Either way, we answer with a response, but returning errors as `Err` lets us use `?`.
+type Result = std::result::Result<Response, Response>;
+
+impl Server {
+	/// Does whatever `request` asks for, and returns the answer.
+	pub(super) fn handle(&mut self, request: &Request) -> Response {
+		self.route(&request.method, &request.segments(), &request.body)
+			.unwrap_or_else(|error| error)
+	}
+
+	fn route(&mut self, method: &str, segments: &[&str], body: &str) -> Result {
+		let application = &self.application;
+
+		let response = match (method, segments) {
+			("GET", ["today"]) => self.today(),
+			("GET", ["week"]) => self.week(),
+			("GET", ["conflicts"]) => self.conflicts(),
+			("GET", ["overdue"]) => self.overdue(),
+			("GET", ["order"]) => self.order(),
+			("GET", ["grades"]) => self.grades(),
+
+			("GET", ["courses"]) => Response::ok(&application.courses),
+			("POST", ["courses"]) => {
+				no_tasks(body)?;
+				let course: Course = merge(&Course::default(), body)?;
+				check_course(&course)?;
+				self.application.add_course(course.clone());
+				Response::with_status(201, &course)
+			}
+			("GET", ["courses", c]) => Response::ok(&application.courses[course(application, c)?]),
+			("PATCH", ["courses", c]) => {
+				let c = course(application, c)?;
+				no_tasks(body)?;
+				let course: Course = merge(&application.courses[c], body)?;
+				check_course(&course)?;
+				let label = format!("Edit course '{}'", course.name);
+				self.application
+					.apply(label, Command::SetCourse(c, course.clone()));
+				Response::ok(&course)
+			}
+			("DELETE", ["courses", c]) => {
+				let c = course(application, c)?;
+				let course = application.courses[c].clone();
+				self.application.rm_course(c);
+				Response::ok(&course)
+			}
+
+			("GET", ["courses", c, "times"]) => {
+				Response::ok(&application.courses[course(application, c)?].times)
+			}
+			("POST", ["courses", c, "times"]) => {
+				let c = course(application, c)?;
+				let (day, start, end): (Day, Time, Time) = parse(body)?;
+				check_time(start, end)?;
+				self.application
+					.edit_course(c, "Add time", |course| course.add_time(day, start, end));
+				Response::with_status(201, &self.application.courses[c].times)
+			}
+			("DELETE", ["courses", c, "times", t]) => {
+				let c = course(application, c)?;
+				let t = index(t, application.courses[c].times.len(), "time")?;
+				let time = application.courses[c].times[t];
+				self.application
+					.edit_course(c, "Remove time", |course| course.rm_time(t));
+				Response::ok(&time)
+			}
+
+			("GET", ["courses", c, "assessments"]) => {
+				Response::ok(&application.courses[course(application, c)?].assessments)
+			}
+			("POST", ["courses", c, "assessments"]) => {
+				let c = course(application, c)?;
+				let assessment: Assessment = merge(&Assessment::default(), body)?;
+				check_assessment(&assessment)?;
+				let label = format!("Add assessment '{}'", assessment.name);
+				self.application.edit_course(c, label, |course| {
+					course.assessments.push(assessment.clone())
+				});
+				Response::with_status(201, &assessment)
+			}
+			("PATCH", ["courses", c, "assessments", a]) => {
+				let (c, a) = assessment(application, c, a)?;
+				let assessment: Assessment = merge(&application.courses[c].assessments[a], body)?;
+				check_assessment(&assessment)?;
+				let label = format!("Edit assessment '{}'", assessment.name);
+				self.application.edit_course(c, label, |course| {
+					course.assessments[a] = assessment.clone()
+				});
+				Response::ok(&assessment)
+			}
+			("DELETE", ["courses", c, "assessments", a]) => {
+				let (c, a) = assessment(application, c, a)?;
+				let assessment = application.courses[c].assessments[a].clone();
+				let label = format!("Remove assessment '{}'", assessment.name);
+				self.application.edit_course(c, label, |course| {
+					course.assessments.remove(a);
+				});
+				Response::ok(&assessment)
+			}
+			("GET", ["courses", c, "assessments", a, "needed", p]) => {
+				let (c, a) = assessment(application, c, a)?;
+				let target: f64 = p
+					.parse()
+					.map_err(|_| Response::error(400, "The average has to be a number."))?;
+				// `null` if the assessment makes no difference to the average
+				Response::ok(&json!({ "needed": application.courses[c].needed_on(a, target) }))
+			}
+
+			("GET", ["courses", c, "tasks"]) => {
+				Response::ok(&application.courses[course(application, c)?].tasks)
+			}
+			("POST", ["courses", c, "tasks"]) => {
+				let c = course(application, c)?;
+				let new = Task::default();
+				let mut task: Task = merge(&new, body)?;
+				task.id = new.id;
+				check_task(&task)?;
+				let label = format!("Add task '{}'", task.name);
+				self.application
+					.edit_course(c, label, |course| course.tasks.push(task.clone()));
+				Response::with_status(201, &task)
+			}
+			("GET", ["courses", c, "tasks", t]) => {
+				let (c, t) = task(application, c, t)?;
+				Response::ok(&application.courses[c].tasks[t])
+			}
+			("PATCH", ["courses", c, "tasks", t]) => {
+				let (c, t) = task(application, c, t)?;
+				let old = &application.courses[c].tasks[t];
+				let mut task: Task = merge(old, body)?;
+				task.id = old.id;
+				check_task(&task)?;
+				// Only new dependencies need checking, since the old ones didn't make a loop
+				for &id in &task.depends_on {
+					if !old.depends_on.contains(&id)
+						&& (id == task.id || application.depends_on(id, task.id))
+					{
+						return Err(Response::error(
+							400,
+							"That would make tasks wait for each other in a loop.",
+						));
+					}
+				}
+				task.roll_up();
+				let label = format!("Edit task '{}'", task.name);
+				self.application
+					.edit_task(c, t, label, |old| *old = task.clone());
+				Response::ok(&task)
+			}
+			("DELETE", ["courses", c, "tasks", t]) => {
+				let (c, t) = task(application, c, t)?;
+				let task = application.courses[c].tasks[t].clone();
+				let label = format!("Remove task '{}'", task.name);
+				self.application
+					.edit_course(c, label, |course| course.rm_task(t));
+				Response::ok(&task)
+			}
+			("POST", ["courses", c, "tasks", t, "snooze"]) => {
+				let (c, t) = task(application, c, t)?;
+				let due: Date = parse(body)?;
+				if !valid_date(due) {
+					return Err(Response::error(
+						400,
+						"Tasks can only be snoozed to a real date.",
+					));
+				}
+				self.application.snooze_tasks(&[(c, t)], due);
+				Response::ok(&self.application.courses[c].tasks[t])
+			}
+
+			("GET", ["courses", c, "tasks", t, "dependencies"]) => {
+				let (c, t) = task(application, c, t)?;
+				Response::ok(&self.dependencies(c, t))
+			}
+			("POST", ["courses", c, "tasks", t, "dependencies"]) => {
+				let (c, t) = task(application, c, t)?;
+				let (on_c, on_t): (usize, usize) = parse(body)?;
+				let prerequisite = task(application, &on_c.to_string(), &on_t.to_string())?;
+				self.application
+					.add_dependency((c, t), prerequisite)
+					.map_err(|message| Response::error(400, message))?;
+				Response::with_status(201, &self.dependencies(c, t))
+			}
+			("DELETE", ["courses", c, "tasks", t, "dependencies", d]) => {
+				let (c, t) = task(application, c, t)?;
+				let prerequisites = application.prerequisites(c, t);
+				let d = index(d, prerequisites.len(), "dependency")?;
+				self.application.rm_dependency((c, t), prerequisites[d]);
+				Response::ok(&self.dependencies(c, t))
+			}
+
+			("GET", ["courses", c, "tasks", t, "steps"]) => {
+				let (c, t) = task(application, c, t)?;
+				Response::ok(&application.courses[c].tasks[t].steps)
+			}
+			("POST", ["courses", c, "tasks", t, "steps"]) => {
+				let (c, t) = task(application, c, t)?;
+				let mut step: Step = merge(&Step::default(), body)?;
+				check_step(&step)?;
+				// New steps always go at the end, and can be moved inside another one afterwards
+				step.depth = 0;
+				self.application.edit_task(c, t, "Add step", |task| {
+					task.steps.push(step);
+					task.roll_up();
+				});
+				Response::with_status(201, &self.application.courses[c].tasks[t].steps)
+			}
+			("PATCH", ["courses", c, "tasks", t, "steps", s]) => {
+				let (c, t) = task(application, c, t)?;
+				let s = index(s, application.courses[c].tasks[t].steps.len(), "step")?;
+				let old = &application.courses[c].tasks[t].steps[s];
+				let mut step: Step = merge(old, body)?;
+				check_step(&step)?;
+				// Changing how deep a step is would mix up which steps it's inside, so that's done by moving it
+				step.depth = old.depth;
+				self.application.edit_task(c, t, "Edit step", |task| {
+					task.steps[s] = step;
+					task.roll_up();
+				});
+				Response::ok(&self.application.courses[c].tasks[t].steps[s])
+			}
+			("DELETE", ["courses", c, "tasks", t, "steps", s]) => {
+				let (c, t) = task(application, c, t)?;
+				let s = index(s, application.courses[c].tasks[t].steps.len(), "step")?;
+				let step = application.courses[c].tasks[t].steps[s].clone();
+				self.application
+					.edit_task(c, t, "Remove step", |task| task.rm_step(s));
+				Response::ok(&step)
+			}
+			("POST", ["courses", c, "tasks", t, "steps", s, "move"]) => {
+				let (c, t) = task(application, c, t)?;
+				let s = index(s, application.courses[c].tasks[t].steps.len(), "step")?;
+				let place: StepPlace = parse(body)?;
+				// Trying it on a copy first, so a bad move doesn't leave anything to undo
+				let mut moved = application.courses[c].tasks[t].clone();
+				if moved.move_step(s, place).is_err() {
+					return Err(Response::error(
+						400,
+						"Steps can only be moved next to or into another step, and not into themselves.",
+					));
+				}
+				self.application
+					.edit_task(c, t, "Move step", |task| task.steps = moved.steps);
+				Response::ok(&self.application.courses[c].tasks[t].steps)
+			}
+
+			("GET", ["holidays"]) => Response::ok(&application.holidays),
+			("POST", ["holidays"]) => {
+				let (start, end): (Date, Date) = parse(body)?;
+				if !valid_date(start) || !valid_date(end) || end < start {
+					return Err(Response::error(
+						400,
+						"Holidays need two real dates, the start first.",
+					));
+				}
+				self.application.add_holiday(start, end);
+				Response::with_status(201, &self.application.holidays)
+			}
+			("DELETE", ["holidays", h]) => {
+				let h = index(h, application.holidays.len(), "holiday")?;
+				let holiday = application.holidays[h];
+				self.application.rm_holiday(h);
+				Response::ok(&holiday)
+			}
+
+			_ => {
+				return Err(Response::error(
+					404,
+					format!("Nothing at {} /{}", method, segments.join("/")),
+				))
+			}
+		};
+
+		// Anything that isn't just reading was a change, so it's saved straight away
+		if method != "GET" {
+			self.save();
+		}
+		Ok(response)
+	}
+
+	/// Today's lessons, and the tasks due today.
+	fn today(&self) -> Response {
+		let today = self.application.today();
+		Response::ok(&json!({
+			"date": today,
+			"holiday": self.application.is_holiday(today),
+			"lessons": self.lessons(today),
+			"tasks": self.tasks(today, today),
+		}))
+	}
+
+	/// Every day this week with its lessons, and the tasks due this week.
+	fn week(&self) -> Response {
+		let locale = &self.application.locale;
+		let today = self.application.today();
+		// Only the very first week there is has days before 1970, and those are left out
+		let start = locale.start_of_week(today).unwrap_or(Date::FIRST);
+		let end = locale.end_of_week(today).unwrap_or(Date::LAST);
+
+		let days: Vec<Value> = locale
+			.week()
+			.into_iter()
+			.filter_map(|day| Some((day, locale.date_in_week(today, day).ok()?)))
+			.map(|(day, date)| {
+				json!({
+					"date": date,
+					"day": day,
+					"school_day": locale.school_days.contains(&day),
+					"holiday": self.application.is_holiday(date),
+					"lessons": self.lessons(date),
+				})
+			})
+			.collect();
+
+		Response::ok(&json!({ "days": days, "tasks": self.tasks(start, end) }))
+	}
+
+	/// Lessons this week that happen at the same time. Holidays are skipped, since there's no clash if there's no lesson.
+	fn conflicts(&self) -> Response {
+		let locale = &self.application.locale;
+		let today = self.application.today();
+		let conflicts: Vec<_> = locale
+			.week()
+			.into_iter()
+			.filter_map(|day| locale.date_in_week(today, day).ok())
+			.filter(|&date| !self.application.is_holiday(date))
+			.flat_map(|date| self.application.conflicts_on(date))
+			.collect();
+		Response::ok(&conflicts)
+	}
+
+	/// The tasks that are overdue today, longest overdue first, and how many each course has.
+	fn overdue(&self) -> Response {
+		let today = self.application.today();
+		let tasks: Vec<Value> = self
+			.application
+			.overdue_tasks(today)
+			.into_iter()
+			.map(|entry| {
+				json!({
+					"course_index": entry.course_index,
+					"task_index": entry.task_index,
+					"course": entry.course.name,
+					"days_overdue": entry.task.days_overdue(today),
+					"task": entry.task,
+				})
+			})
+			.collect();
+		let courses: Vec<Value> = self
+			.application
+			.courses
+			.iter()
+			.zip(self.application.overdue_counts(today))
+			.enumerate()
+			.map(|(index, (course, count))| {
+				json!({
+					"course_index": index,
+					"course": course.name,
+					"overdue": count,
+				})
+			})
+			.collect();
+		Response::ok(&json!({ "tasks": tasks, "courses": courses }))
+	}
+
+	/// The tasks that the task at (c, t) has to wait for, and whether they're done yet.
+	fn dependencies(&self, c: usize, t: usize) -> Vec<Value> {
+		self.application
+			.prerequisites(c, t)
+			.into_iter()
+			.map(|(course_index, task_index)| {
+				let course = &self.application.courses[course_index];
+				let task = &course.tasks[task_index];
+				json!({
+					"course_index": course_index,
+					"task_index": task_index,
+					"course": course.name,
+					"task": task.name,
+					"complete": task.is_complete(),
+				})
+			})
+			.collect()
+	}
+
+	/// Every unfinished task, in an order they can be done in, with what each one is still waiting for.
+	fn order(&self) -> Response {
+		let application = &self.application;
+		let position = |(c, t): (usize, usize)| json!({ "course_index": c, "task_index": t });
+		let tasks: Vec<Value> = application
+			.work_order()
+			.into_iter()
+			.map(|(course_index, task_index)| {
+				let blocked_by: Vec<Value> = application
+					.blockers(course_index, task_index)
+					.into_iter()
+					.map(position)
+					.collect();
+				json!({
+					"course_index": course_index,
+					"task_index": task_index,
+					"course": application.courses[course_index].name,
+					"task": application.courses[course_index].tasks[task_index],
+					"blocked_by": blocked_by,
+				})
+			})
+			.collect();
+		let waiting_on_each_other: Vec<Value> = application
+			.dependency_loop()
+			.unwrap_or_default()
+			.into_iter()
+			.map(position)
+			.collect();
+		Response::ok(&json!({ "tasks": tasks, "loop": waiting_on_each_other }))
+	}
+
+	/// Every course's average so far with the grade and points it gets, and the GPA.
+	fn grades(&self) -> Response {
+		let application = &self.application;
+		let courses: Vec<Value> = application
+			.courses
+			.iter()
+			.enumerate()
+			.map(|(index, course)| {
+				let grade = application.grade(index);
+				json!({
+					"course_index": index,
+					"course": course.name,
+					"average": course.average(),
+					"grade": grade.map(|grade| &grade.name),
+					"points": grade.map(|grade| grade.points),
+					"credits": course.credits,
+				})
+			})
+			.collect();
+		Response::ok(&json!({ "courses": courses, "gpa": application.gpa() }))
+	}
+
+	/// The lessons on `date` (none if it's a holiday), in order.
+	fn lessons(&self, date: Date) -> Vec<Value> {
+		if self.application.is_holiday(date) {
+			return Vec::new();
+		}
+
+		let mut lessons: Vec<(usize, &Course, Time, Time)> = self
+			.application
+			.courses
+			.iter()
+			.enumerate()
+			.flat_map(|(index, course)| {
+				course
+					.lessons_on(date, self.application.timezone)
+					.into_iter()
+					.map(move |(start, end)| (index, course, start, end))
+			})
+			.collect();
+		lessons.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+		lessons
+			.into_iter()
+			.map(|(index, course, start, end)| {
+				json!({
+					"course_index": index,
+					"course": course.name,
+					"teacher": course.teacher,
+					"room": course.room,
+					"start": start,
+					"end": end,
+				})
+			})
+			.collect()
+	}
+
+	/// The tasks due from `start` to `end`, soonest first.
+	fn tasks(&self, start: Date, end: Date) -> Vec<Value> {
+		self.application
+			.tasks(&TaskQuery::new().due_between(start, end))
+			.into_iter()
+			.map(|entry| {
+				json!({
+					"course_index": entry.course_index,
+					"task_index": entry.task_index,
+					"course": entry.course.name,
+					"task": entry.task,
+				})
+			})
+			.collect()
+	}
+}
+
+/// Reads `body` as JSON.
+fn parse<T: DeserializeOwned>(body: &str) -> std::result::Result<T, Response> {
+	serde_json::from_str(body)
+		.map_err(|error| Response::error(400, format!("Invalid JSON: {}", error)))
+}
+
+/// Returns `current` with the fields in `body` (a JSON object) changed.
+/// This way, only the fields that are changing have to be sent, and the rest stay as they are.
+fn merge<T: Serialize + DeserializeOwned>(
+	current: &T,
+	body: &str,
+) -> std::result::Result<T, Response> {
+	let mut value = serde_json::to_value(current).unwrap();
+	let changes: Value = parse(body)?;
+
+	match (value.as_object_mut(), changes) {
+		(Some(object), Value::Object(changes)) => {
+			for (field, change) in changes {
+				// Without this, a typo like "nmae" would be silently ignored
+				if !object.contains_key(&field) {
+					return Err(Response::error(
+						400,
+						format!("There's no field called '{}'.", field),
+					));
+				}
+				object.insert(field, change);
+			}
+		}
+		_ => return Err(Response::error(400, "Expected a JSON object.")),
+	}
+
+	serde_json::from_value(value)
+		.map_err(|error| Response::error(400, format!("Invalid JSON: {}", error)))
+}
+
+/// Reads a number from the path, and checks there's something there.
+fn index(text: &str, len: usize, what: &str) -> std::result::Result<usize, Response> {
+	match text.parse() {
+		Ok(index) if index < len => Ok(index),
+		_ => Err(Response::error(
+			404,
+			format!("There's no {} '{}'.", what, text),
+		)),
+	}
+}
+
+fn course(application: &Application, c: &str) -> std::result::Result<usize, Response> {
+	index(c, application.courses.len(), "course")
+}
+
+fn assessment(
+	application: &Application,
+	c: &str,
+	a: &str,
+) -> std::result::Result<(usize, usize), Response> {
+	let c = course(application, c)?;
+	let a = index(a, application.courses[c].assessments.len(), "assessment")?;
+	Ok((c, a))
+}
+
+fn task(
+	application: &Application,
+	c: &str,
+	t: &str,
+) -> std::result::Result<(usize, usize), Response> {
+	let c = course(application, c)?;
+	let t = index(t, application.courses[c].tasks.len(), "task")?;
+	Ok((c, t))
+}
+
+// JSON can have any numbers in it, so dates like the 31st of February have to be checked for
+// Date::new and Time::new already do all the checking, so we just see if they'd make the same thing
+
+fn valid_date(date: Date) -> bool {
+	Date::new(date.year(), date.month(), date.day()).is_ok()
+}
+
+fn check_time(start: Time, end: Time) -> std::result::Result<(), Response> {
+	let valid = |time: Time| Time::new(time.hour(), time.minute()).is_ok();
+	if valid(start) && valid(end) && start < end {
+		Ok(())
+	} else {
+		Err(Response::error(
+			400,
+			"Times need a real start and end, the start first.",
+		))
+	}
+}
+
+fn check_task(task: &Task) -> std::result::Result<(), Response> {
+	if !valid_date(task.due) {
+		return Err(Response::error(
+			400,
+			format!("'{}' isn't due on a real date.", task.name),
+		));
+	}
+	if matches!(task.reminder_days, Some(days) if days > MAX_REMINDER_DAYS) {
+		return Err(Response::error(
+			400,
+			format!(
+				"'{}' can't be reminded about more than {} days before.",
+				task.name, MAX_REMINDER_DAYS
+			),
+		));
+	}
+	let mut depth = 0;
+	for step in &task.steps {
+		check_step(step)?;
+		// A step can only be one deeper than the step before it, or the first step would be inside nothing
+		if step.depth > depth {
+			return Err(Response::error(
+				400,
+				format!("'{}' is deeper than the step it's inside.", step.desc),
+			));
+		}
+		depth = step.depth + 1;
+	}
+	Ok(())
+}
+
+fn check_step(step: &Step) -> std::result::Result<(), Response> {
+	match step.due {
+		Some(due) if !valid_date(due) => Err(Response::error(
+			400,
+			format!("'{}' isn't due on a real date.", step.desc),
+		)),
+		_ => Ok(()),
+	}
+}
+
+fn check_assessment(assessment: &Assessment) -> std::result::Result<(), Response> {
+	assessment
+		.check()
+		.map_err(|message| Response::error(400, message))
+}
+
+fn check_grading(course: &Course) -> std::result::Result<(), Response> {
+	course
+		.check_grading()
+		.map_err(|message| Response::error(400, message))
+}
+
+/// Returns an error if `body` has a course's tasks in it.
+/// Tasks have their own routes, which give them IDs and make sure they don't wait for each other in a loop,
+/// so I don't let a whole list of them (with whatever IDs and dependencies it likes) come in with the course.
+fn no_tasks(body: &str) -> std::result::Result<(), Response> {
+	// If the body isn't an object, merge will say so
+	match serde_json::from_str::<Value>(body) {
+		Ok(Value::Object(fields)) if fields.contains_key("tasks") => Err(Response::error(
+			400,
+			"A course's tasks are changed through /courses/<c>/tasks.",
+		)),
+		_ => Ok(()),
+	}
+}
+
+fn check_course(course: &Course) -> std::result::Result<(), Response> {
+	if matches!(course.reminder_minutes, Some(minutes) if minutes > MAX_REMINDER_MINUTES) {
+		return Err(Response::error(
+			400,
+			format!(
+				"'{}' can't be reminded about more than a week before.",
+				course.name
+			),
+		));
+	}
+	for &(_, start, end) in &course.times {
+		check_time(start, end)?;
+	}
+	for task in &course.tasks {
+		check_task(task)?;
+	}
+	check_grading(course)
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn server() -> Server {
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_task("Essay", "", Date::new(2020, 1, 8).unwrap());
+		let mut application = Application::default();
+		application.add_course(math);
+
+		let location = std::env::temp_dir().join(format!(
+			"scheduler-server-test-{:?}.bin",
+			std::thread::current().id()
+		));
+		Server::new(application, location.to_str().unwrap())
+	}
+
+	fn request(server: &mut Server, method: &str, path: &str, body: &str) -> (u16, Value) {
+		let request = Request {
+			method: method.to_string(),
+			path: path.to_string(),
+			body: body.to_string(),
+			headers: Vec::new(),
+		};
+		let response = server.handle(&request);
+		(
+			response.status,
+			serde_json::from_str(&response.body).unwrap(),
+		)
+	}
+
+	#[test]
+	fn courses() {
+		let mut server = server();
+
+		let (status, body) = request(&mut server, "GET", "/courses/0", "");
+		assert_eq!(status, 200);
+		assert_eq!(body["name"], "Math");
+
+		let (status, body) = request(&mut server, "POST", "/courses", r#"{"name": "Science"}"#);
+		assert_eq!(status, 201);
+		assert_eq!(body["room"], "Room");
+
+		let (status, _) = request(&mut server, "PATCH", "/courses/1", r#"{"room": "L2"}"#);
+		assert_eq!(status, 200);
+		assert_eq!(server.application.courses[1].room, "L2");
+		// The rest of the course is still there
+		assert_eq!(server.application.courses[1].name, "Science");
+
+		let (status, _) = request(&mut server, "DELETE", "/courses/0", "");
+		assert_eq!(status, 200);
+		assert_eq!(server.application.courses.len(), 1);
+
+		// Changes can be undone, just like in the GUI
+		server.application.undo();
+		assert_eq!(server.application.courses.len(), 2);
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn tasks_and_steps() {
+		let mut server = server();
+
+		let (status, body) = request(
+			&mut server,
+			"PATCH",
+			"/courses/0/tasks/0",
+			r#"{"priority": "High"}"#,
+		);
+		assert_eq!(status, 200);
+		assert_eq!(body["name"], "Essay");
+		assert_eq!(body["priority"], "High");
+
+		let (status, _) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/0/steps",
+			r#"{"desc": "Plan", "weight": 2}"#,
+		);
+		assert_eq!(status, 201);
+		let (status, _) = request(
+			&mut server,
+			"PATCH",
+			"/courses/0/tasks/0/steps/0",
+			r#"{"complete": true}"#,
+		);
+		assert_eq!(status, 200);
+		let step = &server.application.courses[0].tasks[0].steps[0];
+		assert_eq!((step.desc.as_str(), step.weight), ("Plan", Some(2)));
+		assert!(server.application.courses[0].tasks[0].is_complete());
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn moving_steps() {
+		let mut server = server();
+		for desc in &["Research", "Find sources", "Write"] {
+			let body = format!(r#"{{"desc": "{}"}}"#, desc);
+			request(&mut server, "POST", "/courses/0/tasks/0/steps", &body);
+		}
+
+		let (status, body) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/0/steps/1/move",
+			r#"{"Into": 0}"#,
+		);
+		assert_eq!(status, 200);
+		assert_eq!(body[1]["depth"], 1);
+
+		// Research can't go inside Find sources, which is inside it
+		let (status, _) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/0/steps/0/move",
+			r#"{"Into": 1}"#,
+		);
+		assert_eq!(status, 400);
+
+		// Ticking the only sub-step ticks Research, but the depth can't be changed like this
+		let (status, _) = request(
+			&mut server,
+			"PATCH",
+			"/courses/0/tasks/0/steps/1",
+			r#"{"complete": true, "depth": 0}"#,
+		);
+		assert_eq!(status, 200);
+		let steps = &server.application.courses[0].tasks[0].steps;
+		assert_eq!(steps[1].depth, 1);
+		assert!(steps[0].complete);
+
+		// Removing Research takes Find sources with it
+		request(&mut server, "DELETE", "/courses/0/tasks/0/steps/0", "");
+		let steps = &server.application.courses[0].tasks[0].steps;
+		assert_eq!(steps.len(), 1);
+		assert_eq!(steps[0].desc, "Write");
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn dependencies() {
+		let mut server = server();
+		let body = r#"{"name": "Final", "due": {"year": 2020, "month": 1, "day": 20}, "id": 5}"#;
+		let (status, body) = request(&mut server, "POST", "/courses/0/tasks", body);
+		assert_eq!(status, 201);
+		// IDs are given out by the scheduler, not picked
+		assert_ne!(body["id"], 5);
+
+		// The final has to wait for the essay
+		let (status, body) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/1/dependencies",
+			"[0, 0]",
+		);
+		assert_eq!(status, 201);
+		assert_eq!(body[0]["task"], "Essay");
+		let (_, body) = request(&mut server, "GET", "/order", "");
+		assert_eq!(body["tasks"][0]["task"]["name"], "Essay");
+		assert_eq!(body["tasks"][1]["blocked_by"][0]["task_index"], 0);
+
+		// The essay can't wait for the final too, either way it's asked for
+		let (status, _) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/0/dependencies",
+			"[0, 1]",
+		);
+		assert_eq!(status, 400);
+		let id = server.application.courses[0].tasks[1].id;
+		let body = format!(r#"{{"depends_on": [{}]}}"#, id);
+		let (status, _) = request(&mut server, "PATCH", "/courses/0/tasks/0", &body);
+		assert_eq!(status, 400);
+
+		let (status, body) = request(
+			&mut server,
+			"DELETE",
+			"/courses/0/tasks/1/dependencies/0",
+			"",
+		);
+		assert_eq!(status, 200);
+		assert_eq!(body, json!([]));
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn grades() {
+		let mut server = server();
+
+		let body = r#"{"name": "Midterm", "category": "Exams", "max": 50, "score": 40}"#;
+		let (status, body) = request(&mut server, "POST", "/courses/0/assessments", body);
+		assert_eq!(status, 201);
+		assert_eq!(body["weight"], 1.0);
+		let body = r#"{"name": "Final", "category": "Exams"}"#;
+		request(&mut server, "POST", "/courses/0/assessments", body);
+
+		// 80% on the midterm is a B, and the final needs 90% for an 85% average
+		let (status, body) = request(&mut server, "GET", "/grades", "");
+		assert_eq!(status, 200);
+		assert_eq!(body["courses"][0]["average"], 80.0);
+		assert_eq!(body["courses"][0]["grade"], "B");
+		assert_eq!(body["gpa"], 3.0);
+		let (_, body) = request(&mut server, "GET", "/courses/0/assessments/1/needed/85", "");
+		assert_eq!(body["needed"], 90.0);
+
+		let (status, _) = request(
+			&mut server,
+			"PATCH",
+			"/courses/0/assessments/1",
+			r#"{"score": -5}"#,
+		);
+		assert_eq!(status, 400);
+		let (status, body) = request(&mut server, "DELETE", "/courses/0/assessments/0", "");
+		assert_eq!(status, 200);
+		assert_eq!(body["name"], "Midterm");
+		let (_, body) = request(&mut server, "GET", "/grades", "");
+		assert_eq!(body["gpa"], Value::Null);
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn overdue_and_snoozing() {
+		let mut server = server();
+
+		// The essay was due in 2020, so it's long overdue
+		let (status, body) = request(&mut server, "GET", "/overdue", "");
+		assert_eq!(status, 200);
+		assert_eq!(body["tasks"][0]["task"]["name"], "Essay");
+		assert_eq!(body["courses"][0]["overdue"], 1);
+
+		let later = server.application.today() + 7;
+		let (status, body) = request(
+			&mut server,
+			"POST",
+			"/courses/0/tasks/0/snooze",
+			&serde_json::to_string(&later).unwrap(),
+		);
+		assert_eq!(status, 200);
+		assert_eq!(body["original_due"]["day"], 8);
+
+		let (_, body) = request(&mut server, "GET", "/overdue", "");
+		assert_eq!(body["tasks"].as_array().unwrap().len(), 0);
+		assert_eq!(body["courses"][0]["overdue"], 0);
+
+		std::fs::remove_file(&server.location).unwrap();
+	}
+
+	#[test]
+	fn errors() {
+		let mut server = server();
+
+		assert_eq!(request(&mut server, "GET", "/courses/5", "").0, 404);
+		assert_eq!(request(&mut server, "GET", "/nowhere", "").0, 404);
+		assert_eq!(request(&mut server, "POST", "/courses", "{").0, 400);
+		assert_eq!(
+			request(&mut server, "PATCH", "/courses/0", r#"{"nmae": "Maths"}"#).0,
+			400
+		);
+		// There's no 31st of February
+		let body = r#"{"due": {"year": 2020, "month": 2, "day": 31}}"#;
+		let (status, body) = request(&mut server, "POST", "/courses/0/tasks", body);
+		assert_eq!(status, 400);
+		assert!(body["error"].is_string());
+		let body = r#"["Monday", {"hour": 10, "minute": 0}, {"hour": 9, "minute": 0}]"#;
+		assert_eq!(
+			request(&mut server, "POST", "/courses/0/times", body).0,
+			400
+		);
+		// Negative or infinite credits and weights would make the averages and GPA meaningless
+		// (JSON can't hold infinity, but a number too big for an f64 is read as it)
+		assert_eq!(
+			request(&mut server, "PATCH", "/courses/0", r#"{"credits": -1}"#).0,
+			400
+		);
+		assert_eq!(
+			request(&mut server, "PATCH", "/courses/0", r#"{"credits": 1e999}"#).0,
+			400
+		);
+		let body = r#"{"category_weights": [["Exams", -50]]}"#;
+		assert_eq!(request(&mut server, "PATCH", "/courses/0", body).0, 400);
+		// Tasks only come in through their own routes, so their IDs and dependencies can be checked
+		let body = r#"{"name": "Maths", "tasks": [{"id": 1, "depends_on": [1]}]}"#;
+		assert_eq!(request(&mut server, "POST", "/courses", body).0, 400);
+		assert_eq!(
+			request(&mut server, "PATCH", "/courses/0", r#"{"tasks": []}"#).0,
+			400
+		);
+		// Reminders more than a year ahead would have to look through every day in between
+		assert_eq!(
+			request(
+				&mut server,
+				"PATCH",
+				"/courses/0/tasks/0",
+				r#"{"reminder_days": 30000}"#
+			)
+			.0,
+			400
+		);
+
+		// Nothing was changed, so nothing was saved
+		assert!(!std::path::Path::new(&server.location).exists());
+	}
+}
@@ -0,0 +1,386 @@
+use super::*;
+
+// Editing happens on the bottom line of the screen, with whatever's there already filled in
+// Enter saves it (as one edit that can be undone, like the GUI's Save buttons), and Escape forgets it
+// Anything that's typed wrong is left alone, and the message says why
+
+const DATE_HELP: &str = "Try 'YYYY-MM-DD', 'tomorrow', 'next friday', 'in 3 days' or 'DD/MM'.";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// The details of a course that can be typed in, from the GUI's course window.
+pub(super) enum CourseField {
+	Name,
+	Teacher,
+	Room,
+	Timezone,
+	Tags,
+	Reminder,
+}
+
+impl CourseField {
+	pub fn all() -> [Self; 6] {
+		[
+			Self::Name,
+			Self::Teacher,
+			Self::Room,
+			Self::Timezone,
+			Self::Tags,
+			Self::Reminder,
+		]
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Name => "Course Name",
+			Self::Teacher => "Teacher Name",
+			Self::Room => "Room ID",
+			Self::Timezone => "Time Zone (blank for the application's)",
+			Self::Tags => "Tags (separated by commas)",
+			Self::Reminder => "Remind Minutes Before Lessons (blank for the default)",
+		}
+	}
+
+	/// Returns the field as it's typed, for the `course_index`th course.
+	pub fn value(self, application: &Application, course_index: usize) -> String {
+		let course = &application.courses[course_index];
+		match self {
+			Self::Name => course.name.clone(),
+			Self::Teacher => course.teacher.clone(),
+			Self::Room => course.room.clone(),
+			Self::Timezone => course
+				.timezone
+				.map(|zone| zone.to_string())
+				.unwrap_or_default(),
+			Self::Tags => course.tags.join(", "),
+			Self::Reminder => course
+				.reminder_minutes
+				.map(|minutes| minutes.to_string())
+				.unwrap_or_default(),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// The details of a task that can be typed in, from the GUI's task window.
+pub(super) enum TaskField {
+	Name,
+	Description,
+	Due,
+	Priority,
+	Tags,
+	Reminder,
+	/// A new due date, which (unlike changing `Due`) remembers when it was first due.
+	Snooze,
+}
+
+impl TaskField {
+	pub fn all() -> [Self; 7] {
+		[
+			Self::Name,
+			Self::Description,
+			Self::Due,
+			Self::Priority,
+			Self::Tags,
+			Self::Reminder,
+			Self::Snooze,
+		]
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Name => "Task Name",
+			Self::Description => "Description",
+			Self::Due => "Due Date",
+			Self::Priority => "Priority (low, normal, high or urgent)",
+			Self::Tags => "Tags (separated by commas)",
+			Self::Reminder => "Remind Days Before Due (blank for the default)",
+			Self::Snooze => "Snooze Until (a new due date, keeping the first one)",
+		}
+	}
+
+	/// Returns the field as it's typed, for the task at (`course_index`, `task_index`).
+	pub fn value(
+		self,
+		application: &Application,
+		course_index: usize,
+		task_index: usize,
+	) -> String {
+		let task = &application.courses[course_index].tasks[task_index];
+		match self {
+			Self::Name => task.name.clone(),
+			Self::Description => task.desc.clone(),
+			Self::Due => application.locale.format_date(task.due),
+			Self::Priority => task.priority.to_string(),
+			Self::Tags => task.tags.join(", "),
+			Self::Reminder => task
+				.reminder_days
+				.map(|days| days.to_string())
+				.unwrap_or_default(),
+			// There's nothing to change, only a new date to type in
+			Self::Snooze => String::new(),
+		}
+	}
+}
+
+/// A line of text being typed in, with a cursor that can be moved around.
+pub(super) struct Prompt {
+	/// What's being typed, like "Course Name".
+	pub label: String,
+	/// Kept as characters rather than a String, so the cursor can't end up in the middle of one.
+	text: Vec<char>,
+	/// Which character the cursor is before. `text.len()` is the end.
+	pub cursor: usize,
+}
+
+impl Prompt {
+	/// A prompt with `text` already typed, and the cursor at the end.
+	pub fn new<T: Into<String>>(label: T, text: &str) -> Self {
+		let text: Vec<char> = text.chars().collect();
+		Self {
+			label: label.into(),
+			cursor: text.len(),
+			text,
+		}
+	}
+
+	pub fn text(&self) -> String {
+		self.text.iter().collect()
+	}
+
+	/// Handles a key for typing, returning false if it isn't one (like Enter).
+	pub fn key(&mut self, code: KeyCode) -> bool {
+		match code {
+			KeyCode::Char(c) => {
+				self.text.insert(self.cursor, c);
+				self.cursor += 1;
+			}
+			KeyCode::Backspace if self.cursor > 0 => {
+				self.cursor -= 1;
+				self.text.remove(self.cursor);
+			}
+			KeyCode::Delete if self.cursor < self.text.len() => {
+				self.text.remove(self.cursor);
+			}
+			KeyCode::Left if self.cursor > 0 => self.cursor -= 1,
+			KeyCode::Right if self.cursor < self.text.len() => self.cursor += 1,
+			KeyCode::Home => self.cursor = 0,
+			KeyCode::End => self.cursor = self.text.len(),
+			KeyCode::Backspace | KeyCode::Delete | KeyCode::Left | KeyCode::Right => (),
+			_ => return false,
+		}
+		true
+	}
+}
+
+impl Target {
+	/// Returns a prompt for typing in a new value for this row, or `None` if it isn't something that's typed.
+	pub fn prompt(self, application: &Application) -> Option<Prompt> {
+		let locale = &application.locale;
+		match self {
+			Target::CourseField(course_index, field) => Some(Prompt::new(
+				field.as_str(),
+				&field.value(application, course_index),
+			)),
+			Target::TaskField(course_index, task_index, field) => Some(Prompt::new(
+				field.as_str(),
+				&field.value(application, course_index, task_index),
+			)),
+			Target::Time(course_index, time_index) => {
+				let (day, start, end) = application.courses[course_index].times[time_index];
+				Some(Prompt::new(
+					"Time (day, then start 'to' end)",
+					&format!(
+						"{} {} to {}",
+						day,
+						locale.format_time(start),
+						locale.format_time(end)
+					),
+				))
+			}
+			Target::Holiday(index) => {
+				let (start, end) = application.holidays[index];
+				Some(Prompt::new(
+					"Holiday (start 'to' end)",
+					&format!(
+						"{} to {}",
+						locale.format_date(start),
+						locale.format_date(end)
+					),
+				))
+			}
+			Target::Step(course_index, task_index, step_index) => Some(Prompt::new(
+				"Step",
+				&application.courses[course_index].tasks[task_index].steps[step_index].desc,
+			)),
+			Target::AddDependency(..) => Some(Prompt::new("Depends on (course: task)", "")),
+			_ => None,
+		}
+	}
+
+	/// Changes this row to what was typed in `text`, as one edit that can be undone.
+	/// Returns a message for the user if it can't be understood.
+	pub fn set(self, application: &mut Application, text: &str) -> Result<(), String> {
+		let today = application.today();
+		let locale = application.locale.clone();
+
+		match self {
+			Target::CourseField(course_index, field) => {
+				let text = text.to_string();
+				match field {
+					CourseField::Timezone => {
+						let timezone = if text.trim().is_empty() {
+							None
+						} else {
+							Some(Zone::try_from(text).map_err(|_| {
+								"Time zone invalid. Use a name like 'Europe/London' or 'UTC'."
+									.to_string()
+							})?)
+						};
+						application.edit_course(course_index, "Edit course", |course| {
+							course.timezone = timezone
+						});
+					}
+					CourseField::Reminder => {
+						let reminder = parse_optional_number(&text, MAX_REMINDER_MINUTES)
+							.map_err(|_| {
+								"Reminder invalid. Use a whole number of minutes up to a week (10080), or leave it blank.".to_string()
+							})?;
+						application.edit_course(course_index, "Edit course", |course| {
+							course.reminder_minutes = reminder
+						});
+					}
+					_ => {
+						application.edit_course(course_index, "Edit course", |course| match field {
+							CourseField::Name => course.name = text,
+							CourseField::Teacher => course.teacher = text,
+							CourseField::Room => course.room = text,
+							_ => course.tags = parse_tags(&text),
+						})
+					}
+				}
+			}
+			Target::TaskField(course_index, task_index, field) => {
+				let text = text.to_string();
+				match field {
+					TaskField::Due => {
+						let due = locale
+							.parse_date(&text, today)
+							.map_err(|_| format!("Date entry invalid. {}", DATE_HELP))?;
+						application.edit_task(course_index, task_index, "Edit task", |task| {
+							task.due = due
+						});
+					}
+					TaskField::Snooze => {
+						let due = locale
+							.parse_date(&text, today)
+							.map_err(|_| format!("Date entry invalid. {}", DATE_HELP))?;
+						application.snooze_tasks(&[(course_index, task_index)], due);
+					}
+					TaskField::Priority => {
+						let priority = Priority::try_from(text).map_err(|_| {
+							"Priority invalid. Use low, normal, high or urgent.".to_string()
+						})?;
+						application.edit_task(course_index, task_index, "Edit task", |task| {
+							task.priority = priority
+						});
+					}
+					TaskField::Reminder => {
+						let reminder =
+							parse_optional_number(&text, MAX_REMINDER_DAYS).map_err(|_| {
+								"Reminder invalid. Use a whole number of days up to 365, or leave it blank.".to_string()
+							})?;
+						application.edit_task(course_index, task_index, "Edit task", |task| {
+							task.reminder_days = reminder
+						});
+					}
+					_ => application.edit_task(course_index, task_index, "Edit task", |task| {
+						match field {
+							TaskField::Name => task.name = text,
+							TaskField::Description => task.desc = text,
+							_ => task.tags = parse_tags(&text),
+						}
+					}),
+				}
+			}
+			Target::Time(course_index, time_index) => {
+				// Like "Monday 9:00 to 10:30", where the times can have spaces in them too ("9:00 AM")
+				let text = text.trim();
+				let invalid = || {
+					"Time/Day entry invalid. Try 'Monday 9:00 to 10:30', or '3pm' or '15h30' for times.".to_string()
+				};
+				let space = text.find(' ').ok_or_else(invalid)?;
+				let (day, times) = text.split_at(space);
+				let mut times = times.splitn(2, " to ");
+				let (day, start, end) = match (
+					Day::parse(day),
+					Time::parse(times.next().unwrap_or("")),
+					Time::parse(times.next().unwrap_or("")),
+				) {
+					(Ok(day), Ok(start), Ok(end)) => (day, start, end),
+					_ => return Err(invalid()),
+				};
+				if start > end {
+					return Err("Start time greater than end time!".to_string());
+				}
+				application.edit_course(course_index, "Edit time", |course| {
+					course.times[time_index] = (day, start, end)
+				});
+			}
+			Target::Holiday(index) => {
+				let mut dates = text.splitn(2, " to ");
+				let (start, end) = match (
+					locale.parse_date(dates.next().unwrap_or(""), today),
+					locale.parse_date(dates.next().unwrap_or(""), today),
+				) {
+					(Ok(start), Ok(end)) => (start, end),
+					_ => return Err(format!("Date entry invalid. {}", DATE_HELP)),
+				};
+				if start > end {
+					return Err("Start date greater than end date!".to_string());
+				}
+				application
+					.edit_holidays("Edit holiday", |holidays| holidays[index] = (start, end));
+			}
+			Target::Step(course_index, task_index, step_index) => {
+				let text = text.to_string();
+				application.edit_task(course_index, task_index, "Edit step", |task| {
+					task.steps[step_index].desc = text
+				});
+			}
+			Target::AddDependency(course_index, task_index) => {
+				let prerequisite = find_task(application, text).ok_or_else(|| {
+					format!("No task called '{}'. Try 'Course: Task'.", text.trim())
+				})?;
+				application.add_dependency((course_index, task_index), prerequisite)?;
+			}
+			_ => (),
+		}
+		Ok(())
+	}
+}
+
+/// Finds a task from text like "Math: Essay", or just "Essay" for the first task with that name in any course.
+/// Names are matched ignoring case. Returns (course index, task index).
+fn find_task(application: &Application, text: &str) -> Option<(usize, usize)> {
+	let (course_name, task_name) = match text.find(':') {
+		Some(colon) => (Some(text[..colon].trim()), text[colon + 1..].trim()),
+		None => (None, text.trim()),
+	};
+	let same = |a: &str, b: &str| a.to_lowercase() == b.to_lowercase();
+
+	for (course_index, course) in application.courses.iter().enumerate() {
+		if let Some(course_name) = course_name {
+			if !same(&course.name, course_name) {
+				continue;
+			}
+		}
+		if let Some(task_index) = course
+			.tasks
+			.iter()
+			.position(|task| same(&task.name, task_name))
+		{
+			return Some((course_index, task_index));
+		}
+	}
+	None
+}
@@ -0,0 +1,190 @@
+//! This module contains the terminal interface, used when the scheduler is run with `tui`.
+//! It has the same four menus as the GUI, for when GTK isn't available (like over SSH).
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::convert::TryFrom;
+use std::io::{stdout, Write};
+
+use crate::application::{
+	parse_optional_number, parse_tags, Application, Priority, Task, TaskQuery, TaskSort,
+	MAX_REMINDER_DAYS, MAX_REMINDER_MINUTES,
+};
+use crate::clock::*;
+use edit::*;
+use screen::Tui;
+use view::*;
+
+/// For typing in new values, and what they change.
+mod edit;
+/// For the state of the TUI, and what every key does.
+mod screen;
+/// For the four menus and the course and task views, as lists of rows.
+mod view;
+
+// The TUI is laid out like so:
+//
+//  1 Main | 2 Weekly | 3 Courses | 4 Holidays           <- the menus, with the open one highlighted
+//  Courses > Math                                       <- where we are
+//  Details                                              <- the rows, with the selected one highlighted
+//    Course Name: Math
+//    ...
+//  Saved to 'scheduler.bin'.                            <- messages, or what's being typed
+//  Enter: edit/open  d: remove  Esc: back ...           <- the keys
+//
+// The whole screen is drawn again after every key, which is plenty fast for a list of courses
+
+/// Puts the terminal back to normal when dropped, even if something panics, so it isn't left in a mess.
+struct RawTerminal;
+
+impl RawTerminal {
+	fn start() -> crossterm::Result<Self> {
+		terminal::enable_raw_mode()?;
+		execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+		Ok(Self)
+	}
+}
+
+impl Drop for RawTerminal {
+	fn drop(&mut self) {
+		let _ = execute!(stdout(), LeaveAlternateScreen, cursor::Show);
+		let _ = terminal::disable_raw_mode();
+	}
+}
+
+/// Runs the TUI until the user quits, saving the application to `location` when asked.
+pub fn run(application: Application, location: &str) -> Result<(), String> {
+	let mut tui = Tui::new(application, location);
+	let _terminal =
+		RawTerminal::start().map_err(|error| format!("Couldn't start the TUI: {}", error))?;
+
+	while !tui.quit {
+		draw(&tui).map_err(|error| error.to_string())?;
+		// Resizing and the mouse are events too, and drawing again is all they need
+		if let Event::Key(key) = event::read().map_err(|error| error.to_string())? {
+			tui.key(key);
+		}
+	}
+
+	Ok(())
+}
+
+/// Cuts `text` down to `width` characters, so long lines don't wrap and push everything down.
+fn fit(text: &str, width: usize) -> String {
+	text.chars().take(width).collect()
+}
+
+/// Draws the whole screen.
+fn draw(tui: &Tui) -> crossterm::Result<()> {
+	let (width, height) = terminal::size()?;
+	let (width, height) = (width as usize, height as usize);
+	let mut out = stdout();
+	queue!(out, terminal::Clear(ClearType::All), cursor::Hide)?;
+
+	// The menus along the top
+	queue!(out, cursor::MoveTo(0, 0))?;
+	for (index, menu) in Menu::all().iter().enumerate() {
+		if index > 0 {
+			queue!(out, Print(" | "))?;
+		}
+		let text = format!("{} {}", index + 1, menu.as_str());
+		if *menu == tui.menu {
+			queue!(
+				out,
+				SetAttribute(Attribute::Reverse),
+				Print(text),
+				SetAttribute(Attribute::Reset)
+			)?;
+		} else {
+			queue!(out, Print(text))?;
+		}
+	}
+	if tui.application.is_dirty() {
+		queue!(out, Print("   (unsaved changes)"))?;
+	}
+
+	let title = fit(&tui.view.title(&tui.application), width);
+	queue!(
+		out,
+		cursor::MoveTo(0, 1),
+		SetAttribute(Attribute::Bold),
+		Print(title),
+		SetAttribute(Attribute::Reset)
+	)?;
+
+	// The rows, scrolled so the selected one is always on the screen
+	let items = tui.items();
+	let space = height.saturating_sub(5).max(1);
+	let selected = tui.selected_line();
+	let first = match selected {
+		Some(line) if line >= space => line + 1 - space,
+		_ => 0,
+	};
+	for (row, (line, item)) in items.iter().enumerate().skip(first).take(space).enumerate() {
+		queue!(out, cursor::MoveTo(0, row as u16 + 2))?;
+		match item {
+			Item::Heading(text) => queue!(
+				out,
+				SetAttribute(Attribute::Bold),
+				SetAttribute(Attribute::Underlined),
+				Print(fit(text, width)),
+				SetAttribute(Attribute::Reset)
+			)?,
+			Item::Row(text, _) if Some(line) == selected => queue!(
+				out,
+				SetAttribute(Attribute::Reverse),
+				Print(fit(&format!("  {}", text), width)),
+				SetAttribute(Attribute::Reset)
+			)?,
+			Item::Row(text, _) => queue!(out, Print(fit(&format!("  {}", text), width)))?,
+		}
+	}
+
+	// The keys, and above them the message or what's being typed
+	// The prompt goes last, so the cursor is left where the typing is
+	let bottom = height.saturating_sub(1) as u16;
+	queue!(
+		out,
+		cursor::MoveTo(0, bottom),
+		SetAttribute(Attribute::Dim),
+		Print(fit(tui.help(), width)),
+		SetAttribute(Attribute::Reset)
+	)?;
+	match tui.prompt() {
+		Some(prompt) => {
+			// Mistakes are shown just above what's being typed
+			queue!(
+				out,
+				cursor::MoveTo(0, bottom.saturating_sub(2)),
+				Print(fit(&tui.message, width))
+			)?;
+
+			let label = format!("{}: ", prompt.label);
+			// If the text is too long, only the part around the cursor fits
+			let skip = (label.chars().count() + prompt.cursor + 1).saturating_sub(width);
+			let line: String = label
+				.chars()
+				.chain(prompt.text().chars())
+				.skip(skip)
+				.collect();
+			let column = label.chars().count() + prompt.cursor - skip;
+			queue!(
+				out,
+				cursor::MoveTo(0, bottom.saturating_sub(1)),
+				Print(fit(&line, width)),
+				cursor::MoveTo(column as u16, bottom.saturating_sub(1)),
+				cursor::Show
+			)?;
+		}
+		None => queue!(
+			out,
+			cursor::MoveTo(0, bottom.saturating_sub(1)),
+			Print(fit(&tui.message, width))
+		)?,
+	}
+
+	out.flush()?;
+	Ok(())
+}
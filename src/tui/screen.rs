@@ -0,0 +1,731 @@
+use super::*;
+
+// This is everything the TUI knows, and what every key does to it
+// Nothing in here touches the terminal, which is what mod.rs is for, so the tests can press keys without one
+
+/// What the keys are doing at the moment.
+enum Mode {
+	/// Moving around and opening things.
+	Normal,
+	/// Typing in a new value for a row.
+	Editing(Target, Prompt),
+	/// Waiting for a yes or no.
+	Confirm(Question),
+}
+
+/// Something that needs a yes or no before it's done.
+enum Question {
+	/// Removing a course, time, task, step or holiday.
+	Remove(Target),
+	/// Quitting with unsaved changes. Yes saves them, no throws them away.
+	Quit,
+}
+
+/// The state of the TUI, which owns the application while it's running.
+pub(super) struct Tui {
+	pub application: Application,
+	/// Where the application is saved.
+	location: String,
+	/// Which of the four menus is open, even if a course or task is open on top of it.
+	pub menu: Menu,
+	pub view: View,
+	/// The views to go back to with Escape, and which row was selected in each.
+	back: Vec<(View, usize)>,
+	/// Which row is selected, counting only rows (not headings).
+	selected: usize,
+	mode: Mode,
+	/// A message for the user, shown at the bottom until the next key.
+	pub message: String,
+	/// Whether it's time to stop.
+	pub quit: bool,
+}
+
+impl Tui {
+	pub fn new<T: Into<String>>(application: Application, location: T) -> Self {
+		Self {
+			application,
+			location: location.into(),
+			menu: Menu::Main,
+			view: View::Menu(Menu::Main),
+			back: Vec::new(),
+			selected: 0,
+			mode: Mode::Normal,
+			message: String::new(),
+			quit: false,
+		}
+	}
+
+	/// Every line of the current view.
+	pub fn items(&self) -> Vec<Item> {
+		self.view.items(&self.application)
+	}
+
+	/// Returns which line the selected row is on, counting headings this time.
+	pub fn selected_line(&self) -> Option<usize> {
+		self.items()
+			.iter()
+			.enumerate()
+			.filter(|(_, item)| match item {
+				Item::Row(..) => true,
+				Item::Heading(_) => false,
+			})
+			.nth(self.selected)
+			.map(|(line, _)| line)
+	}
+
+	/// Returns what the selected row is about.
+	fn target(&self) -> Target {
+		self.items()
+			.into_iter()
+			.filter_map(|item| match item {
+				Item::Row(_, target) => Some(target),
+				Item::Heading(_) => None,
+			})
+			.nth(self.selected)
+			.unwrap_or(Target::Nothing)
+	}
+
+	/// How many rows there are to select from.
+	fn rows(&self) -> usize {
+		self.items()
+			.iter()
+			.filter(|item| match item {
+				Item::Row(..) => true,
+				Item::Heading(_) => false,
+			})
+			.count()
+	}
+
+	/// Returns what's being typed, if anything.
+	pub fn prompt(&self) -> Option<&Prompt> {
+		match &self.mode {
+			Mode::Editing(_, prompt) => Some(prompt),
+			_ => None,
+		}
+	}
+
+	/// The line at the bottom saying which keys do what, which changes with what's going on.
+	pub fn help(&self) -> &'static str {
+		match (&self.mode, self.view) {
+			(Mode::Editing(..), _) => "Enter: save  Esc: cancel",
+			(Mode::Confirm(Question::Quit), _) => "y: save and quit  n: quit without saving  Esc: cancel",
+			(Mode::Confirm(_), _) => "y: yes  n: no",
+			(Mode::Normal, View::Menu(_)) => {
+				"1-4/Tab: menu  Enter: open/edit  d: remove  s: save  u/r: undo/redo  q: quit"
+			}
+			(Mode::Normal, View::Course(_)) => {
+				"Enter: edit/open  d: remove  Esc: back  s: save  u/r: undo/redo  q: quit"
+			}
+			(Mode::Normal, View::Task(..)) => {
+				"Enter: edit  Space: tick  J/K: move  </>: out/in  d: remove  Esc: back  s: save  u/r: undo/redo  q: quit"
+			}
+		}
+	}
+
+	/// Does whatever `key` does.
+	pub fn key(&mut self, key: KeyEvent) {
+		self.message.clear();
+
+		// Taking the mode out lets us change self while looking at it, and it's put back (or replaced) after
+		match std::mem::replace(&mut self.mode, Mode::Normal) {
+			Mode::Normal => self.normal_key(key),
+			Mode::Editing(target, mut prompt) => match key.code {
+				KeyCode::Enter => {
+					if let Err(message) = target.set(&mut self.application, &prompt.text()) {
+						self.message = message;
+						// Keep what was typed, so the mistake can be fixed instead of typed again
+						self.mode = Mode::Editing(target, prompt);
+					}
+				}
+				KeyCode::Esc => (),
+				code => {
+					prompt.key(code);
+					self.mode = Mode::Editing(target, prompt);
+				}
+			},
+			Mode::Confirm(question) => match (key.code, question) {
+				(KeyCode::Char('y'), Question::Remove(target)) => self.remove(target),
+				(KeyCode::Char('y'), Question::Quit) => {
+					self.save();
+					self.quit = true;
+				}
+				(KeyCode::Char('n'), Question::Quit) => self.quit = true,
+				(KeyCode::Char('n'), _) | (KeyCode::Esc, _) => (),
+				(_, question) => self.mode = Mode::Confirm(question),
+			},
+		}
+
+		self.refresh();
+
+		// Questions stay on the screen until they're answered
+		match self.mode {
+			Mode::Confirm(Question::Remove(target)) => {
+				self.message = format!(
+					"Remove {}? Ctrl+Z or 'u' undoes this.",
+					self.describe(target)
+				);
+			}
+			Mode::Confirm(Question::Quit) => {
+				self.message = "There are unsaved changes. Save them before quitting?".to_string();
+			}
+			_ => (),
+		}
+	}
+
+	/// Handles a key when nothing is being typed or asked.
+	fn normal_key(&mut self, key: KeyEvent) {
+		let control = key.modifiers.contains(KeyModifiers::CONTROL);
+		match key.code {
+			KeyCode::Char('c') if control => self.ask_quit(),
+			KeyCode::Char('s') if control => self.save(),
+			KeyCode::Char('z') if control => self.undo(),
+			KeyCode::Char('y') if control => self.redo(),
+			KeyCode::Char('q') => self.ask_quit(),
+			KeyCode::Char('s') => self.save(),
+			KeyCode::Char('u') => self.undo(),
+			KeyCode::Char('r') => self.redo(),
+
+			KeyCode::Char(c @ '1'..='4') => self.open_menu(Menu::all()[c as usize - '1' as usize]),
+			KeyCode::Tab => {
+				let index = self.menu as usize;
+				self.open_menu(Menu::all()[(index + 1) % 4]);
+			}
+			KeyCode::BackTab => {
+				let index = self.menu as usize;
+				self.open_menu(Menu::all()[(index + 3) % 4]);
+			}
+
+			KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+			KeyCode::Down | KeyCode::Char('j') => self.selected += 1,
+			KeyCode::PageUp => self.selected = self.selected.saturating_sub(10),
+			KeyCode::PageDown => self.selected += 10,
+			KeyCode::Home => self.selected = 0,
+			KeyCode::End => self.selected = self.rows().saturating_sub(1),
+
+			KeyCode::Enter | KeyCode::Char('e') => self.activate(self.target()),
+			KeyCode::Char(' ') => self.tick(self.target()),
+			KeyCode::Char('K') => self.move_step(self.target(), "Move step up", Task::move_step_up),
+			KeyCode::Char('J') => {
+				self.move_step(self.target(), "Move step down", Task::move_step_down)
+			}
+			KeyCode::Char('>') => self.move_step(self.target(), "Indent step", Task::indent_step),
+			KeyCode::Char('<') => self.move_step(self.target(), "Outdent step", Task::outdent_step),
+			KeyCode::Char('d') | KeyCode::Delete => match self.target() {
+				Target::Course(_)
+				| Target::Holiday(_)
+				| Target::Time(..)
+				| Target::Task(..)
+				| Target::Step(..)
+				| Target::Dependency(..) => self.mode = Mode::Confirm(Question::Remove(self.target())),
+				_ => (),
+			},
+			KeyCode::Esc | KeyCode::Backspace | KeyCode::Left => self.go_back(),
+			_ => (),
+		}
+	}
+
+	/// Does what Enter does to a row: opens it, adds something, or starts typing a new value.
+	fn activate(&mut self, target: Target) {
+		match target {
+			Target::Course(course_index) => self.open(View::Course(course_index)),
+			Target::Task(course_index, task_index) => {
+				self.open(View::Task(course_index, task_index))
+			}
+			Target::AddCourse => {
+				self.application.new_course();
+				self.open(View::Course(self.application.courses.len() - 1));
+			}
+			Target::AddTask(course_index) => {
+				self.application
+					.edit_course(course_index, "Add task", |course| course.new_task());
+				let task_index = self.application.courses[course_index].tasks.len() - 1;
+				self.open(View::Task(course_index, task_index));
+			}
+			// New times, holidays and steps start with something useless in them, so they're edited straight away
+			Target::AddTime(course_index) => {
+				self.application
+					.edit_course(course_index, "Add time", |course| course.new_time());
+				let time_index = self.application.courses[course_index].times.len() - 1;
+				self.edit(Target::Time(course_index, time_index));
+			}
+			Target::AddHoliday => {
+				self.application.new_holiday();
+				self.edit(Target::Holiday(self.application.holidays.len() - 1));
+			}
+			Target::AddStep(course_index, task_index) => {
+				self.application
+					.edit_task(course_index, task_index, "Add step", |task| task.new_step());
+				let step_index = self.application.courses[course_index].tasks[task_index]
+					.steps
+					.len() - 1;
+				self.edit(Target::Step(course_index, task_index, step_index));
+			}
+			Target::TaskDone(..) => self.tick(target),
+			// A dependency opens the task it's on, which is usually what's wanted next
+			Target::Dependency(course_index, task_index, index) => {
+				if let Some(&(other_course, other_task)) = self
+					.application
+					.prerequisites(course_index, task_index)
+					.get(index)
+				{
+					self.open(View::Task(other_course, other_task));
+				}
+			}
+			target => self.edit(target),
+		}
+	}
+
+	/// Ticks (or un-ticks) a step, or a task without any steps.
+	fn tick(&mut self, target: Target) {
+		match target {
+			Target::Step(course_index, task_index, step_index) => {
+				self.application
+					.edit_task(course_index, task_index, "Tick step", |task| {
+						task.toggle_step(step_index)
+					});
+			}
+			Target::TaskDone(course_index, task_index) => {
+				self.application
+					.edit_task(course_index, task_index, "Tick task", |task| {
+						task.set_complete(!task.complete)
+					});
+			}
+			_ => (),
+		}
+	}
+
+	/// Starts typing a new value for `target`, and selects its row so it's clear what's being edited.
+	fn edit(&mut self, target: Target) {
+		if let Some(prompt) = target.prompt(&self.application) {
+			self.select(target);
+			self.mode = Mode::Editing(target, prompt);
+		}
+	}
+
+	/// Selects the row for `target`, if there is one.
+	fn select(&mut self, target: Target) {
+		if let Some(row) = self
+			.items()
+			.iter()
+			.filter_map(|item| match item {
+				Item::Row(_, target) => Some(*target),
+				Item::Heading(_) => None,
+			})
+			.position(|other| other == target)
+		{
+			self.selected = row;
+		}
+	}
+
+	/// Moves a step (and its sub-steps) with `how`, and keeps it selected wherever it ends up.
+	/// Steps can only move among the steps they're next to, so at the top or bottom nothing happens.
+	fn move_step(
+		&mut self,
+		target: Target,
+		label: &str,
+		how: fn(&mut Task, usize) -> Result<usize, ()>,
+	) {
+		if let Target::Step(course_index, task_index, step_index) = target {
+			// Trying it on a copy first, so a move that can't happen doesn't leave anything to undo
+			let mut task = self.application.courses[course_index].tasks[task_index].clone();
+			match how(&mut task, step_index) {
+				Ok(new_index) => {
+					self.application
+						.edit_task(course_index, task_index, label, |old| *old = task);
+					self.select(Target::Step(course_index, task_index, new_index));
+				}
+				Err(()) => self.message = "That step can't go any further that way.".to_string(),
+			}
+		}
+	}
+
+	/// Removes whatever `target` is about, as an edit that can be undone.
+	fn remove(&mut self, target: Target) {
+		let label = format!("Remove {}", self.describe(target));
+		match target {
+			Target::Course(course_index) => self.application.rm_course(course_index),
+			Target::Holiday(index) => self.application.rm_holiday(index),
+			Target::Time(course_index, time_index) => {
+				self.application
+					.edit_course(course_index, label, |course| course.rm_time(time_index))
+			}
+			Target::Task(course_index, task_index) => {
+				self.application
+					.edit_course(course_index, label, |course| course.rm_task(task_index))
+			}
+			Target::Step(course_index, task_index, step_index) => {
+				self.application
+					.edit_task(course_index, task_index, label, |task| {
+						task.rm_step(step_index)
+					})
+			}
+			Target::Dependency(course_index, task_index, index) => {
+				let prerequisite = self.application.prerequisites(course_index, task_index)[index];
+				self.application
+					.rm_dependency((course_index, task_index), prerequisite);
+			}
+			_ => (),
+		}
+	}
+
+	/// A short description of `target`, like "task 'Homework'".
+	fn describe(&self, target: Target) -> String {
+		let courses = &self.application.courses;
+		match target {
+			Target::Course(course_index) => format!("course '{}'", courses[course_index].name),
+			Target::Holiday(_) => "holiday".to_string(),
+			Target::Time(..) => "time".to_string(),
+			Target::Task(course_index, task_index) => {
+				format!("task '{}'", courses[course_index].tasks[task_index].name)
+			}
+			Target::Step(..) => "step".to_string(),
+			Target::Dependency(course_index, task_index, index) => {
+				let (other_course, other_task) =
+					self.application.prerequisites(course_index, task_index)[index];
+				format!(
+					"dependency on '{}'",
+					courses[other_course].tasks[other_task].name
+				)
+			}
+			_ => String::new(),
+		}
+	}
+
+	/// Opens `view` on top of the current one, so Escape comes back here.
+	fn open(&mut self, view: View) {
+		self.back.push((self.view, self.selected));
+		self.view = view;
+		self.selected = 0;
+	}
+
+	/// Goes back to the view before this one, if there is one.
+	fn go_back(&mut self) {
+		if let Some((view, selected)) = self.back.pop() {
+			self.view = view;
+			self.selected = selected;
+		}
+	}
+
+	/// Switches to one of the four menus, closing any course or task that's open.
+	fn open_menu(&mut self, menu: Menu) {
+		self.menu = menu;
+		self.view = View::Menu(menu);
+		self.back.clear();
+		self.selected = 0;
+	}
+
+	fn save(&mut self) {
+		self.application.save(&self.location);
+		self.message = format!("Saved to '{}'.", self.location);
+	}
+
+	fn undo(&mut self) {
+		self.message = match self.application.undo() {
+			Some(label) => format!("Undid: {}", label),
+			None => "Nothing to undo.".to_string(),
+		};
+	}
+
+	fn redo(&mut self) {
+		self.message = match self.application.redo() {
+			Some(label) => format!("Redid: {}", label),
+			None => "Nothing to redo.".to_string(),
+		};
+	}
+
+	/// Quits, asking about unsaved changes first if there are any.
+	fn ask_quit(&mut self) {
+		if self.application.is_dirty() {
+			self.mode = Mode::Confirm(Question::Quit);
+		} else {
+			self.quit = true;
+		}
+	}
+
+	/// Makes sure everything still makes sense after a key, since undoing can remove what's open.
+	fn refresh(&mut self) {
+		while !self.view.exists(&self.application) {
+			if self.back.is_empty() {
+				self.view = View::Menu(self.menu);
+			} else {
+				self.go_back();
+			}
+			self.mode = Mode::Normal;
+		}
+		self.selected = self.selected.min(self.rows().saturating_sub(1));
+	}
+}
+
+//  XXXXXXXX XXXXXX   XXXXX XXXXXXXX  XXXXX
+//     XX    XX      XX        XX    XX
+//     XX    XXXXX   XXXXXX    XX    XXXXXX
+//     XX    XX          XX    XX        XX
+//     XX    XXXXXX  XXXXX     XX    XXXXX
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::application::Course;
+
+	fn press(tui: &mut Tui, keys: &[KeyCode]) {
+		for code in keys {
+			tui.key(KeyEvent::new(*code, KeyModifiers::NONE));
+		}
+	}
+
+	fn type_text(tui: &mut Tui, text: &str) {
+		for c in text.chars() {
+			press(tui, &[KeyCode::Char(c)]);
+		}
+	}
+
+	fn rows(tui: &Tui) -> Vec<String> {
+		tui.items()
+			.into_iter()
+			.filter_map(|item| match item {
+				Item::Row(text, _) => Some(text),
+				Item::Heading(_) => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn adding_and_editing_a_course() {
+		let mut tui = Tui::new(Application::default(), "/nonexistent/tui.bin");
+
+		// Courses menu, then "+ Add course" opens the new course
+		press(&mut tui, &[KeyCode::Char('3'), KeyCode::Enter]);
+		assert_eq!(tui.view, View::Course(0));
+
+		// The first row is the name, and the old name is already typed in
+		press(&mut tui, &[KeyCode::Enter]);
+		for _ in 0..tui.application.courses[0].name.chars().count() {
+			press(&mut tui, &[KeyCode::Backspace]);
+		}
+		type_text(&mut tui, "Math");
+		press(&mut tui, &[KeyCode::Enter]);
+		assert_eq!(tui.application.courses[0].name, "Math");
+
+		// "+ Add time" is after the six details, and starts typing the new time straight away
+		press(&mut tui, &[KeyCode::End, KeyCode::Up, KeyCode::Enter]);
+		let length = tui.prompt().unwrap().text().len();
+		for _ in 0..length {
+			press(&mut tui, &[KeyCode::Backspace]);
+		}
+		type_text(&mut tui, "monday 9:00 to 10:30");
+		press(&mut tui, &[KeyCode::Enter]);
+		assert_eq!(
+			tui.application.courses[0].times,
+			vec![(
+				Day::Monday,
+				Time::new(9, 0).unwrap(),
+				Time::new(10, 30).unwrap()
+			)]
+		);
+
+		// Escape goes back to the list of courses
+		press(&mut tui, &[KeyCode::Esc]);
+		assert_eq!(tui.view, View::Menu(Menu::Courses));
+		assert!(rows(&tui)[0].starts_with("Math"));
+	}
+
+	#[test]
+	fn mistakes_are_kept() {
+		let mut application = Application::default();
+		application.add_holiday(
+			Date::new(2020, 3, 1).unwrap(),
+			Date::new(2020, 3, 7).unwrap(),
+		);
+		let mut tui = Tui::new(application, "/nonexistent/tui.bin");
+
+		press(
+			&mut tui,
+			&[KeyCode::Char('4'), KeyCode::Enter, KeyCode::End],
+		);
+		type_text(&mut tui, "blah");
+		press(&mut tui, &[KeyCode::Enter]);
+
+		// The holiday is still the same, and what was typed is still there to fix
+		assert!(tui.message.starts_with("Date entry invalid"));
+		assert_eq!(tui.prompt().unwrap().text(), "2020-03-01 to 2020-03-07blah");
+		press(&mut tui, &[KeyCode::Esc]);
+		assert!(tui.prompt().is_none());
+		assert_eq!(rows(&tui)[0], "2020-03-01 to 2020-03-07");
+	}
+
+	#[test]
+	fn steps_and_undo() {
+		let mut application = Application::default();
+		let mut course = Course::new("Math", "Willis", "S37");
+		course.add_task("Homework", "", Date::new(2020, 3, 1).unwrap());
+		application.add_course(course);
+		let mut tui = Tui::new(application, "/nonexistent/tui.bin");
+
+		// Courses > Math > (past the details and "+ Add time") Homework
+		press(&mut tui, &[KeyCode::Char('3'), KeyCode::Enter]);
+		press(&mut tui, &[KeyCode::End, KeyCode::Up, KeyCode::Enter]);
+		assert_eq!(tui.view, View::Task(0, 0));
+
+		// A task without steps has one box to tick after the seven details
+		for _ in 0..7 {
+			press(&mut tui, &[KeyCode::Down]);
+		}
+		press(&mut tui, &[KeyCode::Char(' ')]);
+		assert!(tui.application.courses[0].tasks[0].is_complete());
+
+		// Adding a step that isn't done yet means the task isn't either
+		press(&mut tui, &[KeyCode::Down, KeyCode::Enter, KeyCode::Enter]);
+		assert_eq!(tui.application.courses[0].tasks[0].steps.len(), 1);
+		assert!(!tui.application.courses[0].tasks[0].is_complete());
+
+		// Removing asks first
+		press(&mut tui, &[KeyCode::Esc, KeyCode::Char('d')]);
+		assert_eq!(tui.application.courses[0].tasks.len(), 1);
+		press(&mut tui, &[KeyCode::Char('y')]);
+		assert!(tui.application.courses[0].tasks.is_empty());
+
+		// Undoing everything (including adding the course) goes back to the menu, since the course is gone
+		press(
+			&mut tui,
+			&[KeyCode::Char('u'), KeyCode::Char('u'), KeyCode::Char('u')],
+		);
+		assert!(tui.application.courses[0].tasks[0].steps.is_empty());
+		assert!(tui.application.courses[0].tasks[0].is_complete());
+		press(&mut tui, &[KeyCode::Enter]);
+		assert_eq!(tui.view, View::Task(0, 0));
+		press(&mut tui, &[KeyCode::Char('u')]);
+		assert!(!tui.application.courses[0].tasks[0].is_complete());
+		press(&mut tui, &[KeyCode::Char('u')]);
+		assert_eq!(tui.view, View::Menu(Menu::Courses));
+		assert!(tui.application.courses.is_empty());
+	}
+
+	#[test]
+	fn dependencies() {
+		let mut application = Application::default();
+		let mut math = Course::new("Math", "Willis", "S37");
+		math.add_task("Draft", "", Date::new(2020, 3, 1).unwrap());
+		math.add_task("Final", "", Date::new(2020, 3, 8).unwrap());
+		application.add_course(math);
+		let mut tui = Tui::new(application, "/nonexistent/tui.bin");
+		tui.open(View::Task(0, 1));
+
+		// "+ Add dependency" is the last row
+		press(&mut tui, &[KeyCode::End, KeyCode::Enter]);
+		type_text(&mut tui, "math: draft");
+		press(&mut tui, &[KeyCode::Enter]);
+		assert!(tui.application.is_blocked(0, 1));
+		assert!(tui.items().iter().any(|item| match item {
+			Item::Heading(text) => text == "Depends On (blocked)",
+			_ => false,
+		}));
+
+		// A loop isn't allowed, and the message says why
+		tui.open(View::Task(0, 0));
+		press(&mut tui, &[KeyCode::End, KeyCode::Enter]);
+		type_text(&mut tui, "Final");
+		press(&mut tui, &[KeyCode::Enter]);
+		assert!(tui.application.prerequisites(0, 0).is_empty());
+		assert!(tui.message.contains("can't be the other way around"));
+
+		// Opening the dependency goes to the draft, and removing it unblocks the final
+		press(&mut tui, &[KeyCode::Esc, KeyCode::Esc]);
+		assert_eq!(tui.view, View::Task(0, 1));
+		press(&mut tui, &[KeyCode::End, KeyCode::Up, KeyCode::Enter]);
+		assert_eq!(tui.view, View::Task(0, 0));
+		press(
+			&mut tui,
+			&[KeyCode::Esc, KeyCode::Char('d'), KeyCode::Char('y')],
+		);
+		assert!(!tui.application.is_blocked(0, 1));
+	}
+
+	#[test]
+	fn nesting_steps() {
+		let mut application = Application::default();
+		let mut course = Course::new("Math", "Willis", "S37");
+		course.add_task("Essay", "", Date::new(2020, 3, 1).unwrap());
+		for desc in &["Research", "Find sources", "Write"] {
+			course.tasks[0].add_step(*desc);
+		}
+		application.add_course(course);
+		let mut tui = Tui::new(application, "/nonexistent/tui.bin");
+		tui.open(View::Task(0, 0));
+
+		// Past the seven details, to Find sources, which goes inside Research
+		for _ in 0..8 {
+			press(&mut tui, &[KeyCode::Down]);
+		}
+		press(&mut tui, &[KeyCode::Char('>')]);
+		assert_eq!(tui.application.courses[0].tasks[0].steps[1].depth, 1);
+		assert_eq!(rows(&tui)[8], "  [ ] Find sources");
+
+		// It can't go past Research, but Research can go down past Write, taking it along
+		press(&mut tui, &[KeyCode::Char('K')]);
+		assert_eq!(
+			tui.application.courses[0].tasks[0].steps[1].desc,
+			"Find sources"
+		);
+		press(&mut tui, &[KeyCode::Up, KeyCode::Char('J')]);
+		let steps = &tui.application.courses[0].tasks[0].steps;
+		assert_eq!(steps[0].desc, "Write");
+		assert_eq!(
+			(steps[2].desc.as_str(), steps[2].depth),
+			("Find sources", 1)
+		);
+		// Research stays selected, so it can be moved again
+		assert_eq!(tui.target(), Target::Step(0, 0, 1));
+
+		press(&mut tui, &[KeyCode::Down, KeyCode::Char('<')]);
+		assert_eq!(tui.application.courses[0].tasks[0].steps[2].depth, 0);
+		press(&mut tui, &[KeyCode::Char('u')]);
+		assert_eq!(tui.application.courses[0].tasks[0].steps[2].depth, 1);
+	}
+
+	#[test]
+	fn snoozing() {
+		let mut application = Application::default();
+		let mut course = Course::new("Math", "Willis", "S37");
+		course.add_task("Homework", "", Date::new(2020, 3, 1).unwrap());
+		application.add_course(course);
+		let mut tui = Tui::new(application, "/nonexistent/tui.bin");
+		press(&mut tui, &[KeyCode::Char('3'), KeyCode::Enter]);
+		press(&mut tui, &[KeyCode::End, KeyCode::Up, KeyCode::Enter]);
+
+		// Snoozing is the last of the details, and starts out blank
+		for _ in 0..6 {
+			press(&mut tui, &[KeyCode::Down]);
+		}
+		press(&mut tui, &[KeyCode::Enter]);
+		type_text(&mut tui, "2020-03-08");
+		press(&mut tui, &[KeyCode::Enter]);
+
+		let task = &tui.application.courses[0].tasks[0];
+		assert_eq!(task.due, Date::new(2020, 3, 8).unwrap());
+		assert_eq!(task.original_due, Some(Date::new(2020, 3, 1).unwrap()));
+		assert!(rows(&tui).contains(&"First due 2020-03-01".to_string()));
+	}
+
+	#[test]
+	fn quitting_asks_about_changes() {
+		let mut tui = Tui::new(Application::default(), "/nonexistent/tui.bin");
+		press(&mut tui, &[KeyCode::Char('q')]);
+		assert!(tui.quit);
+
+		let mut tui = Tui::new(Application::default(), "/nonexistent/tui.bin");
+		press(
+			&mut tui,
+			&[
+				KeyCode::Char('4'),
+				KeyCode::Enter,
+				KeyCode::Esc,
+				KeyCode::Char('q'),
+			],
+		);
+		assert!(!tui.quit);
+		press(&mut tui, &[KeyCode::Esc]);
+		assert!(!tui.quit);
+		press(&mut tui, &[KeyCode::Char('q'), KeyCode::Char('n')]);
+		assert!(tui.quit);
+	}
+}
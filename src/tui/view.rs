@@ -0,0 +1,378 @@
+use super::*;
+
+// Every screen in the TUI is a list of rows, with headings between them
+// Each row knows what it's about (its `Target`), so the keys can do the right thing with whatever's selected
+// This is the same as the GUI's ListBoxes, except everything is in one list instead of spread over windows
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// The four menus, the same as the buttons down the side of the GUI.
+pub(super) enum Menu {
+	Main,
+	Weekly,
+	Courses,
+	Holidays,
+}
+
+impl Menu {
+	pub fn all() -> [Self; 4] {
+		[Self::Main, Self::Weekly, Self::Courses, Self::Holidays]
+	}
+
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Main => "Main",
+			Self::Weekly => "Weekly",
+			Self::Courses => "Courses",
+			Self::Holidays => "Holidays",
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// What's on the screen. Courses and tasks open on top of a menu, like the GUI's pop-up windows.
+pub(super) enum View {
+	Menu(Menu),
+	/// The course at this index.
+	Course(usize),
+	/// The task at (course index, task index).
+	Task(usize, usize),
+}
+
+/// One line on the screen.
+pub(super) enum Item {
+	/// A title for the rows under it, which can't be selected.
+	Heading(String),
+	/// A row that can be selected, and what it's about.
+	Row(String, Target),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+/// What a row is about, which decides what Enter, Space and Delete do to it.
+/// The `Add` ones are the "+ Add ..." rows at the end of each list.
+pub(super) enum Target {
+	/// Just text, like a lesson on the weekly menu.
+	Nothing,
+	Course(usize),
+	AddCourse,
+	Holiday(usize),
+	AddHoliday,
+	CourseField(usize, CourseField),
+	/// The time at (course index, time index).
+	Time(usize, usize),
+	AddTime(usize),
+	/// The task at (course index, task index).
+	Task(usize, usize),
+	AddTask(usize),
+	TaskField(usize, usize, TaskField),
+	/// The step at (course index, task index, step index).
+	Step(usize, usize, usize),
+	/// Whether the task at (course index, task index) is done, for tasks without steps.
+	TaskDone(usize, usize),
+	AddStep(usize, usize),
+	/// The task that the task at (course index, task index) depends on, numbered as in `Application::prerequisites`.
+	Dependency(usize, usize, usize),
+	AddDependency(usize, usize),
+}
+
+impl View {
+	/// Returns whether everything this view is about still exists.
+	/// Undoing can remove the course or task that's open, and then we have to go back.
+	pub fn exists(self, application: &Application) -> bool {
+		match self {
+			View::Menu(_) => true,
+			View::Course(course_index) => course_index < application.courses.len(),
+			View::Task(course_index, task_index) => match application.courses.get(course_index) {
+				Some(course) => task_index < course.tasks.len(),
+				None => false,
+			},
+		}
+	}
+
+	/// The title at the top of the screen, like "Math > Homework".
+	pub fn title(self, application: &Application) -> String {
+		match self {
+			View::Menu(menu) => menu.as_str().to_string(),
+			View::Course(course_index) => {
+				format!("Courses > {}", application.courses[course_index].name)
+			}
+			View::Task(course_index, task_index) => {
+				let course = &application.courses[course_index];
+				format!(
+					"Courses > {} > {}",
+					course.name, course.tasks[task_index].name
+				)
+			}
+		}
+	}
+
+	/// Every line of this view, in order.
+	pub fn items(self, application: &Application) -> Vec<Item> {
+		match self {
+			View::Menu(Menu::Main) => main(application),
+			View::Menu(Menu::Weekly) => weekly(application),
+			View::Menu(Menu::Courses) => courses(application),
+			View::Menu(Menu::Holidays) => holidays(application),
+			View::Course(course_index) => course(application, course_index),
+			View::Task(course_index, task_index) => task(application, course_index, task_index),
+		}
+	}
+}
+
+/// Today's courses and this week's tasks, like the GUI's main menu.
+fn main(application: &Application) -> Vec<Item> {
+	let today = application.today();
+	let locale = &application.locale;
+
+	let mut items = Vec::new();
+
+	// Overdue tasks go first, so they can't be missed
+	let overdue = application.overdue_tasks(today);
+	if !overdue.is_empty() {
+		items.push(Item::Heading(format!("Overdue Tasks ({})", overdue.len())));
+		for entry in overdue {
+			items.push(Item::Row(
+				format!(
+					"[{}] {}: {} ({} days late)",
+					entry.task.priority,
+					entry.course.name,
+					entry.task.name,
+					entry.task.days_overdue(today).unwrap_or(0)
+				),
+				Target::Task(entry.course_index, entry.task_index),
+			));
+		}
+	}
+
+	items.push(Item::Heading("Today's Courses".to_string()));
+	if application.is_holiday(today) {
+		items.push(Item::Row(
+			"Today's a holiday - no lessons!".to_string(),
+			Target::Nothing,
+		));
+	} else {
+		for (course, start, end) in application.lessons_on(today) {
+			items.push(Item::Row(
+				format!(
+					"{}: {} to {}",
+					course.name,
+					locale.format_time(start),
+					locale.format_time(end)
+				),
+				Target::Nothing,
+			));
+		}
+	}
+
+	items.push(Item::Heading("Week's Tasks".to_string()));
+	let query = TaskQuery::new()
+		.due_between(today, today + 7)
+		.complete(false)
+		.sort_by(TaskSort::Due);
+	for entry in application.tasks(&query) {
+		items.push(Item::Row(
+			format!(
+				"[{}] {}: {}",
+				entry.task.priority, entry.task.name, entry.task.desc
+			),
+			Target::Task(entry.course_index, entry.task_index),
+		));
+	}
+	items
+}
+
+/// This week's lessons, one heading per school day.
+fn weekly(application: &Application) -> Vec<Item> {
+	let today = application.today();
+	let locale = &application.locale;
+
+	let mut items = Vec::new();
+	for day in locale.school_week() {
+		// Only the very first week there is has days before 1970, which can't be dates
+		let date = match locale.date_in_week(today, day) {
+			Ok(date) => date,
+			Err(()) => continue,
+		};
+		items.push(Item::Heading(format!(
+			"{} ({})",
+			day,
+			locale.format_date(date)
+		)));
+		for (course, start, end) in application.lessons_on(date) {
+			items.push(Item::Row(
+				format!(
+					"{}: {} to {}",
+					course.name,
+					locale.format_time(start),
+					locale.format_time(end)
+				),
+				Target::Nothing,
+			));
+		}
+	}
+	items
+}
+
+fn courses(application: &Application) -> Vec<Item> {
+	let overdue = application.overdue_counts(application.today());
+
+	let mut items = vec![Item::Heading("Courses".to_string())];
+	for (course_index, course) in application.courses.iter().enumerate() {
+		let mut text = format!("{} ({}, {})", course.name, course.teacher, course.room);
+		if overdue[course_index] > 0 {
+			text.push_str(&format!(" - {} overdue", overdue[course_index]));
+		}
+		items.push(Item::Row(text, Target::Course(course_index)));
+	}
+	items.push(Item::Row("+ Add course".to_string(), Target::AddCourse));
+	items
+}
+
+fn holidays(application: &Application) -> Vec<Item> {
+	let locale = &application.locale;
+
+	let mut items = vec![Item::Heading("Holidays".to_string())];
+	for (index, (start, end)) in application.holidays.iter().enumerate() {
+		items.push(Item::Row(
+			format!(
+				"{} to {}",
+				locale.format_date(*start),
+				locale.format_date(*end)
+			),
+			Target::Holiday(index),
+		));
+	}
+	items.push(Item::Row("+ Add holiday".to_string(), Target::AddHoliday));
+	items
+}
+
+/// Everything in the GUI's course window: its details, times and tasks.
+fn course(application: &Application, course_index: usize) -> Vec<Item> {
+	let course = &application.courses[course_index];
+	let locale = &application.locale;
+
+	let mut items = vec![Item::Heading("Details".to_string())];
+	for field in CourseField::all().iter() {
+		items.push(Item::Row(
+			format!(
+				"{}: {}",
+				field.as_str(),
+				field.value(application, course_index)
+			),
+			Target::CourseField(course_index, *field),
+		));
+	}
+
+	items.push(Item::Heading("Times".to_string()));
+	for (time_index, (day, start, end)) in course.times.iter().enumerate() {
+		items.push(Item::Row(
+			format!(
+				"{}: From {} to {}",
+				day,
+				locale.format_time(*start),
+				locale.format_time(*end)
+			),
+			Target::Time(course_index, time_index),
+		));
+	}
+	items.push(Item::Row(
+		"+ Add time".to_string(),
+		Target::AddTime(course_index),
+	));
+
+	items.push(Item::Heading("Tasks".to_string()));
+	for (task_index, task) in course.tasks.iter().enumerate() {
+		items.push(Item::Row(
+			format!(
+				"{}: {} (due {})",
+				task.is_complete_str(),
+				task.name,
+				locale.format_date(task.due)
+			),
+			Target::Task(course_index, task_index),
+		));
+	}
+	items.push(Item::Row(
+		"+ Add task".to_string(),
+		Target::AddTask(course_index),
+	));
+	items
+}
+
+/// Everything in the GUI's task window: its details and steps.
+fn task(application: &Application, course_index: usize, task_index: usize) -> Vec<Item> {
+	let task = &application.courses[course_index].tasks[task_index];
+
+	let mut items = vec![Item::Heading("Details".to_string())];
+	for field in TaskField::all().iter() {
+		items.push(Item::Row(
+			format!(
+				"{}: {}",
+				field.as_str(),
+				field.value(application, course_index, task_index)
+			),
+			Target::TaskField(course_index, task_index, *field),
+		));
+	}
+	if let Some(original) = task.original_due {
+		items.push(Item::Row(
+			format!("First due {}", application.locale.format_date(original)),
+			Target::Nothing,
+		));
+	}
+
+	items.push(Item::Heading(format!("Steps ({}% done)", task.progress())));
+	// Without any steps, there's just the one box to tick
+	if task.steps.is_empty() {
+		let mark = if task.complete { "x" } else { " " };
+		items.push(Item::Row(
+			format!("[{}] Done", mark),
+			Target::TaskDone(course_index, task_index),
+		));
+	}
+	for (step_index, step) in task.steps.iter().enumerate() {
+		let mark = if step.complete { "x" } else { " " };
+		// Sub-steps are indented under the step they're in
+		let indent = "  ".repeat(step.depth);
+		let mut text = format!("{}[{}] {}", indent, mark, step.desc);
+		if let Some(weight) = step.weight {
+			text.push_str(&format!(" (counts {}x)", weight));
+		}
+		if let Some(due) = step.due {
+			text.push_str(&format!(" (by {})", application.locale.format_date(due)));
+		}
+		items.push(Item::Row(
+			text,
+			Target::Step(course_index, task_index, step_index),
+		));
+	}
+	items.push(Item::Row(
+		"+ Add step".to_string(),
+		Target::AddStep(course_index, task_index),
+	));
+
+	let heading = if application.is_blocked(course_index, task_index) {
+		"Depends On (blocked)"
+	} else {
+		"Depends On"
+	};
+	items.push(Item::Heading(heading.to_string()));
+	let prerequisites = application.prerequisites(course_index, task_index);
+	for (index, (other_course, other_task)) in prerequisites.into_iter().enumerate() {
+		let course = &application.courses[other_course];
+		let task = &course.tasks[other_task];
+		items.push(Item::Row(
+			format!(
+				"[{}] {}: {}",
+				if task.is_complete() { "x" } else { " " },
+				course.name,
+				task.name
+			),
+			Target::Dependency(course_index, task_index, index),
+		));
+	}
+	items.push(Item::Row(
+		"+ Add dependency".to_string(),
+		Target::AddDependency(course_index, task_index),
+	));
+	items
+}